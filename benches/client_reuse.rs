@@ -0,0 +1,69 @@
+//! 对比每次请求新建客户端与复用运行作用域客户端的单请求开销
+//!
+//! 运行：`cargo bench --bench client_reuse`
+
+use std::time::{Duration, Instant};
+
+use remote_task::http_client::{HttpClient, HttpClientCache, HttpClientConfig};
+use remote_task::mock_server::{MockServer, MockServerConfig};
+
+const WARMUP: usize = 20;
+const ITERATIONS: usize = 500;
+
+#[tokio::main]
+async fn main() {
+    let server = MockServer::start("127.0.0.1:0".parse().unwrap(), MockServerConfig::default())
+        .await
+        .expect("mock server starts");
+    let url = format!(
+        "{}/ISAPI/System/AlgoPackageScheduling/capabilities",
+        server.base_url()
+    );
+
+    let fresh = measure(&url, || {
+        HttpClient::new(HttpClientConfig::default()).expect("client builds")
+    })
+    .await;
+    let cache = HttpClientCache::new();
+    let reused = measure(&url, || {
+        cache
+            .session(HttpClientConfig::default())
+            .expect("client builds")
+    })
+    .await;
+
+    println!("client_reuse: {} requests each", ITERATIONS);
+    println!(
+        "  new client per request : {:>8.1} µs/request",
+        micros(fresh)
+    );
+    println!(
+        "  shared client session  : {:>8.1} µs/request",
+        micros(reused)
+    );
+    println!(
+        "  speedup                : {:>8.2}x",
+        fresh.as_secs_f64() / reused.as_secs_f64()
+    );
+}
+
+/// 每次迭代取得一个客户端并发送一个请求，返回平均耗时（不含预热）
+async fn measure(url: &str, mut client: impl FnMut() -> HttpClient) -> Duration {
+    let mut total = Duration::ZERO;
+    for iteration in 0..WARMUP + ITERATIONS {
+        let start = Instant::now();
+        let response = client()
+            .send_request("GET", url, None, None)
+            .await
+            .expect("request succeeds");
+        response.bytes().await.expect("body reads");
+        if iteration >= WARMUP {
+            total += start.elapsed();
+        }
+    }
+    total / ITERATIONS as u32
+}
+
+fn micros(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1_000_000.0
+}
@@ -0,0 +1,19 @@
+use std::process::Command;
+
+/// 构建时记录工具源码的 `git describe`，写入运行元数据（不在git仓库中构建时省略）
+fn main() {
+    println!("cargo:rerun-if-changed=.git/HEAD");
+    println!("cargo:rerun-if-changed=.git/index");
+    println!("cargo:rerun-if-changed=.git/refs");
+    let describe = Command::new("git")
+        .args(["describe", "--always", "--dirty", "--tags"])
+        .output()
+        .ok()
+        .filter(|output| output.status.success())
+        .and_then(|output| String::from_utf8(output.stdout).ok())
+        .map(|describe| describe.trim().to_string())
+        .filter(|describe| !describe.is_empty());
+    if let Some(describe) = describe {
+        println!("cargo:rustc-env=REMOTE_TASK_GIT_DESCRIBE={}", describe);
+    }
+}
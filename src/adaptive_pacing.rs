@@ -0,0 +1,178 @@
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::Instant;
+
+use crate::config::AdaptivePacingConfig;
+use crate::observer::RequestObserver;
+use crate::stats::{self, RequestResult};
+
+// Import logger macros from crate root
+use crate::{log_info, log_warn};
+
+const DEFAULT_WINDOW_MS: u64 = 5000;
+const DEFAULT_MIN_REQUESTS: usize = 10;
+const DEFAULT_MAX_INTERVAL_MS: u64 = 60_000;
+const DEFAULT_BACKOFF_FACTOR: f64 = 2.0;
+const DEFAULT_RECOVERY_FACTOR: f64 = 0.8;
+
+/// 自适应节奏的运行结果
+#[derive(Debug, Clone, PartialEq)]
+pub struct PacingReport {
+    pub interval: Duration,                     // 运行结束时的请求A间隔
+    pub sustainable_interval: Option<Duration>, // 未超过阈值的窗口中最短的间隔（没有健康窗口时为None）
+    pub slowdowns: usize,                       // 放慢的次数
+    pub speedups: usize,                        // 加快的次数
+}
+
+impl PacingReport {
+    /// 可持续的周期速率（每秒周期数）
+    pub fn sustainable_cycles_per_second(&self) -> Option<f64> {
+        self.sustainable_interval
+            .filter(|interval| !interval.is_zero())
+            .map(|interval| 1.0 / interval.as_secs_f64())
+    }
+}
+
+struct PacerState {
+    interval: Duration,
+    window_start: Instant,
+    latencies: Vec<Duration>,
+    failures: usize,
+    report: PacingReport,
+}
+
+/// 自适应节奏：按窗口统计请求结果，错误率或p95延迟超过阈值时加大请求A间隔，
+/// 恢复正常后逐步缩小间隔，直到配置的下限
+pub struct AdaptivePacer {
+    max_error_rate: Option<f64>,
+    max_p95: Option<Duration>,
+    window: Duration,
+    min_requests: usize,
+    min_interval: Duration,
+    max_interval: Duration,
+    backoff_factor: f64,
+    recovery_factor: f64,
+    inner: Mutex<PacerState>,
+}
+
+impl AdaptivePacer {
+    /// 以 `initial_interval`（delay_between_a_requests_ms）作为起始间隔
+    pub fn new(config: &AdaptivePacingConfig, initial_interval: Duration) -> Self {
+        let min_interval = config
+            .min_interval_ms
+            .map_or(initial_interval, Duration::from_millis);
+        let max_interval =
+            Duration::from_millis(config.max_interval_ms.unwrap_or(DEFAULT_MAX_INTERVAL_MS))
+                .max(min_interval);
+        let interval = initial_interval.clamp(min_interval, max_interval);
+        Self {
+            max_error_rate: config.max_error_rate,
+            max_p95: config.max_p95_ms.map(Duration::from_millis),
+            window: Duration::from_millis(config.window_ms.unwrap_or(DEFAULT_WINDOW_MS)),
+            min_requests: config.min_requests.unwrap_or(DEFAULT_MIN_REQUESTS).max(1),
+            min_interval,
+            max_interval,
+            backoff_factor: config.backoff_factor.unwrap_or(DEFAULT_BACKOFF_FACTOR),
+            recovery_factor: config.recovery_factor.unwrap_or(DEFAULT_RECOVERY_FACTOR),
+            inner: Mutex::new(PacerState {
+                interval,
+                window_start: Instant::now(),
+                latencies: Vec::new(),
+                failures: 0,
+                report: PacingReport {
+                    interval,
+                    sustainable_interval: None,
+                    slowdowns: 0,
+                    speedups: 0,
+                },
+            }),
+        }
+    }
+
+    /// 当前的请求A间隔
+    pub fn interval(&self) -> Duration {
+        self.inner.lock().unwrap().interval
+    }
+
+    /// 当前的节奏结果
+    pub fn report(&self) -> PacingReport {
+        let inner = self.inner.lock().unwrap();
+        PacingReport {
+            interval: inner.interval,
+            ..inner.report.clone()
+        }
+    }
+
+    /// 窗口结束时评估：超过阈值返回原因
+    fn breach(&self, inner: &PacerState) -> Option<String> {
+        let total = inner.latencies.len();
+        let error_rate = inner.failures as f64 * 100.0 / total as f64;
+        if let Some(max) = self.max_error_rate
+            && error_rate > max
+        {
+            return Some(format!("error rate {:.1}% > {}%", error_rate, max));
+        }
+        if let Some(max) = self.max_p95
+            && let Some(p95) = stats::nearest_rank(&inner.latencies, 95.0)
+            && p95 > max
+        {
+            return Some(format!("p95 {}ms > {}ms", p95.as_millis(), max.as_millis()));
+        }
+        None
+    }
+
+    fn scale(interval: Duration, factor: f64) -> Duration {
+        Duration::from_secs_f64(interval.as_secs_f64() * factor)
+    }
+}
+
+impl RequestObserver for AdaptivePacer {
+    fn on_result(&self, result: &RequestResult) {
+        if result.cancelled {
+            return;
+        }
+        let mut inner = self.inner.lock().unwrap();
+        inner.latencies.push(result.elapsed);
+        if !result.success {
+            inner.failures += 1;
+        }
+        if inner.window_start.elapsed() < self.window || inner.latencies.len() < self.min_requests {
+            return;
+        }
+
+        let previous = inner.interval;
+        match self.breach(&inner) {
+            Some(reason) => {
+                inner.interval = Self::scale(previous, self.backoff_factor).min(self.max_interval);
+                if inner.interval != previous {
+                    inner.report.slowdowns += 1;
+                    log_warn!(
+                        "🐢 {} over the last window, slowing A requests to every {}ms",
+                        reason,
+                        inner.interval.as_millis()
+                    );
+                }
+            }
+            None => {
+                if inner
+                    .report
+                    .sustainable_interval
+                    .is_none_or(|sustainable| previous < sustainable)
+                {
+                    inner.report.sustainable_interval = Some(previous);
+                }
+                inner.interval = Self::scale(previous, self.recovery_factor).max(self.min_interval);
+                if inner.interval != previous {
+                    inner.report.speedups += 1;
+                    log_info!(
+                        "🐇 Target healthy, speeding A requests up to every {}ms",
+                        inner.interval.as_millis()
+                    );
+                }
+            }
+        }
+        inner.window_start = Instant::now();
+        inner.latencies.clear();
+        inner.failures = 0;
+    }
+}
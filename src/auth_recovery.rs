@@ -0,0 +1,91 @@
+use anyhow::{Result, bail};
+use reqwest::StatusCode;
+use tokio::sync::Mutex;
+use tokio::time::Instant;
+
+use crate::capture::{Capturer, VariableStore};
+use crate::config::{AuthRecoveryConfig, HttpRequestConfig};
+use crate::cycle_context::CycleContext;
+use crate::http_client::{HttpClient, ResponseData};
+
+// Import logger macros from crate root
+use crate::log_info;
+
+/// 认证失效自动恢复：请求收到401时清除认证缓存并执行登录步骤，之后由调用方重发该请求一次。
+/// 并发请求同时失效时只登录一次，登录之后才发出的请求直接重发
+pub struct AuthRecovery {
+    config: AuthRecoveryConfig,
+    last_login: Mutex<Option<Instant>>, // 最近一次登录完成的时刻
+}
+
+impl AuthRecovery {
+    pub fn new(config: AuthRecoveryConfig) -> Self {
+        Self {
+            config,
+            last_login: Mutex::new(None),
+        }
+    }
+
+    /// 请求结果是否表示认证失效
+    pub fn is_auth_failure(result: &Result<ResponseData>) -> bool {
+        matches!(result, Ok(response) if response.status == StatusCode::UNAUTHORIZED)
+    }
+
+    /// 重新认证：`sent_at` 之后已有其他请求完成登录时不再重复登录。
+    /// 返回是否执行了登录（计为一次重新认证），登录请求失败时返回错误
+    pub async fn recover(
+        &self,
+        http_client: &HttpClient,
+        context: &CycleContext,
+        run_captures: &VariableStore,
+        sent_at: Instant,
+    ) -> Result<bool> {
+        let mut last_login = self.last_login.lock().await;
+        if last_login.is_some_and(|login| login >= sent_at) {
+            return Ok(false);
+        }
+
+        http_client.clear_auth_cache().await;
+        if let Some(login) = &self.config.login {
+            Self::login(http_client, login, context, run_captures).await?;
+        }
+        log_info!("🔑 Re-authenticated after HTTP 401");
+        *last_login = Some(Instant::now());
+        Ok(true)
+    }
+
+    /// 按周期上下文和已捕获的变量渲染并发送登录请求，从成功的响应中提取变量
+    pub async fn login(
+        http_client: &HttpClient,
+        login: &HttpRequestConfig,
+        context: &CycleContext,
+        run_captures: &VariableStore,
+    ) -> Result<()> {
+        let mut request = login.clone();
+        Capturer::render_with(
+            &mut request,
+            &context.scoped_variables(0, login.variables.as_ref()),
+        );
+        Capturer::render(&mut request, &context.captures, run_captures);
+        let headers = request.headers.as_ref().map(|headers| {
+            headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .collect::<Vec<_>>()
+        });
+        let response = http_client
+            .send_request(&request.method, &request.url, request.body.clone(), headers)
+            .await?;
+        let response = ResponseData::read(response).await?;
+        if !response.status.is_success() {
+            bail!("login request returned HTTP {}", response.status);
+        }
+        if let Some(captures) = &request.captures
+            && let Some(failure) =
+                Capturer::capture(captures, &response, &context.captures, run_captures)
+        {
+            bail!("login request {}", failure);
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,102 @@
+use bytes::Bytes;
+use reqwest::Body;
+use std::sync::Arc;
+use tokio::sync::Mutex;
+use tokio::time::sleep;
+use tokio_stream::StreamExt;
+
+use crate::config::BandwidthConfig;
+use crate::rate_limiter::TokenBucket;
+
+/// 上传时每个分块的最大字节数，较小的分块使发送速率更平滑
+const MAX_UPLOAD_CHUNK: usize = 16 * 1024;
+
+/// 按字节计的令牌桶，限制body的传输速率
+pub struct BandwidthLimiter {
+    rate: f64,
+    burst: f64,
+    bucket: Mutex<TokenBucket>,
+}
+
+impl BandwidthLimiter {
+    /// 允许约100ms的突发量，令牌初始为空，避免开头瞬间发送大量数据
+    pub fn new(bytes_per_second: u64) -> Self {
+        let rate = bytes_per_second.max(1) as f64;
+        Self {
+            rate,
+            burst: (rate / 10.0).max(1.0),
+            bucket: Mutex::new(TokenBucket::new(0.0)),
+        }
+    }
+
+    /// 消耗 `bytes` 个令牌，不足时等待（预占令牌，并发传输依次排队）
+    pub async fn consume(&self, bytes: usize) {
+        let wait = self
+            .bucket
+            .lock()
+            .await
+            .reserve(self.rate, self.burst, bytes as f64);
+        if !wait.is_zero() {
+            sleep(wait).await;
+        }
+    }
+
+    /// 将请求body拆成小块，按限速逐块发送
+    pub fn body(limiter: Arc<Self>, data: &str) -> Body {
+        let chunk_size = (limiter.burst as usize).clamp(1, MAX_UPLOAD_CHUNK);
+        let data = Bytes::copy_from_slice(data.as_bytes());
+        let chunks: Vec<Bytes> = (0..data.len())
+            .step_by(chunk_size)
+            .map(|start| data.slice(start..(start + chunk_size).min(data.len())))
+            .collect();
+        Body::wrap_stream(tokio_stream::iter(chunks).then(move |chunk| {
+            let limiter = limiter.clone();
+            async move {
+                limiter.consume(chunk.len()).await;
+                Ok::<_, std::io::Error>(chunk)
+            }
+        }))
+    }
+}
+
+/// 一次运行的带宽限制：global作用域下所有请求共享同一组令牌桶，否则每个请求单独限速
+pub struct BandwidthThrottle {
+    config: BandwidthConfig,
+    shared: Option<RequestBandwidth>,
+}
+
+/// 单个请求使用的上传、下载限速器
+#[derive(Clone, Default)]
+pub struct RequestBandwidth {
+    pub upload: Option<Arc<BandwidthLimiter>>,
+    pub download: Option<Arc<BandwidthLimiter>>,
+}
+
+impl BandwidthThrottle {
+    pub fn new(config: &BandwidthConfig) -> Self {
+        let shared = (config.scope.as_deref() == Some("global")).then(|| Self::limiters(config));
+        Self {
+            config: config.clone(),
+            shared,
+        }
+    }
+
+    /// 获取一个请求使用的限速器
+    pub fn for_request(&self) -> RequestBandwidth {
+        match &self.shared {
+            Some(shared) => shared.clone(),
+            None => Self::limiters(&self.config),
+        }
+    }
+
+    fn limiters(config: &BandwidthConfig) -> RequestBandwidth {
+        RequestBandwidth {
+            upload: config
+                .upload_bytes_per_second
+                .map(|rate| Arc::new(BandwidthLimiter::new(rate))),
+            download: config
+                .download_bytes_per_second
+                .map(|rate| Arc::new(BandwidthLimiter::new(rate))),
+        }
+    }
+}
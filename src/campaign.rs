@@ -0,0 +1,361 @@
+use anyhow::{Result, bail};
+use serde_json::{Value, json};
+use std::path::Path;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{CampaignConfig, RequestConfig};
+use crate::progress::ProgressBar;
+use crate::report::{JunitReport, ThresholdChecker, ThresholdResult, write_report};
+use crate::request_handler::{RequestHandler, RunOptions};
+use crate::stats::{RequestStats, StatsHandler};
+
+// Import logger macros from crate root
+use crate::{log_error, log_info, log_warn};
+
+/// 批量运行中一个场景的结果
+#[derive(Debug, Clone)]
+pub struct CampaignRun {
+    pub name: String,
+    /// 未运行（前面的场景未通过或运行被中断）时为None
+    pub stats: Option<RequestStats>,
+    pub thresholds: Vec<ThresholdResult>,
+    /// 因达到 `max_duration_ms` 而结束
+    pub timed_out: bool,
+}
+
+impl CampaignRun {
+    /// 场景通过：已运行、阈值全部满足，且没有提前停止（达到 `max_duration_ms` 除外）
+    pub fn passed(&self) -> bool {
+        self.stats
+            .as_ref()
+            .is_some_and(|stats| stats.stop_reason.is_none() || self.timed_out)
+            && self.thresholds.iter().all(|threshold| threshold.passed)
+    }
+}
+
+/// 批量运行的合并结果
+#[derive(Debug, Clone)]
+pub struct CampaignResult {
+    pub name: String,
+    pub runs: Vec<CampaignRun>,
+    pub elapsed: Duration,
+}
+
+impl CampaignResult {
+    /// 全部场景都已运行并通过
+    pub fn passed(&self) -> bool {
+        self.runs.iter().all(CampaignRun::passed)
+    }
+
+    /// 合并报告：各场景的完整统计和阈值结果，以及全部场景的请求总数
+    pub fn to_json(&self) -> Value {
+        let ran = || self.runs.iter().filter_map(|run| run.stats.as_ref());
+        let scenarios: Vec<Value> = self
+            .runs
+            .iter()
+            .map(|run| {
+                json!({
+                    "name": run.name,
+                    "status": if run.stats.is_none() {
+                        "skipped"
+                    } else if run.passed() {
+                        "passed"
+                    } else {
+                        "failed"
+                    },
+                    "timed_out": run.timed_out,
+                    "thresholds": run.thresholds.iter().map(|result| json!({
+                        "name": result.name,
+                        "passed": result.passed,
+                        "message": result.message,
+                    })).collect::<Vec<_>>(),
+                    "stats": run.stats.as_ref().map(StatsHandler::to_json),
+                })
+            })
+            .collect();
+        json!({
+            "campaign": self.name,
+            "passed": self.passed(),
+            "elapsed_ms": self.elapsed.as_millis() as u64,
+            "total_requests": ran().map(|stats| stats.total_requests).sum::<usize>(),
+            "successful_requests": ran().map(|stats| stats.successful_requests).sum::<usize>(),
+            "failed_requests": ran().map(|stats| stats.failed_requests).sum::<usize>(),
+            "scenarios": scenarios,
+        })
+    }
+
+    /// 将合并报告写为JSON文件
+    pub fn write_json(&self, path: impl AsRef<Path>) -> Result<()> {
+        let json = serde_json::to_string_pretty(&self.to_json())?;
+        write_report(path.as_ref(), &json, "campaign")
+    }
+
+    /// 将合并报告写为JUnit XML，每个场景一个套件
+    pub fn write_junit(&self, path: impl AsRef<Path>) -> Result<()> {
+        let suites: Vec<_> = self
+            .runs
+            .iter()
+            .map(|run| {
+                (
+                    run.name.as_str(),
+                    run.stats.as_ref(),
+                    run.thresholds.as_slice(),
+                )
+            })
+            .collect();
+        JunitReport::write_suites(path, &self.name, &suites)
+    }
+
+    /// 输出各场景一行的汇总
+    pub fn print_summary(&self) {
+        log_info!(
+            "📋 Campaign {} ({:.1}s):",
+            self.name,
+            self.elapsed.as_secs_f64()
+        );
+        for run in &self.runs {
+            let Some(stats) = &run.stats else {
+                log_info!("  ⏭️  {}: skipped", run.name);
+                continue;
+            };
+            let line = format!(
+                "{}: {} requests, {} failed ({:.2}% errors), p95 {}, {:.1}s{}",
+                run.name,
+                stats.total_requests,
+                stats.failed_requests,
+                stats.error_rate(),
+                stats
+                    .latency_percentile(95.0)
+                    .map_or("-".to_string(), |p95| format!("{}ms", p95.as_millis())),
+                stats.elapsed.as_secs_f64(),
+                stats
+                    .stop_reason
+                    .as_ref()
+                    .map_or(String::new(), |reason| format!(" — {}", reason))
+            );
+            if run.passed() {
+                log_info!("  ✅ {}", line);
+            } else {
+                log_error!("  {}", line);
+                for threshold in run.thresholds.iter().filter(|threshold| !threshold.passed) {
+                    log_error!(
+                        "     Threshold {} failed: {}",
+                        threshold.name,
+                        threshold.message
+                    );
+                }
+            }
+        }
+        let passed = self.runs.iter().filter(|run| run.passed()).count();
+        log_info!("📋 {}/{} scenarios passed", passed, self.runs.len());
+    }
+}
+
+/// 一个待运行的场景（已应用覆盖项）
+#[derive(Debug, Clone)]
+struct CampaignEntry {
+    name: String,
+    config: RequestConfig,
+    max_duration: Option<Duration>,
+}
+
+/// 批量运行：依次或并行运行多个场景，各自按自己的max_requests、时长上限和阈值停止和判定，最后合并报告
+pub struct Campaign {
+    name: String,
+    parallel: bool,
+    stop_on_failure: bool,
+    progress: bool,
+    entries: Vec<CampaignEntry>,
+}
+
+impl Campaign {
+    /// `scenarios` 与 `config.scenarios` 一一对应（已加载、选定环境并解析凭据的场景配置），
+    /// 各场景的覆盖项（变量、max_requests、thresholds）在这里应用
+    pub fn new(config: &CampaignConfig, scenarios: Vec<RequestConfig>) -> Result<Self> {
+        if config.scenarios.len() != scenarios.len() {
+            bail!(
+                "{} campaign entries but {} scenarios",
+                config.scenarios.len(),
+                scenarios.len()
+            );
+        }
+        let entries = config
+            .scenarios
+            .iter()
+            .zip(scenarios)
+            .enumerate()
+            .map(|(index, (entry, mut scenario))| {
+                if let Some(variables) = &entry.variables {
+                    let overrides: Vec<_> = variables
+                        .iter()
+                        .map(|(name, value)| (name.clone(), value.clone()))
+                        .collect();
+                    scenario.apply_overrides(&overrides);
+                }
+                if entry.max_requests.is_some() {
+                    scenario.max_requests = entry.max_requests;
+                }
+                if entry.thresholds.is_some() {
+                    scenario.thresholds = entry.thresholds.clone();
+                }
+                CampaignEntry {
+                    name: Self::entry_name(entry.name.as_deref(), entry.scenario.as_deref(), index),
+                    config: scenario,
+                    max_duration: entry.max_duration_ms.map(Duration::from_millis),
+                }
+            })
+            .collect();
+        Ok(Self {
+            name: config
+                .name
+                .clone()
+                .unwrap_or_else(|| "campaign".to_string()),
+            parallel: config.mode.as_deref() == Some("parallel"),
+            stop_on_failure: config.stop_on_failure.unwrap_or(false),
+            progress: false,
+            entries,
+        })
+    }
+
+    /// 顺序运行时为每个场景显示进度条（按场景的max_requests和max_duration_ms计算，stderr不是终端时不显示）；
+    /// 并行运行时不显示
+    pub fn with_progress(mut self, progress: bool) -> Self {
+        self.progress = progress;
+        self
+    }
+
+    /// 场景名称：配置的名称，否则为场景文件名（不含扩展名），都没有时为 `scenario-<序号>`
+    pub fn entry_name(name: Option<&str>, scenario: Option<&str>, index: usize) -> String {
+        name.map(str::to_string)
+            .or_else(|| {
+                scenario
+                    .and_then(|path| Path::new(path).file_stem())
+                    .map(|stem| stem.to_string_lossy().into_owned())
+            })
+            .unwrap_or_else(|| format!("scenario-{}", index + 1))
+    }
+
+    /// 运行全部场景；`shutdown` 取消时中断进行中的场景，未开始的场景记为跳过
+    pub async fn run(&self, shutdown: CancellationToken) -> CampaignResult {
+        let start = Instant::now();
+        log_info!(
+            "🗂️  Campaign {}: {} scenario(s), {}",
+            self.name,
+            self.entries.len(),
+            if self.parallel {
+                "parallel"
+            } else {
+                "sequential"
+            }
+        );
+        let runs = if self.parallel {
+            let handles: Vec<_> = self
+                .entries
+                .iter()
+                .map(|entry| tokio::spawn(Self::run_entry(entry.clone(), shutdown.clone(), false)))
+                .collect();
+            let mut runs = Vec::with_capacity(handles.len());
+            for (entry, handle) in self.entries.iter().zip(handles) {
+                runs.push(handle.await.unwrap_or_else(|e| {
+                    log_error!("Scenario {} panicked: {}", entry.name, e);
+                    Self::skipped(entry)
+                }));
+            }
+            runs
+        } else {
+            let mut runs = Vec::with_capacity(self.entries.len());
+            let mut skip_rest = false;
+            for entry in &self.entries {
+                if skip_rest || shutdown.is_cancelled() {
+                    runs.push(Self::skipped(entry));
+                    continue;
+                }
+                let run = Self::run_entry(entry.clone(), shutdown.clone(), self.progress).await;
+                if self.stop_on_failure && !run.passed() {
+                    log_warn!(
+                        "Scenario {} failed, skipping the remaining scenarios",
+                        entry.name
+                    );
+                    skip_rest = true;
+                }
+                runs.push(run);
+            }
+            runs
+        };
+        CampaignResult {
+            name: self.name.clone(),
+            runs,
+            elapsed: start.elapsed(),
+        }
+    }
+
+    async fn run_entry(
+        entry: CampaignEntry,
+        shutdown: CancellationToken,
+        progress: bool,
+    ) -> CampaignRun {
+        // 时长上限只中断本场景
+        let scenario_shutdown = shutdown.child_token();
+        let timer = entry.max_duration.map(|max_duration| {
+            let scenario_shutdown = scenario_shutdown.clone();
+            tokio::spawn(async move {
+                tokio::time::sleep(max_duration).await;
+                scenario_shutdown.cancel();
+            })
+        });
+        log_info!("▶️  Scenario {} started", entry.name);
+        let thresholds = entry.config.thresholds.clone();
+        let progress = progress
+            .then(|| ProgressBar::new(entry.config.max_requests, entry.max_duration))
+            .flatten()
+            .map(Arc::new);
+        let mut options = RunOptions {
+            shutdown: Some(scenario_shutdown.clone()),
+            ..Default::default()
+        };
+        if let Some(progress) = &progress {
+            options.observers.push(progress.clone());
+        }
+        let mut stats = RequestHandler::run_with_options(entry.config, options).await;
+        if let Some(progress) = &progress {
+            progress.finish();
+        }
+        if let Some(timer) = timer {
+            timer.abort();
+        }
+        let timed_out = scenario_shutdown.is_cancelled() && !shutdown.is_cancelled();
+        if timed_out && let Some(max_duration) = entry.max_duration {
+            stats.stop_reason = Some(format!(
+                "reached max_duration_ms of {}",
+                max_duration.as_millis()
+            ));
+        }
+        let thresholds = thresholds
+            .map(|thresholds| ThresholdChecker::evaluate(&thresholds, &stats))
+            .unwrap_or_default();
+        log_info!(
+            "⏹️  Scenario {} finished: {} requests, {} failed ({:.2}% errors)",
+            entry.name,
+            stats.total_requests,
+            stats.failed_requests,
+            stats.error_rate()
+        );
+        CampaignRun {
+            name: entry.name,
+            stats: Some(stats),
+            thresholds,
+            timed_out,
+        }
+    }
+
+    fn skipped(entry: &CampaignEntry) -> CampaignRun {
+        CampaignRun {
+            name: entry.name.clone(),
+            stats: None,
+            thresholds: Vec::new(),
+            timed_out: false,
+        }
+    }
+}
@@ -0,0 +1,186 @@
+use anyhow::{Result, bail};
+use serde_json::{Value, json};
+
+use crate::config::RequestConfig;
+use crate::report::ThresholdChecker;
+use crate::request_handler::RequestHandler;
+use crate::stats::{RequestStats, StatsHandler};
+use crate::sweep::ParameterSweep;
+
+// Import logger macros from crate root
+use crate::{log_info, log_warn};
+
+/// 容量搜索中单个负载级别的运行结果
+#[derive(Debug, Clone)]
+pub struct CapacityPoint {
+    pub value: String,
+    pub stats: RequestStats,
+    pub violations: Vec<String>, // 未满足的阈值和SLO（为空表示该负载下SLO成立）
+}
+
+impl CapacityPoint {
+    pub fn passed(&self) -> bool {
+        self.violations.is_empty()
+    }
+}
+
+/// 容量搜索结果
+#[derive(Debug, Clone)]
+pub struct CapacityReport {
+    pub param: String,
+    pub points: Vec<CapacityPoint>, // 按运行顺序
+    pub knee: Option<String>,       // SLO仍成立的最高负载（拐点），最低负载也不满足时为None
+    pub breaking: Option<String>,   // 拐点之上最近的不满足SLO的负载（所有负载都满足时为None）
+}
+
+impl CapacityReport {
+    /// JSON报告（`--output json`），每个负载级别附带完整统计
+    pub fn to_json(&self) -> Value {
+        json!({
+            "param": self.param,
+            "knee": self.knee,
+            "breaking": self.breaking,
+            "points": self
+                .points
+                .iter()
+                .map(|point| {
+                    json!({
+                        "value": point.value,
+                        "passed": point.passed(),
+                        "violations": point.violations,
+                        "stats": StatsHandler::to_json(&point.stats),
+                    })
+                })
+                .collect::<Vec<_>>(),
+        })
+    }
+}
+
+/// 容量搜索：按负载从低到高排列参数取值（如 `batch.size` 或 `delay_between_a_requests_ms`），
+/// 逐级（step）或二分（binary）运行短场景，找出配置的 `thresholds` 和请求SLO仍成立的最高负载
+pub struct CapacitySearch;
+
+impl CapacitySearch {
+    /// 配置中是否有判定SLO是否成立的标准（thresholds或请求A/B的slo）
+    pub fn has_criteria(config: &RequestConfig) -> bool {
+        config.thresholds.as_ref().is_some_and(|thresholds| {
+            thresholds.max_error_rate.is_some() || thresholds.max_p95_ms.is_some()
+        }) || config.request_a.slo.is_some()
+            || config.request_b.slo.is_some()
+    }
+
+    /// 一次运行未满足的阈值、耗尽的SLO预算和提前停止的原因
+    pub fn violations(config: &RequestConfig, stats: &RequestStats) -> Vec<String> {
+        let mut violations: Vec<String> = config
+            .thresholds
+            .as_ref()
+            .map(|thresholds| ThresholdChecker::evaluate(thresholds, stats))
+            .unwrap_or_default()
+            .into_iter()
+            .filter(|result| !result.passed)
+            .map(|result| format!("{}: {}", result.name, result.message))
+            .collect();
+        violations.extend(
+            stats
+                .slo
+                .iter()
+                .filter(|report| report.exhausted())
+                .map(|report| {
+                    format!(
+                        "SLO {}: {:.2}% within {}ms (target {}%)",
+                        report.label,
+                        report.compliance_percent(),
+                        report.latency_ms,
+                        report.target_percent
+                    )
+                }),
+        );
+        if let Some(reason) = &stats.stop_reason {
+            violations.push(format!("stopped early: {}", reason));
+        }
+        violations
+    }
+
+    /// 在按负载从低到高排列的 `levels` 中搜索拐点；`mode` 为 "step"（逐级，遇到第一个不满足的负载即停止）
+    /// 或 "binary"（二分，假定负载越高越难满足SLO）
+    pub async fn run(
+        config: &RequestConfig,
+        param: &str,
+        levels: &[String],
+        mode: &str,
+    ) -> Result<CapacityReport> {
+        if levels.is_empty() {
+            bail!("No load levels given");
+        }
+        // 先应用全部取值，任一取值无效时不发送请求
+        let configs = levels
+            .iter()
+            .map(|value| ParameterSweep::apply(config, param, value))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut points = Vec::new();
+        let mut knee = None;
+        let mut breaking = None;
+        match mode {
+            "step" => {
+                for (index, (config, value)) in configs.iter().zip(levels).enumerate() {
+                    let point = Self::run_point(config, param, value).await;
+                    let passed = point.passed();
+                    points.push(point);
+                    if !passed {
+                        breaking = Some(index);
+                        break;
+                    }
+                    knee = Some(index);
+                }
+            }
+            "binary" => {
+                let (mut low, mut high) = (0, levels.len());
+                while low < high {
+                    let middle = low + (high - low) / 2;
+                    let point = Self::run_point(&configs[middle], param, &levels[middle]).await;
+                    if point.passed() {
+                        knee = Some(middle);
+                        low = middle + 1;
+                    } else {
+                        breaking = Some(middle);
+                        high = middle;
+                    }
+                    points.push(point);
+                }
+            }
+            other => bail!(
+                "Unknown search mode \"{}\" (expected step or binary)",
+                other
+            ),
+        }
+
+        Ok(CapacityReport {
+            param: param.to_string(),
+            points,
+            knee: knee.map(|index| levels[index].clone()),
+            breaking: breaking.map(|index| levels[index].clone()),
+        })
+    }
+
+    async fn run_point(config: &RequestConfig, param: &str, value: &str) -> CapacityPoint {
+        log_info!("📈 Capacity search: {} = {}", param, value);
+        let stats = RequestHandler::run_concurrent_requests(config.clone()).await;
+        let violations = Self::violations(config, &stats);
+        if violations.is_empty() {
+            log_info!("  ✅ SLO holds at {} = {}", param, value);
+        } else {
+            log_warn!(
+                "  ❌ SLO violated at {} = {}: {}",
+                param,
+                value,
+                violations.join("; ")
+            );
+        }
+        CapacityPoint {
+            value: value.to_string(),
+            stats,
+            violations,
+        }
+    }
+}
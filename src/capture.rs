@@ -0,0 +1,250 @@
+use anyhow::{Result, anyhow, bail};
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Mutex;
+
+use crate::config::{CaptureConfig, HttpRequestConfig};
+use crate::http_client::ResponseData;
+use crate::jq::JqFilter;
+use crate::json_diff;
+use crate::template;
+
+// Import logger macros from crate root
+use crate::{log_debug, log_warn};
+
+/// 捕获变量的存储，周期作用域每个周期、每个目标一份，运行作用域整个运行共享一份
+#[derive(Debug, Default)]
+pub struct VariableStore {
+    vars: Mutex<HashMap<String, String>>,
+}
+
+impl VariableStore {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set(&self, name: &str, value: String) {
+        self.vars.lock().unwrap().insert(name.to_string(), value);
+    }
+
+    pub fn remove(&self, name: &str) {
+        self.vars.lock().unwrap().remove(name);
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.vars.lock().unwrap().is_empty()
+    }
+
+    pub fn get(&self, name: &str) -> Option<String> {
+        self.vars.lock().unwrap().get(name).cloned()
+    }
+
+    /// 当前全部变量（按名称排序）
+    pub fn snapshot(&self) -> BTreeMap<String, String> {
+        self.vars
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|(name, value)| (name.clone(), value.clone()))
+            .collect()
+    }
+
+    fn extend_into(&self, vars: &mut HashMap<String, String>) {
+        for (name, value) in self.vars.lock().unwrap().iter() {
+            vars.insert(name.clone(), value.clone());
+        }
+    }
+}
+
+/// 响应捕获：从响应中提取变量，并在发送前把已捕获的变量渲染到请求中
+pub struct Capturer;
+
+impl Capturer {
+    /// 用已捕获的变量渲染请求中剩余的占位符（周期变量优先于运行变量）
+    pub fn render(request: &mut HttpRequestConfig, cycle: &VariableStore, run: &VariableStore) {
+        if cycle.is_empty() && run.is_empty() {
+            return;
+        }
+        let mut vars = HashMap::new();
+        run.extend_into(&mut vars);
+        cycle.extend_into(&mut vars);
+        Self::render_with(request, &vars);
+    }
+
+    /// 用给定变量渲染请求URL、下载路径、头部、body和要设置的Cookie中剩余的占位符
+    pub fn render_with(request: &mut HttpRequestConfig, vars: &HashMap<String, String>) {
+        request.url = template::render(&request.url, vars);
+        if let Some(download) = request.download.as_mut() {
+            download.path = template::render(&download.path, vars);
+        }
+        if let Some(headers) = request.headers.as_mut() {
+            for value in headers.values_mut() {
+                *value = template::render(value, vars);
+            }
+        }
+        if let Some(body) = request.body.as_mut() {
+            *body = template::render(body, vars);
+        }
+        if let Some(cookies) = request.cookies.as_mut() {
+            for value in cookies.set.iter_mut().flat_map(HashMap::values_mut) {
+                *value = template::render(value, vars);
+            }
+        }
+    }
+
+    /// 按配置从响应中提取变量，返回第一个失败的原因
+    pub fn capture(
+        captures: &[CaptureConfig],
+        response: &ResponseData,
+        cycle: &VariableStore,
+        run: &VariableStore,
+    ) -> Option<String> {
+        let mut failure = None;
+        for capture in captures {
+            match Self::extract(capture, response) {
+                Ok(value) => {
+                    log_debug!("📌 Captured {} = {}", capture.name, value);
+                    match capture.scope.as_deref() {
+                        Some("run") => run.set(&capture.name, value),
+                        _ => cycle.set(&capture.name, value),
+                    }
+                }
+                Err(e) => {
+                    log_warn!("Capture {} failed: {}", capture.name, e);
+                    failure.get_or_insert_with(|| format!("capture {}: {}", capture.name, e));
+                }
+            }
+        }
+        failure
+    }
+
+    /// 提取单个变量
+    fn extract(capture: &CaptureConfig, response: &ResponseData) -> Result<String> {
+        match capture.source.as_deref().unwrap_or("json") {
+            "json" => {
+                let path = capture
+                    .path
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("no path configured"))?;
+                let body: Value = serde_json::from_slice(&response.body)
+                    .map_err(|e| anyhow!("response is not JSON: {}", e))?;
+                match json_diff::select(&body, path).first() {
+                    Some(Value::String(value)) => Ok(value.clone()),
+                    Some(value) => Ok(value.to_string()),
+                    None => bail!("{} not found in response", path),
+                }
+            }
+            "jq" => {
+                let filter = capture
+                    .filter
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("no filter configured"))?;
+                let body: Value = serde_json::from_slice(&response.body)
+                    .map_err(|e| anyhow!("response is not JSON: {}", e))?;
+                match JqFilter::run(filter, &body)?.into_iter().next() {
+                    Some(Value::String(value)) => Ok(value),
+                    Some(value) => Ok(value.to_string()),
+                    None => bail!("{} produced no output", filter),
+                }
+            }
+            "regex" => {
+                let pattern = capture
+                    .pattern
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("no pattern configured"))?;
+                let text = String::from_utf8_lossy(&response.body);
+                Self::regex_match(pattern, capture.group, &text)?
+                    .ok_or_else(|| anyhow!("/{}/ did not match response", pattern))
+            }
+            "header" => {
+                let name = capture
+                    .header
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("no header configured"))?;
+                let values: Vec<&str> = response
+                    .headers
+                    .get_all(name)
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .collect();
+                if values.is_empty() {
+                    bail!("header {} not found in response", name);
+                }
+                // 头部可能出现多次（如多个Set-Cookie），配置了pattern时取第一个匹配的值
+                let Some(pattern) = capture.pattern.as_deref() else {
+                    return Ok(values[0].to_string());
+                };
+                for value in values {
+                    if let Some(matched) = Self::regex_match(pattern, capture.group, value)? {
+                        return Ok(matched);
+                    }
+                }
+                bail!("/{}/ did not match header {}", pattern, name)
+            }
+            "cookie" => {
+                let name = capture
+                    .cookie
+                    .as_deref()
+                    .ok_or_else(|| anyhow!("no cookie configured"))?;
+                response
+                    .headers
+                    .get_all("Set-Cookie")
+                    .iter()
+                    .filter_map(|value| value.to_str().ok())
+                    .find_map(|value| {
+                        let (cookie, value) = value.split(';').next()?.split_once('=')?;
+                        (cookie.trim() == name).then(|| value.trim().trim_matches('"').to_string())
+                    })
+                    .ok_or_else(|| anyhow!("cookie {} not set in response", name))
+            }
+            source => bail!("unknown source \"{}\"", source),
+        }
+    }
+
+    /// 正则匹配文本，返回指定捕获组（默认有捕获组时为1，否则为整个匹配）
+    fn regex_match(pattern: &str, group: Option<usize>, text: &str) -> Result<Option<String>> {
+        let regex = template::regex(pattern)?;
+        let group = group.unwrap_or(if regex.captures_len() > 1 { 1 } else { 0 });
+        Ok(regex
+            .captures(text)
+            .and_then(|captures| captures.get(group))
+            .map(|matched| matched.as_str().to_string()))
+    }
+
+    /// 请求声明的捕获变量名（含从Cookie罐读取的变量）
+    pub fn names(request: &HttpRequestConfig) -> impl Iterator<Item = &str> {
+        request
+            .captures
+            .iter()
+            .flatten()
+            .map(|capture| capture.name.as_str())
+            .chain(
+                request
+                    .cookies
+                    .iter()
+                    .flat_map(|cookies| cookies.read.iter().flatten())
+                    .map(|(variable, _)| variable.as_str()),
+            )
+    }
+
+    /// 请求的URL、头部、body或要设置的Cookie是否引用了指定变量
+    pub fn references(request: &HttpRequestConfig, name: &str) -> bool {
+        std::iter::once(request.url.as_str())
+            .chain(
+                request
+                    .headers
+                    .iter()
+                    .flatten()
+                    .map(|(_, value)| value.as_str()),
+            )
+            .chain(request.body.as_deref())
+            .chain(
+                request
+                    .cookies
+                    .iter()
+                    .flat_map(|cookies| cookies.set.iter().flatten())
+                    .map(|(_, value)| value.as_str()),
+            )
+            .any(|text| template::placeholders(text).contains(&name))
+    }
+}
@@ -0,0 +1,156 @@
+use std::collections::{BTreeMap, VecDeque};
+use std::fmt;
+use std::sync::Mutex;
+use std::time::Duration;
+use tokio::time::{Instant, sleep};
+
+use crate::config::CircuitBreakerConfig;
+use crate::observer::RequestObserver;
+use crate::stats::RequestResult;
+
+// Import logger macros from crate root
+use crate::{log_info, log_warn};
+
+/// 熔断器状态
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CircuitState {
+    /// 正常发送
+    Closed,
+    /// 暂停发送
+    Open,
+    /// 试探性发送，成功则恢复，失败则重新熔断
+    HalfOpen,
+}
+
+impl fmt::Display for CircuitState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CircuitState::Closed => write!(f, "closed"),
+            CircuitState::Open => write!(f, "open"),
+            CircuitState::HalfOpen => write!(f, "half-open"),
+        }
+    }
+}
+
+struct BreakerState {
+    state: CircuitState,
+    failures: VecDeque<Instant>,
+    opened_at: Instant,
+    half_open_successes: usize,
+    transitions: BTreeMap<String, usize>,
+}
+
+/// 熔断器：窗口内失败次数达到阈值后暂停发送，冷却后以半开状态试探，成功后恢复
+pub struct CircuitBreaker {
+    failure_threshold: usize,
+    window: Duration,
+    open_duration: Duration,
+    required_successes: usize,
+    inner: Mutex<BreakerState>,
+}
+
+impl CircuitBreaker {
+    pub fn new(config: &CircuitBreakerConfig) -> Self {
+        Self {
+            failure_threshold: config.failure_threshold.max(1),
+            window: Duration::from_millis(config.window_ms),
+            open_duration: Duration::from_millis(config.open_duration_ms),
+            required_successes: config.half_open_successes.unwrap_or(1).max(1),
+            inner: Mutex::new(BreakerState {
+                state: CircuitState::Closed,
+                failures: VecDeque::new(),
+                opened_at: Instant::now(),
+                half_open_successes: 0,
+                transitions: BTreeMap::new(),
+            }),
+        }
+    }
+
+    /// 当前状态
+    pub fn state(&self) -> CircuitState {
+        self.inner.lock().unwrap().state
+    }
+
+    /// 各状态转换（如 "closed → open"）发生的次数
+    pub fn transitions(&self) -> BTreeMap<String, usize> {
+        self.inner.lock().unwrap().transitions.clone()
+    }
+
+    /// 熔断期间等待冷却结束，随后进入半开状态；未熔断时立即返回
+    pub async fn wait_until_allowed(&self) {
+        loop {
+            let remaining = {
+                let mut inner = self.inner.lock().unwrap();
+                if inner.state != CircuitState::Open {
+                    return;
+                }
+                let elapsed = inner.opened_at.elapsed();
+                if elapsed >= self.open_duration {
+                    self.transition(&mut inner, CircuitState::HalfOpen);
+                    return;
+                }
+                self.open_duration - elapsed
+            };
+            log_info!("⏸️  Circuit open, pausing for {}ms", remaining.as_millis());
+            sleep(remaining).await;
+        }
+    }
+
+    fn transition(&self, inner: &mut BreakerState, to: CircuitState) {
+        let from = inner.state;
+        inner.state = to;
+        *inner
+            .transitions
+            .entry(format!("{} → {}", from, to))
+            .or_default() += 1;
+        match to {
+            CircuitState::Open => {
+                inner.opened_at = Instant::now();
+                log_warn!(
+                    "🔌 Circuit {} → {}: pausing requests for {}ms",
+                    from,
+                    to,
+                    self.open_duration.as_millis()
+                );
+            }
+            CircuitState::HalfOpen => {
+                inner.half_open_successes = 0;
+                log_info!("🔌 Circuit {} → {}: probing target", from, to);
+            }
+            CircuitState::Closed => {
+                inner.failures.clear();
+                log_info!("🔌 Circuit {} → {}: target recovered", from, to);
+            }
+        }
+    }
+}
+
+impl RequestObserver for CircuitBreaker {
+    fn on_result(&self, result: &RequestResult) {
+        let mut inner = self.inner.lock().unwrap();
+        match (inner.state, result.success) {
+            (CircuitState::Closed, false) => {
+                let now = Instant::now();
+                inner.failures.push_back(now);
+                while inner
+                    .failures
+                    .front()
+                    .is_some_and(|failure| now.duration_since(*failure) > self.window)
+                {
+                    inner.failures.pop_front();
+                }
+                if inner.failures.len() >= self.failure_threshold {
+                    self.transition(&mut inner, CircuitState::Open);
+                }
+            }
+            (CircuitState::HalfOpen, false) => self.transition(&mut inner, CircuitState::Open),
+            (CircuitState::HalfOpen, true) => {
+                inner.half_open_successes += 1;
+                if inner.half_open_successes >= self.required_successes {
+                    self.transition(&mut inner, CircuitState::Closed);
+                }
+            }
+            _ => {}
+        }
+    }
+}
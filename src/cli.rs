@@ -0,0 +1,348 @@
+use clap::{Args, Parser, Subcommand};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+
+/// 命令行参数
+#[derive(Debug, Parser)]
+#[command(
+    name = "RemoteTask-rs",
+    version,
+    about = "Advanced Rust Concurrent HTTP Request Tool"
+)]
+pub struct Cli {
+    /// JSON配置文件路径（未指定时使用内置示例配置）
+    #[arg(short, long, global = true)]
+    pub config: Option<PathBuf>,
+
+    /// 选用配置中 `environments` 定义的环境变量集
+    #[arg(short, long, global = true)]
+    pub env: Option<String>,
+
+    /// 覆盖运行作用域的变量（可重复），如 `--var device_ip=10.0.0.5`，优先于配置和环境中的同名变量
+    #[arg(long = "var", value_name = "KEY=VALUE", global = true, value_parser = parse_var)]
+    pub vars: Vec<(String, String)>,
+
+    /// 只输出错误日志
+    #[arg(short, long, global = true)]
+    pub quiet: bool,
+
+    /// 最终统计的输出格式：text（日志行）或 json（stdout上的单个JSON文档，日志改为输出到stderr）
+    #[arg(long, global = true, default_value = "text", value_parser = ["text", "json"])]
+    pub output: String,
+
+    /// tokio工作线程数（覆盖配置中的 runtime.worker_threads，默认为CPU核数）
+    #[arg(long, global = true, value_parser = clap::value_parser!(u16).range(1..))]
+    pub worker_threads: Option<u16>,
+
+    /// 阻塞线程池上限（覆盖配置中的 runtime.max_blocking_threads，默认512）
+    #[arg(long, global = true, value_parser = clap::value_parser!(u16).range(1..))]
+    pub max_blocking_threads: Option<u16>,
+
+    /// 随机数种子（覆盖配置中的 seed），用报告中输出的种子重现一次运行的随机字段、延迟抖动和故障注入
+    #[arg(long, global = true)]
+    pub seed: Option<u64>,
+
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// 解析 `--var` 的 `key=value`（值中可以包含 `=`）
+fn parse_var(argument: &str) -> Result<(String, String), String> {
+    match argument.split_once('=') {
+        Some((key, value)) if !key.trim().is_empty() => {
+            Ok((key.trim().to_string(), value.to_string()))
+        }
+        _ => Err(format!("expected KEY=VALUE, got '{}'", argument)),
+    }
+}
+
+/// 子命令，缺省为 `run`
+#[derive(Debug, Clone, Subcommand)]
+pub enum Command {
+    /// 校验配置后运行A+B请求循环
+    Run(RunArgs),
+    /// 仅校验配置并报告所有问题
+    Validate,
+    /// 输出配置文件格式的JSON Schema
+    Schema,
+    /// 汇总SQLite结果库中的运行结果
+    Report(ReportArgs),
+    /// 对比两次运行（如固件v1与v2）各请求的延迟和错误率变化
+    Compare(CompareArgs),
+    /// 删除记录文件中由本工具创建、仍残留在设备上的任务
+    Cleanup(CleanupArgs),
+    /// 启动模拟AddTask/DeleteTask接口的本地服务器，无需实验室设备即可调试
+    MockServer(MockServerArgs),
+    /// 依次以参数的每个取值运行短场景，输出对比表
+    Sweep(SweepArgs),
+    /// 容量搜索：逐级或二分调整负载参数，找出thresholds和SLO仍成立的最高负载（拐点）
+    Capacity(CapacityArgs),
+    /// 交互模式：加载配置后按需渲染、发送单个请求，查看和修改变量
+    Repl,
+    /// 启动本地HTTP代理，将经由它发出的请求录制为场景配置文件
+    RecordProxy(RecordProxyArgs),
+    /// 分布式模式的worker：等待coordinator下发场景并运行
+    Worker(WorkerArgs),
+    /// 分布式模式的coordinator：将配置中的场景分发给多个worker并汇总统计
+    Coordinate(CoordinateArgs),
+    /// 守护模式：按配置中 `daemon.schedules` 的cron表达式定时运行场景
+    Daemon,
+    /// 批量运行：依次或并行运行配置中 `campaign.scenarios` 列出的场景，输出合并报告
+    Campaign(CampaignArgs),
+    /// 将守护模式注册为开机自动运行（Linux: systemd服务，Windows: 开机启动的计划任务，不是Windows服务）
+    Service(ServiceArgs),
+}
+
+/// `run` 子命令参数
+#[derive(Debug, Clone, Args)]
+pub struct RunArgs {
+    /// 监视配置文件，运行期间热更新延迟、max_requests和日志级别
+    #[arg(long)]
+    pub watch: bool,
+
+    /// 只渲染并打印请求（方法、URL、头部、body），不发送
+    #[arg(long)]
+    pub dry_run: bool,
+
+    /// 将每个响应保存到 `<DIR>/<cycle>/<label>.json`（缺省目录为 recordings）
+    #[arg(long, value_name = "DIR", num_args = 0..=1, default_missing_value = "recordings")]
+    pub record: Option<PathBuf>,
+
+    /// 用实际响应重写请求声明的快照文件
+    #[arg(long)]
+    pub update_snapshots: bool,
+
+    /// 运行结束后将结果写为JUnit XML报告
+    #[arg(long, value_name = "PATH")]
+    pub junit: Option<PathBuf>,
+
+    /// 运行结束后生成单文件HTML报告
+    #[arg(long, value_name = "PATH")]
+    pub html: Option<PathBuf>,
+
+    /// 将每个请求的结果写入SQLite结果库
+    #[arg(long, value_name = "PATH")]
+    pub db: Option<PathBuf>,
+
+    /// 不显示进度条（默认在配置了max_requests且stderr为终端时显示）
+    #[arg(long)]
+    pub no_progress: bool,
+
+    /// dry-run 渲染的周期数
+    #[arg(long, default_value_t = 1, requires = "dry_run")]
+    pub cycles: usize,
+}
+
+impl Default for RunArgs {
+    fn default() -> Self {
+        Self {
+            watch: false,
+            dry_run: false,
+            record: None,
+            update_snapshots: false,
+            junit: None,
+            html: None,
+            db: None,
+            no_progress: false,
+            cycles: 1,
+        }
+    }
+}
+
+/// `report` 子命令参数
+#[derive(Debug, Clone, Args)]
+pub struct ReportArgs {
+    /// SQLite结果库路径（由 `run --db` 生成）
+    #[arg(long, value_name = "PATH")]
+    pub db: PathBuf,
+
+    /// 按请求标签展开的运行ID，可重复指定（缺省为最近一次运行）
+    #[arg(long = "run", value_name = "ID")]
+    pub runs: Vec<i64>,
+}
+
+/// `compare` 子命令参数
+#[derive(Debug, Clone, Args)]
+pub struct CompareArgs {
+    /// 基线结果库
+    pub baseline: PathBuf,
+
+    /// 待比较的结果库（可与基线为同一文件）
+    pub candidate: PathBuf,
+
+    /// 基线运行ID（缺省为基线结果库中最近一次运行）
+    #[arg(long, value_name = "ID")]
+    pub baseline_run: Option<i64>,
+
+    /// 待比较运行ID（缺省为其结果库中最近一次运行）
+    #[arg(long, value_name = "ID")]
+    pub candidate_run: Option<i64>,
+}
+
+/// `cleanup` 子命令参数
+#[derive(Debug, Clone, Args)]
+pub struct CleanupArgs {
+    /// 任务记录文件（缺省使用配置中的 task_cleanup.ledger_file）
+    #[arg(long, value_name = "PATH")]
+    pub ledger: Option<PathBuf>,
+}
+
+/// `record-proxy` 子命令参数
+#[derive(Debug, Clone, Args)]
+pub struct RecordProxyArgs {
+    /// 代理监听地址
+    #[arg(long, default_value = "127.0.0.1:8888")]
+    pub bind: SocketAddr,
+
+    /// 只录制URL匹配该正则的请求（其余请求照常转发）
+    #[arg(long, value_name = "REGEX")]
+    pub include: Option<String>,
+
+    /// 场景配置文件的保存路径
+    #[arg(long, value_name = "PATH", default_value = "recorded_scenario.json")]
+    pub save: PathBuf,
+}
+
+/// `worker` 子命令参数
+#[derive(Debug, Clone, Args)]
+pub struct WorkerArgs {
+    /// 监听地址（默认只监听本机；监听其他地址时须设置 --token）
+    #[arg(long, default_value = "127.0.0.1:9400")]
+    pub bind: SocketAddr,
+
+    /// 共享口令，coordinator须使用相同的 --token
+    #[arg(long)]
+    pub token: Option<String>,
+}
+
+/// `coordinate` 子命令参数
+#[derive(Debug, Clone, Args)]
+pub struct CoordinateArgs {
+    /// worker地址（host:port），可重复指定
+    #[arg(long = "worker", value_name = "ADDR", required = true)]
+    pub workers: Vec<String>,
+
+    /// 共享口令，须与worker的 --token 一致
+    #[arg(long)]
+    pub token: Option<String>,
+}
+
+/// `service` 子命令参数
+#[derive(Debug, Clone, Args)]
+pub struct ServiceArgs {
+    /// install：注册并启动；uninstall：停止并注销；print：只打印将要注册的内容
+    #[arg(value_parser = ["install", "uninstall", "print"])]
+    pub action: String,
+
+    /// systemd服务或Windows计划任务的名称
+    #[arg(long, default_value = "remote-task")]
+    pub name: String,
+
+    /// systemd看门狗超时（秒），0表示不启用
+    #[arg(long, default_value_t = 60)]
+    pub watchdog_secs: u64,
+}
+
+/// `campaign` 子命令参数
+#[derive(Debug, Clone, Args)]
+pub struct CampaignArgs {
+    /// 将合并结果写为JUnit XML报告（每个场景一个套件）
+    #[arg(long, value_name = "PATH")]
+    pub junit: Option<PathBuf>,
+
+    /// 将合并报告（各场景的完整统计和阈值结果）写为JSON文件
+    #[arg(long, value_name = "PATH")]
+    pub report: Option<PathBuf>,
+
+    /// 不显示进度条（默认在顺序运行且stderr为终端时为每个场景显示）
+    #[arg(long)]
+    pub no_progress: bool,
+}
+
+/// `sweep` 子命令参数
+#[derive(Debug, Clone, Args)]
+pub struct SweepArgs {
+    /// 要扫描的配置参数（点分路径，如 variables.pollingTime、batch.size）
+    #[arg(long, value_name = "PATH")]
+    pub param: String,
+
+    /// 取值：逗号分隔的列表（1,2,4,8）或闭区间（1..60，可带步长 1..60:5）
+    #[arg(long, value_name = "VALUES", allow_hyphen_values = true)]
+    pub values: String,
+
+    /// 每个取值运行的周期数（缺省使用配置中的 max_requests）
+    #[arg(long)]
+    pub cycles: Option<usize>,
+}
+
+/// `capacity` 子命令参数
+#[derive(Debug, Clone, Args)]
+pub struct CapacityArgs {
+    /// 控制负载的配置参数（点分路径，如 batch.size、delay_between_a_requests_ms）
+    #[arg(long, value_name = "PATH")]
+    pub param: String,
+
+    /// 负载级别：逗号分隔的列表或闭区间（如 1..64），默认按取值从小到大表示负载从低到高
+    #[arg(long, value_name = "VALUES", allow_hyphen_values = true)]
+    pub values: String,
+
+    /// 取值越小负载越高（如 delay_between_a_requests_ms）
+    #[arg(long)]
+    pub descending: bool,
+
+    /// 搜索方式：binary（二分，假定负载越高越难满足SLO）或 step（逐级，遇到第一个不满足的负载即停止）
+    #[arg(long, default_value = "binary", value_parser = ["binary", "step"])]
+    pub search: String,
+
+    /// 每个负载级别运行的周期数（缺省使用配置中的 max_requests）
+    #[arg(long)]
+    pub cycles: Option<usize>,
+}
+
+/// `mock-server` 子命令参数
+#[derive(Debug, Clone, Args)]
+pub struct MockServerArgs {
+    /// 监听地址
+    #[arg(long, default_value = "127.0.0.1:8080")]
+    pub bind: SocketAddr,
+
+    /// 每个响应的固定延迟（毫秒）
+    #[arg(long, default_value_t = 0)]
+    pub latency_ms: u64,
+
+    /// 随机返回500错误的概率（0-1）
+    #[arg(long, default_value_t = 0.0)]
+    pub failure_rate: f64,
+
+    /// AddTask/DeleteTask返回成功但实际不生效的概率（0-1）
+    #[arg(long, default_value_t = 0.0)]
+    pub silent_failure_rate: f64,
+
+    /// 要求Digest认证的用户名（需同时指定 --password）
+    #[arg(long, requires = "password")]
+    pub username: Option<String>,
+
+    /// Digest认证密码
+    #[arg(long, requires = "username")]
+    pub password: Option<String>,
+
+    /// 模拟设备时钟相对本机时钟的偏差（毫秒，可为负），影响 /ISAPI/System/time 和响应的Date头
+    #[arg(long, default_value_t = 0, allow_hyphen_values = true)]
+    pub clock_skew_ms: i64,
+
+    /// 作为HTTP代理使用时要求的代理用户名（需同时指定 --proxy-password）
+    #[arg(long, requires = "proxy_password")]
+    pub proxy_username: Option<String>,
+
+    /// 代理密码
+    #[arg(long, requires = "proxy_username")]
+    pub proxy_password: Option<String>,
+
+    /// 代理认证使用Digest（默认Basic）
+    #[arg(long, requires = "proxy_username")]
+    pub proxy_digest: bool,
+
+    /// 同时存在的任务数上限，已满时AddTask返回错误（缺省不限）
+    #[arg(long)]
+    pub max_tasks: Option<usize>,
+}
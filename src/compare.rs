@@ -0,0 +1,167 @@
+use std::collections::BTreeMap;
+use std::time::Duration;
+
+use crate::stats::LabelStats;
+
+/// 进行显著性检验所需的最少样本数
+const MIN_SAMPLES: usize = 5;
+
+/// 双侧 p<0.05 对应的z值
+const Z_CRITICAL: f64 = 1.96;
+
+/// 单个请求标签+URL在两次运行之间的对比
+#[derive(Debug, Clone)]
+pub struct LabelComparison {
+    pub label: String,
+    pub url: String,
+    pub baseline: Option<LabelStats>,
+    pub candidate: Option<LabelStats>,
+}
+
+impl LabelComparison {
+    /// 错误率变化（百分点）
+    pub fn error_rate_delta(&self) -> Option<f64> {
+        Some(self.candidate.as_ref()?.error_rate() - self.baseline.as_ref()?.error_rate())
+    }
+
+    /// 延迟百分位变化（毫秒）
+    pub fn latency_delta_ms(&self, percentile: f64) -> Option<f64> {
+        let baseline = self.baseline.as_ref()?.latency_percentile(percentile)?;
+        let candidate = self.candidate.as_ref()?.latency_percentile(percentile)?;
+        Some(millis(candidate) - millis(baseline))
+    }
+
+    /// 延迟变化的显著性提示（Mann-Whitney U检验）
+    pub fn latency_significance(&self) -> Significance {
+        let (Some(baseline), Some(candidate)) = (&self.baseline, &self.candidate) else {
+            return Significance::InsufficientData;
+        };
+        if baseline.latencies.len() < MIN_SAMPLES || candidate.latencies.len() < MIN_SAMPLES {
+            return Significance::InsufficientData;
+        }
+        Significance::from_z(mann_whitney_z(
+            baseline.latencies.values(),
+            candidate.latencies.values(),
+        ))
+    }
+
+    /// 错误率变化的显著性提示（两比例z检验）
+    pub fn error_rate_significance(&self) -> Significance {
+        let (Some(baseline), Some(candidate)) = (&self.baseline, &self.candidate) else {
+            return Significance::InsufficientData;
+        };
+        if baseline.total_requests < MIN_SAMPLES || candidate.total_requests < MIN_SAMPLES {
+            return Significance::InsufficientData;
+        }
+        let (n1, n2) = (
+            baseline.total_requests as f64,
+            candidate.total_requests as f64,
+        );
+        let (p1, p2) = (
+            baseline.failed_requests as f64 / n1,
+            candidate.failed_requests as f64 / n2,
+        );
+        let pooled = (baseline.failed_requests + candidate.failed_requests) as f64 / (n1 + n2);
+        let standard_error = (pooled * (1.0 - pooled) * (1.0 / n1 + 1.0 / n2)).sqrt();
+        if standard_error == 0.0 {
+            return Significance::NotSignificant;
+        }
+        Significance::from_z((p2 - p1) / standard_error)
+    }
+}
+
+/// 显著性提示
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Significance {
+    /// 显著变差（p<0.05）
+    Regression,
+    /// 显著改善（p<0.05）
+    Improvement,
+    NotSignificant,
+    InsufficientData,
+}
+
+impl Significance {
+    /// z为正表示候选运行的数值更大（更慢或错误更多）
+    fn from_z(z: f64) -> Self {
+        if z > Z_CRITICAL {
+            Self::Regression
+        } else if z < -Z_CRITICAL {
+            Self::Improvement
+        } else {
+            Self::NotSignificant
+        }
+    }
+
+    pub fn hint(&self) -> &'static str {
+        match self {
+            Self::Regression => "regression (p<0.05)",
+            Self::Improvement => "improvement (p<0.05)",
+            Self::NotSignificant => "not significant",
+            Self::InsufficientData => "insufficient data",
+        }
+    }
+}
+
+/// 运行对比
+pub struct RunComparison;
+
+impl RunComparison {
+    /// 按 (请求标签, URL) 对齐两次运行的统计
+    pub fn compare(
+        baseline: &BTreeMap<(String, String), LabelStats>,
+        candidate: &BTreeMap<(String, String), LabelStats>,
+    ) -> Vec<LabelComparison> {
+        let mut keys: Vec<_> = baseline.keys().chain(candidate.keys()).collect();
+        keys.sort();
+        keys.dedup();
+        keys.into_iter()
+            .map(|key| LabelComparison {
+                label: key.0.clone(),
+                url: key.1.clone(),
+                baseline: baseline.get(key).cloned(),
+                candidate: candidate.get(key).cloned(),
+            })
+            .collect()
+    }
+}
+
+fn millis(duration: Duration) -> f64 {
+    duration.as_secs_f64() * 1000.0
+}
+
+/// Mann-Whitney U检验的z值（正态近似，相同值取平均秩）
+fn mann_whitney_z(baseline: &[Duration], candidate: &[Duration]) -> f64 {
+    let mut samples: Vec<(Duration, bool)> = baseline
+        .iter()
+        .map(|latency| (*latency, false))
+        .chain(candidate.iter().map(|latency| (*latency, true)))
+        .collect();
+    samples.sort_by_key(|(latency, _)| *latency);
+
+    // 计算候选运行样本的秩和
+    let mut candidate_rank_sum = 0.0;
+    let mut start = 0;
+    while start < samples.len() {
+        let mut end = start;
+        while end + 1 < samples.len() && samples[end + 1].0 == samples[start].0 {
+            end += 1;
+        }
+        let average_rank = (start + end) as f64 / 2.0 + 1.0;
+        for sample in &samples[start..=end] {
+            if sample.1 {
+                candidate_rank_sum += average_rank;
+            }
+        }
+        start = end + 1;
+    }
+
+    let (n1, n2) = (baseline.len() as f64, candidate.len() as f64);
+    let u = candidate_rank_sum - n2 * (n2 + 1.0) / 2.0;
+    let mean = n1 * n2 / 2.0;
+    let standard_deviation = (n1 * n2 * (n1 + n2 + 1.0) / 12.0).sqrt();
+    if standard_deviation == 0.0 {
+        return 0.0;
+    }
+    (u - mean) / standard_deviation
+}
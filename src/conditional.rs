@@ -0,0 +1,89 @@
+use reqwest::StatusCode;
+use reqwest::header::{ETAG, LAST_MODIFIED};
+use std::collections::HashMap;
+use std::sync::Mutex;
+
+use crate::config::HttpRequestConfig;
+use crate::http_client::ResponseData;
+
+/// 上次响应中的缓存校验值
+#[derive(Debug, Clone, Default)]
+struct Validators {
+    etag: Option<String>,
+    last_modified: Option<String>,
+}
+
+/// 条件请求：记住每个请求（标签+URL）上次响应的ETag/Last-Modified，
+/// 在之后的周期中发送 If-None-Match/If-Modified-Since，用于测试设备的缓存行为
+#[derive(Debug, Default)]
+pub struct ConditionalCache {
+    validators: Mutex<HashMap<(String, String), Validators>>,
+}
+
+impl ConditionalCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 启用条件请求的请求中，304视为成功
+    pub fn is_success(config: &HttpRequestConfig, status: StatusCode) -> bool {
+        status.is_success()
+            || (status == StatusCode::NOT_MODIFIED && config.conditional == Some(true))
+    }
+
+    /// 为请求添加条件头部（请求中已显式配置的头部不覆盖）
+    pub fn apply(&self, label: &str, config: &mut HttpRequestConfig) {
+        if config.conditional != Some(true) {
+            return;
+        }
+        let Some(validators) = self
+            .validators
+            .lock()
+            .unwrap()
+            .get(&(label.to_string(), config.url.clone()))
+            .cloned()
+        else {
+            return;
+        };
+
+        let headers = config.headers.get_or_insert_with(HashMap::new);
+        let has_header = |headers: &HashMap<String, String>, name: &str| {
+            headers.keys().any(|key| key.eq_ignore_ascii_case(name))
+        };
+        if let Some(etag) = validators.etag
+            && !has_header(headers, "If-None-Match")
+        {
+            headers.insert("If-None-Match".to_string(), etag);
+        }
+        if let Some(last_modified) = validators.last_modified
+            && !has_header(headers, "If-Modified-Since")
+        {
+            headers.insert("If-Modified-Since".to_string(), last_modified);
+        }
+    }
+
+    /// 记住成功响应中的校验值（304响应不含新的校验值时保留原值）
+    pub fn remember(&self, label: &str, config: &HttpRequestConfig, response: &ResponseData) {
+        if config.conditional != Some(true) || !response.status.is_success() {
+            return;
+        }
+        let header = |name| {
+            response
+                .headers
+                .get(name)
+                .and_then(|value| value.to_str().ok())
+                .map(str::to_string)
+        };
+        let validators = Validators {
+            etag: header(ETAG),
+            last_modified: header(LAST_MODIFIED),
+        };
+        let key = (label.to_string(), config.url.clone());
+        let mut cache = self.validators.lock().unwrap();
+        if validators.etag.is_none() && validators.last_modified.is_none() {
+            cache.remove(&key);
+        } else {
+            cache.insert(key, validators);
+        }
+    }
+}
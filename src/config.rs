@@ -1,35 +1,565 @@
+use anyhow::{Context, Result, anyhow};
+use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 use std::collections::HashMap;
+use std::path::Path;
+use std::time::Duration;
 
 /// HTTP 请求配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct HttpRequestConfig {
     pub method: String, // "GET" or "POST"
     pub url: String,
     pub headers: Option<HashMap<String, String>>,
-    pub body: Option<String>, // JSON string for POST requests
+    pub body: Option<String>,             // JSON string for POST requests
+    pub snapshot: Option<SnapshotConfig>, // 期望响应快照，不匹配时计为失败
+    pub captures: Option<Vec<CaptureConfig>>, // 从响应中提取变量，供后续请求的模板引用
+    pub conditional: Option<bool>, // 记住ETag/Last-Modified，之后的周期发送If-None-Match/If-Modified-Since，304计为成功
+    pub download: Option<DownloadConfig>, // 下载模式：成功响应的body流式写入文件，不在内存中缓存
+    pub ip_family: Option<String>, // 连接使用的地址族："auto"（默认，双栈时自动回退）、"ipv4" 或 "ipv6"
+    pub user_agent: Option<String>, // 该请求的User-Agent（优先于全局user_agent，headers中的User-Agent优先级最高）
+    pub expect_json: Option<ExpectJsonConfig>, // 期望的响应JSON，深度比较不一致时计为失败
+    pub expect_jq: Option<Vec<String>>, // 对响应JSON的jq断言，每个表达式的输出都必须为真（不为false或null），如 "[.TaskList[].taskID] | unique | length == 3"
+    pub expect_status: Option<ExpectStatusConfig>, // 状态码期望：哪些状态码计为成功、预期失败或失败（默认2xx为成功）
+    pub slo: Option<SloConfig>,                    // 响应时间SLO，如99%的请求在500ms内成功
+    pub tags: Option<Vec<String>>, // 统计分组标签，如 "write"、"cleanup"、"device:{{target.name}}"（支持 {{name}} 变量）
+    pub variables: Option<HashMap<String, String>>, // 步骤作用域变量，仅用于该请求，优先级最高（值中可引用其他作用域的变量）
+    pub cookies: Option<CookiesConfig>, // 操作共享Cookie罐：发送前设置或删除Cookie，响应后读取Cookie，断言Set-Cookie属性
+    pub pre_delay: Option<StepDelayConfig>, // 发送该步骤前的等待（思考时间）
+    pub post_delay: Option<StepDelayConfig>, // 该步骤完成后、后续步骤开始前的等待
+    #[serde(skip)]
+    pub url_template: Option<String>, // 渲染前的URL（运行时填写），按请求统计时以此分组
+}
+
+/// 步骤延迟：固定延迟，或以 `ms` 为基准按分布随机取值。请求A的post_delay或请求B的pre_delay
+/// 取代全局的 `delay_between_a_and_b_ms`
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct StepDelayConfig {
+    pub ms: u64, // 延迟（毫秒），配置distribution时为分布的基准值
+    pub distribution: Option<DelayDistribution>, // 随机分布（可选），同 delay_between_a_and_b_distribution
+}
+
+/// 请求步骤中的Cookie操作，Cookie罐由同一运行的全部请求共享
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CookiesConfig {
+    pub set: Option<HashMap<String, String>>, // 发送前写入的Cookie（名称→值，值支持 {{name}} 变量），作用于请求URL的主机，Path=/
+    pub delete: Option<Vec<String>>,          // 发送前删除的Cookie名称（Path=/ 的Cookie）
+    pub read: Option<HashMap<String, String>>, // 响应后从Cookie罐读取的Cookie（变量名→Cookie名），存为本周期的捕获变量，不存在时计为失败
+    pub expect: Option<Vec<ExpectCookieConfig>>, // 对响应Set-Cookie的断言，不满足时计为失败
+}
+
+/// 响应Set-Cookie断言：响应必须设置该Cookie，配置的属性必须一致
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ExpectCookieConfig {
+    pub name: String,              // Cookie名称
+    pub secure: Option<bool>,      // 是否带Secure属性
+    pub http_only: Option<bool>,   // 是否带HttpOnly属性
+    pub max_age: Option<i64>,      // Max-Age属性的值（秒）
+    pub same_site: Option<String>, // SameSite属性的值，如 "Strict"、"Lax"（不区分大小写）
+}
+
+/// 响应捕获配置：请求成功后从响应中提取值，存为 `{{name}}` 变量
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CaptureConfig {
+    pub name: String,            // 变量名
+    pub source: Option<String>, // 来源："json"（默认，按 path 取body字段）、"jq"（按 filter 过滤body）、"regex"（按 pattern 匹配body文本）、"header" 或 "cookie"
+    pub path: Option<String>,   // JSON路径，如 "$.taskID"、"$.TaskList[0].taskID"
+    pub filter: Option<String>, // jq过滤表达式（source为 "jq" 时），取第一个输出，如 ".TaskList | map(select(.enabled)) | length"
+    pub header: Option<String>, // 响应头名称（source为 "header" 时），如 "Location"、"Set-Cookie"、"ETag"
+    pub cookie: Option<String>, // Cookie名称（source为 "cookie" 时），取响应 Set-Cookie 中该Cookie的值，如 "csrftoken"
+    pub pattern: Option<String>, // 正则表达式，如 "taskId=(\\w+)"；source为 "header" 时可选，用于从头部值中截取
+    pub group: Option<usize>,    // 取第几个捕获组，默认有捕获组时为1，否则为整个匹配
+    pub scope: Option<String>, // "cycle"（默认，仅本周期内同一目标的请求可用）或 "run"（保留到整个运行结束）
+}
+
+/// 守护模式配置：常驻运行，按计划定时执行场景
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DaemonConfig {
+    pub schedules: Vec<ScheduleConfig>, // 定时计划
+    pub utc_offset: Option<String>,     // cron表达式使用的时区偏移，如 "+08:00"（默认 "Z"，即UTC）
+    pub status_bind: Option<String>, // 状态接口监听地址（可选），如 "127.0.0.1:9500"，GET /status 返回JSON
+    pub status_token: Option<String>, // 状态接口口令（可选），设置后请求须带 Authorization: Bearer <口令>，未设置时 POST /run 被禁用
+    pub keep_runs: Option<usize>,     // 每个计划保留的最近运行结果数（默认20）
+    pub keep_days: Option<u64>,       // 结果保留天数（可选），更早的结果文件和结果库中的运行被删除
+    pub results_dir: Option<String>, // 每次运行的统计保存目录（可选），写入 <dir>/<计划名>/<开始时间>.json
+    pub results_db: Option<String>, // SQLite结果库路径（可选），每次运行按计划名登记，可用 report 子命令查看
+    pub pid_file: Option<String>,   // PID文件路径（可选），同时作为单实例锁，已有实例运行时拒绝启动
+}
+
+/// tokio运行时调优（命令行参数优先），用于在高配压测机上提高请求吞吐
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct RuntimeConfig {
+    pub worker_threads: Option<usize>, // 工作线程数（默认为CPU核数）
+    pub max_blocking_threads: Option<usize>, // 阻塞线程池上限（默认512），用于文件写入、SQLite等阻塞操作
+}
+
+/// 单个定时计划
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ScheduleConfig {
+    pub name: String,                // 计划名称，用于日志、状态接口和结果目录
+    pub cron: String, // 五段式cron表达式（分 时 日 月 周），如 "*/15 * * * *"；也可用 "@hourly"、"@daily"
+    pub scenario: Option<String>, // 场景配置文件路径（可选，缺省使用守护进程自身的配置）
+    pub max_requests: Option<usize>, // 覆盖场景的max_requests（定时运行须有界）
+}
+
+/// 批量运行配置（`campaign` 子命令）：依次或并行运行多个场景，各自的停止条件，最后输出一份合并报告
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CampaignConfig {
+    pub name: Option<String>,                   // 报告中的名称，默认 "campaign"
+    pub mode: Option<String>, // "sequential"（默认，依次运行）或 "parallel"（同时运行，如设备机架上的不同设备）
+    pub stop_on_failure: Option<bool>, // 顺序模式下某个场景未通过时跳过其余场景，默认false
+    pub scenarios: Vec<CampaignScenarioConfig>, // 场景列表
+}
+
+/// 批量运行中的单个场景
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CampaignScenarioConfig {
+    pub name: Option<String>,     // 场景名称，用于日志和报告（默认取场景文件名）
+    pub scenario: Option<String>, // 场景配置文件（相对于campaign配置文件所在目录，缺省使用campaign配置本身）
+    pub env: Option<String>,      // 选用场景中 `environments` 的环境（缺省使用命令行的 --env）
+    pub variables: Option<HashMap<String, String>>, // 覆盖场景的运行作用域变量
+    pub max_requests: Option<usize>, // 覆盖场景的max_requests
+    pub max_duration_ms: Option<u64>, // 运行时长上限（毫秒），到时不再开始新周期
+    pub thresholds: Option<ThresholdsConfig>, // 覆盖场景的thresholds，决定该场景是否通过
+}
+
+/// 任务队列配置：其他系统向队列推送任务（占位符取值或请求定义），每个任务驱动一个A+B周期
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct JobQueueConfig {
+    pub backend: Option<String>, // 队列类型："redis"（默认，仅支持Redis列表）
+    pub url: String,             // 连接地址，如 "redis://:password@127.0.0.1:6379/0"
+    pub input_list: String,      // 任务列表（BLMOVE取出），如 "remote-task:jobs"
+    pub processing_list: Option<String>, // 处理中列表，取出的任务在结果发布后才删除（缺省为 "<input_list>:processing"，每个消费者应使用各自的列表）
+    pub result_list: Option<String>,     // 结果列表（RPUSH写入，可选），每个周期一条JSON
+    pub idle_timeout_ms: Option<u64>,    // 超过该时长没有新任务时结束运行（可选，缺省一直等待）
+    pub variables: Option<Vec<String>>,  // 任务提供的变量名，用于校验请求中的占位符
+}
+
+/// 带宽限制配置，模拟慢速网络下设备的表现
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct BandwidthConfig {
+    pub upload_bytes_per_second: Option<u64>, // 请求body上传速率上限（字节/秒）
+    pub download_bytes_per_second: Option<u64>, // 响应body下载速率上限（字节/秒）
+    pub scope: Option<String>, // "request"（默认，每个请求单独限速）或 "global"（所有并发请求共享限速）
+}
+
+/// 响应body缓冲上限，防止异常接口返回超大响应导致长时间压测内存耗尽
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ResponseLimitConfig {
+    pub max_bytes: u64, // 每个响应在内存中缓冲的最大字节数，超出部分截断并标记
+    pub spill_dir: Option<String>, // 超出上限时将完整body流式写入该目录（可选，缺省为截断后停止读取）
+    pub keep_spill_files: Option<usize>, // 本次运行最多保留的溢出文件数（默认100），超出时删除最早的文件
+}
+
+/// 下载模式配置，用于测试固件下载、图片获取等大响应接口
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct DownloadConfig {
+    pub path: String,           // 保存路径，支持 {{name}} 变量，父目录不存在时自动创建
+    pub sha256: Option<String>, // 期望的SHA-256（十六进制），不一致时计为失败
+    pub progress_interval_ms: Option<u64>, // 进度日志间隔，默认1000，0为不输出
+    pub max_resumes: Option<usize>, // 传输中断时用Range请求续传的最大次数，默认0（不续传）
+}
+
+/// 响应快照配置
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SnapshotConfig {
+    pub file: String,                      // 快照文件路径
+    pub ignore_paths: Option<Vec<String>>, // 比较时忽略的字段路径
+}
+
+/// 期望响应JSON断言配置
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct ExpectJsonConfig {
+    pub expected: serde_json::Value,          // 期望的JSON文档
+    pub ignore_paths: Option<Vec<String>>, // 比较时忽略的字段路径，如 "$.requestTime"、"$.TaskList[*].updateTime"
+    pub type_only_paths: Option<Vec<String>>, // 只比较类型的路径（含子字段），数组按第一个期望元素比较每个实际元素
+}
+
+/// 状态码期望配置：每项为 "409"、"4xx" 或 "400-404"，同时匹配时 failure 优先于 expected_failure 优先于 success
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ExpectStatusConfig {
+    pub success: Option<Vec<String>>, // 计为成功的状态码（未配置时为2xx），如 ["2xx", "404"]
+    pub expected_failure: Option<Vec<String>>, // 预期失败的状态码（如重复创建的409），计为成功并单独统计
+    pub failure: Option<Vec<String>>, // 始终计为失败的状态码，用于从 success 的范围中排除，如 "204"
+}
+
+/// 分支配置：请求A完成后按顺序匹配，第一个匹配的分支代替默认的请求B
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct BranchConfig {
+    pub name: Option<String>, // 分支名，也是分支请求的统计标签（默认 "branch<序号>"）
+    pub status: Option<Vec<String>>, // 匹配请求A的状态码，如 ["401"]、["5xx"]，"error" 匹配未收到响应（连接错误、超时）
+    pub variable: Option<String>,    // 匹配请求A捕获的变量
+    pub equals: Option<String>,      // 变量的期望值（未配置时只要求变量已捕获）
+    pub wait_ms: Option<u64>,        // 执行分支前等待（毫秒）
+    pub request: Option<HttpRequestConfig>, // 分支请求（可选），如重新登录
+    pub then: Option<String>, // 之后的动作："end"（默认，结束本周期，不发送B）、"b"（发送请求B）或 "retry"（重发请求A并重新匹配分支）
+    pub max_retries: Option<usize>, // then为 "retry" 时本周期最多重发请求A的次数，默认3，用完后发送请求B
+}
+
+/// 重复步骤配置：每个周期在请求A完成后重复发送一个请求，达到 `count` 次或满足 `until` 条件后再进入请求B
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct RepeatConfig {
+    pub name: Option<String>,              // 统计标签，默认 "repeat"
+    pub request: HttpRequestConfig, // 重复发送的请求，可用 {{iteration}} 引用本次的序号（从1开始）
+    pub count: usize,               // 最多发送次数
+    pub interval_ms: Option<u64>,   // 相邻两次发送之间的间隔（毫秒），默认0
+    pub until_status: Option<Vec<String>>, // 响应状态码匹配时提前结束，如 ["200"]，"error" 匹配未收到响应
+    pub until_variable: Option<String>, // 捕获的变量满足条件时提前结束（可由本请求的captures捕获）
+    pub until_equals: Option<String>,   // until_variable的期望值（未配置时只要求变量已捕获）
+}
+
+/// 设备时钟同步：从设备取得当前时间并缓存与本机时钟的偏差，时间相关的生成字段按设备时间生成，
+/// 避免实验室机器时钟漂移导致认证或时间校验失败
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct DeviceTimeConfig {
+    pub url: String, // 取设备时间的地址，如 "http://{{host}}/ISAPI/System/time"，可使用运行作用域的变量
+    pub source: Option<String>, // "date_header"（默认，响应的Date头，精确到秒）或 "body"（响应body中的RFC 3339时间字段）
+    pub field: Option<String>,  // source为body时的字段名（JSON键或XML元素），默认 "localTime"
+    pub refresh_ms: Option<u64>, // 运行期间重新同步的间隔（毫秒），默认只在运行开始时同步一次
+}
+
+/// 认证失效自动恢复：会话或nonce在运行中途过期导致请求收到401时，清除认证缓存、执行登录步骤，
+/// 然后重发失败的请求一次（只重发一次，仍为401时按正常结果计入统计）
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct AuthRecoveryConfig {
+    pub login: Option<HttpRequestConfig>, // 登录请求（可选），会话Cookie由客户端保存，captures提取的变量（如token）在重发前重新渲染到请求中；未配置时使用csrf.login，都未配置时只重新进行Digest认证
+}
+
+/// CSRF令牌：运行开始时发送一次登录请求，从响应中提取令牌，之后自动加到修改状态的请求（默认POST、PUT、PATCH、DELETE）头部，
+/// 用于Web界面背后的设备接口
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CsrfConfig {
+    pub login: HttpRequestConfig, // 登录请求（或获取登录页面的请求），会话Cookie由客户端保存
+    pub token: CaptureConfig, // 从登录响应中提取令牌：source为 "cookie"、"header" 或 "json"（body字段），总是运行作用域，请求中也可用 {{name}} 引用
+    pub header: Option<String>, // 注入令牌的请求头，默认 "X-CSRF-Token"，请求中已显式配置时不覆盖
+    pub methods: Option<Vec<String>>, // 注入令牌的请求方法，默认 ["POST", "PUT", "PATCH", "DELETE"]
+}
+
+/// 心跳（保活）请求：主循环运行期间在后台按固定间隔发送，如ISAPI sessionHeartbeat保持登录会话
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct HeartbeatConfig {
+    pub url: String,            // 心跳地址，可使用运行作用域的变量（如 {{host}}）
+    pub method: Option<String>, // 请求方法（默认 "PUT"，与ISAPI sessionHeartbeat一致）
+    pub headers: Option<HashMap<String, String>>, // 请求头部（可选）
+    pub body: Option<String>,   // 请求body（可选）
+    pub interval_ms: u64,       // 发送间隔（毫秒），首个心跳在运行开始一个间隔后发送
+    pub timeout_ms: Option<u64>, // 单个心跳的超时（毫秒），默认与间隔相同（最多30秒）
+}
+
+/// 长时间运行时的自身资源监测：定期采样本工具的内存、文件描述符和tokio任务数，持续增长时告警
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct SelfMonitorConfig {
+    pub interval_ms: Option<u64>, // 采样间隔（毫秒），默认60000，每次采样输出一行区间报告
+    pub growth_samples: Option<usize>, // 连续多少次采样持续增长时告警，默认5
+}
+
+/// 运行时间窗：只在允许的时段内开始新周期，时段外暂停，进入下一个时段后继续。
+/// 结构与设备载荷中的 `TimeList` 相同（也接受 `TimeList`、`timeRange`、`startTime`、`endTime` 写法）
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TimeWindowsConfig {
+    #[serde(alias = "TimeList")]
+    pub time_list: Vec<TimeListEntry>, // 每天的允许时段
+    pub utc_offset: Option<String>, // 时段使用的时区偏移，如 "+08:00"（默认使用 daemon.utc_offset，都未配置时为UTC）
+}
+
+/// 一天（或一组日期）的允许时段
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TimeListEntry {
+    pub day: String, // "monday" … "sunday"，或 "weekdays"、"weekends"、"everyday"
+    #[serde(alias = "timeRange")]
+    pub time_range: Vec<TimeRangeConfig>, // 当天的时段列表
+}
+
+/// 时段：`HH:MM` 或 `HH:MM:SS`，不含结束时刻（"23:59:59" 表示到当天结束）；结束早于开始时跨越午夜，延续到次日
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TimeRangeConfig {
+    #[serde(alias = "startTime")]
+    pub start_time: String,
+    #[serde(alias = "endTime")]
+    pub end_time: String,
+}
+
+/// 响应时间SLO配置：`target_percent` 的请求需在 `latency_ms` 内成功，其余为违规预算
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SloConfig {
+    pub latency_ms: u64,                 // 延迟目标（毫秒），超过或失败计为违规
+    pub target_percent: f64,             // 达标比例（百分比），如 99.0
+    pub min_requests: Option<usize>,     // 判断预算耗尽前至少完成的请求数，默认10
+    pub stop_on_exhausted: Option<bool>, // 违规预算耗尽时停止运行，默认false
 }
 
 /// Digest 认证配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct DigestAuthConfig {
     pub username: String,
-    pub password: String,
-    pub realm: Option<String>,
-    pub nonce: Option<String>,
+    #[serde(default)]
+    pub password: String, // 为空时启动时交互式输入
+    pub realm: Option<String>, // 已知的认证域，抢先Digest认证时与nonce一起用于计算首个请求的Authorization
+    pub nonce: Option<String>, // 已知的服务器nonce（可选）
+    pub auth_type: Option<String>, // 认证方式："digest"（默认）或 "basic"
+    pub preemptive: Option<bool>, // 抢先认证：首个请求即携带Authorization，不先发送探测请求，被401拒绝时按挑战重发（默认false）
+}
+
+impl DigestAuthConfig {
+    pub fn uses_basic(&self) -> bool {
+        self.auth_type
+            .as_deref()
+            .is_some_and(|auth_type| auth_type.eq_ignore_ascii_case("basic"))
+    }
+
+    /// 配置中预先已知的Digest挑战参数 (realm, nonce)
+    pub fn known_challenge(&self) -> Option<(String, String)> {
+        self.realm.clone().zip(self.nonce.clone())
+    }
+}
+
+/// HTTP代理配置：代理凭据与目标的digest_auth相互独立
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ProxyConfig {
+    pub url: String, // 代理地址，如 "http://10.0.0.1:3128"（支持 {{name}} 变量）
+    pub username: Option<String>, // 代理用户名（可选，支持 {{name}} 变量和 secret:<name>）
+    pub password: Option<String>, // 代理密码
+    pub auth_type: Option<String>, // 代理认证方式："basic"（默认）或 "digest"（仅适用于http://目标，HTTPS隧道只支持Basic）
+}
+
+impl ProxyConfig {
+    pub fn uses_digest(&self) -> bool {
+        self.auth_type
+            .as_deref()
+            .is_some_and(|auth_type| auth_type.eq_ignore_ascii_case("digest"))
+    }
 }
 
 /// 动态生成字段配置
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
 pub struct GeneratedField {
-    pub name: String,          // 字段名
-    pub generator: String,     // 生成器类型："random", "timestamp", "counter", "uuid"
-    pub field_type: String,    // 字段类型："header" 或 "body"
-    pub value: Option<String>, // 生成的值（可选，用于固定值）
+    pub name: String,            // 字段名
+    pub generator: String, // 生成器类型："random", "timestamp", "counter", "uuid", "ulid", "nanoid", "fixed", "padding"
+    pub field_type: String, // 字段类型："header" 或 "body"
+    pub value: Option<String>, // 生成的值（可选，用于固定值；padding为目标body大小，如 "64KB"；nanoid为长度，默认21）
+    pub version: Option<String>, // uuid版本："v4"（默认，随机）或 "v7"（按时间排序）
+    pub format: Option<String>, // uuid格式："hyphenated"（默认，36位）、"simple"（32位无连字符）、"upper" 或 "upper_simple"；timestamp格式："unix_ms"（毫秒）、"unix"（秒）或 "rfc3339"（设备时区，未知时为UTC），默认 "timestamp_<周期>_<毫秒>"
+    pub filters: Option<String>, // 对生成值依次应用的模板过滤器（可选），如 "sha256 | base64"
+    pub unique: Option<String>, // 运行内去重（可选）："regenerate"（与已生成的值重复时重新生成）或 "error"（重复时停止运行）
+}
+
+/// age加密凭据文件配置
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct SecretsConfig {
+    pub file: String,     // age加密的JSON凭据文件路径
+    pub identity: String, // 用于解密的age身份文件路径
+}
+
+/// 跨周期响应漂移检测配置
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct DriftDetectionConfig {
+    pub labels: Option<Vec<String>>, // 参与比较的请求标签，缺省为 ["A"]
+    pub ignore_paths: Option<Vec<String>>, // 忽略的易变字段路径，如 "$.requestTime"、"$.items[*].id"
+}
+
+/// CI阈值配置，任一阈值未满足时以非零状态码退出
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ThresholdsConfig {
+    pub max_error_rate: Option<f64>, // 最大错误率（百分比，0-100）
+    pub max_p95_ms: Option<u64>,     // 所有请求的最大p95延迟（毫秒）
+}
+
+/// 指标导出配置
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct MetricsConfig {
+    pub protocol: String, // 导出协议："influx"（InfluxDB行协议，HTTP写入）或 "statsd"（UDP）
+    pub address: String, // influx为写入URL，如 "http://localhost:8086/write?db=remote_task"；statsd为 "host:port"
+    pub flush_interval_ms: Option<u64>, // 推送间隔，默认1000ms
+    pub prefix: Option<String>, // influx measurement名 / statsd指标前缀，默认 "remote_task"
+    pub tags: Option<HashMap<String, String>>, // 附加到每条influx指标的标签
+}
+
+/// OpenTelemetry追踪配置
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TracingConfig {
+    pub otlp_endpoint: String, // OTLP/HTTP追踪接收地址，如 "http://localhost:4318/v1/traces"
+    pub service_name: Option<String>, // 上报的服务名，默认 "remote-task"
+}
+
+/// Webhook通知配置（消息体兼容Slack incoming webhook）
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct NotificationConfig {
+    pub webhook_url: String,
+    pub error_rate_threshold: Option<f64>, // 错误率（百分比）超过该值时通知
+    pub min_requests: Option<usize>,       // 计算错误率前至少完成的请求数，默认10
+    pub on_complete: Option<bool>,         // 运行结束时通知，默认true
+    pub on_stop: Option<bool>,             // 因停止条件提前结束时通知，默认true
+}
+
+/// 延迟随机分布配置，以对应的固定延迟为基准值
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct DelayDistribution {
+    pub distribution: String,   // 分布类型："uniform"、"normal" 或 "exponential"
+    pub jitter_ms: Option<u64>, // uniform：在 基准值 ± jitter_ms 内均匀取值
+    pub stddev_ms: Option<u64>, // normal：以基准值为均值的标准差
+    pub min_ms: Option<u64>,    // 取值下限（可选）
+    pub max_ms: Option<u64>,    // 取值上限（可选）
+}
+
+/// 熔断配置
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct CircuitBreakerConfig {
+    pub failure_threshold: usize, // 窗口内失败次数达到该值时熔断（open）
+    pub window_ms: u64,           // 统计失败次数的滑动窗口（毫秒）
+    pub open_duration_ms: u64,    // 熔断后暂停发送的时长，之后进入半开（half-open）试探
+    pub half_open_successes: Option<usize>, // 半开状态下连续成功多少个请求后恢复（closed），默认1
+}
+
+/// 自适应节奏配置：窗口内错误率或p95延迟超过阈值时放慢周期节奏，恢复后逐步加快，用于找出设备可持续的吞吐量
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct AdaptivePacingConfig {
+    pub max_error_rate: Option<f64>, // 窗口内错误率（百分比，0-100）超过该值时放慢
+    pub max_p95_ms: Option<u64>,     // 窗口内p95延迟（毫秒）超过该值时放慢
+    pub window_ms: Option<u64>,      // 评估窗口，默认5000ms
+    pub min_requests: Option<usize>, // 窗口内至少完成的请求数，不足时延长窗口，默认10
+    pub min_interval_ms: Option<u64>, // 请求A间隔的下限（最快节奏），默认为 delay_between_a_requests_ms
+    pub max_interval_ms: Option<u64>, // 请求A间隔的上限（最慢节奏），默认60000
+    pub backoff_factor: Option<f64>,  // 超过阈值时间隔乘以该系数，默认2.0
+    pub recovery_factor: Option<f64>, // 恢复正常时间隔乘以该系数，默认0.8
+}
+
+/// 按主机限速配置（令牌桶）
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct RateLimitConfig {
+    pub requests_per_second: f64, // 每个主机每秒最多请求数
+    pub burst: Option<u32>,       // 允许的突发请求数，默认1
+}
+
+/// 客户端故障注入配置，各概率取值 0-1
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct FaultInjectionConfig {
+    pub delay_probability: Option<f64>,    // 发送前注入延迟的概率
+    pub delay_ms: Option<u64>,             // 注入延迟的上限（在 0..=delay_ms 内随机）
+    pub abort_probability: Option<f64>,    // 请求发出后中途中止的概率
+    pub abort_after_ms: Option<u64>, // 中止时机的上限（在 0..=abort_after_ms 内随机），默认100
+    pub truncate_probability: Option<f64>, // 发送前截断请求body的概率
+}
+
+/// 遗留任务清理配置：记录请求A创建的任务，运行结束或执行 `cleanup` 时删除设备上残留的任务
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TaskCleanupConfig {
+    pub ledger_file: Option<String>, // 已创建任务的记录文件，默认 "remote_task_tasks.json"
+    pub task_id_field: Option<String>, // 请求body中任务ID的字段名，默认 "taskID"
+    pub on_shutdown: Option<bool>,   // 运行结束（含Ctrl+C）时自动清理，默认true
+}
+
+/// 数据预置配置：主循环前批量创建前置对象（如注册N个摄像头），提取的对象ID组成变量池，运行结束后逐个删除
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct ProvisioningConfig {
+    pub count: usize,                        // 创建的对象数
+    pub setup: HttpRequestConfig, // 创建请求，{{index}} 为对象序号（从1开始），captures 中名为 pool 的捕获为对象ID
+    pub pool: String, // 池变量名：周期中 {{<pool>}} 按周期轮流取池中的对象，另有 {{<pool>.<序号>}}、{{<pool>.count}}、{{<pool>.all}}（逗号分隔）
+    pub teardown: Option<HttpRequestConfig>, // 删除请求（可选），运行结束时每个对象发送一次，{{<pool>}} 为该对象的ID，{{index}} 为序号
+}
+
+/// 任务存在性校验配置：请求A/B成功后查询设备任务列表，确认任务确实已创建/删除
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct TaskVerificationConfig {
+    pub task_id_field: Option<String>, // 请求body中任务ID的字段名，默认 "taskID"
+    pub after_create: Option<bool>,    // 请求A成功后确认任务存在，默认true
+    pub after_delete: Option<bool>,    // 请求B成功后确认任务已消失，默认true
+    pub settle_ms: Option<u64>,        // 查询任务列表前的等待时间，默认0
+}
+
+/// RTSP视频流可达性预检配置，每个周期发送请求A前检查
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct RtspPrecheckConfig {
+    pub urls: Option<Vec<String>>, // 要检查的流地址，缺省为渲染后请求A body中出现的 rtsp:// 地址
+    pub method: Option<String>,    // "OPTIONS"（默认）或 "DESCRIBE"（同时校验流路径和凭据）
+    pub timeout_ms: Option<u64>,   // 单个流的超时，默认3000
+    pub on_failure: Option<String>, // "abort"（默认，停止运行）或 "skip_cycle"（跳过本周期）
+    pub interval_ms: Option<u64>,  // 同一流的重新检查间隔，默认30000，期间沿用上次结果
+}
+
+/// 运行开始前的端口探测：确认设备的辅助端口（如8000、554、6011）已开放
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct PortProbesConfig {
+    pub probes: Vec<PortProbeConfig>,
+    pub on_failure: Option<String>, // "abort"（默认，不发送请求直接结束运行）或 "warn"（仅告警）
+}
+
+/// 单个TCP/UDP端口探测
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct PortProbeConfig {
+    pub protocol: Option<String>, // "tcp"（默认，能建立连接即开放）或 "udp"
+    pub host: String,             // 主机，支持 {{name}} 变量
+    pub port: u16,
+    pub payload: Option<String>, // 连接后发送的数据（UDP缺省发送空数据报）
+    pub expect: Option<String>,  // 期望响应中包含的数据，未收到时探测失败
+    pub encoding: Option<String>, // payload/expect的编码："text"（默认）或 "hex"（如 "48 49" 或 "4849"）
+    pub timeout_ms: Option<u64>,  // 超时，默认3000
+}
+
+/// 批量创建后批量删除：每个周期发送 `size` 个请求A（各自重新生成字段），
+/// 收集其中的任务ID渲染到请求B的 `{{task_id_list}}` 占位符
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct BatchConfig {
+    pub size: usize,                   // 每个周期的请求A数量
+    pub task_id_field: Option<String>, // 请求A body中的任务ID字段，默认 "taskID"
+    pub list_variable: Option<String>, // 请求B中的列表占位符名，默认 "task_id_list"
+}
+
+/// 每个周期的关联ID：自动加到请求A和B的头部，并出现在日志和结果记录中，便于与设备日志对应
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct CorrelationIdConfig {
+    pub header: Option<String>, // 头部名，默认 "X-Request-ID"
+    pub prefix: Option<String>, // ID前缀（可选），如 "rt-"
+}
+
+/// 幂等键配置：按运行和周期生成幂等键头部，失败时按策略重试，用于验证目标API的重试语义
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct IdempotencyKeyConfig {
+    pub header: Option<String>,        // 头部名，默认 "Idempotency-Key"
+    pub prefix: Option<String>, // 键前缀（可选，支持 {{name}} 变量），如 "rt-"；跨运行需区分时可引用变量
+    pub retries: Option<usize>, // 连接错误、中止或5xx时的重试次数，默认0
+    pub retry_key: Option<String>, // 重试时的键："same"（默认，沿用原键）或 "new"（附加重试序号生成新键）
+    pub retry_backoff_ms: Option<u64>, // 首次重试前的等待（毫秒，默认100），之后每次重试翻倍，最长10秒
+}
+
+impl IdempotencyKeyConfig {
+    pub fn header_name(&self) -> &str {
+        self.header.as_deref().unwrap_or("Idempotency-Key")
+    }
+
+    /// 第 `attempt` 次重试（从1开始）前的等待时间：指数退避，最长10秒
+    pub fn retry_backoff(&self, attempt: usize) -> Duration {
+        let base = Duration::from_millis(self.retry_backoff_ms.unwrap_or(100));
+        let factor = 1u32 << attempt.saturating_sub(1).min(16);
+        base.saturating_mul(factor).min(Duration::from_secs(10))
+    }
+}
+
+/// User-Agent配置：固定值或按周期轮换的列表，用于模拟不同的客户端
+#[derive(Debug, Clone, Default, Serialize, Deserialize, JsonSchema)]
+pub struct UserAgentConfig {
+    pub value: Option<String>, // 固定的User-Agent，替代默认的 "RemoteTask-HTTP-Client/1.0"
+    pub rotation: Option<Vec<String>>, // 轮换列表，每个周期选一个（优先于value）
+    pub mode: Option<String>,  // 轮换方式："round_robin"（默认，按周期依次使用）或 "random"
+}
+
+/// 目标设备配置
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
+pub struct TargetConfig {
+    pub name: Option<String>,                  // 目标名称（可选，用于统计展示）
+    pub base_url: String,                      // 替换 {{target.base_url}} 占位符
+    pub digest_auth: Option<DigestAuthConfig>, // 目标专属凭据（可选，缺省使用全局digest_auth）
+    pub unix_socket: Option<String>, // 通过Unix域套接字连接（可选，缺省使用全局unix_socket），URL中的主机仅用作Host头
+}
+
+impl TargetConfig {
+    /// 统计和日志中使用的目标标识
+    pub fn display_name(&self) -> &str {
+        self.name.as_deref().unwrap_or(&self.base_url)
+    }
 }
 
 /// 主配置结构
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize, JsonSchema)]
 pub struct RequestConfig {
     pub request_a: HttpRequestConfig,
     pub request_b: HttpRequestConfig,
@@ -38,6 +568,122 @@ pub struct RequestConfig {
     pub max_requests: Option<usize>,
     pub digest_auth: Option<DigestAuthConfig>,
     pub generated_fields: Option<Vec<GeneratedField>>,
+    pub targets: Option<Vec<TargetConfig>>,
+    pub target_mode: Option<String>, // 多目标执行方式："round_robin"（默认）或 "concurrent"
+    pub variables: Option<HashMap<String, String>>, // 运行作用域的模板变量，替换 {{name}} 占位符
+    pub worker_variables: Option<HashMap<String, String>>, // worker作用域变量，覆盖 variables（分布式模式下注入 worker_index）
+    pub environments: Option<HashMap<String, HashMap<String, String>>>, // 命名环境，覆盖 variables
+    pub secrets: Option<SecretsConfig>,                    // 凭据文件，供 secret:<name> 引用
+    pub log_level: Option<String>, // 日志级别："error"、"warn"、"info"、"debug"、"trace"
+    pub drift_detection: Option<DriftDetectionConfig>, // 跨周期响应漂移检测
+    pub thresholds: Option<ThresholdsConfig>, // CI阈值，未满足时以非零状态码退出
+    pub metrics: Option<MetricsConfig>, // 指标导出（InfluxDB/statsd）
+    pub tracing: Option<TracingConfig>, // OpenTelemetry追踪导出
+    pub notifications: Option<NotificationConfig>, // Webhook通知
+    pub warmup_cycles: Option<usize>, // 预热周期数，预热期间的请求正常发送但不计入统计
+    pub warmup_duration_ms: Option<u64>, // 预热时长（毫秒），与warmup_cycles同时配置时两者都满足后结束预热
+    pub delay_between_a_and_b_distribution: Option<DelayDistribution>, // A→B延迟的随机分布（可选，缺省为固定延迟）
+    pub delay_between_a_requests_distribution: Option<DelayDistribution>, // A→A延迟的随机分布（可选，缺省为固定延迟）
+    pub circuit_breaker: Option<CircuitBreakerConfig>, // 熔断：目标连续失败时暂停发送
+    pub rate_limit: Option<RateLimitConfig>,           // 按主机限速
+    pub adaptive_pacing: Option<AdaptivePacingConfig>, // 自适应节奏：按错误率和p95延迟自动调整请求A间隔
+    pub fault_injection: Option<FaultInjectionConfig>, // 客户端故障注入（混沌模式）
+    pub task_cleanup: Option<TaskCleanupConfig>,       // 遗留任务清理（AddTask/DeleteTask场景）
+    pub task_verification: Option<TaskVerificationConfig>, // 请求成功后在设备任务列表中校验任务
+    pub rtsp_precheck: Option<RtspPrecheckConfig>,     // 发送请求A前检查RTSP视频流是否可达
+    pub batch: Option<BatchConfig>, // 批量模式：每个周期发送K个请求A，再用一个请求B批量删除
+    pub default_headers: Option<HashMap<String, String>>, // 所有请求共用的默认头部，请求中的同名头部优先
+    pub correlation_id: Option<CorrelationIdConfig>, // 每个周期的关联ID，加到请求A和B的头部，可用 {{correlation_id}} 引用
+    pub user_agent: Option<UserAgentConfig>,         // 全局User-Agent：固定值或按周期轮换的列表
+    pub port_probes: Option<PortProbesConfig>,       // 运行开始前的TCP/UDP端口探测
+    pub unix_socket: Option<String>, // 通过Unix域套接字连接本地服务（可选），URL中的主机仅用作Host头
+    pub bandwidth: Option<BandwidthConfig>, // 上传/下载带宽限制（模拟慢速网络）
+    pub job_queue: Option<JobQueueConfig>, // 由外部任务队列驱动周期：每个周期取一个任务，结果推送回队列
+    pub daemon: Option<DaemonConfig>,      // 守护模式：按cron表达式定时运行场景（`daemon` 子命令）
+    pub campaign: Option<CampaignConfig>, // 批量运行：依次或并行运行多个场景并输出合并报告（`campaign` 子命令）
+    pub shutdown_grace_ms: Option<u64>, // 中断时等待进行中请求完成的宽限期（毫秒），超时后取消这些请求，默认5000
+    pub runtime: Option<RuntimeConfig>, // tokio运行时调优（工作线程数、阻塞线程池）
+    pub send_mode: Option<String>, // A和B的发送方式："sequential"（默认，A之后按延迟发送B）或 "barrier"（本周期的全部请求同时放行，复现设备任务调度的竞态）
+    pub response_limit: Option<ResponseLimitConfig>, // 响应body缓冲上限，超出时截断或写入磁盘
+    pub idempotency_key: Option<IdempotencyKeyConfig>, // 按周期生成的幂等键头部和失败重试策略
+    pub json_templates: Option<HashMap<String, serde_json::Value>>, // 内联JSON子模板，body中用 {{ name | json_escape }} 嵌入为转义字符串
+    pub strict_json: Option<bool>, // 发送前将渲染后的body按JSON解析，不合法时不发送并计为失败，默认false
+    pub branches: Option<Vec<BranchConfig>>, // 按请求A的状态码或捕获的变量选择后续请求，未匹配任何分支时发送请求B
+    pub repeat: Option<RepeatConfig>, // 请求A完成后、请求B之前重复发送的步骤（如心跳），直到满足条件或达到次数
+    pub time_windows: Option<TimeWindowsConfig>, // 运行时间窗：只在允许的时段内开始新周期（如工作日20:00–06:00），时段外暂停
+    pub self_monitor: Option<SelfMonitorConfig>, // 自身资源监测：定期采样RSS、打开的文件描述符和tokio任务数，单调增长时告警
+    pub seed: Option<u64>, // 随机数种子，用于重现运行（未配置时随机选取并输出到报告）
+    pub provisioning: Option<ProvisioningConfig>, // 数据预置：主循环前创建前置对象组成变量池，运行结束后删除
+    pub heartbeat: Option<HeartbeatConfig>, // 心跳请求：运行期间在后台定期发送（如保持登录会话），结果单独统计
+    pub device_time: Option<DeviceTimeConfig>, // 设备时钟同步：时间相关的生成字段按设备时间而非本机时间生成
+    pub proxy: Option<ProxyConfig>, // 经HTTP代理连接目标（unix_socket连接不经过代理），代理的连接和认证失败单独统计
+    pub auth_recovery: Option<AuthRecoveryConfig>, // 认证失效自动恢复：运行中途收到401时重新登录并重发该请求一次
+    pub csrf: Option<CsrfConfig>, // CSRF令牌：运行开始时登录并提取令牌，之后自动加到修改状态的请求头部
+}
+
+impl RequestConfig {
+    /// 从JSON文件加载配置，字符串值中的 `${VAR}` 会替换为环境变量
+    pub fn load(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        let content = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read config file {}", path.display()))?;
+        let mut value: serde_json::Value = serde_json::from_str(&content)
+            .with_context(|| format!("Failed to parse config file {}", path.display()))?;
+
+        let mut missing = Vec::new();
+        interpolate_env_vars(&mut value, "$", &mut missing);
+        if !missing.is_empty() {
+            return Err(anyhow!(
+                "Missing environment variables in {}:\n  {}",
+                path.display(),
+                missing.join("\n  ")
+            ));
+        }
+
+        serde_json::from_value(value)
+            .with_context(|| format!("Invalid config file {}", path.display()))
+    }
+
+    /// 将指定环境的变量合并到 `variables` 中，同名变量以环境为准
+    pub fn apply_environment(&mut self, name: &str) -> Result<()> {
+        let overrides = self
+            .environments
+            .as_ref()
+            .and_then(|environments| environments.get(name))
+            .cloned()
+            .ok_or_else(|| {
+                let mut available: Vec<_> = self
+                    .environments
+                    .iter()
+                    .flat_map(|environments| environments.keys().cloned())
+                    .collect();
+                available.sort();
+                anyhow!(
+                    "Unknown environment '{}' (available: {})",
+                    name,
+                    if available.is_empty() {
+                        "none".to_string()
+                    } else {
+                        available.join(", ")
+                    }
+                )
+            })?;
+
+        self.variables
+            .get_or_insert_with(HashMap::new)
+            .extend(overrides);
+        Ok(())
+    }
+
+    /// 应用命令行的 `--var key=value` 覆盖：写入运行作用域，优先于配置和环境中的同名变量
+    pub fn apply_overrides(&mut self, overrides: &[(String, String)]) {
+        if overrides.is_empty() {
+            return;
+        }
+        self.variables
+            .get_or_insert_with(HashMap::new)
+            .extend(overrides.iter().cloned());
+    }
 }
 
 impl Default for RequestConfig {
@@ -48,18 +694,182 @@ impl Default for RequestConfig {
                 url: "https://httpbin.org/get".to_string(),
                 headers: None,
                 body: None,
+                ..Default::default()
             },
             request_b: HttpRequestConfig {
                 method: "GET".to_string(),
                 url: "https://httpbin.org/get".to_string(),
                 headers: None,
                 body: None,
+                ..Default::default()
             },
             delay_between_a_and_b_ms: 100,
             delay_between_a_requests_ms: 1000,
             max_requests: None,
             digest_auth: None,
             generated_fields: None,
+            targets: None,
+            target_mode: None,
+            variables: None,
+            worker_variables: None,
+            environments: None,
+            secrets: None,
+            log_level: None,
+            drift_detection: None,
+            thresholds: None,
+            metrics: None,
+            tracing: None,
+            notifications: None,
+            warmup_cycles: None,
+            warmup_duration_ms: None,
+            delay_between_a_and_b_distribution: None,
+            delay_between_a_requests_distribution: None,
+            circuit_breaker: None,
+            rate_limit: None,
+            adaptive_pacing: None,
+            fault_injection: None,
+            task_cleanup: None,
+            task_verification: None,
+            rtsp_precheck: None,
+            batch: None,
+            default_headers: None,
+            correlation_id: None,
+            user_agent: None,
+            port_probes: None,
+            unix_socket: None,
+            bandwidth: None,
+            job_queue: None,
+            daemon: None,
+            campaign: None,
+            shutdown_grace_ms: None,
+            runtime: None,
+            send_mode: None,
+            response_limit: None,
+            idempotency_key: None,
+            json_templates: None,
+            strict_json: None,
+            branches: None,
+            repeat: None,
+            time_windows: None,
+            self_monitor: None,
+            seed: None,
+            provisioning: None,
+            heartbeat: None,
+            device_time: None,
+            proxy: None,
+            auth_recovery: None,
+            csrf: None,
+        }
+    }
+}
+
+/// 运行期间可热更新的参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct LiveSettings {
+    pub delay_between_a_and_b_ms: u64,
+    pub delay_between_a_requests_ms: u64,
+    pub max_requests: Option<usize>,
+    pub log_level: Option<String>,
+}
+
+impl LiveSettings {
+    /// 从配置中提取可热更新的参数
+    pub fn from_config(config: &RequestConfig) -> Self {
+        Self {
+            delay_between_a_and_b_ms: config.delay_between_a_and_b_ms,
+            delay_between_a_requests_ms: config.delay_between_a_requests_ms,
+            max_requests: config.max_requests,
+            log_level: config.log_level.clone(),
         }
     }
+
+    /// 列出与新参数相比发生变化的项，格式为 "name: old → new"
+    pub fn changes(&self, new: &LiveSettings) -> Vec<String> {
+        let mut changes = Vec::new();
+        if self.delay_between_a_and_b_ms != new.delay_between_a_and_b_ms {
+            changes.push(format!(
+                "delay_between_a_and_b_ms: {} → {}",
+                self.delay_between_a_and_b_ms, new.delay_between_a_and_b_ms
+            ));
+        }
+        if self.delay_between_a_requests_ms != new.delay_between_a_requests_ms {
+            changes.push(format!(
+                "delay_between_a_requests_ms: {} → {}",
+                self.delay_between_a_requests_ms, new.delay_between_a_requests_ms
+            ));
+        }
+        if self.max_requests != new.max_requests {
+            changes.push(format!(
+                "max_requests: {:?} → {:?}",
+                self.max_requests, new.max_requests
+            ));
+        }
+        if self.log_level != new.log_level {
+            changes.push(format!(
+                "log_level: {:?} → {:?}",
+                self.log_level, new.log_level
+            ));
+        }
+        changes
+    }
+}
+
+/// 递归替换JSON中所有字符串值的 `${VAR}` 引用，缺失的变量以 "路径: 变量名" 形式收集到 `missing`
+fn interpolate_env_vars(value: &mut serde_json::Value, path: &str, missing: &mut Vec<String>) {
+    match value {
+        serde_json::Value::String(text) if text.contains("${") => {
+            *text = interpolate_str(text, path, missing);
+        }
+        serde_json::Value::Array(items) => {
+            for (index, item) in items.iter_mut().enumerate() {
+                interpolate_env_vars(item, &format!("{}[{}]", path, index), missing);
+            }
+        }
+        serde_json::Value::Object(map) => {
+            for (key, item) in map.iter_mut() {
+                interpolate_env_vars(item, &format!("{}.{}", path, key), missing);
+            }
+        }
+        _ => {}
+    }
+}
+
+/// 替换单个字符串中的 `${VAR}` 和 `${VAR:-default}`，`$${` 表示字面量 `${`
+fn interpolate_str(text: &str, path: &str, missing: &mut Vec<String>) -> String {
+    let mut output = String::with_capacity(text.len());
+    let mut rest = text;
+
+    while let Some(start) = rest.find("${") {
+        // $${ 转义为字面量 ${
+        if start > 0 && rest.as_bytes()[start - 1] == b'$' {
+            output.push_str(&rest[..start - 1]);
+            output.push_str("${");
+            rest = &rest[start + 2..];
+            continue;
+        }
+
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find('}') else {
+            output.push_str(&rest[start..]);
+            return output;
+        };
+
+        let expr = &after[..end];
+        let (name, default) = match expr.split_once(":-") {
+            Some((name, default)) => (name, Some(default)),
+            None => (expr, None),
+        };
+        match (std::env::var(name), default) {
+            (Ok(value), _) => output.push_str(&value),
+            (Err(_), Some(default)) => output.push_str(default),
+            (Err(_), None) => {
+                missing.push(format!("{}: ${{{}}}", path, name));
+            }
+        }
+        rest = &after[end + 1..];
+    }
+
+    output.push_str(rest);
+    output
 }
@@ -0,0 +1,119 @@
+use anyhow::Result;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+use tokio::sync::watch;
+use tokio::time::sleep;
+
+use crate::config::{LiveSettings, RequestConfig};
+use crate::logger::{LogLevel, set_log_level};
+use crate::validation::{ConfigValidator, Severity};
+
+// Import logger macros from crate root
+use crate::{log_info, log_warn};
+
+/// 配置文件监视器，用于运行期间热更新非结构性参数
+pub struct ConfigWatcher;
+
+impl ConfigWatcher {
+    /// 定期检查配置文件的修改时间，变化时重新加载并发布新的运行参数
+    ///
+    /// 仅延迟、max_requests和日志级别会生效；请求、认证等结构性修改会被忽略并提示需要重启。
+    /// 新配置未通过校验时整体忽略；删除 `log_level` 时恢复为Info。
+    /// `cli_log_level` 为命令行指定的级别（如 --quiet），设置时优先于配置中的 `log_level`，重新加载也不会改变。
+    pub fn spawn(
+        path: PathBuf,
+        environment: Option<String>,
+        interval: Duration,
+        cli_log_level: Option<LogLevel>,
+    ) -> Result<watch::Receiver<LiveSettings>> {
+        let mut last_modified = Self::modified_time(&path);
+        let mut current = Self::load(&path, environment.as_deref())?;
+        let (sender, receiver) = watch::channel(LiveSettings::from_config(&current));
+
+        tokio::spawn(async move {
+            loop {
+                sleep(interval).await;
+                if sender.is_closed() {
+                    break;
+                }
+
+                let modified = Self::modified_time(&path);
+                if modified == last_modified {
+                    continue;
+                }
+                last_modified = modified;
+
+                let new = match Self::load(&path, environment.as_deref()) {
+                    Ok(new) => new,
+                    Err(e) => {
+                        log_warn!("Config reload failed, keeping current settings: {:#}", e);
+                        continue;
+                    }
+                };
+                let errors: Vec<String> = ConfigValidator::validate(&new)
+                    .into_iter()
+                    .filter(|diagnostic| diagnostic.severity == Severity::Error)
+                    .map(|diagnostic| diagnostic.to_string())
+                    .collect();
+                if !errors.is_empty() {
+                    log_warn!(
+                        "Config reload rejected, keeping current settings: {}",
+                        errors.join("; ")
+                    );
+                    continue;
+                }
+
+                if Self::structural_part(&current) != Self::structural_part(&new) {
+                    log_warn!(
+                        "Config file {} has structural changes that require a restart; only live settings are applied",
+                        path.display()
+                    );
+                }
+
+                let old_settings = LiveSettings::from_config(&current);
+                let new_settings = LiveSettings::from_config(&new);
+                let changes = old_settings.changes(&new_settings);
+                if !changes.is_empty() {
+                    log_info!("🔄 Config reloaded: {}", changes.join(", "));
+                    if old_settings.log_level != new_settings.log_level && cli_log_level.is_none() {
+                        set_log_level(
+                            new_settings
+                                .log_level
+                                .as_deref()
+                                .and_then(LogLevel::parse)
+                                .unwrap_or(LogLevel::Info),
+                        );
+                    }
+                    let _ = sender.send(new_settings);
+                }
+                current = new;
+            }
+        });
+
+        Ok(receiver)
+    }
+
+    fn load(path: &Path, environment: Option<&str>) -> Result<RequestConfig> {
+        let mut config = RequestConfig::load(path)?;
+        if let Some(environment) = environment {
+            config.apply_environment(environment)?;
+        }
+        Ok(config)
+    }
+
+    fn modified_time(path: &Path) -> Option<SystemTime> {
+        std::fs::metadata(path)
+            .and_then(|meta| meta.modified())
+            .ok()
+    }
+
+    /// 去掉可热更新字段后的配置，用于检测结构性修改
+    fn structural_part(config: &RequestConfig) -> serde_json::Value {
+        let mut config = config.clone();
+        config.delay_between_a_and_b_ms = 0;
+        config.delay_between_a_requests_ms = 0;
+        config.max_requests = None;
+        config.log_level = None;
+        serde_json::to_value(config).unwrap_or_default()
+    }
+}
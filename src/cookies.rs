@@ -0,0 +1,133 @@
+use anyhow::Result;
+use std::collections::HashMap;
+
+use crate::capture::VariableStore;
+use crate::config::{CookiesConfig, ExpectCookieConfig};
+use crate::http_client::{HttpClient, ResponseData};
+
+// Import logger macros from crate root
+use crate::log_debug;
+
+/// 请求步骤中的Cookie操作：发送前设置、删除，响应后读取和断言Set-Cookie属性
+pub struct CookieSteps;
+
+impl CookieSteps {
+    /// 发送前按配置删除和设置Cookie（先删除后设置）
+    pub fn prepare(config: &CookiesConfig, http_client: &HttpClient, url: &str) -> Result<()> {
+        for name in config.delete.iter().flatten() {
+            http_client.delete_cookie(url, name)?;
+            log_debug!("🍪 Deleted cookie {}", name);
+        }
+        for (name, value) in config.set.iter().flatten() {
+            http_client.set_cookie(url, name, value)?;
+            log_debug!("🍪 Set cookie {} = {}", name, value);
+        }
+        Ok(())
+    }
+
+    /// 从Cookie罐读取Cookie存为本周期的捕获变量，返回第一个失败的原因
+    pub fn read(
+        read: &HashMap<String, String>,
+        http_client: &HttpClient,
+        url: &str,
+        cycle: &VariableStore,
+    ) -> Option<String> {
+        let mut failure = None;
+        for (variable, name) in read {
+            match http_client.cookie(url, name) {
+                Ok(Some(value)) => {
+                    log_debug!("📌 Read cookie {} into {} = {}", name, variable, value);
+                    cycle.set(variable, value);
+                }
+                Ok(None) => {
+                    failure.get_or_insert_with(|| format!("cookie {} not in the cookie jar", name));
+                }
+                Err(e) => {
+                    failure.get_or_insert_with(|| format!("cookie {}: {}", name, e));
+                }
+            }
+        }
+        failure
+    }
+
+    /// 检查响应的Set-Cookie是否满足断言，返回第一个不满足的原因
+    pub fn check(expect: &[ExpectCookieConfig], response: &ResponseData) -> Option<String> {
+        expect.iter().find_map(|expected| {
+            let Some(attributes) = Self::set_cookie(response, &expected.name) else {
+                return Some(format!("response did not set cookie {}", expected.name));
+            };
+            let attribute = |name: &str| {
+                attributes
+                    .iter()
+                    .find(|(attribute, _)| attribute.eq_ignore_ascii_case(name))
+                    .map(|(_, value)| value.as_str())
+            };
+            let mismatch = |attribute: &str, expected_value: String, actual: Option<&str>| {
+                Some(format!(
+                    "cookie {}: expected {} {}, got {}",
+                    expected.name,
+                    attribute,
+                    expected_value,
+                    actual.unwrap_or("none")
+                ))
+            };
+            if let Some(secure) = expected.secure
+                && attribute("Secure").is_some() != secure
+            {
+                return Some(Self::flag_mismatch(&expected.name, "Secure", secure));
+            }
+            if let Some(http_only) = expected.http_only
+                && attribute("HttpOnly").is_some() != http_only
+            {
+                return Some(Self::flag_mismatch(&expected.name, "HttpOnly", http_only));
+            }
+            if let Some(max_age) = expected.max_age {
+                let actual = attribute("Max-Age");
+                if actual.and_then(|value| value.parse::<i64>().ok()) != Some(max_age) {
+                    return mismatch("Max-Age", max_age.to_string(), actual);
+                }
+            }
+            if let Some(same_site) = &expected.same_site {
+                let actual = attribute("SameSite");
+                if !actual.is_some_and(|value| value.eq_ignore_ascii_case(same_site)) {
+                    return mismatch("SameSite", same_site.clone(), actual);
+                }
+            }
+            None
+        })
+    }
+
+    fn flag_mismatch(cookie: &str, flag: &str, expected: bool) -> String {
+        if expected {
+            format!("cookie {} is missing the {} attribute", cookie, flag)
+        } else {
+            format!("cookie {} has an unexpected {} attribute", cookie, flag)
+        }
+    }
+
+    /// 响应中设置指定Cookie的Set-Cookie的属性（名称→值，无值属性如Secure的值为空），有多个时取最后一个
+    fn set_cookie(response: &ResponseData, name: &str) -> Option<Vec<(String, String)>> {
+        response
+            .headers
+            .get_all("Set-Cookie")
+            .iter()
+            .filter_map(|value| value.to_str().ok())
+            .rfind(|value| {
+                value
+                    .split(';')
+                    .next()
+                    .and_then(|pair| pair.split_once('='))
+                    .is_some_and(|(cookie, _)| cookie.trim() == name)
+            })
+            .map(|value| {
+                value
+                    .split(';')
+                    .skip(1)
+                    .map(|attribute| match attribute.split_once('=') {
+                        Some((name, value)) => (name.trim().to_string(), value.trim().to_string()),
+                        None => (attribute.trim().to_string(), String::new()),
+                    })
+                    .collect()
+            })
+    }
+}
@@ -0,0 +1,104 @@
+use anyhow::{Result, anyhow, bail};
+use chrono::{DateTime, FixedOffset, NaiveDateTime, SecondsFormat};
+use croner::Cron;
+
+/// 五段式cron表达式（分 时 日 月 周），由 `croner` 解析：支持 `*`、`a-b`、`*/n`、`a-b/n`、逗号列表，
+/// 以及 `@hourly`、`@daily`（`@midnight`）、`@weekly`、`@monthly`、`@yearly` 简写。周几取 0-7（0和7都表示周日）。
+///
+/// 日和周同时受限（都不是 `*`）时满足其一即可，与标准cron一致。
+#[derive(Debug, Clone)]
+pub struct CronSchedule {
+    cron: Cron,
+}
+
+impl CronSchedule {
+    pub fn parse(expression: &str) -> Result<Self> {
+        let expression = match expression.trim() {
+            "@midnight" => "@daily",
+            expression => expression,
+        };
+        let fields = expression.split_whitespace().count();
+        if !expression.starts_with('@') && fields != 5 {
+            bail!(
+                "expected 5 fields (minute hour day month weekday), got {}",
+                fields
+            );
+        }
+        let cron = Cron::new(expression)
+            .parse()
+            .map_err(|e| anyhow!("invalid cron expression '{}': {}", expression, e))?;
+        Ok(Self { cron })
+    }
+
+    /// `after`（Unix秒，按 `offset_secs` 换算为本地时间）之后的下一个触发时刻（Unix秒）
+    pub fn next_after(&self, after: i64, offset_secs: i64) -> Option<i64> {
+        let after = local_time(after, offset_secs)?;
+        self.cron
+            .find_next_occurrence(&after, false)
+            .ok()
+            .map(|next| next.timestamp())
+    }
+}
+
+/// Unix秒在 `offset_secs` 时区偏移下的本地时间
+pub(crate) fn local_time(unix_secs: i64, offset_secs: i64) -> Option<DateTime<FixedOffset>> {
+    let offset = FixedOffset::east_opt(i32::try_from(offset_secs).ok()?)?;
+    DateTime::from_timestamp(unix_secs, 0).map(|time| time.with_timezone(&offset))
+}
+
+/// 解析UTC偏移，如 "+08:00"、"-05:30"、"Z"，返回秒数
+pub fn parse_utc_offset(offset: &str) -> Result<i64> {
+    if offset == "Z" {
+        return Ok(0);
+    }
+    let (sign, rest) = match offset.split_at_checked(1) {
+        Some(("+", rest)) => (1, rest),
+        Some(("-", rest)) => (-1, rest),
+        _ => bail!("invalid UTC offset '{}' (expected e.g. +08:00)", offset),
+    };
+    let (hours, minutes) = rest.split_once(':').unwrap_or((rest, "0"));
+    let (Ok(hours), Ok(minutes)) = (hours.parse::<i64>(), minutes.parse::<i64>()) else {
+        bail!("invalid UTC offset '{}' (expected e.g. +08:00)", offset);
+    };
+    if hours > 14 || minutes > 59 {
+        bail!("UTC offset '{}' is out of range", offset);
+    }
+    Ok(sign * (hours * 3600 + minutes * 60))
+}
+
+/// 将Unix秒格式化为带偏移的RFC 3339时间，如 `2026-10-16T14:00:00+08:00`
+pub fn format_timestamp(unix_secs: i64, offset_secs: i64) -> String {
+    local_time(unix_secs, offset_secs).map_or_else(
+        || unix_secs.to_string(),
+        |time| time.to_rfc3339_opts(SecondsFormat::Secs, true),
+    )
+}
+
+/// 解析RFC 3339时间（如设备返回的 `2026-10-16T21:07:30+08:00`，秒可带小数，缺少偏移时按UTC），
+/// 返回 (Unix毫秒, UTC偏移秒数)
+pub fn parse_timestamp(text: &str) -> Result<(i64, i64)> {
+    let text = text.trim();
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok((
+            time.timestamp_millis(),
+            i64::from(time.offset().local_minus_utc()),
+        ));
+    }
+    ["%Y-%m-%dT%H:%M:%S%.f", "%Y-%m-%d %H:%M:%S%.f"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(text, format).ok())
+        .map(|time| (time.and_utc().timestamp_millis(), 0))
+        .ok_or_else(|| {
+            anyhow!(
+                "invalid timestamp '{}' (expected e.g. 2026-10-16T21:07:30+08:00)",
+                text
+            )
+        })
+}
+
+/// 解析HTTP `Date` 头（IMF-fixdate，如 `Fri, 16 Oct 2026 13:07:30 GMT`），返回Unix秒
+pub fn parse_http_date(text: &str) -> Result<i64> {
+    DateTime::parse_from_rfc2822(text.trim())
+        .map(|time| time.timestamp())
+        .map_err(|_| anyhow!("invalid HTTP date '{}'", text))
+}
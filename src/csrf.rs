@@ -0,0 +1,59 @@
+use std::collections::HashMap;
+
+use crate::capture::VariableStore;
+use crate::config::{CsrfConfig, HttpRequestConfig};
+
+/// 默认注入令牌的请求头
+const DEFAULT_HEADER: &str = "X-CSRF-Token";
+
+/// 默认注入令牌的请求方法（修改状态的方法）
+const DEFAULT_METHODS: [&str; 4] = ["POST", "PUT", "PATCH", "DELETE"];
+
+/// CSRF令牌：令牌由登录请求捕获到运行作用域的变量中，发送修改状态的请求前加到头部
+pub struct CsrfSession {
+    header: String,
+    methods: Vec<String>,
+    token_name: String,
+}
+
+impl CsrfSession {
+    pub fn new(config: &CsrfConfig) -> Self {
+        Self {
+            header: config
+                .header
+                .clone()
+                .unwrap_or_else(|| DEFAULT_HEADER.to_string()),
+            methods: match &config.methods {
+                Some(methods) => methods.iter().map(|method| method.to_uppercase()).collect(),
+                None => DEFAULT_METHODS.map(str::to_string).to_vec(),
+            },
+            token_name: config.token.name.clone(),
+        }
+    }
+
+    /// 登录请求：在配置的captures之后追加令牌捕获（总是运行作用域）
+    pub fn login_request(config: &CsrfConfig) -> HttpRequestConfig {
+        let mut login = config.login.clone();
+        let mut token = config.token.clone();
+        token.scope = Some("run".to_string());
+        login.captures.get_or_insert_with(Vec::new).push(token);
+        login
+    }
+
+    /// 修改状态的请求加上令牌头部（请求中已显式配置该头部或尚未取得令牌时不修改）
+    pub fn apply(&self, request: &mut HttpRequestConfig, run: &VariableStore) {
+        if !self.methods.contains(&request.method.to_uppercase()) {
+            return;
+        }
+        let Some(token) = run.get(&self.token_name) else {
+            return;
+        };
+        let headers = request.headers.get_or_insert_with(HashMap::new);
+        if !headers
+            .keys()
+            .any(|name| name.eq_ignore_ascii_case(&self.header))
+        {
+            headers.insert(self.header.clone(), token);
+        }
+    }
+}
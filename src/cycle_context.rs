@@ -0,0 +1,217 @@
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::sync::{Arc, OnceLock};
+use std::time::{Duration, Instant};
+use tokio::sync::Barrier;
+
+use crate::capture::VariableStore;
+use crate::config::{RequestConfig, TargetConfig};
+use crate::provisioning::Provisioner;
+use crate::template;
+
+/// 一个周期内单个目标的上下文：周期序号、生成字段、变量、捕获的变量和目标信息
+///
+/// 渲染请求、发送、校验响应和回调观察者都从这里取周期信息，
+/// 新增上下文来源只需在这里加字段，不必修改沿途各函数的签名。
+///
+/// 模板变量分四个作用域，同名时窄作用域优先：步骤（请求的 `variables`）> 周期（生成字段、
+/// 目标变量、关联ID、预置对象池）> worker（`worker_variables`）> 运行（`variables`、环境和 `--var`）。
+#[derive(Debug)]
+pub struct CycleContext {
+    /// 周期序号（从1开始）
+    pub cycle: usize,
+    /// 预热周期的结果不计入最终统计
+    pub warmup: bool,
+    /// 目标名称（未配置targets时为None）
+    pub target: Option<String>,
+    /// 本周期的关联ID（未配置correlation_id时为None）
+    pub correlation_id: Option<String>,
+    /// 生成的body字段：请求A的每个副本各一份（非批量模式只有一份），请求B使用第一份。
+    /// 没有基础body时由这些字段组成JSON body
+    pub generated_fields: Vec<HashMap<String, String>>,
+    /// 生成的header字段，份数与 `generated_fields` 相同；和body字段一样属于周期作用域
+    pub header_fields: Vec<HashMap<String, String>>,
+    /// 运行、worker和周期作用域合并后的变量（不含生成字段）：配置变量（已合并选定环境）、
+    /// worker变量、目标变量和关联ID
+    pub variables: HashMap<String, String>,
+    /// 周期作用域的捕获变量
+    pub captures: VariableStore,
+    /// 屏障模式下本周期全部请求共用的放行屏障
+    pub barrier: Option<Arc<SendBarrier>>,
+    /// JSON子模板（字符串中的占位符在渲染请求时替换）
+    pub json_templates: Vec<(String, Value)>,
+}
+
+impl CycleContext {
+    pub fn new(
+        config: &RequestConfig,
+        cycle: usize,
+        target: Option<&TargetConfig>,
+        fields: Vec<(HashMap<String, String>, HashMap<String, String>)>,
+        correlation_id: Option<String>,
+    ) -> Self {
+        let (mut header_fields, mut generated_fields): (Vec<_>, Vec<_>) =
+            fields.into_iter().unzip();
+        if generated_fields.is_empty() {
+            header_fields.push(HashMap::new());
+            generated_fields.push(HashMap::new());
+        }
+        let mut variables = config.variables.clone().unwrap_or_default();
+        if let Some(worker_variables) = &config.worker_variables {
+            resolve_worker_variables(worker_variables, &mut variables);
+        }
+        if let Some(target) = target {
+            variables.insert("target.base_url".to_string(), target.base_url.clone());
+            variables.insert("target.name".to_string(), target.display_name().to_string());
+        }
+        if let Some(correlation_id) = &correlation_id {
+            variables.insert("correlation_id".to_string(), correlation_id.clone());
+        }
+        if let Some(provisioning) = &config.provisioning {
+            Provisioner::select(&provisioning.pool, cycle, &mut variables);
+        }
+        Self {
+            cycle,
+            warmup: false,
+            target: target.map(|target| target.display_name().to_string()),
+            correlation_id,
+            generated_fields,
+            header_fields,
+            variables,
+            captures: VariableStore::new(),
+            barrier: None,
+            json_templates: config
+                .json_templates
+                .iter()
+                .flatten()
+                .map(|(name, value)| (name.clone(), value.clone()))
+                .collect(),
+        }
+    }
+
+    /// 请求A第 `copy` 个副本的生成字段（超出范围时取第一份）
+    pub fn fields(&self, copy: usize) -> &HashMap<String, String> {
+        self.generated_fields
+            .get(copy)
+            .unwrap_or(&self.generated_fields[0])
+    }
+
+    /// 渲染请求A第 `copy` 个副本（或请求B等其他步骤，`copy` 为0）使用的全部变量：
+    /// 本周期变量，叠加该副本的生成字段，再叠加步骤变量（值先按前面的作用域渲染）
+    pub fn scoped_variables(
+        &self,
+        copy: usize,
+        step: Option<&HashMap<String, String>>,
+    ) -> HashMap<String, String> {
+        let mut variables = self.variables.clone();
+        variables.extend(
+            self.header_fields
+                .get(copy)
+                .unwrap_or(&self.header_fields[0])
+                .clone(),
+        );
+        variables.extend(self.fields(copy).clone());
+        if let Some(step) = step {
+            let rendered: Vec<_> = step
+                .iter()
+                .map(|(name, value)| (name.clone(), template::render(value, &variables)))
+                .collect();
+            variables.extend(rendered);
+        }
+        variables
+    }
+
+    /// 按给定变量（见 [`Self::scoped_variables`]）渲染JSON子模板，返回子模板名和渲染后的紧凑JSON文本
+    ///
+    /// 只替换JSON值中字符串里的占位符，变量值中的引号、反斜杠等由序列化转义，渲染结果总是合法JSON。
+    pub fn json_templates<'a>(
+        &'a self,
+        variables: &'a HashMap<String, String>,
+    ) -> impl Iterator<Item = (&'a str, String)> {
+        self.json_templates
+            .iter()
+            .map(move |(name, value)| (name.as_str(), render_strings(value, variables).to_string()))
+    }
+
+    /// 用本周期的变量渲染模板中的占位符
+    pub fn render(&self, text: &str) -> String {
+        template::render(text, &self.variables)
+    }
+}
+
+/// 把worker变量合并到运行作用域的变量中：值中可以引用运行作用域的变量和其他worker变量（如 {{worker_index}}），
+/// 被引用的worker变量先渲染，其余按名称顺序，结果与 `HashMap` 的遍历顺序无关。
+/// 引用同名变量时取运行作用域的值，循环引用的变量保持未渲染的占位符
+fn resolve_worker_variables(
+    worker_variables: &HashMap<String, String>,
+    variables: &mut HashMap<String, String>,
+) {
+    fn resolve(
+        name: &str,
+        worker_variables: &HashMap<String, String>,
+        variables: &mut HashMap<String, String>,
+        visited: &mut HashSet<String>,
+    ) {
+        if !visited.insert(name.to_string()) {
+            return;
+        }
+        let template = &worker_variables[name];
+        for dependency in template::placeholders(template) {
+            if dependency != name && worker_variables.contains_key(dependency) {
+                resolve(dependency, worker_variables, variables, visited);
+            }
+        }
+        let value = template::render(template, variables);
+        variables.insert(name.to_string(), value);
+    }
+
+    let mut names: Vec<_> = worker_variables.keys().collect();
+    names.sort();
+    let mut visited = HashSet::new();
+    for name in names {
+        resolve(name, worker_variables, variables, &mut visited);
+    }
+}
+
+/// 渲染JSON值中全部字符串（不含对象键）里的占位符
+fn render_strings(value: &Value, variables: &HashMap<String, String>) -> Value {
+    match value {
+        Value::String(text) => Value::String(template::render(text, variables)),
+        Value::Array(items) => Value::Array(
+            items
+                .iter()
+                .map(|item| render_strings(item, variables))
+                .collect(),
+        ),
+        Value::Object(fields) => Value::Object(
+            fields
+                .iter()
+                .map(|(key, item)| (key.clone(), render_strings(item, variables)))
+                .collect(),
+        ),
+        other => other.clone(),
+    }
+}
+
+/// 屏障模式（`send_mode: "barrier"`）的放行屏障：本周期的全部请求就绪后同时放行
+#[derive(Debug)]
+pub struct SendBarrier {
+    barrier: Barrier,
+    released: OnceLock<Instant>,
+}
+
+impl SendBarrier {
+    pub fn new(requests: usize) -> Self {
+        Self {
+            barrier: Barrier::new(requests),
+            released: OnceLock::new(),
+        }
+    }
+
+    /// 等待本周期的全部请求就绪，返回本请求相对最早放行的请求的时间差
+    pub async fn wait(&self) -> Duration {
+        self.barrier.wait().await;
+        let now = Instant::now();
+        now.saturating_duration_since(*self.released.get_or_init(|| now))
+    }
+}
@@ -0,0 +1,511 @@
+use anyhow::{Context, Result, bail};
+use bytes::Bytes;
+use http_body_util::Full;
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::Serialize;
+use serde_json::{Value, json};
+use std::collections::VecDeque;
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::net::TcpListener;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{DaemonConfig, RequestConfig, ScheduleConfig};
+use crate::cron::{CronSchedule, format_timestamp, parse_utc_offset};
+use crate::listener;
+use crate::request_handler::{RequestHandler, RunOptions};
+use crate::result_store::ResultStore;
+use crate::service::SystemdNotifier;
+use crate::stats::StatsHandler;
+use crate::template::percent_decode;
+
+// Import logger macros from crate root
+use crate::{log_error, log_info, log_warn};
+
+/// 每个计划默认保留的运行结果数
+const DEFAULT_KEEP_RUNS: usize = 20;
+
+/// 计划循环等待下一次触发时至少每隔这么久推进一次，看门狗据此判断循环是否卡住
+const LOOP_TICK: Duration = Duration::from_secs(1);
+
+/// 计划已停用（不再触发），看门狗不再检查其循环
+const LOOP_DISABLED: u64 = u64::MAX;
+
+/// 一次定时运行的结果摘要
+#[derive(Debug, Clone, Serialize)]
+pub struct ScheduledRun {
+    pub started_at: String,
+    pub elapsed_ms: u64,
+    pub total_requests: usize,
+    pub successful_requests: usize,
+    pub failed_requests: usize,
+    pub error_rate: f64,
+    pub p95_ms: Option<u64>,
+    pub stop_reason: Option<String>,
+    pub last_error: Option<String>,
+}
+
+struct ScheduleState {
+    config: ScheduleConfig,
+    cron: CronSchedule,
+    scenario: RequestConfig,
+    running: AtomicBool,
+    next_run: Mutex<Option<i64>>,
+    runs: Mutex<VecDeque<ScheduledRun>>,
+    /// 计划循环最近一次推进的时刻（守护进程启动后的毫秒数）
+    last_tick_ms: AtomicU64,
+}
+
+/// 守护模式：常驻运行，按cron表达式定时执行场景，保留最近的运行结果并提供状态接口
+pub struct Daemon {
+    schedules: Vec<Arc<ScheduleState>>,
+    offset_secs: i64,
+    keep_runs: usize,
+    /// 结果文件和结果库中运行的最长保留时间
+    max_age: Option<Duration>,
+    results_dir: Option<PathBuf>,
+    results_db: Option<PathBuf>,
+    status_bind: Option<SocketAddr>,
+    status_token: Option<String>,
+    notifier: Option<SystemdNotifier>,
+    /// 守护进程停止时中断进行中的运行
+    shutdown: CancellationToken,
+    started: Instant,
+}
+
+impl Daemon {
+    /// `scenarios` 与 `config.schedules` 一一对应（已加载并解析凭据的场景配置）
+    pub fn new(config: &DaemonConfig, scenarios: Vec<RequestConfig>) -> Result<Self> {
+        if config.schedules.len() != scenarios.len() {
+            bail!(
+                "{} schedules but {} scenarios",
+                config.schedules.len(),
+                scenarios.len()
+            );
+        }
+        let schedules = config
+            .schedules
+            .iter()
+            .zip(scenarios)
+            .map(|(schedule, mut scenario)| {
+                let cron = CronSchedule::parse(&schedule.cron)
+                    .with_context(|| format!("schedule {}", schedule.name))?;
+                if schedule.max_requests.is_some() {
+                    scenario.max_requests = schedule.max_requests;
+                }
+                Ok(Arc::new(ScheduleState {
+                    config: schedule.clone(),
+                    cron,
+                    scenario,
+                    running: AtomicBool::new(false),
+                    next_run: Mutex::new(None),
+                    runs: Mutex::new(VecDeque::new()),
+                    last_tick_ms: AtomicU64::new(0),
+                }))
+            })
+            .collect::<Result<_>>()?;
+        let status_bind = config
+            .status_bind
+            .as_deref()
+            .map(|bind| {
+                bind.parse()
+                    .with_context(|| format!("invalid status_bind '{}'", bind))
+            })
+            .transpose()?;
+        Ok(Self {
+            schedules,
+            offset_secs: parse_utc_offset(config.utc_offset.as_deref().unwrap_or("Z"))?,
+            keep_runs: config.keep_runs.unwrap_or(DEFAULT_KEEP_RUNS).max(1),
+            max_age: config
+                .keep_days
+                .map(|days| Duration::from_secs(days.saturating_mul(86_400))),
+            results_dir: config.results_dir.as_ref().map(PathBuf::from),
+            results_db: config.results_db.as_ref().map(PathBuf::from),
+            status_bind,
+            status_token: config.status_token.clone(),
+            notifier: SystemdNotifier::from_env(),
+            shutdown: CancellationToken::new(),
+            started: Instant::now(),
+        })
+    }
+
+    /// 运行所有计划直到 `shutdown` 完成（进行中的运行被中断，等其在宽限期内结束后退出）
+    pub async fn run(self: Arc<Self>, shutdown: impl Future<Output = ()>) -> Result<()> {
+        if let Some(addr) = self.status_bind {
+            let addr = Arc::clone(&self).serve_status(addr).await?;
+            log_info!("📋 Status endpoint: http://{}/status", addr);
+        }
+
+        let mut tasks = Vec::new();
+        for schedule in &self.schedules {
+            let daemon = Arc::clone(&self);
+            let schedule = Arc::clone(schedule);
+            tasks.push(tokio::spawn(async move {
+                daemon.run_schedule(schedule).await;
+            }));
+        }
+
+        // 在systemd下以 Type=notify 运行时，计划启动后才报告就绪；看门狗心跳只在所有计划循环仍在推进时发送
+        let watchdog = self.notifier.as_ref().and_then(|notifier| {
+            notifier.ready();
+            notifier.status(&format!("{} schedule(s) waiting", self.schedules.len()));
+            let within = (notifier.watchdog_interval()? / 2).max(LOOP_TICK * 2);
+            let daemon = Arc::clone(&self);
+            notifier.spawn_watchdog(move || daemon.schedules_alive(within))
+        });
+
+        shutdown.await;
+        log_info!("🛑 Daemon stopping");
+        if let Some(notifier) = &self.notifier {
+            notifier.stopping();
+        }
+        self.shutdown.cancel();
+        while self
+            .schedules
+            .iter()
+            .any(|schedule| schedule.running.load(Ordering::Acquire))
+        {
+            tokio::time::sleep(Duration::from_millis(50)).await;
+        }
+        for task in tasks.into_iter().chain(watchdog) {
+            task.abort();
+        }
+        Ok(())
+    }
+
+    /// 按计划循环：等到下一个触发时刻，上一次运行未结束时跳过本次
+    async fn run_schedule(self: Arc<Self>, schedule: Arc<ScheduleState>) {
+        loop {
+            let now = unix_now();
+            let Some(next) = schedule.cron.next_after(now, self.offset_secs) else {
+                log_warn!(
+                    "Schedule {} never fires ({}), disabled",
+                    schedule.config.name,
+                    schedule.config.cron
+                );
+                schedule
+                    .last_tick_ms
+                    .store(LOOP_DISABLED, Ordering::Release);
+                return;
+            };
+            *schedule.next_run.lock().unwrap() = Some(next);
+            log_info!(
+                "⏰ Schedule {} next runs at {}",
+                schedule.config.name,
+                format_timestamp(next, self.offset_secs)
+            );
+            // 分段等待并记录推进，供看门狗判断循环没有卡住；最后一段不足50ms时等到触发的那一秒
+            while unix_now() < next {
+                self.tick(&schedule);
+                let remaining = Duration::from_secs((next - unix_now()).max(0) as u64);
+                tokio::time::sleep(remaining.clamp(Duration::from_millis(50), LOOP_TICK)).await;
+            }
+            self.tick(&schedule);
+
+            if let Err(e) = Self::claim(&schedule) {
+                log_warn!("{}", e);
+                continue;
+            }
+            let daemon = Arc::clone(&self);
+            let state = Arc::clone(&schedule);
+            tokio::spawn(async move {
+                daemon.execute(&state).await;
+            });
+        }
+    }
+
+    fn tick(&self, schedule: &ScheduleState) {
+        let elapsed = self.started.elapsed().as_millis() as u64;
+        schedule.last_tick_ms.store(elapsed, Ordering::Release);
+    }
+
+    /// 所有未停用的计划循环是否都在 `within` 内推进过
+    fn schedules_alive(&self, within: Duration) -> bool {
+        let now = self.started.elapsed().as_millis() as u64;
+        self.schedules.iter().all(|schedule| {
+            let last = schedule.last_tick_ms.load(Ordering::Acquire);
+            last == LOOP_DISABLED || now.saturating_sub(last) <= within.as_millis() as u64
+        })
+    }
+
+    /// 立即运行指定计划（不影响定时），返回本次运行的结果
+    pub async fn trigger(&self, name: &str) -> Result<ScheduledRun> {
+        let Some(schedule) = self.schedule(name) else {
+            bail!("unknown schedule '{}'", name);
+        };
+        Self::claim(schedule)?;
+        Ok(self.execute(schedule).await)
+    }
+
+    fn schedule(&self, name: &str) -> Option<&Arc<ScheduleState>> {
+        self.schedules
+            .iter()
+            .find(|schedule| schedule.config.name == name)
+    }
+
+    /// 标记计划为运行中；上一次运行未结束时返回错误
+    fn claim(schedule: &ScheduleState) -> Result<()> {
+        if schedule.running.swap(true, Ordering::AcqRel) {
+            bail!(
+                "Schedule {} is still running, skipping this run",
+                schedule.config.name
+            );
+        }
+        Ok(())
+    }
+
+    /// 运行已标记为运行中的计划
+    async fn execute(&self, schedule: &ScheduleState) -> ScheduledRun {
+        let name = &schedule.config.name;
+        let started = unix_now();
+        log_info!("▶️  Schedule {} started", name);
+        let mut options = RunOptions {
+            shutdown: Some(self.shutdown.clone()),
+            ..Default::default()
+        };
+        // 结果库不可用时照常运行，只是不登记本次运行
+        let store = self.results_db.as_ref().and_then(|path| {
+            ResultStore::start_run(path, Some(name))
+                .inspect_err(|e| log_error!("{:#}", e))
+                .ok()
+                .map(Arc::new)
+        });
+        if let Some(store) = &store {
+            options.observers.push(store.clone());
+        }
+        let stats = RequestHandler::run_with_options(schedule.scenario.clone(), options).await;
+        schedule.running.store(false, Ordering::Release);
+
+        let run = ScheduledRun {
+            started_at: format_timestamp(started, self.offset_secs),
+            elapsed_ms: stats.elapsed.as_millis() as u64,
+            total_requests: stats.total_requests,
+            successful_requests: stats.successful_requests,
+            failed_requests: stats.failed_requests,
+            error_rate: stats.error_rate(),
+            p95_ms: stats
+                .latency_percentile(95.0)
+                .map(|p95| p95.as_millis() as u64),
+            stop_reason: stats.stop_reason.clone(),
+            last_error: stats.last_error.clone(),
+        };
+        let summary = format!(
+            "Schedule {} finished: {} requests, {} failed ({:.2}% errors)",
+            name, run.total_requests, run.failed_requests, run.error_rate
+        );
+        log_info!("⏹️  {}", summary);
+        if let Some(notifier) = &self.notifier {
+            notifier.status(&summary);
+        }
+
+        {
+            let mut runs = schedule.runs.lock().unwrap();
+            runs.push_front(run.clone());
+            runs.truncate(self.keep_runs);
+        }
+
+        if let Some(dir) = &self.results_dir
+            && let Err(e) = self.save_result(dir, name, &run, &StatsHandler::to_json(&stats))
+        {
+            log_error!("Failed to save result of schedule {}: {:#}", name, e);
+        }
+        if let (Some(path), Some(store)) = (&self.results_db, store) {
+            let stored = store.finish_run(&stats).await.and_then(|_| {
+                ResultStore::prune(path, Some(name), Some(self.keep_runs), self.max_age)
+            });
+            match stored {
+                Ok(0) => {}
+                Ok(pruned) => log_info!(
+                    "🧹 Pruned {} old run(s) of schedule {} from {}",
+                    pruned,
+                    name,
+                    path.display()
+                ),
+                Err(e) => log_error!("Failed to store result of schedule {}: {:#}", name, e),
+            }
+        }
+        run
+    }
+
+    /// 保存运行统计，并删除超出保留数或超过保留天数的旧结果
+    fn save_result(
+        &self,
+        dir: &std::path::Path,
+        name: &str,
+        run: &ScheduledRun,
+        stats: &Value,
+    ) -> Result<()> {
+        let dir = dir.join(sanitize(name));
+        std::fs::create_dir_all(&dir)
+            .with_context(|| format!("Failed to create {}", dir.display()))?;
+        let path = dir.join(format!("{}.json", sanitize(&run.started_at)));
+        let document = json!({ "run": run, "stats": stats });
+        std::fs::write(&path, serde_json::to_string_pretty(&document)? + "\n")
+            .with_context(|| format!("Failed to write {}", path.display()))?;
+
+        // 文件名为开始时间，按名称排序即按时间排序
+        let mut files: Vec<_> = std::fs::read_dir(&dir)?
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| {
+                path.extension()
+                    .is_some_and(|extension| extension == "json")
+            })
+            .collect();
+        files.sort();
+        let excess = files.len().saturating_sub(self.keep_runs);
+        let expired = |path: &PathBuf| {
+            self.max_age.is_some_and(|max_age| {
+                std::fs::metadata(path)
+                    .and_then(|metadata| metadata.modified())
+                    .ok()
+                    .and_then(|modified| modified.elapsed().ok())
+                    .is_some_and(|age| age > max_age)
+            })
+        };
+        let mut pruned = 0;
+        for (index, old) in files.iter().enumerate() {
+            if index < excess || (old != &path && expired(old)) {
+                std::fs::remove_file(old)
+                    .with_context(|| format!("Failed to remove {}", old.display()))?;
+                pruned += 1;
+            }
+        }
+        if pruned > excess {
+            log_info!(
+                "🧹 Removed {} result file(s) of schedule {} older than the retention period",
+                pruned - excess,
+                name
+            );
+        }
+        Ok(())
+    }
+
+    /// 各计划的状态：cron、下次运行时间、是否运行中、最近的运行结果（新的在前）
+    pub fn status(&self) -> Value {
+        let schedules: Vec<Value> = self
+            .schedules
+            .iter()
+            .map(|schedule| {
+                json!({
+                    "name": schedule.config.name,
+                    "cron": schedule.config.cron,
+                    "running": schedule.running.load(Ordering::Acquire),
+                    "next_run": schedule
+                        .next_run
+                        .lock()
+                        .unwrap()
+                        .map(|next| format_timestamp(next, self.offset_secs)),
+                    "runs": schedule.runs.lock().unwrap().iter().collect::<Vec<_>>(),
+                })
+            })
+            .collect();
+        json!({ "schedules": schedules })
+    }
+
+    /// 启动状态接口：`GET /status` 返回JSON，`POST /run/<计划名>` 在后台立即运行一次（返回202，结果见 `/status`）。
+    /// 配置了 `status_token` 时请求须带 `Authorization: Bearer <口令>`；未配置时 `/run` 被禁用
+    pub async fn serve_status(self: Arc<Self>, addr: SocketAddr) -> Result<SocketAddr> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind status endpoint to {}", addr))?;
+        let addr = listener.local_addr()?;
+        listener::serve(listener, move |stream| {
+            let daemon = Arc::clone(&self);
+            async move {
+                let service = service_fn(move |request| {
+                    let daemon = Arc::clone(&daemon);
+                    async move { Ok::<_, Infallible>(Self::handle(&daemon, request)) }
+                });
+                let _ = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await;
+            }
+        });
+        Ok(addr)
+    }
+
+    fn handle(self: &Arc<Self>, request: Request<Incoming>) -> Response<Full<Bytes>> {
+        let authorized = self.status_token.as_ref().map(|token| {
+            request
+                .headers()
+                .get(hyper::header::AUTHORIZATION)
+                .and_then(|value| value.to_str().ok())
+                .and_then(|value| value.strip_prefix("Bearer "))
+                == Some(token.as_str())
+        });
+        if authorized == Some(false) {
+            return json_response(
+                StatusCode::UNAUTHORIZED,
+                json!({ "error": "invalid or missing token" }),
+            );
+        }
+        let path = request.uri().path().to_string();
+        match (request.method(), path.as_str()) {
+            (&Method::GET, "/status") => json_response(StatusCode::OK, self.status()),
+            (&Method::POST, _) if path.starts_with("/run/") && authorized.is_none() => {
+                json_response(
+                    StatusCode::FORBIDDEN,
+                    json!({ "error": "set daemon.status_token to enable POST /run" }),
+                )
+            }
+            (&Method::POST, path) if path.starts_with("/run/") => {
+                let name = percent_decode(&path["/run/".len()..]);
+                let Some(schedule) = self.schedule(&name).cloned() else {
+                    return json_response(
+                        StatusCode::NOT_FOUND,
+                        json!({ "error": format!("unknown schedule '{}'", name) }),
+                    );
+                };
+                if let Err(e) = Self::claim(&schedule) {
+                    return json_response(StatusCode::CONFLICT, json!({ "error": e.to_string() }));
+                }
+                let daemon = Arc::clone(self);
+                tokio::spawn(async move {
+                    daemon.execute(&schedule).await;
+                });
+                json_response(
+                    StatusCode::ACCEPTED,
+                    json!({ "status": "started", "schedule": name }),
+                )
+            }
+            _ => json_response(StatusCode::NOT_FOUND, json!({ "error": "not found" })),
+        }
+    }
+}
+
+fn json_response(status: StatusCode, body: Value) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Full::new(Bytes::from(body.to_string())))
+        .expect("valid response")
+}
+
+fn unix_now() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .unwrap_or_default()
+        .as_secs() as i64
+}
+
+/// 文件名中不允许的字符替换为 `_`
+fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,47 @@
+use rand::Rng;
+use std::time::Duration;
+
+use crate::config::DelayDistribution;
+use crate::rng;
+
+/// 延迟采样：按配置的分布在基准延迟附近随机取值，避免固定延迟造成的同步负载
+pub struct DelaySampler;
+
+impl DelaySampler {
+    /// 以 `base_ms` 为基准采样一次延迟，未配置分布时返回固定延迟
+    pub fn sample(base_ms: u64, distribution: Option<&DelayDistribution>) -> Duration {
+        let Some(distribution) = distribution else {
+            return Duration::from_millis(base_ms);
+        };
+
+        let base = base_ms as f64;
+        let value = rng::with(|rng| match distribution.distribution.as_str() {
+            "uniform" => {
+                let jitter = distribution.jitter_ms.unwrap_or(0) as f64;
+                if jitter > 0.0 {
+                    rng.gen_range(base - jitter..=base + jitter)
+                } else {
+                    base
+                }
+            }
+            "normal" => {
+                // Box-Muller变换
+                let stddev = distribution.stddev_ms.unwrap_or(0) as f64;
+                let u1: f64 = 1.0 - rng.r#gen::<f64>();
+                let u2: f64 = rng.r#gen();
+                let z = (-2.0 * u1.ln()).sqrt() * (2.0 * std::f64::consts::PI * u2).cos();
+                base + stddev * z
+            }
+            "exponential" => {
+                // 均值为基准值，对应泊松到达
+                let u: f64 = 1.0 - rng.r#gen::<f64>();
+                -u.ln() * base
+            }
+            _ => base,
+        });
+
+        let min = distribution.min_ms.unwrap_or(0) as f64;
+        let max = distribution.max_ms.map_or(f64::MAX, |max| max as f64);
+        Duration::from_secs_f64(value.max(min).min(max).max(0.0) / 1000.0)
+    }
+}
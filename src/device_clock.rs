@@ -0,0 +1,206 @@
+use anyhow::{Context, Result, anyhow};
+use serde_json::Value;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicI64, Ordering};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+use tokio::task::JoinHandle;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{DeviceTimeConfig, RequestConfig};
+use crate::cron::{format_timestamp, parse_http_date, parse_timestamp};
+use crate::cycle_context::CycleContext;
+use crate::http_client::{HttpClient, HttpClientConfig, ResponseData};
+use crate::isapi::xml_value;
+use crate::request_handler::RequestHandler;
+use crate::template;
+
+// Import logger macros from crate root
+use crate::{log_debug, log_info, log_warn};
+
+/// 默认的body时间字段（ISAPI `/ISAPI/System/time` 的 `localTime`）
+const DEFAULT_TIME_FIELD: &str = "localTime";
+
+/// 同步请求的超时
+const SYNC_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// 生成字段使用的时钟：本机时钟加上与设备时钟的偏差，未同步时即为本机时钟
+#[derive(Debug, Default)]
+pub struct DeviceClock {
+    offset_ms: AtomicI64,
+    utc_offset_secs: AtomicI64,
+    synced: AtomicBool,
+}
+
+impl DeviceClock {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 当前时间（Unix毫秒）
+    pub fn now_millis(&self) -> i64 {
+        local_millis() + self.offset_ms.load(Ordering::Relaxed)
+    }
+
+    /// 设备时钟相对本机时钟的偏差（毫秒，设备快时为正）
+    pub fn offset_millis(&self) -> i64 {
+        self.offset_ms.load(Ordering::Relaxed)
+    }
+
+    /// 是否已与设备同步过
+    pub fn is_synced(&self) -> bool {
+        self.synced.load(Ordering::Acquire)
+    }
+
+    /// 记录同步结果；`utc_offset_secs` 为设备时间自带的时区偏移（Date头为UTC，不更新）
+    pub fn set_offset(&self, offset_ms: i64, utc_offset_secs: Option<i64>) {
+        self.offset_ms.store(offset_ms, Ordering::Relaxed);
+        if let Some(utc_offset_secs) = utc_offset_secs {
+            self.utc_offset_secs
+                .store(utc_offset_secs, Ordering::Relaxed);
+        }
+        self.synced.store(true, Ordering::Release);
+    }
+
+    /// 当前时间的RFC 3339文本，使用设备的时区偏移（未知时为UTC）
+    pub fn now_rfc3339(&self) -> String {
+        format_timestamp(
+            self.now_millis().div_euclid(1000),
+            self.utc_offset_secs.load(Ordering::Relaxed),
+        )
+    }
+}
+
+/// 设备时钟同步：请求设备时间，按往返时间的中点估算偏差并写入 [`DeviceClock`]
+pub struct DeviceTimeSync {
+    url: String,
+    field: Option<String>,
+    refresh: Option<Duration>,
+    http_client: HttpClient,
+}
+
+impl DeviceTimeSync {
+    /// 按运行作用域的变量渲染地址，凭据和Unix套接字沿用全局配置
+    pub fn new(device_time: &DeviceTimeConfig, config: &RequestConfig) -> Result<Self> {
+        let variables = CycleContext::new(config, 0, None, Vec::new(), None).variables;
+        let http_client = HttpClient::new(HttpClientConfig {
+            timeout: SYNC_TIMEOUT,
+            ..RequestHandler::http_client_config(
+                config.digest_auth.as_ref(),
+                config.unix_socket.as_deref(),
+                config.proxy.as_ref(),
+                &variables,
+            )
+        })?;
+        Ok(Self {
+            url: template::render(&device_time.url, &variables),
+            field: (device_time.source.as_deref() == Some("body")).then(|| {
+                device_time
+                    .field
+                    .clone()
+                    .unwrap_or_else(|| DEFAULT_TIME_FIELD.to_string())
+            }),
+            refresh: device_time
+                .refresh_ms
+                .filter(|refresh_ms| *refresh_ms > 0)
+                .map(Duration::from_millis),
+            http_client,
+        })
+    }
+
+    /// 同步一次，返回设备时钟的偏差（毫秒）；失败时保留之前的偏差
+    pub async fn sync(&self, clock: &DeviceClock) -> Result<i64> {
+        let sent = local_millis();
+        let start = Instant::now();
+        let response = self
+            .http_client
+            .send_request("GET", &self.url, None, None)
+            .await
+            .with_context(|| format!("GET {} failed", self.url))?;
+        let response = ResponseData::read(response).await?;
+        let round_trip = start.elapsed().as_millis() as i64;
+        if !response.status.is_success() {
+            return Err(anyhow!("GET {} returned {}", self.url, response.status));
+        }
+        let (device_millis, utc_offset) = self.device_time(&response)?;
+        // 设备时间取自往返的中点
+        let offset = device_millis - (sent + round_trip / 2);
+        let first = !clock.is_synced();
+        clock.set_offset(offset, utc_offset);
+        let message = format!(
+            "Device clock is {:.3}s {} the local clock (round trip {}ms)",
+            offset.abs() as f64 / 1000.0,
+            if offset >= 0 { "ahead of" } else { "behind" },
+            round_trip
+        );
+        if first {
+            log_info!("🕰️  {}", message);
+        } else {
+            log_debug!("🕰️  {}", message);
+        }
+        Ok(offset)
+    }
+
+    /// 配置了 `refresh_ms` 时启动后台任务定期重新同步，直到 `stop` 取消
+    pub fn spawn_refresh(
+        self: &Arc<Self>,
+        clock: Arc<DeviceClock>,
+        stop: CancellationToken,
+    ) -> Option<JoinHandle<()>> {
+        let refresh = self.refresh?;
+        let sync = Arc::clone(self);
+        Some(tokio::spawn(async move {
+            while stop
+                .run_until_cancelled(tokio::time::sleep(refresh))
+                .await
+                .is_some()
+            {
+                if let Some(Err(e)) = stop.run_until_cancelled(sync.sync(&clock)).await {
+                    log_warn!("Device time sync failed, keeping the last offset: {:#}", e);
+                }
+            }
+        }))
+    }
+
+    /// 从响应中取出设备时间：(Unix毫秒, 设备时区偏移)
+    fn device_time(&self, response: &ResponseData) -> Result<(i64, Option<i64>)> {
+        let Some(field) = &self.field else {
+            let date = response
+                .headers
+                .get("Date")
+                .and_then(|value| value.to_str().ok())
+                .ok_or_else(|| anyhow!("response from {} has no Date header", self.url))?;
+            // Date头只精确到秒，取该秒的中点
+            return Ok((parse_http_date(date)? * 1000 + 500, None));
+        };
+        let body = response.text();
+        let text = time_field(&body, field)
+            .ok_or_else(|| anyhow!("no {} field in the response from {}", field, self.url))?;
+        let (millis, utc_offset) = parse_timestamp(&text)?;
+        Ok((millis, Some(utc_offset)))
+    }
+}
+
+/// 在JSON（任意层级的同名键）或XML（同名元素）body中查找时间字段
+fn time_field(body: &str, field: &str) -> Option<String> {
+    fn find(value: &Value, field: &str) -> Option<String> {
+        match value {
+            Value::Object(map) => map
+                .get(field)
+                .and_then(Value::as_str)
+                .map(str::to_string)
+                .or_else(|| map.values().find_map(|value| find(value, field))),
+            Value::Array(items) => items.iter().find_map(|value| find(value, field)),
+            _ => None,
+        }
+    }
+    match serde_json::from_str::<Value>(body) {
+        Ok(value) => find(&value, field),
+        Err(_) => xml_value(body, field),
+    }
+}
+
+fn local_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64)
+}
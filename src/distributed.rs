@@ -0,0 +1,536 @@
+use anyhow::{Context, Result, anyhow, bail};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use serde::{Deserialize, Serialize};
+use serde_json::json;
+use std::collections::{HashMap, VecDeque};
+use std::convert::Infallible;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+use tokio::time::Instant;
+
+use crate::config::RequestConfig;
+use crate::listener;
+use crate::observer::RequestObserver;
+use crate::request_handler::{RequestHandler, RunOptions};
+use crate::rng;
+use crate::run_metadata::RunMetadata;
+use crate::stats::{RecordedRequest, RequestResult, RequestStats, StatsHandler};
+
+// Import logger macros from crate root
+use crate::{log_info, log_warn};
+
+/// worker与coordinator之间的共享口令头部
+const TOKEN_HEADER: &str = "X-RemoteTask-Token";
+
+/// 连续轮询失败该次数后认为worker失联
+const MAX_POLL_FAILURES: usize = 5;
+
+/// coordinator分配给单个worker的运行任务
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerAssignment {
+    pub worker_index: usize,
+    /// 开始前等待的时间，使各worker的周期错开而不是同时发出
+    pub start_delay_ms: u64,
+    pub config: RequestConfig,
+}
+
+/// worker运行结束时的汇总
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WorkerSummary {
+    pub stop_reason: Option<String>,
+}
+
+/// `GET /results` 的响应
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ResultsPage {
+    pub results: Vec<RecordedRequest>,
+    /// 运行结束时为Some
+    pub finished: Option<WorkerSummary>,
+}
+
+/// 收集worker本地运行结果，并响应coordinator的停止请求
+#[derive(Default)]
+struct ResultSink {
+    results: Mutex<PendingResults>,
+    finished: Mutex<Option<WorkerSummary>>,
+    stop: AtomicBool,
+}
+
+/// 尚未被coordinator确认的结果
+#[derive(Default)]
+struct PendingResults {
+    first: usize, // `results[0]` 在整个运行中的序号
+    results: VecDeque<RecordedRequest>,
+}
+
+impl PendingResults {
+    /// 丢弃序号小于 `since` 的结果（coordinator已收到），返回其余结果
+    fn acknowledge(&mut self, since: usize) -> Vec<RecordedRequest> {
+        let acknowledged = since.saturating_sub(self.first).min(self.results.len());
+        self.results.drain(..acknowledged);
+        self.first += acknowledged;
+        let skip = since.saturating_sub(self.first);
+        self.results.iter().skip(skip).cloned().collect()
+    }
+}
+
+impl RequestObserver for ResultSink {
+    fn on_result(&self, result: &RequestResult) {
+        self.results
+            .lock()
+            .unwrap()
+            .results
+            .push_back(RecordedRequest::from_result(result));
+    }
+
+    fn should_abort(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+    }
+}
+
+struct WorkerState {
+    token: Option<String>,
+    run: Mutex<Option<Arc<ResultSink>>>,
+}
+
+/// 分布式模式的worker：接收coordinator下发的场景并运行，结果供coordinator轮询
+pub struct WorkerServer {
+    addr: SocketAddr,
+    task: JoinHandle<()>,
+}
+
+impl WorkerServer {
+    /// 在指定地址启动worker（端口为0时自动分配），配置了token时拒绝口令不一致的请求。
+    /// 未配置token时只允许监听本机回环地址
+    pub async fn start(addr: SocketAddr, token: Option<String>) -> Result<Self> {
+        if token.is_none() && !addr.ip().is_loopback() {
+            bail!(
+                "refusing to listen on {} without --token: any host that can reach it could start runs",
+                addr
+            );
+        }
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind worker to {}", addr))?;
+        let addr = listener.local_addr()?;
+        let state = Arc::new(WorkerState {
+            token,
+            run: Mutex::new(None),
+        });
+
+        let task = listener::serve(listener, move |stream| {
+            let state = Arc::clone(&state);
+            async move {
+                let service = service_fn(move |request| {
+                    let state = Arc::clone(&state);
+                    async move { Ok::<_, Infallible>(handle(&state, request).await) }
+                });
+                let _ = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await;
+            }
+        });
+
+        Ok(Self { addr, task })
+    }
+
+    /// 实际监听地址
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+}
+
+impl Drop for WorkerServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 处理coordinator的请求：`POST /run`、`GET /results?since=N`、`POST /stop`
+async fn handle(state: &WorkerState, request: Request<Incoming>) -> Response<Full<Bytes>> {
+    if let Some(token) = &state.token {
+        let provided = request
+            .headers()
+            .get(TOKEN_HEADER)
+            .and_then(|value| value.to_str().ok());
+        if provided != Some(token.as_str()) {
+            return json_response(
+                StatusCode::UNAUTHORIZED,
+                json!({ "error": "invalid token" }),
+            );
+        }
+    }
+
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let query = request.uri().query().unwrap_or_default().to_string();
+    match (method, path.as_str()) {
+        (Method::POST, "/run") => {
+            let body = match request.into_body().collect().await {
+                Ok(body) => body.to_bytes(),
+                Err(e) => return error_response(StatusCode::BAD_REQUEST, e),
+            };
+            match serde_json::from_slice::<WorkerAssignment>(&body) {
+                Ok(assignment) => start_run(state, assignment),
+                Err(e) => error_response(StatusCode::BAD_REQUEST, e),
+            }
+        }
+        (Method::GET, "/results") => {
+            let since = query
+                .split('&')
+                .find_map(|pair| pair.strip_prefix("since="))
+                .and_then(|since| since.parse::<usize>().ok())
+                .unwrap_or(0);
+            let Some(sink) = state.run.lock().unwrap().clone() else {
+                return error_response(StatusCode::NOT_FOUND, "no run assigned");
+            };
+            // 先读取结束状态，保证结束前产生的结果都包含在本页中；
+            // `since` 之前的结果已被coordinator收到，从内存中释放
+            let finished = sink.finished.lock().unwrap().clone();
+            let page = ResultsPage {
+                results: sink.results.lock().unwrap().acknowledge(since),
+                finished,
+            };
+            json_response(StatusCode::OK, json!(page))
+        }
+        (Method::POST, "/stop") => {
+            if let Some(sink) = state.run.lock().unwrap().as_ref() {
+                sink.stop.store(true, Ordering::Relaxed);
+                log_info!("🛑 Stop requested by coordinator");
+            }
+            json_response(StatusCode::OK, json!({ "status": "stopping" }))
+        }
+        _ => error_response(StatusCode::NOT_FOUND, "unknown endpoint"),
+    }
+}
+
+fn start_run(state: &WorkerState, assignment: WorkerAssignment) -> Response<Full<Bytes>> {
+    let mut run = state.run.lock().unwrap();
+    if run
+        .as_ref()
+        .is_some_and(|sink| sink.finished.lock().unwrap().is_none())
+    {
+        return error_response(StatusCode::CONFLICT, "a run is already in progress");
+    }
+    let sink = Arc::new(ResultSink::default());
+    *run = Some(Arc::clone(&sink));
+
+    log_info!(
+        "🚀 Starting assigned run as worker {} ({} cycles)",
+        assignment.worker_index,
+        assignment
+            .config
+            .max_requests
+            .map_or("unlimited".to_string(), |cycles| cycles.to_string())
+    );
+    tokio::spawn(async move {
+        tokio::time::sleep(Duration::from_millis(assignment.start_delay_ms)).await;
+        let options = RunOptions {
+            observers: vec![sink.clone()],
+            ..Default::default()
+        };
+        let stats = RequestHandler::run_with_options(assignment.config, options).await;
+        log_info!(
+            "🏁 Assigned run finished: {} requests, {} failed",
+            stats.total_requests,
+            stats.failed_requests
+        );
+        *sink.finished.lock().unwrap() = Some(WorkerSummary {
+            stop_reason: stats.stop_reason,
+        });
+    });
+    json_response(StatusCode::ACCEPTED, json!({ "status": "started" }))
+}
+
+fn json_response(status: StatusCode, body: serde_json::Value) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Full::new(Bytes::from(body.to_string())))
+        .expect("valid response")
+}
+
+fn error_response(status: StatusCode, error: impl std::fmt::Display) -> Response<Full<Bytes>> {
+    json_response(status, json!({ "error": error.to_string() }))
+}
+
+/// 分布式运行的统计：合并结果及各worker的单独结果
+#[derive(Debug, Default)]
+pub struct DistributedStats {
+    pub combined: RequestStats,
+    pub per_worker: Vec<(String, RequestStats)>,
+}
+
+/// 单个worker的轮询状态
+struct WorkerProgress {
+    address: String,
+    next: usize,
+    failures: usize,
+    finished: bool,
+    stats: RequestStats,
+}
+
+/// 分布式模式的coordinator：向各worker下发场景和节奏，实时汇总结果
+pub struct Coordinator {
+    workers: Vec<String>,
+    token: Option<String>,
+    client: reqwest::Client,
+    poll_interval: Duration,
+}
+
+impl Coordinator {
+    /// `workers` 为worker地址（`host:port` 或 `http://host:port`）
+    pub fn new(workers: Vec<String>, token: Option<String>) -> Result<Self> {
+        if workers.is_empty() {
+            bail!("at least one worker is required");
+        }
+        let workers = workers
+            .into_iter()
+            .map(|worker| {
+                let worker = worker.trim_end_matches('/');
+                if worker.contains("://") {
+                    worker.to_string()
+                } else {
+                    format!("http://{}", worker)
+                }
+            })
+            .collect();
+        Ok(Self {
+            workers,
+            token,
+            client: reqwest::Client::builder()
+                .timeout(Duration::from_secs(10))
+                .build()?,
+            poll_interval: Duration::from_secs(1),
+        })
+    }
+
+    /// 修改轮询间隔（默认1秒）
+    pub fn with_poll_interval(mut self, poll_interval: Duration) -> Self {
+        self.poll_interval = poll_interval;
+        self
+    }
+
+    /// 拆分场景：max_requests按worker平分，各worker的起始时间在一个A→A间隔内均匀错开，
+    /// 并注入 `{{worker_index}}` 变量以便生成互不冲突的数据
+    pub fn plan(config: &RequestConfig, workers: usize) -> Vec<WorkerAssignment> {
+        (0..workers)
+            .filter_map(|worker_index| {
+                let mut config = config.clone();
+                if let Some(max_requests) = config.max_requests {
+                    let share =
+                        max_requests / workers + usize::from(worker_index < max_requests % workers);
+                    if share == 0 {
+                        return None;
+                    }
+                    config.max_requests = Some(share);
+                }
+                config
+                    .worker_variables
+                    .get_or_insert_with(HashMap::new)
+                    .insert("worker_index".to_string(), worker_index.to_string());
+                // 各worker使用不同但可重现的种子
+                config.seed = config
+                    .seed
+                    .map(|seed| seed.wrapping_add(worker_index as u64));
+                Some(WorkerAssignment {
+                    worker_index,
+                    start_delay_ms: config.delay_between_a_requests_ms * worker_index as u64
+                        / workers as u64,
+                    config,
+                })
+            })
+            .collect()
+    }
+
+    /// 下发场景并汇总结果，直到所有worker结束；`stop` 完成时通知各worker停止
+    pub async fn run(
+        &self,
+        config: RequestConfig,
+        stop: impl Future<Output = ()>,
+    ) -> Result<DistributedStats> {
+        let start = Instant::now();
+        let mut config = config;
+        let seed = rng::run_seed(config.seed);
+        config.seed = Some(seed);
+        let mut metadata = RunMetadata::new(&config);
+        metadata.seed = Some(seed);
+        metadata.targets.extend(
+            self.workers
+                .iter()
+                .map(|worker| format!("worker {}", worker)),
+        );
+        let assignments = Self::plan(&config, self.workers.len());
+        let mut progress = Vec::new();
+        for assignment in &assignments {
+            let address = self.workers[assignment.worker_index].clone();
+            if let Err(e) = self.assign(&address, assignment).await {
+                self.stop_all(&progress).await;
+                return Err(e.context(format!("worker {}", address)));
+            }
+            log_info!(
+                "📡 Worker {} assigned {} cycles",
+                address,
+                assignment
+                    .config
+                    .max_requests
+                    .map_or("unlimited".to_string(), |cycles| cycles.to_string())
+            );
+            progress.push(WorkerProgress {
+                address,
+                next: 0,
+                failures: 0,
+                finished: false,
+                stats: RequestStats::new(),
+            });
+        }
+
+        let mut combined = RequestStats::new();
+        let mut stop_reasons = Vec::new();
+        let mut interval = tokio::time::interval(self.poll_interval);
+        tokio::pin!(stop);
+        let mut stopping = false;
+        while progress.iter().any(|worker| !worker.finished) {
+            tokio::select! {
+                _ = interval.tick() => {}
+                _ = &mut stop, if !stopping => {
+                    stopping = true;
+                    log_info!("🛑 Stopping workers");
+                    self.stop_all(&progress).await;
+                    stop_reasons.push("stopped by user".to_string());
+                    continue;
+                }
+            }
+
+            for worker in progress.iter_mut().filter(|worker| !worker.finished) {
+                match self.poll(worker).await {
+                    Ok(page) => {
+                        worker.failures = 0;
+                        worker.next += page.results.len();
+                        for result in &page.results {
+                            Self::record(&mut worker.stats, result);
+                            Self::record(&mut combined, result);
+                        }
+                        if let Some(summary) = page.finished {
+                            worker.finished = true;
+                            log_info!("🏁 Worker {} finished", worker.address);
+                            stop_reasons.extend(
+                                summary
+                                    .stop_reason
+                                    .map(|reason| format!("{}: {}", worker.address, reason)),
+                            );
+                        }
+                    }
+                    Err(e) => {
+                        worker.failures += 1;
+                        log_warn!("Polling worker {} failed: {:#}", worker.address, e);
+                        if worker.failures >= MAX_POLL_FAILURES {
+                            worker.finished = true;
+                            stop_reasons.push(format!("{}: worker unreachable", worker.address));
+                        }
+                    }
+                }
+            }
+
+            let elapsed = start.elapsed().as_secs_f64();
+            log_info!(
+                "📊 {} requests ({:.1}% ok, {:.1} req/s) from {}/{} active workers",
+                combined.total_requests,
+                100.0 - combined.error_rate(),
+                combined.total_requests as f64 / elapsed.max(0.001),
+                progress.iter().filter(|worker| !worker.finished).count(),
+                progress.len()
+            );
+        }
+
+        combined.elapsed = start.elapsed();
+        metadata.finish();
+        combined.metadata = Some(metadata);
+        if !stop_reasons.is_empty() {
+            combined.stop_reason = Some(stop_reasons.join("; "));
+        }
+        Ok(DistributedStats {
+            combined,
+            per_worker: progress
+                .into_iter()
+                .map(|worker| {
+                    let mut stats = worker.stats;
+                    stats.elapsed = start.elapsed();
+                    (worker.address, stats)
+                })
+                .collect(),
+        })
+    }
+
+    /// 将worker上报的结果计入统计（预热期间的请求只计数）
+    fn record(stats: &mut RequestStats, result: &RecordedRequest) {
+        if result.warmup {
+            stats.warmup_requests += 1;
+        } else {
+            StatsHandler::record(stats, result);
+        }
+    }
+
+    async fn assign(&self, address: &str, assignment: &WorkerAssignment) -> Result<()> {
+        let response = self
+            .request(reqwest::Method::POST, address, "/run")
+            .json(assignment)
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            let status = response.status();
+            let body = response.text().await.unwrap_or_default();
+            return Err(anyhow!("rejected assignment with {}: {}", status, body));
+        }
+        Ok(())
+    }
+
+    async fn poll(&self, worker: &WorkerProgress) -> Result<ResultsPage> {
+        let response = self
+            .request(
+                reqwest::Method::GET,
+                &worker.address,
+                &format!("/results?since={}", worker.next),
+            )
+            .send()
+            .await?
+            .error_for_status()?;
+        Ok(response.json().await?)
+    }
+
+    async fn stop_all(&self, workers: &[WorkerProgress]) {
+        for worker in workers.iter().filter(|worker| !worker.finished) {
+            if let Err(e) = self
+                .request(reqwest::Method::POST, &worker.address, "/stop")
+                .send()
+                .await
+            {
+                log_warn!("Failed to stop worker {}: {}", worker.address, e);
+            }
+        }
+    }
+
+    fn request(
+        &self,
+        method: reqwest::Method,
+        address: &str,
+        path: &str,
+    ) -> reqwest::RequestBuilder {
+        let request = self.client.request(method, format!("{}{}", address, path));
+        match &self.token {
+            Some(token) => request.header(TOKEN_HEADER, token),
+            None => request,
+        }
+    }
+}
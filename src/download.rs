@@ -0,0 +1,241 @@
+use anyhow::{Context, Result, anyhow, bail};
+use reqwest::header::CONTENT_RANGE;
+use reqwest::{Response, StatusCode};
+use sha2::{Digest, Sha256};
+use std::future::Future;
+use std::path::PathBuf;
+use std::time::Duration;
+use tokio::fs::File;
+use tokio::io::AsyncWriteExt;
+use tokio::time::Instant;
+
+use crate::bandwidth::BandwidthLimiter;
+use crate::config::DownloadConfig;
+use crate::http_client::ResponseData;
+
+// Import logger macros from crate root
+use crate::{log_debug, log_info, log_warn};
+
+const DEFAULT_PROGRESS_INTERVAL_MS: u64 = 1000;
+
+/// 下载模式下保存的文件信息
+#[derive(Debug, Clone)]
+pub struct DownloadInfo {
+    pub path: PathBuf,
+    pub bytes: u64,
+    pub sha256: String,
+    pub elapsed: Duration,
+    /// 传输中断后续传的次数
+    pub resumes: usize,
+}
+
+impl DownloadInfo {
+    /// 下载速率（MB/s）
+    pub fn throughput_mbps(&self) -> f64 {
+        let seconds = self.elapsed.as_secs_f64();
+        if seconds == 0.0 {
+            return 0.0;
+        }
+        self.bytes as f64 / 1_000_000.0 / seconds
+    }
+}
+
+/// 下载模式：将响应body分块写入文件，同时计算SHA-256，不在内存中缓存整个body
+pub struct Downloader;
+
+impl Downloader {
+    /// 流式保存响应body，返回不含body的响应和文件信息
+    ///
+    /// 传输中断时（最多 `max_resumes` 次）调用 `resend` 发送带 `Range: bytes=<已下载>-` 的请求续传；
+    /// 服务器不支持Range（返回200）时从头重新下载。配置了 `limiter` 时按下载限速读取。
+    pub async fn save<F, Fut>(
+        mut response: Response,
+        config: &DownloadConfig,
+        limiter: Option<&BandwidthLimiter>,
+        resend: F,
+    ) -> Result<ResponseData>
+    where
+        F: Fn(u64) -> Fut,
+        Fut: Future<Output = Result<Response>>,
+    {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let remote_addr = response.remote_addr();
+        let mut total = response.content_length();
+
+        let path = PathBuf::from(&config.path);
+        if let Some(parent) = path.parent()
+            && !parent.as_os_str().is_empty()
+        {
+            tokio::fs::create_dir_all(parent)
+                .await
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let mut file = File::create(&path)
+            .await
+            .with_context(|| format!("Failed to create {}", path.display()))?;
+
+        let progress_interval = Duration::from_millis(
+            config
+                .progress_interval_ms
+                .unwrap_or(DEFAULT_PROGRESS_INTERVAL_MS),
+        );
+        let max_resumes = config.max_resumes.unwrap_or(0);
+        let start = Instant::now();
+        let mut last_progress = start;
+        let mut hasher = Sha256::new();
+        let mut bytes = 0u64;
+        let mut resumes = 0;
+        loop {
+            let chunk = match response.chunk().await {
+                Ok(Some(chunk)) => chunk,
+                Ok(None) => break,
+                Err(e) if resumes < max_resumes => {
+                    resumes += 1;
+                    log_warn!(
+                        "⬇️  {} interrupted after {} bytes ({}), resuming ({}/{})",
+                        path.display(),
+                        bytes,
+                        e,
+                        resumes,
+                        max_resumes
+                    );
+                    response = resend(bytes)
+                        .await
+                        .with_context(|| format!("Failed to resume {}", path.display()))?;
+                    match response.status() {
+                        StatusCode::PARTIAL_CONTENT => {
+                            let (range_start, range_total) = content_range(&response)?;
+                            if range_start != bytes {
+                                bail!(
+                                    "resumed at byte {} but {} bytes were already downloaded",
+                                    range_start,
+                                    bytes
+                                );
+                            }
+                            total = range_total.or(total);
+                        }
+                        status if status.is_success() => {
+                            // 服务器忽略了Range，从头重新下载
+                            log_debug!("⬇️  Range not supported, restarting {}", path.display());
+                            file = File::create(&path).await?;
+                            hasher = Sha256::new();
+                            bytes = 0;
+                            total = response.content_length();
+                        }
+                        status => bail!("resume request failed with status {}", status),
+                    }
+                    continue;
+                }
+                Err(e) => return Err(anyhow!("Failed to read response body: {}", e)),
+            };
+            if let Some(limiter) = limiter {
+                limiter.consume(chunk.len()).await;
+            }
+            hasher.update(&chunk);
+            file.write_all(&chunk)
+                .await
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            bytes += chunk.len() as u64;
+
+            if !progress_interval.is_zero() && last_progress.elapsed() >= progress_interval {
+                last_progress = Instant::now();
+                Self::log_progress(&path, bytes, total, start.elapsed());
+            }
+        }
+        file.flush().await?;
+
+        // 校验拼接后的文件大小
+        if let Some(total) = total
+            && bytes != total
+        {
+            bail!(
+                "{} is incomplete: {} of {} bytes",
+                path.display(),
+                bytes,
+                total
+            );
+        }
+
+        let download = DownloadInfo {
+            path,
+            bytes,
+            sha256: hasher
+                .finalize()
+                .iter()
+                .map(|byte| format!("{:02x}", byte))
+                .collect(),
+            elapsed: start.elapsed(),
+            resumes,
+        };
+        log_debug!(
+            "⬇️  Downloaded {} bytes to {} in {}ms ({:.3} MB/s, {} resumes)",
+            download.bytes,
+            download.path.display(),
+            download.elapsed.as_millis(),
+            download.throughput_mbps(),
+            download.resumes
+        );
+
+        Ok(ResponseData {
+            status,
+            headers,
+            body: Default::default(),
+            remote_addr,
+            download: Some(download),
+            overflow: None,
+        })
+    }
+
+    /// 校验下载文件的SHA-256，不一致时返回失败原因
+    pub fn verify(config: &DownloadConfig, response: &ResponseData) -> Option<String> {
+        let expected = config.sha256.as_deref()?;
+        let download = response.download.as_ref()?;
+        if download.sha256.eq_ignore_ascii_case(expected.trim()) {
+            None
+        } else {
+            Some(format!(
+                "SHA-256 of {} is {}, expected {}",
+                download.path.display(),
+                download.sha256,
+                expected
+            ))
+        }
+    }
+
+    fn log_progress(path: &std::path::Path, bytes: u64, total: Option<u64>, elapsed: Duration) {
+        let rate = bytes as f64 / 1_000_000.0 / elapsed.as_secs_f64().max(f64::EPSILON);
+        match total {
+            Some(total) if total > 0 => log_info!(
+                "⬇️  {}: {}/{} bytes ({:.1}%, {:.3} MB/s)",
+                path.display(),
+                bytes,
+                total,
+                bytes as f64 * 100.0 / total as f64,
+                rate
+            ),
+            _ => log_info!("⬇️  {}: {} bytes ({:.3} MB/s)", path.display(), bytes, rate),
+        }
+    }
+}
+
+/// 解析 `Content-Range: bytes <start>-<end>/<total>`，返回起始字节和总大小（`*` 时为None）
+fn content_range(response: &Response) -> Result<(u64, Option<u64>)> {
+    let value = response
+        .headers()
+        .get(CONTENT_RANGE)
+        .and_then(|value| value.to_str().ok())
+        .ok_or_else(|| anyhow!("206 response without Content-Range"))?;
+    let range = value
+        .trim()
+        .strip_prefix("bytes ")
+        .ok_or_else(|| anyhow!("unsupported Content-Range \"{}\"", value))?;
+    let (span, total) = range
+        .split_once('/')
+        .ok_or_else(|| anyhow!("invalid Content-Range \"{}\"", value))?;
+    let start = span
+        .split_once('-')
+        .and_then(|(start, _)| start.trim().parse().ok())
+        .ok_or_else(|| anyhow!("invalid Content-Range \"{}\"", value))?;
+    Ok((start, total.trim().parse().ok()))
+}
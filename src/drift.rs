@@ -0,0 +1,84 @@
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use crate::config::DriftDetectionConfig;
+use crate::json_diff;
+use crate::observer::RequestObserver;
+use crate::stats::RequestResult;
+
+// Import logger macros from crate root
+use crate::log_warn;
+
+/// 跨周期响应漂移检测：比较同一请求在相邻周期的响应，发现结构或取值变化时告警
+pub struct DriftDetector {
+    labels: Vec<String>,
+    ignore_paths: Vec<String>,
+    previous: Mutex<HashMap<(String, Option<String>), Value>>,
+    drift_events: AtomicUsize,
+}
+
+impl DriftDetector {
+    pub fn new(config: &DriftDetectionConfig) -> Self {
+        Self {
+            labels: config
+                .labels
+                .clone()
+                .unwrap_or_else(|| vec!["A".to_string()]),
+            ignore_paths: config.ignore_paths.clone().unwrap_or_default(),
+            previous: Mutex::new(HashMap::new()),
+            drift_events: AtomicUsize::new(0),
+        }
+    }
+
+    /// 运行期间检测到的漂移次数
+    pub fn drift_events(&self) -> usize {
+        self.drift_events.load(Ordering::Relaxed)
+    }
+}
+
+impl RequestObserver for DriftDetector {
+    fn on_result(&self, result: &RequestResult) {
+        if !self.labels.contains(&result.label) {
+            return;
+        }
+        let Some(response) = &result.response else {
+            return;
+        };
+
+        // 非JSON响应按去除首尾空白后的文本比较
+        let current = serde_json::from_slice(&response.body)
+            .unwrap_or_else(|_| Value::String(response.text().trim().to_string()));
+        let key = (result.label.clone(), result.target.clone());
+
+        let mut previous = self.previous.lock().unwrap();
+        if let Some(last) = previous.get(&key) {
+            let differences = json_diff::diff(last, &current, &self.ignore_paths);
+            if !differences.is_empty() {
+                self.drift_events.fetch_add(1, Ordering::Relaxed);
+                let kind = if differences.iter().any(|d| d.is_structural()) {
+                    "shape"
+                } else {
+                    "values"
+                };
+                log_warn!(
+                    "🔀 Response drift in cycle {} for {}{}: {} changed ({} difference(s))",
+                    result.cycle,
+                    result.label,
+                    result
+                        .target
+                        .as_deref()
+                        .map(|target| format!(" @ {}", target))
+                        .unwrap_or_default(),
+                    kind,
+                    differences.len()
+                );
+                for difference in differences.iter().take(10) {
+                    log_warn!("    {}", difference);
+                }
+            }
+        }
+        previous.insert(key, current);
+    }
+}
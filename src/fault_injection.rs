@@ -0,0 +1,90 @@
+use rand::{Rng, RngCore};
+use std::collections::BTreeMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+
+use crate::config::FaultInjectionConfig;
+use crate::rng;
+
+/// 单个请求的故障计划
+#[derive(Debug, Clone, Default)]
+pub struct FaultPlan {
+    /// 发送前的延迟
+    pub delay: Option<Duration>,
+    /// 发出后经过该时长中止请求
+    pub abort_after: Option<Duration>,
+    /// body截断后的长度（字节）
+    pub truncate_to: Option<usize>,
+}
+
+/// 客户端故障注入：按概率为请求注入延迟、中途中止或截断body，用于验证设备对异常客户端的处理
+pub struct FaultInjector {
+    config: FaultInjectionConfig,
+    delays: AtomicUsize,
+    aborts: AtomicUsize,
+    truncations: AtomicUsize,
+}
+
+impl FaultInjector {
+    pub fn new(config: &FaultInjectionConfig) -> Self {
+        Self {
+            config: config.clone(),
+            delays: AtomicUsize::new(0),
+            aborts: AtomicUsize::new(0),
+            truncations: AtomicUsize::new(0),
+        }
+    }
+
+    /// 为一个请求抽取故障计划
+    pub fn plan(&self, body: Option<&str>) -> FaultPlan {
+        rng::with(|rng| self.draw(rng, body))
+    }
+
+    fn draw(&self, rng: &mut dyn RngCore, body: Option<&str>) -> FaultPlan {
+        let mut plan = FaultPlan::default();
+
+        if rng.gen_bool(probability(self.config.delay_probability)) {
+            let max = self.config.delay_ms.unwrap_or(0);
+            plan.delay = Some(Duration::from_millis(rng.gen_range(0..=max)));
+            self.delays.fetch_add(1, Ordering::Relaxed);
+        }
+        if rng.gen_bool(probability(self.config.abort_probability)) {
+            let max = self.config.abort_after_ms.unwrap_or(100);
+            plan.abort_after = Some(Duration::from_millis(rng.gen_range(0..=max)));
+        }
+        if let Some(body) = body.filter(|body| !body.is_empty())
+            && rng.gen_bool(probability(self.config.truncate_probability))
+        {
+            let mut length = rng.gen_range(0..body.len());
+            while !body.is_char_boundary(length) {
+                length -= 1;
+            }
+            plan.truncate_to = Some(length);
+            self.truncations.fetch_add(1, Ordering::Relaxed);
+        }
+        plan
+    }
+
+    /// 记录一次实际发生的中止（请求在中止时机前完成时不计）
+    pub fn record_abort(&self) {
+        self.aborts.fetch_add(1, Ordering::Relaxed);
+    }
+
+    /// 各类故障的注入次数
+    pub fn injected(&self) -> BTreeMap<String, usize> {
+        [
+            ("delay", &self.delays),
+            ("abort", &self.aborts),
+            ("truncate", &self.truncations),
+        ]
+        .into_iter()
+        .map(|(name, count)| (name.to_string(), count.load(Ordering::Relaxed)))
+        .filter(|(_, count)| *count > 0)
+        .collect()
+    }
+}
+
+/// 将可选概率限定在 0-1 之间
+fn probability(value: Option<f64>) -> f64 {
+    value.unwrap_or(0.0).clamp(0.0, 1.0)
+}
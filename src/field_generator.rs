@@ -1,35 +1,76 @@
-use std::collections::HashMap;
-use uuid::Uuid;
+use rand::Rng;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+use std::sync::Mutex;
+use ulid::Ulid;
+use uuid::Builder;
 
 use crate::config::GeneratedField;
+use crate::device_clock::DeviceClock;
+use crate::rng;
+use crate::template;
+
+/// nanoid的默认长度
+const NANOID_DEFAULT_LENGTH: usize = 21;
+
+/// `unique: "regenerate"` 的字段最多重新生成的次数，仍重复时按 `"error"` 处理
+const UNIQUE_MAX_ATTEMPTS: usize = 10;
 
 /// 字段生成器
 pub struct FieldGenerator;
 
 impl FieldGenerator {
-    /// 根据配置生成字段值
+    /// 根据配置生成字段值，配置了 `filters` 时对生成值应用过滤器（出错时保留原值）
     pub fn generate_field(field: &GeneratedField, cycle: usize) -> String {
+        Self::generate_field_with_clock(field, cycle, &DeviceClock::new())
+    }
+
+    /// 同 `generate_field`，时间相关的生成器（timestamp、ulid、uuid v7）使用给定的时钟
+    pub fn generate_field_with_clock(
+        field: &GeneratedField,
+        cycle: usize,
+        clock: &DeviceClock,
+    ) -> String {
+        let value = Self::generate_value(field, cycle, clock);
+        match &field.filters {
+            Some(filters) => template::apply_filter_chain(&value, filters).unwrap_or(value),
+            None => value,
+        }
+    }
+
+    fn generate_value(field: &GeneratedField, cycle: usize, clock: &DeviceClock) -> String {
         match field.generator.as_str() {
             "random" => Self::generate_random(cycle),
-            "timestamp" => Self::generate_timestamp(cycle),
+            "timestamp" => Self::generate_timestamp(field, cycle, clock),
             "counter" => Self::generate_counter(cycle),
-            "uuid" => Self::generate_uuid(),
+            "uuid" => Self::generate_uuid(field, clock),
+            "ulid" => Self::generate_ulid(clock),
+            "nanoid" => Self::generate_nanoid(field),
             "fixed" => field.value.clone().unwrap_or_else(|| "default".to_string()),
+            // 填充内容在渲染完body后按剩余字节数生成，见 `pad_body`
+            "padding" => String::new(),
             _ => field.value.clone().unwrap_or_else(|| "unknown".to_string()),
         }
     }
 
-    /// 分离字段类型（header vs body）
+    /// 分离字段类型（header vs body），配置了 `unique` 的字段在 `unique_values` 中去重
     pub fn separate_fields_by_type(
         generated_fields: &Option<Vec<GeneratedField>>,
         cycle: usize,
+        unique_values: &UniqueValues,
+        clock: &DeviceClock,
     ) -> (HashMap<String, String>, HashMap<String, String>) {
         let mut header_fields = HashMap::new();
         let mut body_fields = HashMap::new();
 
         if let Some(field_configs) = generated_fields {
             for field_config in field_configs {
-                let value = Self::generate_field(field_config, cycle);
+                let value = if field_config.unique.is_some() {
+                    unique_values.generate(field_config, cycle, clock)
+                } else {
+                    Self::generate_field_with_clock(field_config, cycle, clock)
+                };
                 if field_config.field_type == "body" {
                     body_fields.insert(field_config.name.clone(), value);
                 } else {
@@ -47,15 +88,11 @@ impl FieldGenerator {
         body_fields: &HashMap<String, String>,
     ) -> Option<String> {
         if let Some(body) = base_body {
-            let mut dynamic_body = body.clone();
-
-            // 替换body中的占位符为生成的值
-            for (field_name, field_value) in body_fields {
-                let placeholder = format!("{{{}}}", field_name);
-                dynamic_body = dynamic_body.replace(&placeholder, field_value);
-            }
-
-            Some(dynamic_body)
+            // 替换body中的 {{name}} 占位符（及旧版的 {name}）为生成的值
+            Some(template::render(
+                &template::upgrade_legacy(body, body_fields.keys()),
+                body_fields,
+            ))
         } else {
             // 如果没有基础body，创建一个包含body字段的JSON对象
             if !body_fields.is_empty() {
@@ -78,19 +115,19 @@ impl FieldGenerator {
 
     /// 生成随机值
     fn generate_random(cycle: usize) -> String {
-        use rand::Rng;
-        let mut rng = rand::thread_rng();
-        let random_num: u32 = rng.gen_range(1000..9999);
+        let random_num: u32 = rng::with(|rng| rng.gen_range(1000..9999));
         format!("random_{}_{}", cycle, random_num)
     }
 
-    /// 生成时间戳
-    fn generate_timestamp(cycle: usize) -> String {
-        let now = std::time::SystemTime::now()
-            .duration_since(std::time::UNIX_EPOCH)
-            .unwrap()
-            .as_millis();
-        format!("timestamp_{}_{}", cycle, now)
+    /// 生成时间戳（按 `format` 输出毫秒、秒、RFC 3339，或默认的 `timestamp_<周期>_<毫秒>`）
+    fn generate_timestamp(field: &GeneratedField, cycle: usize, clock: &DeviceClock) -> String {
+        let now = clock.now_millis();
+        match field.format.as_deref() {
+            Some("unix_ms") => now.to_string(),
+            Some("unix") => now.div_euclid(1000).to_string(),
+            Some("rfc3339") => clock.now_rfc3339(),
+            _ => format!("timestamp_{}_{}", cycle, now),
+        }
     }
 
     /// 生成计数器值
@@ -98,8 +135,177 @@ impl FieldGenerator {
         format!("counter_{}", cycle)
     }
 
-    /// 生成UUID
-    fn generate_uuid() -> String {
-        Uuid::new_v4().to_string()
+    /// 生成UUID：v4为随机，v7以毫秒时间戳开头、按生成时间排序
+    fn generate_uuid(field: &GeneratedField, clock: &DeviceClock) -> String {
+        let uuid = match field.version.as_deref() {
+            Some("v7") => {
+                let millis = clock.now_millis().max(0) as u64;
+                let random = rng::with(|rng| rng.r#gen());
+                Builder::from_unix_timestamp_millis(millis, &random).into_uuid()
+            }
+            _ => Builder::from_random_bytes(rng::with(|rng| rng.r#gen())).into_uuid(),
+        };
+        match field.format.as_deref() {
+            Some("simple") => uuid.simple().to_string(),
+            Some("upper") => uuid.hyphenated().to_string().to_uppercase(),
+            Some("upper_simple") => uuid.simple().to_string().to_uppercase(),
+            _ => uuid.hyphenated().to_string(),
+        }
+    }
+
+    /// 生成ULID（`ulid` crate）：48位毫秒时间戳（按给定时钟）加80位随机数，按生成时间排序
+    fn generate_ulid(clock: &DeviceClock) -> String {
+        let millis = clock.now_millis().max(0) as u64;
+        let random = rng::with(|rng| rng.r#gen::<u128>());
+        Ulid::from_parts(millis, random).to_string()
+    }
+
+    /// 生成nanoid（`nanoid` crate，URL安全字符）：长度取自value（默认21），随机字节取自运行的随机数源
+    fn generate_nanoid(field: &GeneratedField) -> String {
+        let length = field
+            .value
+            .as_deref()
+            .and_then(|value| value.trim().parse().ok())
+            .unwrap_or(NANOID_DEFAULT_LENGTH);
+        let random = |size: usize| rng::with(|rng| (0..size).map(|_| rng.r#gen::<u8>()).collect());
+        nanoid::format(random, &nanoid::alphabet::SAFE, length)
+    }
+
+    /// 配置中的padding字段及其目标body大小（字节），大小中的 `{{name}}` 占位符按变量渲染
+    pub fn padding_fields(
+        generated_fields: &Option<Vec<GeneratedField>>,
+        vars: &HashMap<String, String>,
+    ) -> Vec<(String, usize)> {
+        generated_fields
+            .iter()
+            .flatten()
+            .filter(|field| field.generator == "padding" && field.field_type == "body")
+            .filter_map(|field| {
+                let size = parse_byte_size(&template::render(field.value.as_deref()?, vars))?;
+                Some((field.name.clone(), size))
+            })
+            .collect()
+    }
+
+    /// 计算padding字段的填充内容，返回渲染后达到目标大小的body模板
+    ///
+    /// padding占位符先按空串渲染以测出其余内容的长度，再用 `x` 填满差额；占位符出现多次时各次出现
+    /// 单独编号并平分差额，余数分给前几次出现，body恰好达到目标大小。其余内容已超过目标大小时不填充。
+    pub fn fill_padding(
+        base_body: &str,
+        padding: &[(String, usize)],
+        body_fields: &mut HashMap<String, String>,
+    ) -> String {
+        let mut body = base_body.to_string();
+        for (name, size) in padding {
+            let (numbered, names) = template::number_occurrences(&body, name);
+            if names.is_empty() {
+                continue;
+            }
+            body = numbered;
+            for name in &names {
+                body_fields.insert(name.clone(), String::new());
+            }
+            let rendered = template::render(&body, body_fields).len();
+            let deficit = size.saturating_sub(rendered);
+            for (index, name) in names.iter().enumerate() {
+                let filler = deficit / names.len() + usize::from(index < deficit % names.len());
+                body_fields.insert(name.clone(), "x".repeat(filler));
+            }
+        }
+        body
+    }
+}
+
+/// 运行内 `unique` 字段已生成的取值，检测重复并按配置重新生成或报告
+///
+/// 记录每个字段在本次运行中生成过的全部取值，只保存64位哈希，每个取值占用固定的少量内存
+#[derive(Debug, Default)]
+pub struct UniqueValues {
+    seen: Mutex<HashMap<String, SeenValues>>,
+    duplicates: Mutex<Vec<DuplicateValue>>,
+}
+
+/// 单个字段生成过的取值（哈希）
+#[derive(Debug, Default)]
+struct SeenValues {
+    hashes: HashSet<u64>,
+}
+
+impl SeenValues {
+    /// 记录取值，之前出现过时返回false
+    fn insert(&mut self, value: &str) -> bool {
+        let mut hasher = DefaultHasher::new();
+        value.hash(&mut hasher);
+        self.hashes.insert(hasher.finish())
+    }
+}
+
+/// 一次检测到的重复取值
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DuplicateValue {
+    pub field: String,
+    pub value: String,
+    /// 已重新生成出不重复的值（为false时重复的值仍被使用）
+    pub regenerated: bool,
+}
+
+impl UniqueValues {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 生成字段值并记录；与之前的值重复时，`"regenerate"` 模式重新生成，其余情况记录为未解决的重复
+    pub fn generate(&self, field: &GeneratedField, cycle: usize, clock: &DeviceClock) -> String {
+        let mut seen = self.seen.lock().unwrap();
+        let values = seen.entry(field.name.clone()).or_default();
+        let attempts = if field.unique.as_deref() == Some("regenerate") {
+            UNIQUE_MAX_ATTEMPTS
+        } else {
+            1
+        };
+        let mut duplicate = None;
+        for _ in 0..attempts {
+            let value = FieldGenerator::generate_field_with_clock(field, cycle, clock);
+            if values.insert(&value) {
+                if let Some(duplicate) = duplicate {
+                    self.record(field, duplicate, true);
+                }
+                return value;
+            }
+            duplicate = Some(value);
+        }
+        let value = duplicate.expect("at least one attempt");
+        self.record(field, value.clone(), false);
+        value
     }
+
+    fn record(&self, field: &GeneratedField, value: String, regenerated: bool) {
+        self.duplicates.lock().unwrap().push(DuplicateValue {
+            field: field.name.clone(),
+            value,
+            regenerated,
+        });
+    }
+
+    /// 取出上次调用以来检测到的重复
+    pub fn take_duplicates(&self) -> Vec<DuplicateValue> {
+        std::mem::take(&mut *self.duplicates.lock().unwrap())
+    }
+}
+
+/// 解析字节大小："1024"、"512B"、"64KB"、"10MB"（按1024进位，不区分大小写）
+pub fn parse_byte_size(text: &str) -> Option<usize> {
+    let text = text.trim().to_uppercase();
+    let (number, unit) = match text.find(|c: char| !c.is_ascii_digit()) {
+        Some(index) => text.split_at(index),
+        None => (text.as_str(), ""),
+    };
+    let multiplier = match unit.trim() {
+        "" | "B" => 1,
+        "K" | "KB" | "KIB" => 1024,
+        "M" | "MB" | "MIB" => 1024 * 1024,
+        _ => return None,
+    };
+    number.parse::<usize>().ok()?.checked_mul(multiplier)
 }
@@ -0,0 +1,134 @@
+use anyhow::Result;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::task::JoinHandle;
+use tokio::time::MissedTickBehavior;
+use tokio_util::sync::CancellationToken;
+
+use crate::config::{HeartbeatConfig, RequestConfig};
+use crate::cycle_context::CycleContext;
+use crate::http_client::{HttpClient, HttpClientConfig, ResponseData};
+use crate::request_handler::RequestHandler;
+use crate::stats::LabelStats;
+use crate::template;
+
+// Import logger macros from crate root
+use crate::{log_debug, log_warn};
+
+/// 心跳请求：主循环运行期间在后台按固定间隔发送（如ISAPI sessionHeartbeat保持登录），
+/// 结果单独统计，不回调观察者，也不计入请求A/B的统计
+pub struct Heartbeat {
+    method: String,
+    url: String,
+    headers: Vec<(String, String)>,
+    body: Option<String>,
+    interval: Duration,
+    http_client: HttpClient,
+    stats: Mutex<LabelStats>,
+}
+
+impl Heartbeat {
+    /// 按运行作用域的变量（配置变量和worker变量）渲染心跳请求，凭据和Unix套接字沿用全局配置
+    pub fn new(heartbeat: &HeartbeatConfig, config: &RequestConfig) -> Result<Self> {
+        let variables = CycleContext::new(config, 0, None, Vec::new(), None).variables;
+        let render = |text: &str| template::render(text, &variables);
+        let interval = Duration::from_millis(heartbeat.interval_ms.max(1));
+        let timeout = heartbeat
+            .timeout_ms
+            .map(Duration::from_millis)
+            .unwrap_or_else(|| interval.min(Duration::from_secs(30)));
+        let http_client = HttpClient::new(HttpClientConfig {
+            timeout,
+            ..RequestHandler::http_client_config(
+                config.digest_auth.as_ref(),
+                config.unix_socket.as_deref(),
+                None,
+                &variables,
+            )
+        })?;
+        Ok(Self {
+            method: heartbeat.method.as_deref().unwrap_or("PUT").to_uppercase(),
+            url: render(&heartbeat.url),
+            headers: heartbeat
+                .headers
+                .iter()
+                .flatten()
+                .map(|(name, value)| (name.clone(), render(value)))
+                .collect(),
+            body: heartbeat.body.as_deref().map(render),
+            interval,
+            http_client,
+            stats: Mutex::new(LabelStats::default()),
+        })
+    }
+
+    /// 启动后台任务，每个间隔发送一次心跳，直到 `stop` 取消（进行中的心跳随之中止）
+    pub fn spawn(self: &Arc<Self>, stop: CancellationToken) -> JoinHandle<()> {
+        let heartbeat = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(heartbeat.interval);
+            // 心跳响应慢时不补发错过的心跳
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            interval.tick().await;
+            loop {
+                if stop.run_until_cancelled(interval.tick()).await.is_none()
+                    || stop.run_until_cancelled(heartbeat.beat()).await.is_none()
+                {
+                    return;
+                }
+            }
+        })
+    }
+
+    /// 心跳统计
+    pub fn stats(&self) -> LabelStats {
+        self.stats.lock().unwrap().clone()
+    }
+
+    /// 发送一次心跳并记录结果
+    async fn beat(&self) {
+        let start = Instant::now();
+        let headers: Vec<(&str, &str)> = self
+            .headers
+            .iter()
+            .map(|(name, value)| (name.as_str(), value.as_str()))
+            .collect();
+        let result = match self
+            .http_client
+            .send_request(&self.method, &self.url, self.body.clone(), Some(headers))
+            .await
+        {
+            Ok(response) => ResponseData::read(response).await,
+            Err(e) => Err(e),
+        };
+        let elapsed = start.elapsed();
+
+        let mut stats = self.stats.lock().unwrap();
+        stats.total_requests += 1;
+        stats.latencies.push(elapsed);
+        stats.bytes_sent += self.body.as_ref().map_or(0, |body| body.len() as u64);
+        let error = match result {
+            Ok(response) => {
+                stats.bytes_received += response.body_len();
+                (!response.status.is_success()).then(|| format!("HTTP {}", response.status))
+            }
+            Err(e) => Some(format!("{:#}", e)),
+        };
+        match error {
+            None => {
+                stats.successful_requests += 1;
+                log_debug!("💓 Heartbeat OK ({}ms)", elapsed.as_millis());
+            }
+            Some(error) => {
+                stats.failed_requests += 1;
+                log_warn!(
+                    "💔 Heartbeat {} {} failed: {}",
+                    self.method,
+                    self.url,
+                    error
+                );
+                stats.last_error = Some(error);
+            }
+        }
+    }
+}
@@ -1,9 +1,31 @@
-use anyhow::{Result, anyhow};
+use anyhow::{Context, Result, anyhow};
 use base64::prelude::*;
+use bytes::Bytes;
 use digest_auth::{AuthContext, HttpMethod, WwwAuthenticateHeader};
-use reqwest::{Client, Response, cookie::Jar};
+use reqwest::cookie::{CookieStore, Jar};
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Response, StatusCode, Url};
+use std::collections::{HashMap, VecDeque};
+use std::net::{IpAddr, Ipv4Addr, Ipv6Addr, SocketAddr};
+use std::path::PathBuf;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio_util::sync::CancellationToken;
+use tracing::{Instrument, info_span};
+
+use crate::bandwidth::BandwidthLimiter;
+use crate::config::{DigestAuthConfig, ResponseLimitConfig};
+use crate::download::DownloadInfo;
+use crate::recorder::sanitize;
+use crate::telemetry::Telemetry;
+
+// Import logger macros from crate root
+use crate::{log_error, log_warn};
+
+/// 默认的User-Agent
+pub const DEFAULT_USER_AGENT: &str = "RemoteTask-HTTP-Client/1.0";
 
 /// HTTP 客户端配置
 #[derive(Debug, Clone)]
@@ -11,6 +33,18 @@ pub struct HttpClientConfig {
     pub timeout: Duration,
     pub user_agent: String,
     pub auth: Option<AuthConfig>,
+    /// 通过Unix域套接字连接（URL中的主机仅用作Host头）
+    pub unix_socket: Option<String>,
+    /// 经HTTP代理连接目标，代理认证与 `auth` 相互独立
+    pub proxy: Option<ProxySettings>,
+}
+
+/// HTTP代理：Basic凭据由reqwest随每个请求和HTTPS隧道的CONNECT发送；
+/// Digest凭据按代理的407挑战在本地计算Proxy-Authorization头（只适用于http://目标）
+#[derive(Debug, Clone)]
+pub struct ProxySettings {
+    pub url: String,
+    pub auth: Option<AuthConfig>,
 }
 
 /// 认证配置
@@ -19,6 +53,27 @@ pub struct AuthConfig {
     pub username: String,
     pub password: String,
     pub auth_type: AuthType,
+    /// 首个请求即携带Authorization，不先发送探测请求
+    pub preemptive: bool,
+    /// 预先已知的Digest挑战参数 (realm, nonce)，抢先认证时尚无挑战可用则据此计算
+    pub known_challenge: Option<(String, String)>,
+}
+
+impl AuthConfig {
+    /// 由配置的凭据创建，用户名和密码经 `render` 替换占位符
+    pub fn from_config(auth: &DigestAuthConfig, render: impl Fn(&str) -> String) -> Self {
+        Self {
+            username: render(&auth.username),
+            password: render(&auth.password),
+            auth_type: if auth.uses_basic() {
+                AuthType::Basic
+            } else {
+                AuthType::Digest
+            },
+            preemptive: auth.preemptive.unwrap_or(false),
+            known_challenge: auth.known_challenge(),
+        }
+    }
 }
 
 /// 认证类型
@@ -32,47 +87,480 @@ impl Default for HttpClientConfig {
     fn default() -> Self {
         Self {
             timeout: Duration::from_secs(30),
-            user_agent: "Rust-HTTP-Client/1.0".to_string(),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
             auth: None,
+            unix_socket: None,
+            proxy: None,
+        }
+    }
+}
+
+/// 连接使用的IP地址族
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum IpFamily {
+    /// 双栈时按DNS结果顺序尝试，失败后回退到另一地址族（Happy Eyeballs）
+    #[default]
+    Auto,
+    V4,
+    V6,
+}
+
+impl IpFamily {
+    pub fn parse(family: &str) -> Option<Self> {
+        match family.to_lowercase().as_str() {
+            "auto" => Some(IpFamily::Auto),
+            "ipv4" => Some(IpFamily::V4),
+            "ipv6" => Some(IpFamily::V6),
+            _ => None,
+        }
+    }
+
+    /// 地址是否属于该地址族
+    pub fn allows(self, address: &SocketAddr) -> bool {
+        match self {
+            IpFamily::Auto => true,
+            IpFamily::V4 => address.is_ipv4(),
+            IpFamily::V6 => address.is_ipv6(),
+        }
+    }
+}
+
+/// 已完整读取body的HTTP响应
+#[derive(Debug, Clone)]
+pub struct ResponseData {
+    pub status: StatusCode,
+    pub headers: HeaderMap,
+    /// 响应body（下载模式下写入文件，此处为空）
+    pub body: Bytes,
+    /// 实际连接的对端地址（Unix域套接字等情况下为None）
+    pub remote_addr: Option<SocketAddr>,
+    /// 下载模式下保存的文件信息
+    pub download: Option<DownloadInfo>,
+    /// body超出缓冲上限时的信息（此时 `body` 只含前 `max_bytes` 字节）
+    pub overflow: Option<BodyOverflow>,
+}
+
+/// 默认保留的溢出文件数
+const DEFAULT_KEEP_SPILL_FILES: usize = 100;
+
+/// 运行作用域的响应body缓冲上限（`response_limit` 配置），为每个请求生成 [`BodyLimit`]
+#[derive(Debug)]
+pub struct ResponseLimit {
+    max_bytes: u64,
+    spill_dir: Option<PathBuf>,
+    spilled: AtomicUsize,
+    spill_files: Arc<SpillFiles>,
+}
+
+impl ResponseLimit {
+    pub fn new(config: &ResponseLimitConfig) -> Self {
+        Self {
+            max_bytes: config.max_bytes,
+            spill_dir: config.spill_dir.as_ref().map(PathBuf::from),
+            spilled: AtomicUsize::new(0),
+            spill_files: Arc::new(SpillFiles {
+                keep: config
+                    .keep_spill_files
+                    .unwrap_or(DEFAULT_KEEP_SPILL_FILES)
+                    .max(1),
+                files: std::sync::Mutex::new(VecDeque::new()),
+            }),
+        }
+    }
+
+    /// 单个请求的上限，`name` 用作溢出文件名（附加序号避免重名）
+    pub fn for_request(&self, name: &str) -> BodyLimit {
+        BodyLimit {
+            max_bytes: self.max_bytes,
+            spill_path: self.spill_dir.as_ref().map(|dir| {
+                let sequence = self.spilled.fetch_add(1, Ordering::Relaxed);
+                dir.join(format!("{}-{}.body", sanitize(name), sequence))
+            }),
+            spill_files: Arc::clone(&self.spill_files),
+        }
+    }
+}
+
+/// 本次运行写出的溢出文件，超出保留数时删除最早的文件，避免长时间运行写满磁盘
+#[derive(Debug)]
+struct SpillFiles {
+    keep: usize,
+    files: std::sync::Mutex<VecDeque<PathBuf>>,
+}
+
+impl SpillFiles {
+    /// 登记新写出的文件，返回超出保留数需要删除的旧文件
+    fn add(&self, path: PathBuf) -> Vec<PathBuf> {
+        let mut files = self.files.lock().unwrap();
+        files.push_back(path);
+        let excess = files.len().saturating_sub(self.keep);
+        files.drain(..excess).collect()
+    }
+}
+
+/// 单个响应的body缓冲上限
+#[derive(Debug, Clone)]
+pub struct BodyLimit {
+    pub max_bytes: u64,
+    /// 超出上限时完整body写入的文件（None时截断并停止读取）
+    pub spill_path: Option<PathBuf>,
+    spill_files: Arc<SpillFiles>,
+}
+
+/// body超出缓冲上限的响应
+#[derive(Debug, Clone)]
+pub struct BodyOverflow {
+    /// 实际读取的字节数（截断时读到超出上限即停止，不含之后未读取的部分）
+    pub bytes: u64,
+    /// 完整body写入的文件（截断时为None）
+    pub spilled_to: Option<PathBuf>,
+}
+
+impl ResponseData {
+    /// 读取响应的状态、头部和完整body
+    pub async fn read(response: Response) -> Result<Self> {
+        Self::read_with(response, None, None).await
+    }
+
+    /// 按下载限速分块读取完整响应
+    pub async fn read_limited(response: Response, limiter: &BandwidthLimiter) -> Result<Self> {
+        Self::read_with(response, Some(limiter), None).await
+    }
+
+    /// 分块读取响应：配置了 `limiter` 时按下载限速读取；配置了 `limit` 时内存中最多缓冲
+    /// `max_bytes` 字节，超出后截断并停止读取，或将完整body继续写入 `spill_path`
+    pub async fn read_with(
+        mut response: Response,
+        limiter: Option<&BandwidthLimiter>,
+        limit: Option<&BodyLimit>,
+    ) -> Result<Self> {
+        let status = response.status();
+        let headers = response.headers().clone();
+        let remote_addr = response.remote_addr();
+        let max_bytes = limit.map_or(usize::MAX, |limit| {
+            usize::try_from(limit.max_bytes).unwrap_or(usize::MAX)
+        });
+        let mut body = Vec::new();
+        let mut received = 0u64;
+        let mut overflowed = false;
+        let mut spill: Option<(PathBuf, tokio::fs::File)> = None;
+        while let Some(chunk) = response
+            .chunk()
+            .await
+            .map_err(|e| anyhow!("Failed to read response body: {}", e))?
+        {
+            if let Some(limiter) = limiter {
+                limiter.consume(chunk.len()).await;
+            }
+            received += chunk.len() as u64;
+            if let Some((path, file)) = spill.as_mut() {
+                file.write_all(&chunk)
+                    .await
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                continue;
+            }
+            if body.len() + chunk.len() <= max_bytes {
+                body.extend_from_slice(&chunk);
+                continue;
+            }
+
+            // 超出上限：已缓冲的部分和本块写入溢出文件，内存中只保留前max_bytes字节
+            overflowed = true;
+            if let Some(path) = limit.and_then(|limit| limit.spill_path.clone()) {
+                if let Some(parent) = path.parent()
+                    && !parent.as_os_str().is_empty()
+                {
+                    tokio::fs::create_dir_all(parent)
+                        .await
+                        .with_context(|| format!("Failed to create {}", parent.display()))?;
+                }
+                let mut file = tokio::fs::File::create(&path)
+                    .await
+                    .with_context(|| format!("Failed to create {}", path.display()))?;
+                file.write_all(&body)
+                    .await
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                file.write_all(&chunk)
+                    .await
+                    .with_context(|| format!("Failed to write {}", path.display()))?;
+                spill = Some((path, file));
+            }
+            body.extend_from_slice(&chunk[..max_bytes - body.len()]);
+            if spill.is_none() {
+                break;
+            }
+        }
+        if let Some((path, file)) = spill.as_mut() {
+            file.flush()
+                .await
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            let evicted = limit.map_or_else(Vec::new, |limit| limit.spill_files.add(path.clone()));
+            for old in evicted {
+                if let Err(e) = tokio::fs::remove_file(&old).await {
+                    log_warn!("Failed to remove old spill file {}: {}", old.display(), e);
+                }
+            }
         }
+        Ok(Self {
+            status,
+            headers,
+            body: Bytes::from(body),
+            remote_addr,
+            download: None,
+            overflow: overflowed.then(|| BodyOverflow {
+                bytes: received,
+                spilled_to: spill.map(|(path, _)| path),
+            }),
+        })
+    }
+
+    /// 响应body字节数（下载模式下为写入文件的字节数，超出缓冲上限时为实际读取的字节数）
+    pub fn body_len(&self) -> u64 {
+        match (&self.download, &self.overflow) {
+            (Some(download), _) => download.bytes,
+            (None, Some(overflow)) => overflow.bytes,
+            (None, None) => self.body.len() as u64,
+        }
+    }
+
+    /// 以UTF-8文本形式返回body（非法字节被替换）
+    pub fn text(&self) -> String {
+        String::from_utf8_lossy(&self.body).into_owned()
     }
 }
 
+/// 运行作用域的客户端缓存：配置相同（超时、User-Agent、凭据、Unix套接字）时复用同一底层客户端，
+/// 避免每个周期重新创建连接池和TLS上下文
+#[derive(Default)]
+pub struct HttpClientCache {
+    clients: std::sync::Mutex<HashMap<String, HttpClient>>,
+}
+
+impl HttpClientCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 按配置取得客户端的新会话（见 [`HttpClient::new_session`]），首次使用该配置时创建客户端
+    pub fn session(&self, config: HttpClientConfig) -> Result<HttpClient> {
+        let credentials = |auth: &AuthConfig| {
+            let kind = match auth.auth_type {
+                AuthType::Basic => "basic",
+                AuthType::Digest => "digest",
+            };
+            (kind, auth.username.clone(), auth.password.clone())
+        };
+        let auth = config.auth.as_ref().map(credentials);
+        let proxy = config
+            .proxy
+            .as_ref()
+            .map(|proxy| (proxy.url.as_str(), proxy.auth.as_ref().map(credentials)));
+        let key = format!(
+            "{:?}",
+            (
+                config.timeout,
+                &config.user_agent,
+                auth,
+                &config.unix_socket,
+                proxy
+            )
+        );
+        let mut clients = self.clients.lock().unwrap();
+        if let Some(client) = clients.get(&key) {
+            return Ok(client.new_session());
+        }
+        let client = HttpClient::new(config)?;
+        clients.insert(key, client.clone());
+        Ok(client)
+    }
+
+    /// 已创建的底层客户端数
+    pub fn len(&self) -> usize {
+        self.clients.lock().unwrap().len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+/// 请求因运行中断被取消（超过宽限期仍未完成）
+#[derive(Debug, Clone, Copy)]
+pub struct RequestCancelled;
+
+impl std::fmt::Display for RequestCancelled {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("request cancelled on shutdown")
+    }
+}
+
+impl std::error::Error for RequestCancelled {}
+
+/// 请求在代理处失败（无法连接代理、隧道建立失败或代理认证被拒绝），
+/// 统计时与目标的失败区分
+#[derive(Debug, Clone)]
+pub struct ProxyError(pub String);
+
+impl std::fmt::Display for ProxyError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "proxy error: {}", self.0)
+    }
+}
+
+impl std::error::Error for ProxyError {}
+
+/// 认证状态：Digest缓存服务器的挑战，每个请求据此在本地计算Authorization头（nc递增）
+#[derive(Debug)]
+enum AuthState {
+    /// 尚未探测
+    Unknown,
+    /// 探测请求未被要求认证
+    NotRequired,
+    Basic,
+    Digest(WwwAuthenticateHeader),
+}
+
 /// HTTP 客户端服务
 #[derive(Clone)]
 pub struct HttpClient {
     client: Client,
     config: HttpClientConfig,
     cookie_jar: Arc<Jar>,
-    auth_cache: Arc<tokio::sync::Mutex<Option<String>>>,
+    auth_cache: Arc<tokio::sync::Mutex<AuthState>>,
+    proxy_auth_cache: Arc<tokio::sync::Mutex<Option<WwwAuthenticateHeader>>>,
+    /// 各会话最近采用的Digest挑战（按源站），抢先认证的新会话据此计算首个请求的Authorization
+    last_challenges: Arc<std::sync::Mutex<HashMap<String, WwwAuthenticateHeader>>>,
+    family_clients: Arc<std::sync::Mutex<HashMap<IpFamily, Client>>>,
+    upload_limit: Option<Arc<BandwidthLimiter>>,
+    cancellation: Option<CancellationToken>,
 }
 
 impl HttpClient {
     /// 创建新的 HTTP 客户端服务
     pub fn new(config: HttpClientConfig) -> Result<Self> {
         let cookie_jar = Arc::new(Jar::default());
-
-        let client = Client::builder()
-            .timeout(config.timeout)
-            .user_agent(&config.user_agent)
-            .danger_accept_invalid_certs(true)
-            .cookie_provider(Arc::clone(&cookie_jar))
-            .build()
-            .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))?;
+        let client = Self::build_client(&config, &cookie_jar, IpFamily::Auto)?;
 
         Ok(Self {
             client,
             config,
             cookie_jar,
-            auth_cache: Arc::new(tokio::sync::Mutex::new(None)),
+            auth_cache: Arc::new(tokio::sync::Mutex::new(AuthState::Unknown)),
+            proxy_auth_cache: Arc::new(tokio::sync::Mutex::new(None)),
+            last_challenges: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            family_clients: Arc::new(std::sync::Mutex::new(HashMap::new())),
+            upload_limit: None,
+            cancellation: None,
         })
     }
 
+    /// 新会话：与原客户端共享连接池、TLS上下文和Cookie，认证缓存从空开始
+    /// （抢先认证时沿用其他会话最近采用的挑战）
+    pub fn new_session(&self) -> Self {
+        Self {
+            auth_cache: Arc::new(tokio::sync::Mutex::new(AuthState::Unknown)),
+            proxy_auth_cache: Arc::new(tokio::sync::Mutex::new(None)),
+            ..self.clone()
+        }
+    }
+
+    /// 限制请求body上传速率的客户端，与原客户端共享连接、Cookie和认证缓存
+    pub fn with_upload_limit(&self, limiter: Arc<BandwidthLimiter>) -> Self {
+        Self {
+            upload_limit: Some(limiter),
+            ..self.clone()
+        }
+    }
+
+    /// 令牌被取消时，进行中和之后的请求立即以 [`RequestCancelled`] 失败
+    pub fn with_cancellation(&self, token: CancellationToken) -> Self {
+        Self {
+            cancellation: Some(token),
+            ..self.clone()
+        }
+    }
+
+    /// 限定地址族的客户端，与原客户端共享Cookie和认证缓存
+    pub fn with_ip_family(&self, family: IpFamily) -> Result<Self> {
+        if family == IpFamily::Auto {
+            return Ok(self.clone());
+        }
+        let mut family_clients = self.family_clients.lock().unwrap();
+        let client = match family_clients.get(&family) {
+            Some(client) => client.clone(),
+            None => {
+                let client = Self::build_client(&self.config, &self.cookie_jar, family)?;
+                family_clients.insert(family, client.clone());
+                client
+            }
+        };
+        Ok(Self {
+            client,
+            ..self.clone()
+        })
+    }
+
+    fn build_client(
+        config: &HttpClientConfig,
+        cookie_jar: &Arc<Jar>,
+        family: IpFamily,
+    ) -> Result<Client> {
+        let mut builder = Client::builder()
+            .timeout(config.timeout)
+            .user_agent(&config.user_agent)
+            .danger_accept_invalid_certs(true)
+            .cookie_provider(Arc::clone(cookie_jar));
+        if let Some(path) = &config.unix_socket {
+            #[cfg(unix)]
+            {
+                builder = builder.unix_socket(path.as_str());
+            }
+            #[cfg(not(unix))]
+            return Err(anyhow!(
+                "Unix domain socket {} is not supported on this platform",
+                path
+            ));
+        }
+        // Unix域套接字连接不经过代理
+        if let Some(proxy) = config
+            .proxy
+            .as_ref()
+            .filter(|_| config.unix_socket.is_none())
+        {
+            let mut settings = reqwest::Proxy::all(&proxy.url)
+                .map_err(|e| anyhow!("Invalid proxy URL {}: {}", proxy.url, e))?;
+            if let Some(AuthConfig {
+                username,
+                password,
+                auth_type: AuthType::Basic,
+                ..
+            }) = &proxy.auth
+            {
+                settings = settings.basic_auth(username, password);
+            }
+            builder = builder.proxy(settings);
+        }
+        // 绑定到某一地址族的本地地址时，只会连接该地址族的远端地址
+        builder = match family {
+            IpFamily::Auto => builder,
+            IpFamily::V4 => builder.local_address(IpAddr::V4(Ipv4Addr::UNSPECIFIED)),
+            IpFamily::V6 => builder.local_address(IpAddr::V6(Ipv6Addr::UNSPECIFIED)),
+        };
+        builder
+            .build()
+            .map_err(|e| anyhow!("Failed to create HTTP client: {}", e))
+    }
+
     /// 使用默认配置创建 HTTP 客户端服务
     pub fn new_default() -> Result<Self> {
         Self::new(Default::default())
     }
 
+    /// 请求超时时间
+    pub fn timeout(&self) -> Duration {
+        self.config.timeout
+    }
+
     /// 发送 POST 请求（支持智能认证和认证复用）
     pub async fn post_json(
         &self,
@@ -80,10 +568,11 @@ impl HttpClient {
         json_data: &str,
         headers: Option<Vec<(&str, &str)>>,
     ) -> Result<Response> {
-        let mut request = self.client.post(url).body(json_data.to_string());
-
         // 添加默认的 Content-Type 头
-        request = request.header("Content-Type", "application/json");
+        let mut request = self
+            .client
+            .post(url)
+            .header("Content-Type", "application/json");
 
         // 添加自定义头部
         if let Some(headers) = headers {
@@ -92,214 +581,432 @@ impl HttpClient {
             }
         }
 
-        // 检查是否有缓存的认证头
-        if let Some(cached_auth) = self.get_cached_auth().await {
-            request = request.header("Authorization", &cached_auth);
-        }
+        self.send_authenticated(request, "POST", url, Some(json_data))
+            .await
+    }
 
-        // 发送请求
-        let response = request.try_clone().unwrap().send().await?;
+    /// 发送通用HTTP请求（支持认证复用）
+    pub async fn send_request(
+        &self,
+        method: &str,
+        url: &str,
+        body: Option<String>,
+        headers: Option<Vec<(&str, &str)>>,
+    ) -> Result<Response> {
+        // 配置中的方法名不区分大小写
+        let method = &method.to_ascii_uppercase();
+        let mut request = self.client.request(Self::http_method(method)?, url);
 
-        // 检查是否需要认证
-        if response.status().as_u16() != 401 {
-            return Ok(response);
+        // 对于有body的POST/PUT/PATCH请求，添加Content-Type头
+        if body.is_some() && matches!(method.as_str(), "POST" | "PUT" | "PATCH") {
+            request = request.header("Content-Type", "application/json");
         }
 
-        // 处理认证
-        let auth_string = self.get_auth(response, url, "POST").await?;
-
-        // 缓存认证头
-        self.set_cached_auth(auth_string.clone()).await;
+        // 添加自定义头部
+        if let Some(headers) = headers {
+            for (key, value) in headers {
+                request = request.header(key, value);
+            }
+        }
 
-        // 重新发送认证请求
-        let request = request.header("Authorization", auth_string);
-        request
-            .send()
+        self.send_authenticated(request, method, url, body.as_deref())
             .await
-            .map_err(|e| anyhow!("HTTP request failed: {}", e))
     }
 
-    /// 使用 digest_auth 库添加认证到请求
-    async fn get_auth(
+    /// 带认证发送请求，设置了取消令牌时与之竞争
+    async fn send_authenticated(
         &self,
-        response: reqwest::Response,
-        url: &str,
+        request: reqwest::RequestBuilder,
         method: &str,
-    ) -> Result<String> {
-        if let Some(auth_config) = &self.config.auth {
-            match auth_config.auth_type {
-                AuthType::Basic => {
-                    // Basic 认证
-                    let auth_value = format!("{}:{}", auth_config.username, auth_config.password);
-                    let encoded = BASE64_STANDARD.encode(auth_value);
-                    Ok(format!("Basic {}", encoded))
-                }
-                AuthType::Digest => {
-                    // 完全使用 digest_auth 库实现 Digest 认证
-                    self.get_digest_auth(response, url, method, auth_config)
-                        .await
-                }
-            }
-        } else {
-            Err(anyhow!("no auth config"))
+        url: &str,
+        body: Option<&str>,
+    ) -> Result<Response> {
+        let send = self.send_with_auth(request, method, url, body);
+        match &self.cancellation {
+            Some(token) => token
+                .run_until_cancelled(send)
+                .await
+                .unwrap_or_else(|| Err(RequestCancelled.into())),
+            None => send.await,
         }
     }
 
-    /// 使用 digest_auth 库实现 Digest 认证
-    async fn get_digest_auth(
+    /// Authorization头在本地计算，body只随带认证头的请求发出；
+    /// 缓存的挑战失效（如nonce过期）时采用401响应中的新挑战重发一次
+    async fn send_with_auth(
         &self,
-        response: reqwest::Response,
-        url: &str,
+        mut request: reqwest::RequestBuilder,
         method: &str,
-        auth_config: &AuthConfig,
-    ) -> Result<String> {
-        // 检查是否有 WWW-Authenticate 头
-        let auth_header = match response.headers().get("WWW-Authenticate") {
-            Some(header) => header,
-            None => return Err(anyhow!("Missing WWW-Authenticate header")),
-        };
-        let header_str = auth_header.to_str().unwrap_or("");
-        // println!("收到认证挑战: {}", header_str);
+        url: &str,
+        body: Option<&str>,
+    ) -> Result<Response> {
+        // 传递追踪上下文
+        for (key, value) in Telemetry::trace_context_headers() {
+            request = request.header(key, value);
+        }
 
-        // 使用 digest_auth 库解析挑战头
-        let mut auth_header = WwwAuthenticateHeader::parse(header_str).map_err(|e| {
-            let error_msg = format!("Digest认证头解析错误: {}", e);
-            println!("{}", error_msg);
-            anyhow!(error_msg)
-        })?;
-        let _http_method = match method.to_uppercase().as_str() {
-            "GET" => HttpMethod::GET,
-            "POST" => HttpMethod::POST,
-            "PUT" => HttpMethod::PUT,
-            "DELETE" => HttpMethod::DELETE,
-            _ => HttpMethod::GET, // 默认使用 GET
-        };
-        // 构建认证上下文 - 需要用户名、密码和URI
-        let context = AuthContext::new_with_method(
-            &auth_config.username,
-            &auth_config.password,
-            url,
-            None::<Vec<u8>>,
-            _http_method,
+        // 尚未附带body的请求可以克隆，重发时基于它重新构建
+        let retry = request
+            .try_clone()
+            .ok_or_else(|| anyhow!("request cannot be retried"))?;
+        let authorization = self.authorization(method, url).await?;
+        let response = self
+            .send_through_proxy(Self::authorize(request, authorization), method, url, body)
+            .await?;
+
+        let uses_digest = matches!(
+            self.config.auth,
+            Some(AuthConfig {
+                auth_type: AuthType::Digest,
+                ..
+            })
         );
+        if response.status() != StatusCode::UNAUTHORIZED || !uses_digest {
+            return Ok(response);
+        }
 
-        // 构建认证响应
-        let auth_response = auth_header.respond(&context).map_err(|e| {
-            let error_msg = format!("Digest认证响应错误: {}", e);
-            println!("{}", error_msg);
-            anyhow!(error_msg)
-        })?;
+        // 认证往返记录为独立span
+        async {
+            let authorization = self.accept_challenge(&response, method, url).await?;
+            self.send_through_proxy(
+                Self::authorize(retry, Some(authorization)),
+                method,
+                url,
+                body,
+            )
+            .await
+        }
+        .instrument(info_span!("auth_roundtrip", retry_attempt = 1))
+        .await
+    }
 
-        // println!("生成的 Digest 认证头: {}", auth_response);
-        Ok(auth_response.to_string())
+    /// 发送请求：代理要求Digest认证（407）时采用Proxy-Authenticate中的新挑战重发一次；
+    /// 无法连接代理或代理认证最终被拒绝时返回 [`ProxyError`]
+    async fn send_through_proxy(
+        &self,
+        request: reqwest::RequestBuilder,
+        method: &str,
+        url: &str,
+        body: Option<&str>,
+    ) -> Result<Response> {
+        let Some(proxy) = self.active_proxy() else {
+            return Ok(self.with_body(request, body).send().await?);
+        };
+        let digest = proxy
+            .auth
+            .as_ref()
+            .filter(|auth| matches!(auth.auth_type, AuthType::Digest));
+        let retry = request
+            .try_clone()
+            .ok_or_else(|| anyhow!("request cannot be retried"))?;
+        let proxy_authorization = match (digest, &mut *self.proxy_auth_cache.lock().await) {
+            (Some(auth_config), Some(challenge)) => {
+                Some(Self::respond(challenge, auth_config, method, url)?)
+            }
+            _ => None,
+        };
+        let response = self
+            .with_body(Self::proxy_authorize(request, proxy_authorization), body)
+            .send()
+            .await
+            .map_err(|e| Self::proxy_failure(proxy, e))?;
+        let response = match digest {
+            Some(auth_config) if response.status() == StatusCode::PROXY_AUTHENTICATION_REQUIRED => {
+                let mut challenge = Self::parse_challenge(&response, "Proxy-Authenticate")?;
+                let proxy_authorization = Self::respond(&mut challenge, auth_config, method, url)?;
+                *self.proxy_auth_cache.lock().await = Some(challenge);
+                self.with_body(
+                    Self::proxy_authorize(retry, Some(proxy_authorization)),
+                    body,
+                )
+                .send()
+                .instrument(info_span!("proxy_auth_roundtrip"))
+                .await
+                .map_err(|e| Self::proxy_failure(proxy, e))?
+            }
+            _ => response,
+        };
+        if response.status() == StatusCode::PROXY_AUTHENTICATION_REQUIRED {
+            return Err(ProxyError(format!(
+                "{} rejected the proxy credentials (407 Proxy Authentication Required)",
+                proxy.url
+            ))
+            .into());
+        }
+        Ok(response)
     }
 
-    /// 获取缓存的认证头
-    async fn get_cached_auth(&self) -> Option<String> {
-        let cache_guard = self.auth_cache.lock().await;
-        cache_guard.clone()
+    /// 实际生效的代理（Unix域套接字连接不经过代理）
+    fn active_proxy(&self) -> Option<&ProxySettings> {
+        self.config
+            .proxy
+            .as_ref()
+            .filter(|_| self.config.unix_socket.is_none())
     }
 
-    /// 设置缓存的认证头
-    async fn set_cached_auth(&self, auth_string: String) {
-        let mut cache_guard = self.auth_cache.lock().await;
-        *cache_guard = Some(auth_string);
+    /// 经代理发送时连接失败（含HTTPS隧道建立失败）归因于代理
+    fn proxy_failure(proxy: &ProxySettings, error: reqwest::Error) -> anyhow::Error {
+        if error.is_connect() {
+            ProxyError(format!("{}: {}", proxy.url, error)).into()
+        } else {
+            error.into()
+        }
     }
 
-    /// 清除缓存的认证头
-    pub async fn clear_auth_cache(&self) {
-        let mut cache_guard = self.auth_cache.lock().await;
-        *cache_guard = None;
+    fn proxy_authorize(
+        request: reqwest::RequestBuilder,
+        authorization: Option<String>,
+    ) -> reqwest::RequestBuilder {
+        match authorization {
+            Some(authorization) => request.header("Proxy-Authorization", authorization),
+            None => request,
+        }
     }
 
-    /// 获取认证头（可用于其他HTTP方法）
-    pub async fn get_auth_header(&self, url: &str, method: &str) -> Result<Option<String>> {
-        // 检查是否有缓存的认证头
-        if let Some(cached_auth) = self.get_cached_auth().await {
-            return Ok(Some(cached_auth));
-        }
-
-        // 如果没有缓存，执行一次预认证请求
-        let request = self.client.request(
-            match method {
-                "GET" => reqwest::Method::GET,
-                "POST" => reqwest::Method::POST,
-                "PUT" => reqwest::Method::PUT,
-                "DELETE" => reqwest::Method::DELETE,
-                _ => reqwest::Method::GET,
-            },
-            url,
-        );
+    fn authorize(
+        request: reqwest::RequestBuilder,
+        authorization: Option<String>,
+    ) -> reqwest::RequestBuilder {
+        match authorization {
+            Some(authorization) => request.header("Authorization", authorization),
+            None => request,
+        }
+    }
 
-        let response = request.send().await?;
+    /// 附加请求body，有上传限速时改为按限速发送的流（保留Content-Length，避免改用分块传输）
+    fn with_body(
+        &self,
+        request: reqwest::RequestBuilder,
+        body: Option<&str>,
+    ) -> reqwest::RequestBuilder {
+        match (&self.upload_limit, body) {
+            (Some(limiter), Some(body)) => request
+                .header(reqwest::header::CONTENT_LENGTH, body.len())
+                .body(BandwidthLimiter::body(limiter.clone(), body)),
+            (None, Some(body)) => request.body(body.to_string()),
+            (_, None) => request,
+        }
+    }
 
-        if response.status().as_u16() == 401 {
-            let auth_string = self.get_auth(response, url, method).await?;
-            self.set_cached_auth(auth_string.clone()).await;
-            Ok(Some(auth_string))
-        } else {
-            Ok(None)
+    fn http_method(method: &str) -> Result<reqwest::Method> {
+        match method {
+            "GET" => Ok(reqwest::Method::GET),
+            "POST" => Ok(reqwest::Method::POST),
+            "PUT" => Ok(reqwest::Method::PUT),
+            "PATCH" => Ok(reqwest::Method::PATCH),
+            "DELETE" => Ok(reqwest::Method::DELETE),
+            _ => Err(anyhow!("Unsupported HTTP method: {}", method)),
         }
     }
 
-    /// 发送通用HTTP请求（支持认证复用）
-    pub async fn send_request(
-        &self,
-        method: &str,
-        url: &str,
-        body: Option<String>,
-        headers: Option<Vec<(&str, &str)>>,
-    ) -> Result<Response> {
-        let http_method = match method {
-            "GET" => reqwest::Method::GET,
-            "POST" => reqwest::Method::POST,
-            "PUT" => reqwest::Method::PUT,
-            "DELETE" => reqwest::Method::DELETE,
-            _ => return Err(anyhow!("Unsupported HTTP method: {}", method)),
+    /// 计算请求的Authorization头：Basic直接生成；Digest使用缓存的挑战。
+    /// 抢先认证时Digest先沿用其他会话最近采用的挑战，其次为配置的realm/nonce；
+    /// 否则（或两者都没有时）先发送HEAD探测请求，被要求认证时才附带（未被要求认证时之后不再探测）
+    async fn authorization(&self, method: &str, url: &str) -> Result<Option<String>> {
+        let Some(auth_config) = &self.config.auth else {
+            return Ok(None);
         };
 
-        let mut request = self.client.request(http_method, url);
-
-        // 添加请求体
-        if let Some(body_data) = body {
-            request = request.body(body_data);
-            // 对于有body的请求，添加Content-Type头
-            if method == "POST" || method == "PUT" {
-                request = request.header("Content-Type", "application/json");
-            }
+        // 持有锁完成探测，并发的请求共用同一次探测结果
+        let mut state = self.auth_cache.lock().await;
+        if let AuthState::Unknown = *state {
+            *state = match self.preemptive_state(auth_config, url)? {
+                Some(preemptive) => preemptive,
+                None => self.probe(auth_config, url).await?,
+            };
         }
-
-        // 添加自定义头部
-        if let Some(headers) = headers {
-            for (key, value) in headers {
-                request = request.header(key, value);
+        match &mut *state {
+            AuthState::Basic => {
+                let auth_value = format!("{}:{}", auth_config.username, auth_config.password);
+                Ok(Some(format!(
+                    "Basic {}",
+                    BASE64_STANDARD.encode(auth_value)
+                )))
             }
+            AuthState::Digest(challenge) => {
+                Self::respond(challenge, auth_config, method, &Self::digest_uri(url)).map(Some)
+            }
+            _ => Ok(None),
         }
+    }
 
-        // 检查是否有缓存的认证头
-        if let Some(cached_auth) = self.get_cached_auth().await {
-            request = request.header("Authorization", &cached_auth);
+    /// 抢先认证时无需探测即可使用的认证状态
+    fn preemptive_state(&self, auth_config: &AuthConfig, url: &str) -> Result<Option<AuthState>> {
+        if !auth_config.preemptive {
+            return Ok(None);
+        }
+        if let AuthType::Basic = auth_config.auth_type {
+            return Ok(Some(AuthState::Basic));
+        }
+        if let Some(challenge) = self.last_challenges.lock().unwrap().get(&Self::origin(url)) {
+            return Ok(Some(AuthState::Digest(challenge.clone())));
         }
+        let Some((realm, nonce)) = &auth_config.known_challenge else {
+            return Ok(None);
+        };
+        let challenge = WwwAuthenticateHeader::parse(&format!(
+            "Digest realm=\"{}\", nonce=\"{}\", qop=\"auth\", algorithm=MD5",
+            realm, nonce
+        ))
+        .map_err(|e| anyhow!("Invalid digest_auth realm/nonce: {}", e))?;
+        Ok(Some(AuthState::Digest(challenge)))
+    }
 
-        // 发送请求
-        let response = request.try_clone().unwrap().send().await?;
+    fn remember_challenge(&self, url: &str, challenge: &WwwAuthenticateHeader) {
+        self.last_challenges
+            .lock()
+            .unwrap()
+            .insert(Self::origin(url), challenge.clone());
+    }
 
-        // 检查是否需要认证
-        if response.status().as_u16() != 401 {
-            return Ok(response);
+    /// URL的源站（协议、主机和端口），解析失败时为URL本身
+    fn origin(url: &str) -> String {
+        reqwest::Url::parse(url)
+            .map(|parsed| parsed.origin().ascii_serialization())
+            .unwrap_or_else(|_| url.to_string())
+    }
+
+    /// 发送HEAD探测请求，按响应确定认证状态；服务器不支持HEAD（405/501）时改用GET。
+    /// 探测只用安全方法，避免以请求本身的方法（如PUT、DELETE）在未认证时触发修改
+    async fn probe(&self, auth_config: &AuthConfig, url: &str) -> Result<AuthState> {
+        let mut response = self.send_probe(reqwest::Method::HEAD, url).await?;
+        if matches!(
+            response.status(),
+            StatusCode::METHOD_NOT_ALLOWED | StatusCode::NOT_IMPLEMENTED
+        ) {
+            response = self.send_probe(reqwest::Method::GET, url).await?;
+        }
+        if response.status() != StatusCode::UNAUTHORIZED {
+            return Ok(AuthState::NotRequired);
         }
+        if let AuthType::Basic = auth_config.auth_type {
+            return Ok(AuthState::Basic);
+        }
+        let challenge = Self::parse_challenge(&response, "WWW-Authenticate")?;
+        self.remember_challenge(url, &challenge);
+        Ok(AuthState::Digest(challenge))
+    }
 
-        // 处理认证
-        let auth_string = self.get_auth(response, url, method).await?;
+    async fn send_probe(&self, method: reqwest::Method, url: &str) -> Result<Response> {
+        let probe = self.client.request(method.clone(), url);
+        self.send_through_proxy(probe, method.as_str(), url, None)
+            .instrument(info_span!("auth_roundtrip", preflight = true))
+            .await
+    }
 
-        // 缓存认证头
-        self.set_cached_auth(auth_string.clone()).await;
+    /// 采用401响应中的新挑战并缓存，返回据此计算的Authorization头
+    async fn accept_challenge(
+        &self,
+        response: &Response,
+        method: &str,
+        url: &str,
+    ) -> Result<String> {
+        let auth_config = self
+            .config
+            .auth
+            .as_ref()
+            .ok_or_else(|| anyhow!("no auth config"))?;
+        let mut challenge = Self::parse_challenge(response, "WWW-Authenticate")?;
+        let authorization =
+            Self::respond(&mut challenge, auth_config, method, &Self::digest_uri(url))?;
+        self.remember_challenge(url, &challenge);
+        *self.auth_cache.lock().await = AuthState::Digest(challenge);
+        Ok(authorization)
+    }
 
-        // 重新发送认证请求
-        let request = request.header("Authorization", auth_string);
-        request
-            .send()
-            .await
-            .map_err(|e| anyhow!("HTTP request failed: {}", e))
+    /// 解析 `header`（WWW-Authenticate或Proxy-Authenticate）中的Digest挑战
+    fn parse_challenge(response: &Response, header: &str) -> Result<WwwAuthenticateHeader> {
+        let header_str = response
+            .headers()
+            .get(header)
+            .ok_or_else(|| anyhow!("Missing {} header", header))?
+            .to_str()
+            .unwrap_or("");
+
+        // 使用 digest_auth 库解析挑战头
+        WwwAuthenticateHeader::parse(header_str).map_err(|e| {
+            let error_msg = format!("Digest认证头解析错误: {}", e);
+            log_error!("{}", error_msg);
+            anyhow!(error_msg)
+        })
+    }
+
+    /// 目标认证的digest-uri为请求目标（路径和查询），而非完整URL；
+    /// 经代理的请求以完整URL为请求目标，代理认证直接使用URL
+    fn digest_uri(url: &str) -> String {
+        reqwest::Url::parse(url)
+            .map(|parsed| match parsed.query() {
+                Some(query) => format!("{}?{}", parsed.path(), query),
+                None => parsed.path().to_string(),
+            })
+            .unwrap_or_else(|_| url.to_string())
+    }
+
+    /// 按挑战为一个请求计算Digest响应（挑战中的nc随之递增）
+    fn respond(
+        challenge: &mut WwwAuthenticateHeader,
+        auth_config: &AuthConfig,
+        method: &str,
+        uri: &str,
+    ) -> Result<String> {
+        let context = AuthContext::new_with_method(
+            &auth_config.username,
+            &auth_config.password,
+            uri.to_string(),
+            None::<Vec<u8>>,
+            HttpMethod::from(method),
+        );
+
+        // 构建认证响应
+        let auth_response = challenge.respond(&context).map_err(|e| {
+            let error_msg = format!("Digest认证响应错误: {}", e);
+            log_error!("{}", error_msg);
+            anyhow!(error_msg)
+        })?;
+        Ok(auth_response.to_string())
+    }
+
+    /// 在共享Cookie罐中设置Cookie，作用于URL的主机（Path=/）
+    pub fn set_cookie(&self, url: &str, name: &str, value: &str) -> Result<()> {
+        let url = Url::parse(url).with_context(|| format!("Invalid URL {}", url))?;
+        self.cookie_jar
+            .add_cookie_str(&format!("{}={}; Path=/", name, value), &url);
+        Ok(())
+    }
+
+    /// 从共享Cookie罐中删除URL主机下的Cookie（Path=/）
+    pub fn delete_cookie(&self, url: &str, name: &str) -> Result<()> {
+        let url = Url::parse(url).with_context(|| format!("Invalid URL {}", url))?;
+        // 写入已过期的同名Cookie即删除原Cookie
+        self.cookie_jar
+            .add_cookie_str(&format!("{}=; Path=/; Max-Age=0", name), &url);
+        Ok(())
+    }
+
+    /// 向URL发送请求时会带上的Cookie的值
+    pub fn cookie(&self, url: &str, name: &str) -> Result<Option<String>> {
+        let url = Url::parse(url).with_context(|| format!("Invalid URL {}", url))?;
+        let Some(header) = self.cookie_jar.cookies(&url) else {
+            return Ok(None);
+        };
+        Ok(header
+            .to_str()
+            .unwrap_or_default()
+            .split("; ")
+            .find_map(|pair| {
+                let (cookie, value) = pair.split_once('=')?;
+                (cookie == name).then(|| value.to_string())
+            }))
+    }
+
+    /// 清除缓存的认证状态和记住的挑战（下一个请求重新探测）
+    pub async fn clear_auth_cache(&self) {
+        *self.auth_cache.lock().await = AuthState::Unknown;
+        self.last_challenges.lock().unwrap().clear();
+    }
+
+    /// 获取认证头（可用于其他HTTP方法），需要时先探测获取挑战
+    pub async fn get_auth_header(&self, url: &str, method: &str) -> Result<Option<String>> {
+        self.authorization(method, url).await
     }
 }
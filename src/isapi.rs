@@ -0,0 +1,365 @@
+use anyhow::{Context, Result, anyhow};
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use sha2::{Digest, Sha256};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::http_client::{HttpClient, ResponseData};
+use crate::template;
+
+// Import logger macros from crate root
+use crate::{log_debug, log_info};
+
+pub const SESSION_LOGIN_CAPABILITIES_PATH: &str = "/ISAPI/Security/sessionLogin/capabilities";
+pub const SESSION_LOGIN_PATH: &str = "/ISAPI/Security/sessionLogin";
+pub const SESSION_HEARTBEAT_PATH: &str = "/ISAPI/Security/sessionHeartbeat";
+pub const CAPABILITIES_PATH: &str = "/ISAPI/System/AlgoPackageScheduling/capabilities";
+pub const ADD_TASK_PATH: &str = "/ISAPI/System/AlgoPackageScheduling/AddTask";
+pub const DELETE_TASK_PATH: &str = "/ISAPI/System/AlgoPackageScheduling/DeleteTask";
+pub const TASK_STATUS_PATH: &str = "/ISAPI/System/AlgoPackageScheduling/GetTaskStatus";
+pub const TASK_LIST_PATH: &str = "/ISAPI/System/AlgoPackageScheduling/TaskList";
+pub const TIME_PATH: &str = "/ISAPI/System/time";
+
+/// ISAPI通用响应状态（ResponseStatus）
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct IsapiStatus {
+    pub status_code: i64, // 1表示成功
+    pub status_string: String,
+    pub sub_status_code: Option<String>,
+    pub error_code: Option<i64>,
+    pub error_msg: Option<String>,
+    #[serde(rename = "taskID")]
+    pub task_id: Option<String>,
+}
+
+impl IsapiStatus {
+    /// 是否为成功状态
+    pub fn is_ok(&self) -> bool {
+        self.status_code == 1
+    }
+}
+
+/// AddTask请求体
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct AddTaskRequest {
+    #[serde(rename = "taskName")]
+    pub task_name: String,
+    #[serde(rename = "customInfo", skip_serializing_if = "Option::is_none")]
+    pub custom_info: Option<String>,
+    #[serde(rename = "taskID")]
+    pub task_id: String,
+    #[serde(rename = "nodeID")]
+    pub node_id: String,
+    #[serde(rename = "algoPackageID")]
+    pub algo_package_id: String,
+    #[serde(rename = "algoID")]
+    pub algo_id: String,
+    #[serde(rename = "DataSource")]
+    pub data_source: Value, // 视频流/图片源，结构随算法包而定
+    #[serde(rename = "Destination", default)]
+    pub destination: Vec<Value>, // 结果上报目的地
+}
+
+/// 单个任务的运行状态
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TaskStatus {
+    #[serde(rename = "taskID")]
+    pub task_id: String,
+    pub status: String, // 如 "running"、"stopped"、"notExist"
+    #[serde(rename = "errorCode")]
+    pub error_code: Option<i64>,
+    #[serde(rename = "errorMsg")]
+    pub error_msg: Option<String>,
+}
+
+/// sessionLogin能力集中的登录挑战参数
+#[derive(Debug, Clone, PartialEq)]
+pub struct SessionChallenge {
+    pub session_id: String,
+    pub challenge: String,
+    pub iterations: u32,
+    pub is_irreversible: bool,
+    pub salt: String,
+    pub session_id_version: Option<String>,
+}
+
+/// 登录成功后的会话信息（会话Cookie保存在HttpClient的Cookie存储中）
+#[derive(Debug, Clone, PartialEq)]
+pub struct IsapiSession {
+    pub session_id: String,
+    pub username: String,
+}
+
+/// 海康ISAPI接口的类型化封装
+///
+/// 复用传入的 [`HttpClient`]，因此Digest认证和sessionLogin建立的会话Cookie在各调用间共享。
+pub struct IsapiClient {
+    http_client: Arc<HttpClient>,
+    base_url: String,
+}
+
+impl IsapiClient {
+    /// 创建客户端，`base_url` 如 `https://10.41.131.87`
+    pub fn new(base_url: impl Into<String>, http_client: Arc<HttpClient>) -> Self {
+        Self {
+            http_client,
+            base_url: base_url.into().trim_end_matches('/').to_string(),
+        }
+    }
+
+    /// 设备根URL
+    pub fn base_url(&self) -> &str {
+        &self.base_url
+    }
+
+    /// 获取sessionLogin挑战参数
+    pub async fn session_login_capabilities(&self, username: &str) -> Result<SessionChallenge> {
+        let path = format!(
+            "{}?username={}",
+            SESSION_LOGIN_CAPABILITIES_PATH,
+            template::urlencode(username)
+        );
+        let response = self.send("GET", &path, None).await?;
+        let xml = response.text();
+        let field = |name: &str| {
+            xml_value(&xml, name)
+                .ok_or_else(|| anyhow!("sessionLogin capabilities missing <{}>", name))
+        };
+
+        Ok(SessionChallenge {
+            session_id: field("sessionID")?,
+            challenge: field("challenge")?,
+            iterations: field("iterations")?
+                .parse()
+                .context("Invalid <iterations> in sessionLogin capabilities")?,
+            is_irreversible: xml_value(&xml, "isIrreversible").as_deref() == Some("true"),
+            salt: xml_value(&xml, "salt").unwrap_or_default(),
+            session_id_version: xml_value(&xml, "sessionIDVersion"),
+        })
+    }
+
+    /// sessionLogin登录：获取挑战参数后提交加盐哈希后的密码，会话Cookie由HttpClient保存
+    pub async fn session_login(&self, username: &str, password: &str) -> Result<IsapiSession> {
+        let challenge = self.session_login_capabilities(username).await?;
+        let hashed = session_password_hash(username, password, &challenge);
+        let body = format!(
+            "<SessionLogin><userName>{}</userName><password>{}</password><sessionID>{}</sessionID>\
+             <isSessionIDValidLongTerm>false</isSessionIDValidLongTerm>{}</SessionLogin>",
+            username,
+            hashed,
+            challenge.session_id,
+            challenge
+                .session_id_version
+                .as_ref()
+                .map(|version| format!("<sessionIDVersion>{}</sessionIDVersion>", version))
+                .unwrap_or_default()
+        );
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_millis();
+        let path = format!("{}?timeStamp={}", SESSION_LOGIN_PATH, timestamp);
+        let response = self.send("POST", &path, Some(body)).await?;
+
+        let xml = response.text();
+        let session_id = xml_value(&xml, "sessionID").unwrap_or(challenge.session_id);
+        log_info!("🔑 ISAPI session login succeeded for {}", username);
+        Ok(IsapiSession {
+            session_id,
+            username: username.to_string(),
+        })
+    }
+
+    /// 会话保活
+    pub async fn session_heartbeat(&self) -> Result<()> {
+        self.send("PUT", SESSION_HEARTBEAT_PATH, None).await?;
+        log_debug!("💓 ISAPI session heartbeat sent");
+        Ok(())
+    }
+
+    /// 查询算法任务调度能力集
+    pub async fn capabilities(&self) -> Result<Value> {
+        self.send_json("GET", CAPABILITIES_PATH, None).await
+    }
+
+    /// 下发算法任务
+    pub async fn add_task(&self, task: &AddTaskRequest) -> Result<IsapiStatus> {
+        let value = self
+            .send_json("POST", ADD_TASK_PATH, Some(serde_json::to_value(task)?))
+            .await?;
+        serde_json::from_value(value).context("Invalid AddTask response")
+    }
+
+    /// 删除算法任务
+    pub async fn delete_tasks(&self, task_ids: &[String]) -> Result<IsapiStatus> {
+        let value = self
+            .send_json("PUT", DELETE_TASK_PATH, Some(task_id_list(task_ids)))
+            .await?;
+        serde_json::from_value(value).context("Invalid DeleteTask response")
+    }
+
+    /// 查询算法任务状态
+    pub async fn task_status(&self, task_ids: &[String]) -> Result<Vec<TaskStatus>> {
+        let value = self
+            .send_json("POST", TASK_STATUS_PATH, Some(task_id_list(task_ids)))
+            .await?;
+        serde_json::from_value(value["TaskStatusList"].clone())
+            .context("Invalid GetTaskStatus response")
+    }
+
+    /// 查询设备上的所有任务ID
+    pub async fn task_list(&self) -> Result<Vec<String>> {
+        let value = self.send_json("GET", TASK_LIST_PATH, None).await?;
+        Ok(value["TaskList"]
+            .as_array()
+            .into_iter()
+            .flatten()
+            .filter_map(|task| task["taskID"].as_str().map(str::to_string))
+            .collect())
+    }
+
+    /// 发送 `?format=json` 请求并解析JSON响应
+    async fn send_json(&self, method: &str, path: &str, body: Option<Value>) -> Result<Value> {
+        let path = format!("{}?format=json", path);
+        let response = self
+            .send(method, &path, body.map(|body| body.to_string()))
+            .await?;
+        serde_json::from_slice(&response.body)
+            .with_context(|| format!("Invalid JSON from {} {}", method, path))
+    }
+
+    /// 发送请求，非2xx状态时将ISAPI错误信息转为错误
+    async fn send(&self, method: &str, path: &str, body: Option<String>) -> Result<ResponseData> {
+        let url = format!("{}{}", self.base_url, path);
+        let response = self
+            .http_client
+            .send_request(method, &url, body, None)
+            .await
+            .with_context(|| format!("{} {} failed", method, url))?;
+        let response = ResponseData::read(response).await?;
+        if !response.status.is_success() {
+            return Err(anyhow!(
+                "{} {} returned {}: {}",
+                method,
+                url,
+                response.status,
+                describe_error(&response.text())
+            ));
+        }
+        Ok(response)
+    }
+}
+
+/// 按设备要求计算sessionLogin提交的密码哈希
+pub fn session_password_hash(
+    username: &str,
+    password: &str,
+    challenge: &SessionChallenge,
+) -> String {
+    if challenge.is_irreversible {
+        let mut hash = sha256_hex(&format!("{}{}{}", username, challenge.salt, password));
+        hash = sha256_hex(&format!("{}{}", hash, challenge.challenge));
+        for _ in 2..challenge.iterations {
+            hash = sha256_hex(&hash);
+        }
+        hash
+    } else {
+        let mut hash = format!("{}{}", sha256_hex(password), challenge.challenge);
+        for _ in 1..challenge.iterations {
+            hash = sha256_hex(&hash);
+        }
+        hash
+    }
+}
+
+fn sha256_hex(input: &str) -> String {
+    Sha256::digest(input.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
+
+/// DeleteTask/GetTaskStatus使用的任务ID列表
+fn task_id_list(task_ids: &[String]) -> Value {
+    json!({
+        "TaskIDList": task_ids
+            .iter()
+            .map(|task_id| json!({ "taskID": task_id }))
+            .collect::<Vec<_>>()
+    })
+}
+
+/// 从JSON或XML格式的ISAPI错误响应中提取可读信息
+fn describe_error(body: &str) -> String {
+    if let Ok(status) = serde_json::from_str::<IsapiStatus>(body) {
+        return match status.sub_status_code {
+            Some(sub_status) => format!("{} ({})", status.status_string, sub_status),
+            None => status.status_string,
+        };
+    }
+    match (
+        xml_value(body, "statusString"),
+        xml_value(body, "subStatusCode"),
+    ) {
+        (Some(status), Some(sub_status)) => format!("{} ({})", status, sub_status),
+        (Some(status), None) => status,
+        _ => body.trim().chars().take(200).collect(),
+    }
+}
+
+/// 取XML中第一个 `<name>...</name>` 的文本内容
+pub fn xml_value(xml: &str, name: &str) -> Option<String> {
+    let open = format!("<{}", name);
+    let mut search = xml;
+    while let Some(start) = search.find(&open) {
+        let after = &search[start + open.len()..];
+        // 跳过同前缀的其他标签，如 <sessionIDVersion> 之于 <sessionID>
+        if !after.starts_with(['>', ' ']) {
+            search = after;
+            continue;
+        }
+        let content_start = after.find('>')? + 1;
+        let content = &after[content_start..];
+        let end = content.find(&format!("</{}>", name))?;
+        return Some(content[..end].trim().to_string());
+    }
+    None
+}
+
+/// 设备地址：URL的 scheme://host:port 部分
+pub fn device_origin(url: &str) -> Option<String> {
+    let url = Url::parse(url).ok()?;
+    url.has_host().then(|| url.origin().ascii_serialization())
+}
+
+/// 从JSON请求body中提取所有名为 `field` 的任务ID（AddTask的 `taskID`、DeleteTask的 `TaskIDList[*].taskID` 等）
+pub fn task_ids_in_body(body: &str, field: &str) -> Vec<String> {
+    let mut task_ids = Vec::new();
+    if let Ok(value) = serde_json::from_str::<Value>(body) {
+        collect_field(&value, field, &mut task_ids);
+    }
+    task_ids
+}
+
+/// 递归收集JSON中所有名为 `field` 的字符串值
+fn collect_field(value: &Value, field: &str, found: &mut Vec<String>) {
+    match value {
+        Value::Object(map) => {
+            for (key, item) in map {
+                match item {
+                    Value::String(text) if key == field => found.push(text.clone()),
+                    _ => collect_field(item, field, found),
+                }
+            }
+        }
+        Value::Array(items) => {
+            for item in items {
+                collect_field(item, field, found);
+            }
+        }
+        _ => {}
+    }
+}
@@ -0,0 +1,326 @@
+use anyhow::{Context, Result, anyhow, bail};
+use redis::aio::MultiplexedConnection;
+use redis::{AsyncCommands, Direction, RedisResult};
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, HashMap};
+use std::future::Future;
+use std::sync::Mutex;
+
+use crate::config::{HttpRequestConfig, JobQueueConfig, RequestConfig};
+use crate::observer::RequestObserver;
+use crate::stats::{RecordedRequest, RequestResult};
+
+// Import logger macros from crate root
+use crate::{log_debug, log_warn};
+
+/// 从队列取出的一个任务：替换本周期的占位符取值，可选地替换请求定义
+///
+/// 消息为JSON对象。含 `variables`、`request_a` 或 `request_b` 字段时按结构解析
+/// （可带 `id`），否则整个对象视为变量，如 `{"taskID": "t-1", "channel": "2"}`。
+#[derive(Debug, Clone)]
+pub struct Job {
+    pub id: Option<String>,
+    pub variables: HashMap<String, String>,
+    pub request_a: Option<HttpRequestConfig>,
+    pub request_b: Option<HttpRequestConfig>,
+    /// 原始消息，随结果一起发布
+    pub message: Value,
+    /// 队列中的消息原文，结果发布后据此从处理中列表删除
+    pub raw: String,
+}
+
+impl Job {
+    pub fn parse(message: &str) -> Result<Self> {
+        let value: Value = serde_json::from_str(message).context("job is not valid JSON")?;
+        let Value::Object(object) = &value else {
+            bail!("job must be a JSON object");
+        };
+        let structured = ["variables", "request_a", "request_b"]
+            .iter()
+            .any(|key| object.contains_key(*key));
+        let variables = if structured {
+            object.get("variables").cloned().unwrap_or(Value::Null)
+        } else {
+            value.clone()
+        };
+        let variables = match variables {
+            Value::Null => HashMap::new(),
+            Value::Object(variables) => variables
+                .into_iter()
+                .map(|(name, value)| {
+                    let value = match value {
+                        Value::String(value) => value,
+                        value => value.to_string(),
+                    };
+                    (name, value)
+                })
+                .collect(),
+            _ => bail!("job variables must be a JSON object"),
+        };
+        let request = |key: &str| -> Result<Option<HttpRequestConfig>> {
+            match object.get(key).filter(|_| structured) {
+                Some(request) => serde_json::from_value(request.clone())
+                    .map(Some)
+                    .with_context(|| format!("invalid {} in job", key)),
+                None => Ok(None),
+            }
+        };
+        Ok(Self {
+            id: object
+                .get("id")
+                .filter(|_| structured)
+                .map(|id| id.as_str().map_or_else(|| id.to_string(), str::to_string)),
+            variables,
+            request_a: request("request_a")?,
+            request_b: request("request_b")?,
+            message: value,
+            raw: message.to_string(),
+        })
+    }
+
+    /// 本周期使用的配置：任务变量覆盖配置变量、worker变量和同名生成字段，任务中的请求定义替换配置中的请求
+    pub fn apply(&self, config: &RequestConfig) -> RequestConfig {
+        let mut config = config.clone();
+        if let Some(fields) = config.generated_fields.as_mut() {
+            fields.retain(|field| !self.variables.contains_key(&field.name));
+        }
+        if let Some(worker_variables) = config.worker_variables.as_mut() {
+            worker_variables.retain(|name, _| !self.variables.contains_key(name));
+        }
+        config
+            .variables
+            .get_or_insert_with(HashMap::new)
+            .extend(self.variables.clone());
+        if let Some(request_a) = &self.request_a {
+            config.request_a = request_a.clone();
+        }
+        if let Some(request_b) = &self.request_b {
+            config.request_b = request_b.clone();
+        }
+        config
+    }
+}
+
+/// 发布到结果队列的任务结果
+#[derive(Debug, Serialize)]
+pub struct JobResult {
+    pub id: Option<String>,
+    pub cycle: usize,
+    pub success: bool,
+    pub error: Option<String>,
+    pub job: Value,
+    pub results: Vec<RecordedRequest>,
+}
+
+/// 任务队列：从Redis列表取任务驱动周期，并将每个周期的结果推送到结果列表
+///
+/// 任务用BLMOVE从任务列表原子地移入处理中列表，结果发布后才从处理中列表删除（确认）；
+/// 运行中断或进程崩溃时未确认的任务留在处理中列表，下次启动时移回任务列表重新处理（至少一次）。
+pub struct JobQueue {
+    config: JobQueueConfig,
+    processing_list: String,
+    /// 阻塞取任务使用的连接
+    consumer: tokio::sync::Mutex<Option<MultiplexedConnection>>,
+    /// 发布结果和确认任务使用的连接，不被阻塞的取任务命令占用
+    publisher: tokio::sync::Mutex<Option<MultiplexedConnection>>,
+    recovered: tokio::sync::OnceCell<()>,
+    results: Mutex<BTreeMap<usize, Vec<RecordedRequest>>>,
+}
+
+impl JobQueue {
+    pub fn new(config: &JobQueueConfig) -> Self {
+        Self {
+            config: config.clone(),
+            processing_list: Self::processing_list(config),
+            consumer: tokio::sync::Mutex::new(None),
+            publisher: tokio::sync::Mutex::new(None),
+            recovered: tokio::sync::OnceCell::new(),
+            results: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// 处理中列表：配置的 `processing_list`，缺省为 `<input_list>:processing`
+    pub fn processing_list(config: &JobQueueConfig) -> String {
+        config
+            .processing_list
+            .clone()
+            .unwrap_or_else(|| format!("{}:processing", config.input_list))
+    }
+
+    /// 取下一个任务（消息原文），超过 `idle_timeout_ms` 没有新任务时返回None
+    ///
+    /// 首次调用时先将处理中列表里上次运行残留（未确认）的任务按原顺序移回任务列表开头。
+    pub async fn next_message(&self) -> Result<Option<String>> {
+        self.recovered.get_or_try_init(|| self.recover()).await?;
+        // BLMOVE的超时单位为秒，0表示一直等待
+        let timeout = self
+            .config
+            .idle_timeout_ms
+            .map_or(0.0, |ms| ms as f64 / 1000.0);
+        let input_list = &self.config.input_list;
+        let processing_list = &self.processing_list;
+        self.with_connection(&self.consumer, "BLMOVE", |mut connection| async move {
+            connection
+                .blmove::<_, _, Option<String>>(
+                    input_list,
+                    processing_list,
+                    Direction::Left,
+                    Direction::Right,
+                    timeout,
+                )
+                .await
+        })
+        .await
+    }
+
+    /// 发布周期结果并确认任务（未配置result_list时只确认并丢弃已收集的结果）
+    pub async fn publish(&self, cycle: usize, job: &Job) -> Result<()> {
+        let results = self
+            .results
+            .lock()
+            .unwrap()
+            .remove(&cycle)
+            .unwrap_or_default();
+        self.push_result(JobResult {
+            id: job.id.clone(),
+            cycle,
+            success: !results.is_empty() && results.iter().all(|result| result.success),
+            error: None,
+            job: job.message.clone(),
+            results,
+        })
+        .await?;
+        self.acknowledge(&job.raw).await
+    }
+
+    /// 发布无法解析、未执行的任务（cycle为0）并确认
+    pub async fn reject(&self, message: &str, error: &anyhow::Error) -> Result<()> {
+        self.push_result(JobResult {
+            id: None,
+            cycle: 0,
+            success: false,
+            error: Some(format!("{:#}", error)),
+            job: Value::String(message.to_string()),
+            results: Vec::new(),
+        })
+        .await?;
+        self.acknowledge(message).await
+    }
+
+    /// 将上次运行残留在处理中列表的任务移回任务列表
+    async fn recover(&self) -> Result<()> {
+        let input_list = &self.config.input_list;
+        let processing_list = &self.processing_list;
+        let mut requeued = 0;
+        while self
+            .with_connection(&self.publisher, "LMOVE", |mut connection| async move {
+                connection
+                    .lmove::<_, _, Option<String>>(
+                        processing_list,
+                        input_list,
+                        Direction::Right,
+                        Direction::Left,
+                    )
+                    .await
+            })
+            .await?
+            .is_some()
+        {
+            requeued += 1;
+        }
+        if requeued > 0 {
+            log_warn!(
+                "📮 Requeued {} unacknowledged job(s) from {}",
+                requeued,
+                processing_list
+            );
+        }
+        Ok(())
+    }
+
+    /// 从处理中列表删除已处理的任务
+    async fn acknowledge(&self, message: &str) -> Result<()> {
+        let processing_list = &self.processing_list;
+        self.with_connection(&self.publisher, "LREM", |mut connection| async move {
+            connection
+                .lrem::<_, _, usize>(processing_list, 1, message)
+                .await
+        })
+        .await?;
+        Ok(())
+    }
+
+    async fn push_result(&self, result: JobResult) -> Result<()> {
+        let Some(result_list) = &self.config.result_list else {
+            return Ok(());
+        };
+        let cycle = result.cycle;
+        let payload = serde_json::to_string(&result)?;
+        let payload = &payload;
+        let pending = self
+            .with_connection(&self.publisher, "RPUSH", |mut connection| async move {
+                connection.rpush::<_, _, usize>(result_list, payload).await
+            })
+            .await?;
+        log_debug!(
+            "📮 Published result of cycle {} ({} results in {})",
+            cycle,
+            pending,
+            result_list
+        );
+        Ok(())
+    }
+
+    /// 在连接上执行命令，连接断开时重连一次
+    async fn with_connection<T, F, Fut>(
+        &self,
+        slot: &tokio::sync::Mutex<Option<MultiplexedConnection>>,
+        command: &str,
+        run: F,
+    ) -> Result<T>
+    where
+        F: Fn(MultiplexedConnection) -> Fut,
+        Fut: Future<Output = RedisResult<T>>,
+    {
+        let mut connection = slot.lock().await;
+        for attempt in 0..2 {
+            if connection.is_none() {
+                *connection = Some(self.connect().await?);
+            }
+            // MultiplexedConnection的克隆共享同一连接
+            let redis = connection.clone().expect("connected above");
+            match run(redis).await {
+                Ok(value) => return Ok(value),
+                Err(e) if attempt == 0 && (e.is_io_error() || e.is_connection_dropped()) => {
+                    log_warn!("Redis connection lost ({}), reconnecting", e);
+                    *connection = None;
+                }
+                Err(e) => return Err(anyhow!("Redis {} failed: {}", command, e)),
+            }
+        }
+        unreachable!("second attempt returns")
+    }
+
+    async fn connect(&self) -> Result<MultiplexedConnection> {
+        let client = redis::Client::open(self.config.url.as_str())
+            .with_context(|| format!("Invalid Redis URL {}", self.config.url))?;
+        let connection = client
+            .get_multiplexed_async_connection()
+            .await
+            .with_context(|| format!("Failed to connect to Redis at {}", self.config.url))?;
+        log_debug!("📮 Connected to Redis at {}", self.config.url);
+        Ok(connection)
+    }
+}
+
+impl RequestObserver for JobQueue {
+    fn on_result(&self, result: &RequestResult) {
+        self.results
+            .lock()
+            .unwrap()
+            .entry(result.cycle)
+            .or_default()
+            .push(RecordedRequest::from_result(result));
+    }
+}
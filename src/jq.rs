@@ -0,0 +1,84 @@
+use anyhow::{Result, anyhow};
+use jaq_core::load::{Arena, File, Loader};
+use jaq_core::{Compiler, Ctx, Filter, Native, RcIter};
+use jaq_json::Val;
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, LazyLock, Mutex};
+
+/// 已编译的过滤器
+type CompiledFilter = Arc<Filter<Native<Val>>>;
+
+/// 按表达式缓存的过滤器（标准库的解析和编译只在首次使用某个表达式时进行）
+static FILTERS: LazyLock<Mutex<HashMap<String, CompiledFilter>>> = LazyLock::new(Mutex::default);
+
+/// jq风格的过滤表达式（jaq实现），用于JSONPath无法表达的选择，如数组过滤、算术和聚合：
+/// `.TaskList | map(select(.status == "running")) | length`
+pub struct JqFilter;
+
+impl JqFilter {
+    /// 检查表达式能否编译，返回错误说明
+    pub fn check(code: &str) -> Result<()> {
+        Self::compiled(code).map(|_| ())
+    }
+
+    /// 对输入执行过滤器，返回全部输出
+    pub fn run(code: &str, input: &Value) -> Result<Vec<Value>> {
+        let filter = Self::compiled(code)?;
+        let inputs = RcIter::new(core::iter::empty());
+        filter
+            .run((Ctx::new([], &inputs), Val::from(input.clone())))
+            .map(|output| {
+                output
+                    .map(Value::from)
+                    .map_err(|e| anyhow!("jq filter '{}' failed: {}", code, e))
+            })
+            .collect()
+    }
+
+    /// 断言：过滤器至少有一个输出，且全部输出都不为false或null
+    pub fn holds(code: &str, input: &Value) -> Result<bool> {
+        let outputs = Self::run(code, input)?;
+        Ok(!outputs.is_empty()
+            && outputs
+                .iter()
+                .all(|output| !matches!(output, Value::Null | Value::Bool(false))))
+    }
+
+    fn compiled(code: &str) -> Result<CompiledFilter> {
+        if let Some(filter) = FILTERS.lock().unwrap().get(code) {
+            return Ok(Arc::clone(filter));
+        }
+        let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+        let arena = Arena::default();
+        let modules = loader
+            .load(&arena, File { code, path: () })
+            .map_err(|errors| {
+                anyhow!(
+                    "invalid jq filter '{}': {:?}",
+                    code,
+                    errors.into_iter().map(|(_, e)| e).collect::<Vec<_>>()
+                )
+            })?;
+        let filter = Compiler::default()
+            .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+            .compile(modules)
+            .map_err(|errors| {
+                anyhow!(
+                    "jq filter '{}' uses undefined {:?}",
+                    code,
+                    errors
+                        .into_iter()
+                        .flat_map(|(_, e)| e)
+                        .map(|(name, _)| name)
+                        .collect::<Vec<_>>()
+                )
+            })?;
+        let filter = Arc::new(filter);
+        FILTERS
+            .lock()
+            .unwrap()
+            .insert(code.to_string(), Arc::clone(&filter));
+        Ok(filter)
+    }
+}
@@ -0,0 +1,306 @@
+use serde_json::Value;
+use std::fmt;
+
+/// 差异类型
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum DiffKind {
+    /// 值不同（类型相同）
+    Changed,
+    /// 类型不同，视为结构变化
+    TypeChanged,
+    /// 期望中有、实际中缺失
+    Missing,
+    /// 实际中多出
+    Added,
+}
+
+/// 单处差异，`path` 形如 `$.DataSource.StreamList[0].rule`
+#[derive(Debug, Clone)]
+pub struct Difference {
+    pub path: String,
+    pub kind: DiffKind,
+    pub expected: Option<Value>,
+    pub actual: Option<Value>,
+}
+
+impl Difference {
+    /// 是否属于结构（字段增减或类型变化）而非取值的差异
+    pub fn is_structural(&self) -> bool {
+        self.kind != DiffKind::Changed
+    }
+}
+
+impl fmt::Display for Difference {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let show = |value: &Option<Value>| match value {
+            Some(value) => truncate(&value.to_string(), 120),
+            None => "<none>".to_string(),
+        };
+        match self.kind {
+            DiffKind::Changed => write!(
+                f,
+                "{}: {} → {}",
+                self.path,
+                show(&self.expected),
+                show(&self.actual)
+            ),
+            DiffKind::TypeChanged => write!(
+                f,
+                "{}: type {} → {}",
+                self.path,
+                type_name(self.expected.as_ref()),
+                type_name(self.actual.as_ref())
+            ),
+            DiffKind::Missing => write!(
+                f,
+                "{}: missing (expected {})",
+                self.path,
+                show(&self.expected)
+            ),
+            DiffKind::Added => write!(f, "{}: unexpected {}", self.path, show(&self.actual)),
+        }
+    }
+}
+
+/// 深度比较两个JSON文档，忽略匹配 `ignore_paths` 的路径
+///
+/// 忽略路径使用与差异相同的格式，`*` 匹配任意单个字段名或数组下标，
+/// 例如 `$.items[*].id`、`$.*.timestamp`。
+pub fn diff(expected: &Value, actual: &Value, ignore_paths: &[String]) -> Vec<Difference> {
+    diff_with_types(expected, actual, ignore_paths, &[])
+}
+
+/// 同 `diff`，另外匹配 `type_only_paths` 的路径及其子字段只比较类型和结构，不比较取值
+///
+/// 只比较类型的数组中，每个实际元素都按期望数组的第一个元素比较，不要求长度相同。
+pub fn diff_with_types(
+    expected: &Value,
+    actual: &Value,
+    ignore_paths: &[String],
+    type_only_paths: &[String],
+) -> Vec<Difference> {
+    let rules = Rules {
+        ignore: ignore_paths.iter().map(|path| parse_path(path)).collect(),
+        type_only: type_only_paths
+            .iter()
+            .map(|path| parse_path(path))
+            .collect(),
+    };
+    let mut differences = Vec::new();
+    diff_at(expected, actual, &mut Vec::new(), &rules, &mut differences);
+    differences
+}
+
+/// 选取路径匹配的所有值（按文档顺序），路径格式同 `diff` 的忽略路径，`*` 匹配任意字段或下标
+pub fn select<'a>(value: &'a Value, path: &str) -> Vec<&'a Value> {
+    let mut matches = vec![value];
+    for segment in parse_path(path) {
+        matches = matches
+            .into_iter()
+            .flat_map(|value| -> Vec<&Value> {
+                match (&segment, value) {
+                    (Segment::Key(key), Value::Object(map)) => map.get(key).into_iter().collect(),
+                    (Segment::Index(index), Value::Array(items)) => {
+                        items.get(*index).into_iter().collect()
+                    }
+                    (Segment::Wildcard, Value::Object(map)) => map.values().collect(),
+                    (Segment::Wildcard, Value::Array(items)) => items.iter().collect(),
+                    _ => Vec::new(),
+                }
+            })
+            .collect();
+    }
+    matches
+}
+
+/// 路径段
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Key(String),
+    Index(usize),
+    Wildcard,
+}
+
+/// 比较时的路径规则
+struct Rules {
+    ignore: Vec<Vec<Segment>>,
+    type_only: Vec<Vec<Segment>>,
+}
+
+fn diff_at(
+    expected: &Value,
+    actual: &Value,
+    path: &mut Vec<Segment>,
+    rules: &Rules,
+    differences: &mut Vec<Difference>,
+) {
+    let ignore = &rules.ignore;
+    if is_ignored(path, ignore) {
+        return;
+    }
+    let type_only = is_type_only(path, &rules.type_only);
+
+    match (expected, actual) {
+        (Value::Object(expected_map), Value::Object(actual_map)) => {
+            for (key, expected_value) in expected_map {
+                path.push(Segment::Key(key.clone()));
+                match actual_map.get(key) {
+                    Some(actual_value) => {
+                        diff_at(expected_value, actual_value, path, rules, differences)
+                    }
+                    None if !is_ignored(path, ignore) => differences.push(Difference {
+                        path: format_path(path),
+                        kind: DiffKind::Missing,
+                        expected: Some(expected_value.clone()),
+                        actual: None,
+                    }),
+                    None => {}
+                }
+                path.pop();
+            }
+            for (key, actual_value) in actual_map {
+                if expected_map.contains_key(key) {
+                    continue;
+                }
+                path.push(Segment::Key(key.clone()));
+                if !is_ignored(path, ignore) {
+                    differences.push(Difference {
+                        path: format_path(path),
+                        kind: DiffKind::Added,
+                        expected: None,
+                        actual: Some(actual_value.clone()),
+                    });
+                }
+                path.pop();
+            }
+        }
+        (Value::Array(expected_items), Value::Array(actual_items))
+            if type_only && !expected_items.is_empty() =>
+        {
+            for (index, actual_item) in actual_items.iter().enumerate() {
+                path.push(Segment::Index(index));
+                diff_at(&expected_items[0], actual_item, path, rules, differences);
+                path.pop();
+            }
+        }
+        (Value::Array(expected_items), Value::Array(actual_items)) => {
+            let len = expected_items.len().max(actual_items.len());
+            for index in 0..len {
+                path.push(Segment::Index(index));
+                match (expected_items.get(index), actual_items.get(index)) {
+                    (Some(e), Some(a)) => diff_at(e, a, path, rules, differences),
+                    (Some(e), None) if !is_ignored(path, ignore) => differences.push(Difference {
+                        path: format_path(path),
+                        kind: DiffKind::Missing,
+                        expected: Some(e.clone()),
+                        actual: None,
+                    }),
+                    (None, Some(a)) if !is_ignored(path, ignore) => differences.push(Difference {
+                        path: format_path(path),
+                        kind: DiffKind::Added,
+                        expected: None,
+                        actual: Some(a.clone()),
+                    }),
+                    _ => {}
+                }
+                path.pop();
+            }
+        }
+        _ if std::mem::discriminant(expected) != std::mem::discriminant(actual) => {
+            differences.push(Difference {
+                path: format_path(path),
+                kind: DiffKind::TypeChanged,
+                expected: Some(expected.clone()),
+                actual: Some(actual.clone()),
+            });
+        }
+        _ if !type_only && expected != actual => differences.push(Difference {
+            path: format_path(path),
+            kind: DiffKind::Changed,
+            expected: Some(expected.clone()),
+            actual: Some(actual.clone()),
+        }),
+        _ => {}
+    }
+}
+
+fn is_ignored(path: &[Segment], ignore: &[Vec<Segment>]) -> bool {
+    ignore.iter().any(|pattern| {
+        pattern.len() == path.len()
+            && pattern
+                .iter()
+                .zip(path)
+                .all(|(p, s)| *p == Segment::Wildcard || p == s)
+    })
+}
+
+/// 路径或其任一上级匹配只比较类型的规则
+fn is_type_only(path: &[Segment], type_only: &[Vec<Segment>]) -> bool {
+    type_only.iter().any(|pattern| {
+        pattern.len() <= path.len()
+            && pattern
+                .iter()
+                .zip(path)
+                .all(|(p, s)| *p == Segment::Wildcard || p == s)
+    })
+}
+
+/// 解析 `$.a.b[0]` / `a.b[*]` 形式的路径
+fn parse_path(path: &str) -> Vec<Segment> {
+    let path = path.strip_prefix('$').unwrap_or(path);
+    let mut segments = Vec::new();
+    for part in path.split('.').filter(|part| !part.is_empty()) {
+        let (key, indexes) = match part.find('[') {
+            Some(pos) => (&part[..pos], &part[pos..]),
+            None => (part, ""),
+        };
+        if key == "*" {
+            segments.push(Segment::Wildcard);
+        } else if !key.is_empty() {
+            segments.push(Segment::Key(key.to_string()));
+        }
+        for index in indexes.split('[').filter(|index| !index.is_empty()) {
+            let index = index.trim_end_matches(']');
+            segments.push(match index.parse() {
+                Ok(index) => Segment::Index(index),
+                Err(_) => Segment::Wildcard,
+            });
+        }
+    }
+    segments
+}
+
+fn format_path(path: &[Segment]) -> String {
+    let mut output = String::from("$");
+    for segment in path {
+        match segment {
+            Segment::Key(key) => {
+                output.push('.');
+                output.push_str(key);
+            }
+            Segment::Index(index) => output.push_str(&format!("[{}]", index)),
+            Segment::Wildcard => output.push_str(".*"),
+        }
+    }
+    output
+}
+
+fn type_name(value: Option<&Value>) -> &'static str {
+    match value {
+        None => "none",
+        Some(Value::Null) => "null",
+        Some(Value::Bool(_)) => "bool",
+        Some(Value::Number(_)) => "number",
+        Some(Value::String(_)) => "string",
+        Some(Value::Array(_)) => "array",
+        Some(Value::Object(_)) => "object",
+    }
+}
+
+fn truncate(text: &str, max_chars: usize) -> String {
+    if text.chars().count() <= max_chars {
+        text.to_string()
+    } else {
+        format!("{}…", text.chars().take(max_chars).collect::<String>())
+    }
+}
@@ -0,0 +1,71 @@
+use serde_json::Value;
+
+use crate::config::ExpectJsonConfig;
+use crate::http_client::ResponseData;
+use crate::jq::JqFilter;
+use crate::json_diff;
+
+// Import logger macros from crate root
+use crate::log_warn;
+
+/// 失败原因中最多列出的差异数，完整差异输出到日志
+const MAX_REPORTED_DIFFERENCES: usize = 5;
+
+/// 期望响应JSON断言
+pub struct JsonExpectation;
+
+impl JsonExpectation {
+    /// 将响应JSON与期望文档深度比较，返回不匹配原因
+    pub fn check(config: &ExpectJsonConfig, response: &ResponseData) -> Option<String> {
+        let actual: Value = match serde_json::from_slice(&response.body) {
+            Ok(actual) => actual,
+            Err(e) => return Some(format!("expected a JSON response: {}", e)),
+        };
+
+        let differences = json_diff::diff_with_types(
+            &config.expected,
+            &actual,
+            config.ignore_paths.as_deref().unwrap_or_default(),
+            config.type_only_paths.as_deref().unwrap_or_default(),
+        );
+        if differences.is_empty() {
+            return None;
+        }
+
+        log_warn!("🧾 Response JSON differs from expected:");
+        for difference in &differences {
+            log_warn!("    {}", difference);
+        }
+        let shown: Vec<String> = differences
+            .iter()
+            .take(MAX_REPORTED_DIFFERENCES)
+            .map(|d| d.to_string())
+            .collect();
+        let more = differences.len().saturating_sub(MAX_REPORTED_DIFFERENCES);
+        Some(format!(
+            "response JSON mismatch ({} difference(s)): {}{}",
+            differences.len(),
+            shown.join("; "),
+            if more > 0 {
+                format!("; … and {} more", more)
+            } else {
+                String::new()
+            }
+        ))
+    }
+
+    /// 对响应JSON执行jq断言，返回第一个不成立的表达式
+    pub fn check_jq(filters: &[String], response: &ResponseData) -> Option<String> {
+        let actual: Value = match serde_json::from_slice(&response.body) {
+            Ok(actual) => actual,
+            Err(e) => return Some(format!("expected a JSON response: {}", e)),
+        };
+        filters
+            .iter()
+            .find_map(|filter| match JqFilter::holds(filter, &actual) {
+                Ok(true) => None,
+                Ok(false) => Some(format!("jq assertion failed: {}", filter)),
+                Err(e) => Some(e.to_string()),
+            })
+    }
+}
@@ -0,0 +1,68 @@
+//! RemoteTask 并发HTTP请求库
+//!
+//! 提供A+B周期请求、Digest认证、字段生成和统计功能，可作为命令行工具或库嵌入使用。
+
+pub mod adaptive_pacing;
+pub mod auth_recovery;
+pub mod bandwidth;
+pub mod campaign;
+pub mod capacity;
+pub mod capture;
+pub mod circuit_breaker;
+pub mod compare;
+pub mod conditional;
+pub mod config;
+pub mod config_watcher;
+pub mod cookies;
+pub mod cron;
+pub mod csrf;
+pub mod cycle_context;
+pub mod daemon;
+pub mod delay;
+pub mod device_clock;
+pub mod distributed;
+pub mod download;
+pub mod drift;
+pub mod fault_injection;
+pub mod field_generator;
+pub mod heartbeat;
+pub mod http_client;
+pub mod isapi;
+pub mod job_queue;
+pub mod jq;
+pub mod json_diff;
+pub mod json_expectation;
+pub mod listener;
+pub mod logger;
+pub mod metrics;
+pub mod mock_server;
+pub mod notifier;
+pub mod observer;
+pub mod port_probe;
+pub mod progress;
+pub mod provisioning;
+pub mod rate_limiter;
+pub mod raw_request;
+pub mod record_proxy;
+pub mod recorder;
+pub mod repl;
+pub mod report;
+pub mod request_handler;
+pub mod result_store;
+pub mod rng;
+pub mod rtsp;
+pub mod run_metadata;
+pub mod secrets;
+pub mod self_monitor;
+pub mod service;
+pub mod slo;
+pub mod snapshot;
+pub mod stats;
+pub mod status_expectation;
+pub mod sweep;
+pub mod task_sweeper;
+pub mod task_verifier;
+pub mod telemetry;
+pub mod template;
+pub mod time_window;
+pub mod validation;
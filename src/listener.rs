@@ -0,0 +1,41 @@
+use std::future::Future;
+use std::time::Duration;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+// Import logger macros from crate root
+use crate::log_warn;
+
+/// accept失败后的首次等待时间，连续失败时逐次加倍
+const ACCEPT_BACKOFF_START: Duration = Duration::from_millis(10);
+/// accept失败后的最长等待时间
+const ACCEPT_BACKOFF_MAX: Duration = Duration::from_secs(1);
+
+/// 在后台接受连接，每个连接交给 `on_connection` 在独立任务中处理。
+/// accept失败（如文件描述符耗尽）时按指数退避等待后重试，不会空转占满CPU
+pub fn serve<F, Fut>(listener: TcpListener, on_connection: F) -> JoinHandle<()>
+where
+    F: Fn(TcpStream) -> Fut + Send + 'static,
+    Fut: Future<Output = ()> + Send + 'static,
+{
+    tokio::spawn(async move {
+        let mut backoff = ACCEPT_BACKOFF_START;
+        loop {
+            match listener.accept().await {
+                Ok((stream, _)) => {
+                    backoff = ACCEPT_BACKOFF_START;
+                    tokio::spawn(on_connection(stream));
+                }
+                Err(e) => {
+                    log_warn!(
+                        "Failed to accept connection: {}, retrying in {:?}",
+                        e,
+                        backoff
+                    );
+                    tokio::time::sleep(backoff).await;
+                    backoff = (backoff * 2).min(ACCEPT_BACKOFF_MAX);
+                }
+            }
+        }
+    })
+}
@@ -1,4 +1,9 @@
-use std::sync::atomic::{AtomicU8, Ordering};
+use std::collections::VecDeque;
+use std::fmt;
+use std::future::Future;
+use std::io::{IsTerminal, Write};
+use std::sync::atomic::{AtomicBool, AtomicU8, AtomicUsize, Ordering};
+use std::sync::{Condvar, Mutex, OnceLock};
 
 // Log level definitions
 #[derive(Debug, Clone, PartialEq, Eq)]
@@ -11,7 +16,7 @@ pub enum LogLevel {
 }
 
 impl LogLevel {
-    pub fn from_str(level: &str) -> Option<Self> {
+    pub fn parse(level: &str) -> Option<Self> {
         match level.to_lowercase().as_str() {
             "error" => Some(LogLevel::Error),
             "warn" => Some(LogLevel::Warn),
@@ -42,12 +47,194 @@ pub fn get_log_level() -> LogLevel {
     }
 }
 
+// Whether log lines go to stderr (keeps stdout free for machine-readable output)
+static LOG_TO_STDERR: AtomicBool = AtomicBool::new(false);
+
+pub fn set_log_to_stderr(to_stderr: bool) {
+    LOG_TO_STDERR.store(to_stderr, Ordering::Relaxed);
+}
+
+/// Whether the stream log lines are written to is a terminal
+pub fn log_stream_is_terminal() -> bool {
+    if LOG_TO_STDERR.load(Ordering::Relaxed) {
+        std::io::stderr().is_terminal()
+    } else {
+        std::io::stdout().is_terminal()
+    }
+}
+
+/// Write one log line to the configured stream (queued when asynchronous logging is on)
+pub fn write_line(line: fmt::Arguments) {
+    let to_stderr = LOG_TO_STDERR.load(Ordering::Relaxed);
+    if ASYNC_LOGGING.load(Ordering::Acquire)
+        && let Some(writer) = ASYNC_WRITER.get()
+    {
+        writer.push(to_stderr, line.to_string());
+    } else if to_stderr {
+        eprintln!("{}", line);
+    } else {
+        println!("{}", line);
+    }
+}
+
+/// Default capacity of the asynchronous log queue
+pub const LOG_QUEUE_CAPACITY: usize = 8192;
+
+// Whether log lines are handed to the background writer instead of written inline
+static ASYNC_LOGGING: AtomicBool = AtomicBool::new(false);
+static ASYNC_WRITER: OnceLock<AsyncWriter> = OnceLock::new();
+
+/// Background writer thread fed by a bounded queue; when the queue is full the oldest
+/// line is dropped so request tasks never wait on a slow terminal or pipe
+struct AsyncWriter {
+    state: Mutex<QueueState>,
+    queued: Condvar,  // signaled when lines are queued
+    drained: Condvar, // signaled when the writer has written everything queued
+    capacity: AtomicUsize,
+    dropped: AtomicUsize,
+}
+
+#[derive(Default)]
+struct QueueState {
+    lines: VecDeque<(bool, String)>, // (to stderr, line)
+    writing: bool,
+}
+
+impl AsyncWriter {
+    fn push(&self, to_stderr: bool, line: String) {
+        let mut state = self.state.lock().unwrap();
+        if state.lines.len() >= self.capacity.load(Ordering::Relaxed) {
+            state.lines.pop_front();
+            self.dropped.fetch_add(1, Ordering::Relaxed);
+        }
+        state.lines.push_back((to_stderr, line));
+        self.queued.notify_one();
+    }
+
+    fn run(&self) {
+        loop {
+            let batch = {
+                let mut state = self.state.lock().unwrap();
+                while state.lines.is_empty() {
+                    state.writing = false;
+                    self.drained.notify_all();
+                    state = self.queued.wait(state).unwrap();
+                }
+                state.writing = true;
+                std::mem::take(&mut state.lines)
+            };
+            let (mut stdout, mut stderr) = (std::io::stdout().lock(), std::io::stderr().lock());
+            for (to_stderr, line) in batch {
+                let _ = if to_stderr {
+                    writeln!(stderr, "{}", line)
+                } else {
+                    writeln!(stdout, "{}", line)
+                };
+            }
+            let _ = stdout.flush();
+        }
+    }
+
+    /// Block until every queued line has been written
+    fn flush(&self) {
+        let mut state = self.state.lock().unwrap();
+        while !state.lines.is_empty() || state.writing {
+            state = self.drained.wait(state).unwrap();
+        }
+    }
+}
+
+/// Switch to asynchronous logging through a background writer with a bounded queue
+/// (`capacity` lines, oldest dropped when full). Call [`stop_async_logging`] before returning
+/// from main, and exit through [`exit`] instead of `std::process::exit`, so queued lines are not lost.
+pub fn start_async_logging(capacity: usize) {
+    let writer = ASYNC_WRITER.get_or_init(|| {
+        std::thread::Builder::new()
+            .name("log-writer".to_string())
+            .spawn(|| ASYNC_WRITER.wait().run())
+            .expect("failed to spawn log writer thread");
+        AsyncWriter {
+            state: Mutex::new(QueueState::default()),
+            queued: Condvar::new(),
+            drained: Condvar::new(),
+            capacity: AtomicUsize::new(capacity),
+            dropped: AtomicUsize::new(0),
+        }
+    });
+    writer.capacity.store(capacity.max(1), Ordering::Relaxed);
+    ASYNC_LOGGING.store(true, Ordering::Release);
+}
+
+/// Flush the asynchronous queue and go back to writing log lines inline.
+/// Returns the number of lines dropped because the queue was full.
+pub fn stop_async_logging() -> usize {
+    if !ASYNC_LOGGING.swap(false, Ordering::AcqRel) {
+        return 0;
+    }
+    let Some(writer) = ASYNC_WRITER.get() else {
+        return 0;
+    };
+    writer.flush();
+    writer.dropped.swap(0, Ordering::Relaxed)
+}
+
+/// Exit the process after writing out the asynchronous log queue, so the lines explaining
+/// why the process stopped are not lost with the background writer
+pub fn exit(code: i32) -> ! {
+    stop_async_logging();
+    std::process::exit(code)
+}
+
+// Whether a progress bar currently occupies the last terminal line
+static PROGRESS_ACTIVE: AtomicBool = AtomicBool::new(false);
+
+pub fn set_progress_active(active: bool) {
+    PROGRESS_ACTIVE.store(active, Ordering::Relaxed);
+}
+
+/// Escape sequence clearing the progress bar line before a log line (empty without a progress bar)
+pub fn line_start() -> &'static str {
+    if PROGRESS_ACTIVE.load(Ordering::Relaxed) {
+        "\r\x1b[2K"
+    } else {
+        ""
+    }
+}
+
+tokio::task_local! {
+    // Correlation ID of the cycle the current request task belongs to
+    static CORRELATION_ID: String;
+}
+
+/// Run a future with a correlation ID prepended to every log line it emits
+pub async fn with_correlation_id<F: Future>(
+    correlation_id: Option<String>,
+    future: F,
+) -> F::Output {
+    match correlation_id {
+        Some(correlation_id) => CORRELATION_ID.scope(correlation_id, future).await,
+        None => future.await,
+    }
+}
+
+/// Log line prefix for the current task (`[<correlation id>] ` or empty)
+pub fn log_prefix() -> String {
+    CORRELATION_ID
+        .try_with(|correlation_id| format!("[{}] ", correlation_id))
+        .unwrap_or_default()
+}
+
 // Logging macros
 #[macro_export]
 macro_rules! log_error {
     ($($arg:tt)*) => {
         if $crate::logger::get_log_level() as u8 >= $crate::logger::LogLevel::Error as u8 {
-            println!("❌ {}", format_args!($($arg)*));
+            $crate::logger::write_line(format_args!(
+                "{}❌ {}{}",
+                $crate::logger::line_start(),
+                $crate::logger::log_prefix(),
+                format_args!($($arg)*)
+            ));
         }
     };
 }
@@ -56,7 +243,12 @@ macro_rules! log_error {
 macro_rules! log_warn {
     ($($arg:tt)*) => {
         if $crate::logger::get_log_level() as u8 >= $crate::logger::LogLevel::Warn as u8 {
-            println!("⚠️  {}", format_args!($($arg)*));
+            $crate::logger::write_line(format_args!(
+                "{}⚠️  {}{}",
+                $crate::logger::line_start(),
+                $crate::logger::log_prefix(),
+                format_args!($($arg)*)
+            ));
         }
     };
 }
@@ -65,7 +257,12 @@ macro_rules! log_warn {
 macro_rules! log_info {
     ($($arg:tt)*) => {
         if $crate::logger::get_log_level() as u8 >= $crate::logger::LogLevel::Info as u8 {
-            println!("ℹ️  {}", format_args!($($arg)*));
+            $crate::logger::write_line(format_args!(
+                "{}ℹ️  {}{}",
+                $crate::logger::line_start(),
+                $crate::logger::log_prefix(),
+                format_args!($($arg)*)
+            ));
         }
     };
 }
@@ -74,7 +271,12 @@ macro_rules! log_info {
 macro_rules! log_debug {
     ($($arg:tt)*) => {
         if $crate::logger::get_log_level() as u8 >= $crate::logger::LogLevel::Debug as u8 {
-            println!("🐛 {}", format_args!($($arg)*));
+            $crate::logger::write_line(format_args!(
+                "{}🐛 {}{}",
+                $crate::logger::line_start(),
+                $crate::logger::log_prefix(),
+                format_args!($($arg)*)
+            ));
         }
     };
 }
@@ -83,7 +285,12 @@ macro_rules! log_debug {
 macro_rules! log_trace {
     ($($arg:tt)*) => {
         if $crate::logger::get_log_level() as u8 >= $crate::logger::LogLevel::Trace as u8 {
-            println!("🔍 {}", format_args!($($arg)*));
+            $crate::logger::write_line(format_args!(
+                "{}🔍 {}{}",
+                $crate::logger::line_start(),
+                $crate::logger::log_prefix(),
+                format_args!($($arg)*)
+            ));
         }
     };
 }
@@ -1,30 +1,1338 @@
 use std::collections::HashMap;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+use std::time::Duration;
 
-// Import modules
-mod config;
-mod field_generator;
-mod http_client;
-mod logger;
-mod request_handler;
-mod stats;
+use clap::Parser;
+use tokio_util::sync::CancellationToken;
 
-use config::{DigestAuthConfig, GeneratedField, HttpRequestConfig, RequestConfig};
-use logger::{LogLevel, set_log_level};
-use request_handler::RequestHandler;
-use stats::StatsHandler;
+mod cli;
 
-#[tokio::main]
-async fn main() {
-    // 设置默认日志级别为Info
-    set_log_level(LogLevel::Info);
+use cli::{
+    CampaignArgs, CapacityArgs, CleanupArgs, Cli, Command, CompareArgs, CoordinateArgs,
+    MockServerArgs, RecordProxyArgs, ReportArgs, RunArgs, ServiceArgs, SweepArgs, WorkerArgs,
+};
+use remote_task::campaign::Campaign;
+use remote_task::capacity::CapacitySearch;
+use remote_task::compare::RunComparison;
+use remote_task::config::{
+    DigestAuthConfig, GeneratedField, HttpRequestConfig, RequestConfig, RuntimeConfig,
+    ThresholdsConfig,
+};
+use remote_task::config_watcher::ConfigWatcher;
+use remote_task::daemon::Daemon;
+use remote_task::device_clock::DeviceClock;
+use remote_task::distributed::{Coordinator, WorkerServer};
+use remote_task::field_generator::UniqueValues;
+use remote_task::logger::{self, LOG_QUEUE_CAPACITY, LogLevel, set_log_level, set_log_to_stderr};
+use remote_task::mock_server::{MockServer, MockServerConfig};
+use remote_task::progress::ProgressBar;
+use remote_task::record_proxy::{RecordProxy, RecordProxyConfig, ScenarioBuilder};
+use remote_task::recorder::ResponseRecorder;
+use remote_task::repl::Repl;
+use remote_task::report::{HtmlReport, JunitReport, ThresholdChecker, ThresholdResult};
+use remote_task::request_handler::{RequestHandler, RunOptions};
+use remote_task::result_store::ResultStore;
+use remote_task::secrets::{SecretResolver, prompt_missing_passwords};
+use remote_task::service::{PidFile, ServiceInstaller, shutdown_signal};
+use remote_task::stats::{RequestStats, StatsHandler};
+use remote_task::sweep::ParameterSweep;
+use remote_task::task_sweeper::{TaskLedger, TaskSweeper};
+use remote_task::telemetry::Telemetry;
+use remote_task::validation::{ConfigValidator, Severity};
+use remote_task::{log_debug, log_error, log_info, log_trace, log_warn};
+
+fn main() {
+    let cli = Cli::parse();
+
+    // schema 输出到stdout供其他工具使用，不打印横幅
+    if let Some(Command::Schema) = cli.command {
+        let schema = schemars::schema_for!(RequestConfig);
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&schema).expect("schema is valid JSON")
+        );
+        return;
+    }
+
+    set_log_level(cli_log_level(&cli).unwrap_or(LogLevel::Info));
+    // JSON输出模式下stdout只保留最终统计
+    set_log_to_stderr(cli.output == "json");
 
     log_info!("🌐 Advanced Rust Concurrent HTTP Request Tool");
     log_info!("==============================================");
     log_info!("📝 Features: GET/POST requests, Digest auth, field generation");
     log_info!("");
 
-    // 示例配置，包含POST请求和digest认证
-    let config = RequestConfig {
+    let runtime = match build_runtime(&runtime_config(&cli)) {
+        Ok(runtime) => runtime,
+        Err(e) => {
+            log_error!("Failed to start the tokio runtime: {}", e);
+            logger::exit(1);
+        }
+    };
+    runtime.block_on(dispatch(&cli));
+}
+
+/// 运行时调优：命令行参数优先于配置文件中的 `runtime`
+///
+/// 运行时须在解析凭据（可能需要异步）之前创建。配置文件在这里按 `--env`、`--var` 加载一次，
+/// 之后各子命令的 [`load_config`] 复用同一份结果；未指定 `--config` 时不加载内置示例配置。
+fn runtime_config(cli: &Cli) -> RuntimeConfig {
+    let mut runtime = cli
+        .config
+        .as_ref()
+        .and_then(|_| load_config(cli).runtime)
+        .unwrap_or_default();
+    if let Some(worker_threads) = cli.worker_threads {
+        runtime.worker_threads = Some(worker_threads.into());
+    }
+    if let Some(max_blocking_threads) = cli.max_blocking_threads {
+        runtime.max_blocking_threads = Some(max_blocking_threads.into());
+    }
+    runtime
+}
+
+/// 多线程tokio运行时，未调优的参数使用tokio的默认值
+fn build_runtime(config: &RuntimeConfig) -> std::io::Result<tokio::runtime::Runtime> {
+    let mut builder = tokio::runtime::Builder::new_multi_thread();
+    builder.enable_all();
+    // 0会导致tokio panic，由配置校验报告
+    if let Some(worker_threads) = config.worker_threads.filter(|threads| *threads > 0) {
+        builder.worker_threads(worker_threads);
+        log_info!("⚙️  Runtime worker threads: {}", worker_threads);
+    }
+    if let Some(max_blocking_threads) = config.max_blocking_threads.filter(|threads| *threads > 0) {
+        builder.max_blocking_threads(max_blocking_threads);
+        log_info!("⚙️  Runtime max blocking threads: {}", max_blocking_threads);
+    }
+    builder.build()
+}
+
+async fn dispatch(cli: &Cli) {
+    match cli
+        .command
+        .clone()
+        .unwrap_or(Command::Run(RunArgs::default()))
+    {
+        Command::Run(args) => run(cli, &args).await,
+        Command::Validate => validate(cli),
+        Command::Report(args) => report(&args),
+        Command::Compare(args) => compare(&args),
+        Command::Cleanup(args) => cleanup(cli, &args).await,
+        Command::MockServer(args) => mock_server(&args).await,
+        Command::Sweep(args) => sweep(cli, &args).await,
+        Command::Capacity(args) => capacity(cli, &args).await,
+        Command::Repl => repl(cli).await,
+        Command::RecordProxy(args) => record_proxy(&args).await,
+        Command::Worker(args) => worker(&args).await,
+        Command::Coordinate(args) => coordinate(cli, &args).await,
+        Command::Daemon => daemon(cli).await,
+        Command::Campaign(args) => campaign(cli, &args).await,
+        Command::Service(args) => service(cli, &args),
+        Command::Schema => unreachable!("handled above"),
+    }
+}
+
+/// 运行A+B请求循环
+async fn run(cli: &Cli, args: &RunArgs) {
+    let mut config = load_config(cli);
+
+    // 校验配置，存在错误时不发送任何请求
+    require_valid(&config);
+
+    // dry-run 只渲染请求，不需要凭据
+    if args.dry_run {
+        print_rendered_requests(&config, args.cycles);
+        return;
+    }
+
+    // 解析凭据引用（env:/keyring:/secret:），未提供密码时交互式输入
+    resolve_credentials(&mut config);
+
+    // 打印配置信息
+    print_config(&config);
+
+    // 启用OpenTelemetry追踪
+    let telemetry = config
+        .tracing
+        .as_ref()
+        .and_then(|tracing| match Telemetry::init(tracing) {
+            Ok(telemetry) => {
+                log_info!("🔭 Exporting traces to {}", tracing.otlp_endpoint);
+                Some(telemetry)
+            }
+            Err(e) => {
+                log_warn!("Tracing disabled: {:#}", e);
+                None
+            }
+        });
+
+    // 监视配置文件以热更新运行参数
+    let mut options = RunOptions::default();
+    if args.watch {
+        match &cli.config {
+            Some(path) => {
+                match ConfigWatcher::spawn(
+                    path.clone(),
+                    cli.env.clone(),
+                    Duration::from_secs(1),
+                    cli_log_level(cli),
+                ) {
+                    Ok(receiver) => {
+                        log_info!("👀 Watching {} for changes", path.display());
+                        options.live_settings = Some(receiver);
+                    }
+                    Err(e) => log_warn!("Config watching disabled: {:#}", e),
+                }
+            }
+            None => log_warn!("--watch requires --config, ignoring"),
+        }
+    }
+
+    // 记录模式
+    if let Some(dir) = &args.record {
+        log_info!("💾 Recording responses to {}", dir.display());
+        options
+            .observers
+            .push(Arc::new(ResponseRecorder::new(dir.clone())));
+    }
+
+    // 结果库
+    let result_store = match &args.db {
+        Some(path) => {
+            let description = cli
+                .config
+                .as_ref()
+                .map(|config| config.display().to_string());
+            match ResultStore::start_run(path, description.as_deref()) {
+                Ok(store) => {
+                    log_info!(
+                        "🗄️  Storing results in {} (run {})",
+                        path.display(),
+                        store.run_id()
+                    );
+                    let store = Arc::new(store);
+                    options.observers.push(store.clone());
+                    Some(store)
+                }
+                Err(e) => {
+                    log_error!("{:#}", e);
+                    logger::exit(1);
+                }
+            }
+        }
+        None => None,
+    };
+
+    // 有界运行时显示进度条
+    let progress = if args.no_progress {
+        None
+    } else {
+        ProgressBar::new(config.max_requests, None).map(Arc::new)
+    };
+    if let Some(progress) = &progress {
+        options.observers.push(progress.clone());
+    }
+
+    options.update_snapshots = args.update_snapshots;
+
+    // Ctrl+C/SIGTERM中断运行：进行中的请求在宽限期内完成，超时后取消
+    let shutdown = CancellationToken::new();
+    options.shutdown = Some(shutdown.clone());
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        shutdown.cancel();
+    });
+    let thresholds = config.thresholds.clone();
+    let report_config = args.html.as_ref().map(|_| config.clone());
+
+    // 运行并发请求
+    // 运行期间日志由后台线程写出，避免高请求速率下阻塞在stdout上
+    logger::start_async_logging(LOG_QUEUE_CAPACITY);
+    let stats = RequestHandler::run_with_options(config, options).await;
+    finish_async_logging();
+    if let Some(progress) = &progress {
+        progress.finish();
+    }
+
+    if let Some(telemetry) = telemetry {
+        telemetry.shutdown().await;
+    }
+    if let Some(store) = &result_store
+        && let Err(e) = store.finish_run(&stats).await
+    {
+        log_warn!("Failed to finalize run in results database: {}", e);
+    }
+
+    // 打印最终统计信息
+    StatsHandler::print_final_stats(&stats);
+
+    // 检查CI阈值
+    let threshold_results = check_thresholds(thresholds.as_ref(), &stats);
+
+    if let Some(path) = &args.junit {
+        match JunitReport::write(path, &stats, &threshold_results) {
+            Ok(()) => log_info!("📄 JUnit report written to {}", path.display()),
+            Err(e) => log_error!("{:#}", e),
+        }
+    }
+
+    if let (Some(path), Some(config)) = (&args.html, &report_config) {
+        match HtmlReport::write(path, &stats, config, &threshold_results) {
+            Ok(()) => log_info!("📄 HTML report written to {}", path.display()),
+            Err(e) => log_error!("{:#}", e),
+        }
+    }
+
+    if cli.output == "json" {
+        print_json_stats(&stats, &threshold_results);
+    }
+
+    if threshold_results.iter().any(|result| !result.passed) {
+        logger::exit(2);
+    }
+}
+
+/// 评估CI阈值并打印结果
+fn check_thresholds(
+    thresholds: Option<&ThresholdsConfig>,
+    stats: &RequestStats,
+) -> Vec<ThresholdResult> {
+    let threshold_results = thresholds
+        .map(|thresholds| ThresholdChecker::evaluate(thresholds, stats))
+        .unwrap_or_default();
+    for result in &threshold_results {
+        if result.passed {
+            log_info!("  ✅ Threshold {}: {}", result.name, result.message);
+        } else {
+            log_error!("  Threshold {} failed: {}", result.name, result.message);
+        }
+    }
+    threshold_results
+}
+
+/// `--output json`：在stdout上输出最终统计和阈值结果
+fn print_json_stats(stats: &RequestStats, threshold_results: &[ThresholdResult]) {
+    let mut document = StatsHandler::to_json(stats);
+    document["thresholds"] = threshold_results
+        .iter()
+        .map(|result| {
+            serde_json::json!({
+                "name": result.name,
+                "passed": result.passed,
+                "message": result.message,
+            })
+        })
+        .collect();
+    println!(
+        "{}",
+        serde_json::to_string_pretty(&document).expect("stats are valid JSON")
+    );
+}
+
+/// 运行分布式worker，直到Ctrl+C
+async fn worker(args: &WorkerArgs) {
+    let server = match WorkerServer::start(args.bind, args.token.clone()).await {
+        Ok(server) => server,
+        Err(e) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+    };
+    log_info!("🛠️  Worker listening on {}", server.local_addr());
+    if args.token.is_none() {
+        log_warn!("No --token set: any host that can reach this port can start runs");
+    }
+    log_info!("Press Ctrl+C to stop");
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// 将场景分发给各worker，实时汇总并输出合并后的统计
+async fn coordinate(cli: &Cli, args: &CoordinateArgs) {
+    let mut config = load_config(cli);
+    require_valid(&config);
+
+    // 凭据在coordinator上解析后随场景下发
+    resolve_credentials(&mut config);
+    print_config(&config);
+
+    let coordinator = match Coordinator::new(args.workers.clone(), args.token.clone()) {
+        Ok(coordinator) => coordinator,
+        Err(e) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+    };
+    let thresholds = config.thresholds.clone();
+    let stop = async {
+        let _ = tokio::signal::ctrl_c().await;
+    };
+    let stats = match coordinator.run(config, stop).await {
+        Ok(stats) => stats,
+        Err(e) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+    };
+
+    log_info!("📡 Per-worker results:");
+    for (address, worker_stats) in &stats.per_worker {
+        log_info!(
+            "  {}: {} requests, {} failed ({:.2}% errors), p95 {}",
+            address,
+            worker_stats.total_requests,
+            worker_stats.failed_requests,
+            worker_stats.error_rate(),
+            worker_stats
+                .latency_percentile(95.0)
+                .map_or("-".to_string(), |p95| format!("{}ms", p95.as_millis()))
+        );
+    }
+    StatsHandler::print_final_stats(&stats.combined);
+
+    let threshold_results = check_thresholds(thresholds.as_ref(), &stats.combined);
+    if cli.output == "json" {
+        print_json_stats(&stats.combined, &threshold_results);
+    }
+    if threshold_results.iter().any(|result| !result.passed) {
+        logger::exit(2);
+    }
+}
+
+/// 加载定时计划或批量运行引用的场景文件（路径相对于主配置文件所在目录），
+/// 应用环境和 `--var` 覆盖并校验，失败时退出
+fn load_scenario(cli: &Cli, path: &str, env: Option<&str>, owner: &str) -> RequestConfig {
+    let path = cli
+        .config
+        .as_deref()
+        .and_then(|config| config.parent())
+        .map(PathBuf::from)
+        .unwrap_or_default()
+        .join(path);
+    let mut scenario = match RequestConfig::load(&path) {
+        Ok(scenario) => scenario,
+        Err(e) => {
+            log_error!("{}: {:#}", owner, e);
+            logger::exit(1);
+        }
+    };
+    if let Some(env) = env
+        && let Err(e) = scenario.apply_environment(env)
+    {
+        log_error!("{}: {}", owner, e);
+        logger::exit(1);
+    }
+    scenario.apply_overrides(&cli.vars);
+    if let Some(seed) = cli.seed {
+        scenario.seed = Some(seed);
+    }
+    log_info!("📄 {} uses {}", owner, path.display());
+    if !report_diagnostics(&scenario) {
+        log_error!("{}: scenario is invalid, aborting", owner);
+        logger::exit(1);
+    }
+    scenario
+}
+
+/// 依次或并行运行 `campaign` 中的场景，输出合并报告；有场景未通过时退出码为2
+async fn campaign(cli: &Cli, args: &CampaignArgs) {
+    let config = load_config(cli);
+    require_valid(&config);
+    let Some(campaign_config) = config.campaign.clone() else {
+        log_error!("The config has no `campaign` section");
+        logger::exit(1);
+    };
+
+    let mut scenarios = Vec::new();
+    for (index, entry) in campaign_config.scenarios.iter().enumerate() {
+        let owner = format!(
+            "Scenario {}",
+            Campaign::entry_name(entry.name.as_deref(), entry.scenario.as_deref(), index)
+        );
+        let env = entry.env.as_deref().or(cli.env.as_deref());
+        let mut scenario = match &entry.scenario {
+            Some(path) => load_scenario(cli, path, env, &owner),
+            None => {
+                let mut scenario = config.clone();
+                if let Some(env) = entry.env.as_deref()
+                    && let Err(e) = scenario.apply_environment(env)
+                {
+                    log_error!("{}: {}", owner, e);
+                    logger::exit(1);
+                }
+                scenario
+            }
+        };
+        if entry.max_requests.is_none()
+            && entry.max_duration_ms.is_none()
+            && scenario.max_requests.is_none()
+        {
+            log_warn!(
+                "{}: no max_requests or max_duration_ms, the campaign never ends",
+                owner
+            );
+        }
+        resolve_credentials(&mut scenario);
+        scenarios.push(scenario);
+    }
+
+    let campaign = match Campaign::new(&campaign_config, scenarios) {
+        Ok(campaign) => campaign.with_progress(!args.no_progress),
+        Err(e) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+    };
+    let shutdown = CancellationToken::new();
+    let signal = shutdown.clone();
+    tokio::spawn(async move {
+        shutdown_signal().await;
+        signal.cancel();
+    });
+    logger::start_async_logging(LOG_QUEUE_CAPACITY);
+    let result = campaign.run(shutdown).await;
+    finish_async_logging();
+
+    result.print_summary();
+    if let Some(path) = &args.junit {
+        match result.write_junit(path) {
+            Ok(()) => log_info!("📄 JUnit report written to {}", path.display()),
+            Err(e) => log_error!("{:#}", e),
+        }
+    }
+    if let Some(path) = &args.report {
+        match result.write_json(path) {
+            Ok(()) => log_info!("📄 Campaign report written to {}", path.display()),
+            Err(e) => log_error!("{:#}", e),
+        }
+    }
+    if cli.output == "json" {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&result.to_json()).expect("report is valid JSON")
+        );
+    }
+    if !result.passed() {
+        logger::exit(2);
+    }
+}
+
+/// 常驻运行，按cron表达式定时执行场景，直到Ctrl+C或SIGTERM
+async fn daemon(cli: &Cli) {
+    let config = load_config(cli);
+    require_valid(&config);
+    let Some(daemon_config) = config.daemon.clone() else {
+        log_error!("The config has no `daemon` section");
+        logger::exit(1);
+    };
+
+    let mut scenarios = Vec::new();
+    for schedule in &daemon_config.schedules {
+        let mut scenario = match &schedule.scenario {
+            Some(path) => {
+                let owner = format!("Schedule {}", schedule.name);
+                let scenario = load_scenario(cli, path, cli.env.as_deref(), &owner);
+                if schedule.max_requests.is_none() && scenario.max_requests.is_none() {
+                    log_warn!(
+                        "{}: neither the schedule nor {} sets max_requests, each run never ends",
+                        owner,
+                        path
+                    );
+                }
+                scenario
+            }
+            None => config.clone(),
+        };
+        resolve_credentials(&mut scenario);
+        scenarios.push(scenario);
+    }
+
+    // PID文件同时作为单实例锁，持有到进程退出
+    let _pid_file = match daemon_config.pid_file.as_deref().map(PidFile::acquire) {
+        Some(Ok(pid_file)) => {
+            log_info!("🔒 PID file: {}", pid_file.path().display());
+            Some(pid_file)
+        }
+        Some(Err(e)) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+        None => None,
+    };
+
+    let daemon = match Daemon::new(&daemon_config, scenarios) {
+        Ok(daemon) => Arc::new(daemon),
+        Err(e) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+    };
+    log_info!(
+        "🗓️  Daemon started with {} schedule(s), press Ctrl+C to stop",
+        daemon_config.schedules.len()
+    );
+    logger::start_async_logging(LOG_QUEUE_CAPACITY);
+    let result = daemon.run(shutdown_signal()).await;
+    finish_async_logging();
+    if let Err(e) = result {
+        log_error!("{:#}", e);
+        logger::exit(1);
+    }
+}
+
+/// 写出异步日志队列中剩余的日志并恢复同步输出，报告因队列已满而丢弃的行数
+fn finish_async_logging() {
+    let dropped = logger::stop_async_logging();
+    if dropped > 0 {
+        log_warn!(
+            "{} log line(s) were dropped because output could not keep up (queue of {} lines)",
+            dropped,
+            LOG_QUEUE_CAPACITY
+        );
+    }
+}
+
+/// 注册、注销或打印运行守护模式的systemd服务（Windows上为开机启动的计划任务）
+fn service(cli: &Cli, args: &ServiceArgs) {
+    let Some(config_path) = &cli.config else {
+        log_error!("--config is required: the service runs `daemon` with that config file");
+        logger::exit(1);
+    };
+    let config = load_config(cli);
+    if config.daemon.is_none() {
+        log_error!("The config has no `daemon` section");
+        logger::exit(1);
+    }
+
+    // 服务以其他工作目录启动，路径需为绝对路径
+    let absolute = |path: &Path| std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let installer = ServiceInstaller {
+        name: args.name.clone(),
+        executable: std::env::current_exe()
+            .map(|exe| absolute(&exe))
+            .unwrap_or_else(|_| PathBuf::from("remote-task")),
+        config: absolute(config_path),
+        env: cli.env.clone(),
+        vars: cli.vars.clone(),
+        watchdog_secs: args.watchdog_secs,
+    };
+    let result = match args.action.as_str() {
+        "install" => installer.install(),
+        "uninstall" => installer.uninstall(),
+        _ => {
+            if cfg!(windows) {
+                println!("schtasks {}", installer.startup_task_arguments().join(" "));
+            } else {
+                println!("# {}", installer.systemd_unit_path().display());
+                print!("{}", installer.systemd_unit());
+            }
+            Ok(())
+        }
+    };
+    if let Err(e) = result {
+        log_error!("{:#}", e);
+        logger::exit(1);
+    }
+}
+
+/// 交互式调试场景中的单个请求
+async fn repl(cli: &Cli) {
+    let mut config = load_config(cli);
+    require_valid(&config);
+
+    resolve_credentials(&mut config);
+
+    Repl::new(config).run().await;
+}
+
+/// 按任务记录文件清理设备上的遗留任务（如崩溃后）
+async fn cleanup(cli: &Cli, args: &CleanupArgs) {
+    let mut config = load_config(cli);
+    let ledger_path = args.ledger.clone().unwrap_or_else(|| {
+        TaskSweeper::ledger_path(&config.task_cleanup.clone().unwrap_or_default())
+    });
+    let ledger = match TaskLedger::load(&ledger_path) {
+        Ok(ledger) => ledger,
+        Err(e) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+    };
+    let tracked: usize = ledger.tasks().values().map(|tasks| tasks.len()).sum();
+    if tracked == 0 {
+        log_info!("✅ No tracked tasks in {}", ledger_path.display());
+        return;
+    }
+    log_info!(
+        "🧹 {} tracked task(s) in {}",
+        tracked,
+        ledger_path.display()
+    );
+
+    resolve_credentials(&mut config);
+
+    let report = TaskSweeper::sweep(&ledger, &config).await;
+    log_info!(
+        "🧹 Cleanup finished: {} deleted, {} already gone, {} device(s) failed",
+        report.deleted,
+        report.already_gone,
+        report.failed_devices
+    );
+    if report.failed_devices > 0 {
+        logger::exit(1);
+    }
+}
+
+/// 参数扫描：依次以每个取值运行，输出对比表
+async fn sweep(cli: &Cli, args: &SweepArgs) {
+    let mut config = load_config(cli);
+    if let Some(cycles) = args.cycles {
+        config.max_requests = Some(cycles);
+    }
+    if config.max_requests.is_none() {
+        log_error!(
+            "Sweep requires max_requests in config or --cycles, otherwise the first run never ends"
+        );
+        logger::exit(1);
+    }
+    let values = match ParameterSweep::parse_values(&args.values) {
+        Ok(values) => values,
+        Err(e) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+    };
+
+    // 运行前校验每个取值下的配置（警告只在第一个取值时打印）
+    for (index, value) in values.iter().enumerate() {
+        let swept = match ParameterSweep::apply(&config, &args.param, value) {
+            Ok(swept) => swept,
+            Err(e) => {
+                log_error!("{:#}", e);
+                logger::exit(1);
+            }
+        };
+        let valid = if index == 0 {
+            report_diagnostics(&swept)
+        } else {
+            let diagnostics = ConfigValidator::validate(&swept);
+            for diagnostic in &diagnostics {
+                if diagnostic.severity == Severity::Error {
+                    log_error!("{}", diagnostic);
+                }
+            }
+            !ConfigValidator::has_errors(&diagnostics)
+        };
+        if !valid {
+            log_error!(
+                "Configuration is invalid with {} = {}, aborting",
+                args.param,
+                value
+            );
+            logger::exit(1);
+        }
+    }
+
+    resolve_credentials(&mut config);
+
+    let points = match ParameterSweep::run(&config, &args.param, &values).await {
+        Ok(points) => points,
+        Err(e) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+    };
+
+    println!();
+    println!("=== Sweep {} ===", args.param);
+    println!(
+        "{:>12} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9} {:>9}  Stopped",
+        "Value", "Requests", "Failed", "Error%", "p50(ms)", "p95(ms)", "p99(ms)", "Req/s"
+    );
+    for point in &points {
+        let stats = &point.stats;
+        let seconds = stats.elapsed.as_secs_f64();
+        println!(
+            "{:>12} {:>9} {:>9} {:>8.2}% {:>9} {:>9} {:>9} {:>9.1}  {}",
+            point.value,
+            stats.total_requests,
+            stats.failed_requests,
+            stats.error_rate(),
+            format_latency(stats.latency_percentile(50.0)),
+            format_latency(stats.latency_percentile(95.0)),
+            format_latency(stats.latency_percentile(99.0)),
+            if seconds > 0.0 {
+                stats.total_requests as f64 / seconds
+            } else {
+                0.0
+            },
+            stats.stop_reason.as_deref().unwrap_or("-")
+        );
+    }
+}
+
+/// 容量搜索：找出SLO仍成立的最高负载并输出各负载级别的结果
+async fn capacity(cli: &Cli, args: &CapacityArgs) {
+    let mut config = load_config(cli);
+    if let Some(cycles) = args.cycles {
+        config.max_requests = Some(cycles);
+    }
+    if config.max_requests.is_none() {
+        log_error!(
+            "Capacity search requires max_requests in config or --cycles, otherwise the first run never ends"
+        );
+        logger::exit(1);
+    }
+    if !CapacitySearch::has_criteria(&config) {
+        log_error!(
+            "Capacity search requires thresholds (max_error_rate / max_p95_ms) or a request slo to judge each load level"
+        );
+        logger::exit(1);
+    }
+    let mut levels = match ParameterSweep::parse_values(&args.values) {
+        Ok(levels) => levels,
+        Err(e) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+    };
+    if args.descending {
+        levels.reverse();
+    }
+
+    // 运行前校验每个负载级别下的配置（警告只在第一个级别时打印）
+    for (index, value) in levels.iter().enumerate() {
+        let level = match ParameterSweep::apply(&config, &args.param, value) {
+            Ok(level) => level,
+            Err(e) => {
+                log_error!("{:#}", e);
+                logger::exit(1);
+            }
+        };
+        let valid = if index == 0 {
+            report_diagnostics(&level)
+        } else {
+            let diagnostics = ConfigValidator::validate(&level);
+            for diagnostic in &diagnostics {
+                if diagnostic.severity == Severity::Error {
+                    log_error!("{}", diagnostic);
+                }
+            }
+            !ConfigValidator::has_errors(&diagnostics)
+        };
+        if !valid {
+            log_error!(
+                "Configuration is invalid with {} = {}, aborting",
+                args.param,
+                value
+            );
+            logger::exit(1);
+        }
+    }
+
+    resolve_credentials(&mut config);
+
+    let report = match CapacitySearch::run(&config, &args.param, &levels, &args.search).await {
+        Ok(report) => report,
+        Err(e) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+    };
+
+    if cli.output == "json" {
+        println!(
+            "{}",
+            serde_json::to_string_pretty(&report.to_json()).expect("report is valid JSON")
+        );
+    } else {
+        println!();
+        println!("=== Capacity {} ({} search) ===", args.param, args.search);
+        println!(
+            "{:>12} {:>9} {:>9} {:>9} {:>9} {:>9}  Result",
+            "Value", "Requests", "Error%", "p95(ms)", "p99(ms)", "Req/s"
+        );
+        for point in &report.points {
+            let stats = &point.stats;
+            let seconds = stats.elapsed.as_secs_f64();
+            println!(
+                "{:>12} {:>9} {:>8.2}% {:>9} {:>9} {:>9.1}  {}",
+                point.value,
+                stats.total_requests,
+                stats.error_rate(),
+                format_latency(stats.latency_percentile(95.0)),
+                format_latency(stats.latency_percentile(99.0)),
+                if seconds > 0.0 {
+                    stats.total_requests as f64 / seconds
+                } else {
+                    0.0
+                },
+                if point.passed() {
+                    "pass".to_string()
+                } else {
+                    format!("FAIL ({})", point.violations.join("; "))
+                }
+            );
+        }
+        match (&report.knee, &report.breaking) {
+            (Some(knee), Some(breaking)) => println!(
+                "Knee point: {} = {} (SLO violated at {})",
+                args.param, knee, breaking
+            ),
+            (Some(knee), None) => println!(
+                "Knee point: {} = {} (SLO held at every level tested; try higher loads)",
+                args.param, knee
+            ),
+            (None, _) => println!("SLO violated even at the lowest load level"),
+        }
+    }
+    if report.knee.is_none() {
+        logger::exit(2);
+    }
+}
+
+/// 运行模拟服务器直到Ctrl+C
+async fn mock_server(args: &MockServerArgs) {
+    for (name, rate) in [
+        ("--failure-rate", args.failure_rate),
+        ("--silent-failure-rate", args.silent_failure_rate),
+    ] {
+        if !(0.0..=1.0).contains(&rate) {
+            log_error!("{} must be between 0 and 1", name);
+            logger::exit(1);
+        }
+    }
+    let config = MockServerConfig {
+        latency: Duration::from_millis(args.latency_ms),
+        failure_rate: args.failure_rate,
+        silent_failure_rate: args.silent_failure_rate,
+        digest_auth: args.username.clone().zip(args.password.clone()),
+        clock_skew_ms: args.clock_skew_ms,
+        proxy_auth: args.proxy_username.clone().zip(args.proxy_password.clone()),
+        proxy_digest: args.proxy_digest,
+        max_tasks: args.max_tasks,
+    };
+    let server = match MockServer::start(args.bind, config).await {
+        Ok(server) => server,
+        Err(e) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+    };
+
+    log_info!("🧪 Mock server listening on {}", server.base_url());
+    log_info!("  POST /ISAPI/System/AlgoPackageScheduling/AddTask");
+    log_info!("  PUT  /ISAPI/System/AlgoPackageScheduling/DeleteTask");
+    log_info!("  GET  /ISAPI/System/AlgoPackageScheduling/TaskList");
+    log_info!("  POST /ISAPI/System/AlgoPackageScheduling/GetTaskStatus");
+    log_info!("  GET  /ISAPI/System/AlgoPackageScheduling/capabilities");
+    log_info!(
+        "  GET  /ISAPI/Security/sessionLogin/capabilities, POST /ISAPI/Security/sessionLogin"
+    );
+    log_info!("  PUT  /ISAPI/Security/sessionHeartbeat");
+    log_info!(
+        "  latency: {}ms, failure rate: {}, silent failure rate: {}, digest auth: {}",
+        args.latency_ms,
+        args.failure_rate,
+        args.silent_failure_rate,
+        if args.username.is_some() { "on" } else { "off" }
+    );
+    log_info!("Press Ctrl+C to stop");
+
+    let _ = tokio::signal::ctrl_c().await;
+    let stats = server.stats();
+    log_info!(
+        "🛑 Mock server stopped: {} requests, {} auth challenges, {} injected failures, {} tasks added, {} deleted, {} remaining",
+        stats.requests,
+        stats.auth_challenges,
+        stats.injected_failures,
+        stats.tasks_added,
+        stats.tasks_deleted,
+        server.tasks().len()
+    );
+}
+
+/// 录制经由代理的请求，停止时写出场景配置
+async fn record_proxy(args: &RecordProxyArgs) {
+    let include = match args.include.as_deref().map(regex::Regex::new).transpose() {
+        Ok(include) => include,
+        Err(e) => {
+            log_error!("Invalid --include regex: {}", e);
+            logger::exit(1);
+        }
+    };
+    let proxy = match RecordProxy::start(args.bind, RecordProxyConfig { include }).await {
+        Ok(proxy) => proxy,
+        Err(e) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+    };
+
+    log_info!("⏺️  Record proxy listening on {}", proxy.proxy_url());
+    log_info!(
+        "  Configure it as the HTTP proxy of the browser or tool, then perform request A and request B"
+    );
+    log_info!("  Press Ctrl+C to stop and write {}", args.save.display());
+
+    let _ = tokio::signal::ctrl_c().await;
+    let scenario = match proxy
+        .scenario()
+        .and_then(|config| ScenarioBuilder::to_json(&config))
+    {
+        Ok(scenario) => scenario,
+        Err(e) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+    };
+    let json = serde_json::to_string_pretty(&scenario).expect("scenario is valid JSON");
+    if let Err(e) = std::fs::write(&args.save, json + "\n") {
+        log_error!("Failed to write {}: {}", args.save.display(), e);
+        logger::exit(1);
+    }
+    log_info!(
+        "💾 Recorded {} request(s), scenario written to {}",
+        proxy.exchanges().len(),
+        args.save.display()
+    );
+}
+
+/// 汇总结果库中的所有运行，并按请求标签展开选定的运行
+fn report(args: &ReportArgs) {
+    let summaries = match ResultStore::run_summaries(&args.db) {
+        Ok(summaries) => summaries,
+        Err(e) => {
+            log_error!("{:#}", e);
+            logger::exit(1);
+        }
+    };
+    if summaries.is_empty() {
+        log_warn!("No runs in {}", args.db.display());
+        return;
+    }
+
+    println!(
+        "{:>5}  {:<19} {:>9} {:>9} {:>9} {:>9} {:>10}  Description",
+        "Run", "Started (UTC)", "Requests", "Failed", "Error%", "p95(ms)", "Duration"
+    );
+    for summary in &summaries {
+        let stats = &summary.stats;
+        println!(
+            "{:>5}  {:<19} {:>9} {:>9} {:>8.2}% {:>9} {:>10}  {}{}",
+            summary.id,
+            summary.started_at,
+            stats.total_requests,
+            stats.failed_requests,
+            stats.error_rate(),
+            format_latency(stats.latency_percentile(95.0)),
+            summary
+                .elapsed
+                .map(|elapsed| format!("{:.1}s", elapsed.as_secs_f64()))
+                .unwrap_or_else(|| "-".to_string()),
+            summary.description.as_deref().unwrap_or_default(),
+            summary
+                .stop_reason
+                .as_ref()
+                .map(|reason| format!(" (stopped: {})", reason))
+                .unwrap_or_default()
+        );
+    }
+
+    let runs = if args.runs.is_empty() {
+        summaries
+            .last()
+            .map(|summary| summary.id)
+            .into_iter()
+            .collect()
+    } else {
+        args.runs.clone()
+    };
+    for run_id in runs {
+        let per_label = match ResultStore::label_stats(&args.db, run_id) {
+            Ok(per_label) => per_label,
+            Err(e) => {
+                log_error!("{:#}", e);
+                logger::exit(1);
+            }
+        };
+        println!();
+        println!("=== Run {} ===", run_id);
+        if let Some(metadata) = summaries
+            .iter()
+            .find(|summary| summary.id == run_id)
+            .and_then(|summary| summary.metadata.as_ref())
+        {
+            for (name, value) in metadata.entries() {
+                println!("{:<15} {}", format!("{}:", name), value);
+            }
+        }
+        println!(
+            "{:<8} {:>8} {:>9} {:>9} {:>9}  URL",
+            "Label", "Count", "Success%", "p50(ms)", "p95(ms)"
+        );
+        for ((label, url), label_stats) in &per_label {
+            println!(
+                "{:<8} {:>8} {:>8.1}% {:>9} {:>9}  {}",
+                label,
+                label_stats.total_requests,
+                label_stats.success_rate(),
+                format_latency(label_stats.latency_percentile(50.0)),
+                format_latency(label_stats.latency_percentile(95.0)),
+                url
+            );
+        }
+    }
+}
+
+/// 对比两次运行各请求标签的延迟和错误率，并给出显著性提示
+fn compare(args: &CompareArgs) {
+    let load = |path: &Path, run: Option<i64>| {
+        let run_id = match run {
+            Some(run_id) => run_id,
+            None => match ResultStore::latest_run_id(path) {
+                Ok(Some(run_id)) => run_id,
+                Ok(None) => {
+                    log_error!("No runs in {}", path.display());
+                    logger::exit(1);
+                }
+                Err(e) => {
+                    log_error!("{:#}", e);
+                    logger::exit(1);
+                }
+            },
+        };
+        match ResultStore::label_stats(path, run_id) {
+            Ok(per_label) => (run_id, per_label),
+            Err(e) => {
+                log_error!("{:#}", e);
+                logger::exit(1);
+            }
+        }
+    };
+    let (baseline_run, baseline) = load(&args.baseline, args.baseline_run);
+    let (candidate_run, candidate) = load(&args.candidate, args.candidate_run);
+
+    println!(
+        "Baseline:  {} (run {})",
+        args.baseline.display(),
+        baseline_run
+    );
+    println!(
+        "Candidate: {} (run {})",
+        args.candidate.display(),
+        candidate_run
+    );
+
+    for comparison in RunComparison::compare(&baseline, &candidate) {
+        println!();
+        println!("[{}] {}", comparison.label, comparison.url);
+        let (Some(base), Some(cand)) = (&comparison.baseline, &comparison.candidate) else {
+            let missing = if comparison.baseline.is_none() {
+                "baseline"
+            } else {
+                "candidate"
+            };
+            println!("  not present in {}", missing);
+            continue;
+        };
+        println!(
+            "  {:<10} {:>10} {:>10} {:>10}",
+            "", "baseline", "candidate", "delta"
+        );
+        println!(
+            "  {:<10} {:>10} {:>10} {:>+10}",
+            "requests",
+            base.total_requests,
+            cand.total_requests,
+            cand.total_requests as i64 - base.total_requests as i64
+        );
+        println!(
+            "  {:<10} {:>9.2}% {:>9.2}% {:>+9.2}pp  {}",
+            "errors",
+            base.error_rate(),
+            cand.error_rate(),
+            comparison.error_rate_delta().unwrap_or_default(),
+            comparison.error_rate_significance().hint()
+        );
+        for percentile in [50.0, 95.0, 99.0] {
+            println!(
+                "  {:<10} {:>10} {:>10} {:>10}",
+                format!("p{}(ms)", percentile),
+                format_latency(base.latency_percentile(percentile)),
+                format_latency(cand.latency_percentile(percentile)),
+                comparison
+                    .latency_delta_ms(percentile)
+                    .map(|delta| format!("{:+.1}", delta))
+                    .unwrap_or_else(|| "-".to_string())
+            );
+        }
+        println!("  latency:   {}", comparison.latency_significance().hint());
+    }
+}
+
+/// 以毫秒格式化延迟，缺失时为 "-"
+fn format_latency(latency: Option<Duration>) -> String {
+    latency
+        .map(|latency| latency.as_millis().to_string())
+        .unwrap_or_else(|| "-".to_string())
+}
+
+/// 校验配置并报告所有问题，存在错误时以非零状态码退出
+fn validate(cli: &Cli) {
+    let config = load_config(cli);
+    if report_diagnostics(&config) {
+        log_info!("✅ Configuration is valid");
+    } else {
+        logger::exit(1);
+    }
+}
+
+/// 加载配置文件（未指定时使用内置示例配置）并应用选定环境，失败时退出
+///
+/// 配置只加载一次（运行时调优和子命令共用），之后每次调用返回其副本
+fn load_config(cli: &Cli) -> RequestConfig {
+    static LOADED: OnceLock<RequestConfig> = OnceLock::new();
+    LOADED.get_or_init(|| load_config_once(cli)).clone()
+}
+
+fn load_config_once(cli: &Cli) -> RequestConfig {
+    let mut config = match &cli.config {
+        Some(path) => match RequestConfig::load(path) {
+            Ok(config) => config,
+            Err(e) => {
+                log_error!("Failed to load config: {:#}", e);
+                logger::exit(1);
+            }
+        },
+        None => builtin_config(),
+    };
+
+    // 应用选定的环境变量集
+    if let Some(env) = &cli.env {
+        if let Err(e) = config.apply_environment(env) {
+            log_error!("{}", e);
+            logger::exit(1);
+        }
+        log_info!("🌍 Using environment: {}", env);
+    }
+    config.apply_overrides(&cli.vars);
+    if let Some(seed) = cli.seed {
+        config.seed = Some(seed);
+    }
+    for (key, value) in &cli.vars {
+        log_debug!("📌 Variable override: {}={}", key, value);
+    }
+
+    // 命令行指定的级别（--quiet）优先于配置
+    if cli_log_level(cli).is_none()
+        && let Some(level) = config.log_level.as_deref().and_then(LogLevel::parse)
+    {
+        set_log_level(level);
+    }
+
+    config
+}
+
+/// 命令行指定的日志级别：--quiet 时只输出错误，且优先于配置中的 `log_level`；未指定时由配置决定（默认Info）
+fn cli_log_level(cli: &Cli) -> Option<LogLevel> {
+    cli.quiet.then_some(LogLevel::Error)
+}
+
+/// 打印前 `cycles` 个周期渲染后的完整请求，用于检查占位符替换（不连接设备，时间字段按本机时间生成）
+fn print_rendered_requests(config: &RequestConfig, cycles: usize) {
+    let unique_values = UniqueValues::new();
+    let clock = DeviceClock::new();
+    for cycle in 1..=cycles {
+        println!("=== Cycle {} ===", cycle);
+        for rendered in RequestHandler::render_cycle_with(config, cycle, &unique_values, &clock) {
+            if let Some(target) = rendered.target {
+                println!("--- Target {} ---", target.display_name());
+            }
+            let branches = rendered
+                .branches
+                .iter()
+                .enumerate()
+                .filter_map(|(index, branch)| {
+                    let label = branch
+                        .name
+                        .clone()
+                        .unwrap_or_else(|| format!("branch{}", index + 1));
+                    Some((label, branch.request.as_ref()?))
+                });
+            let requests = rendered
+                .requests_a
+                .iter()
+                .map(|request| ("A".to_string(), request))
+                .chain(rendered.repeat.iter().map(|repeat| {
+                    let label = repeat.name.as_deref().unwrap_or("repeat");
+                    (format!("{} x{}", label, repeat.count), &repeat.request)
+                }))
+                .chain([("B".to_string(), &rendered.request_b)])
+                .chain(branches);
+            for (label, request) in requests {
+                println!(
+                    "[{}] {} {}",
+                    label,
+                    request.method.to_uppercase(),
+                    request.url
+                );
+                let mut headers: Vec<_> = request.headers.iter().flatten().collect();
+                headers.sort();
+                for (name, value) in headers {
+                    println!("{}: {}", name, value);
+                }
+                if let Some(body) = &request.body {
+                    println!();
+                    println!("{}", body.trim());
+                    if config.strict_json == Some(true)
+                        && request.method.to_uppercase() != "RAW"
+                        && let Some(error) = RequestHandler::json_body_error(body)
+                    {
+                        log_warn!("{}", error);
+                    }
+                }
+                println!();
+            }
+        }
+    }
+}
+
+/// 打印配置诊断，没有错误时返回true
+fn report_diagnostics(config: &RequestConfig) -> bool {
+    let diagnostics = ConfigValidator::validate(config);
+    for diagnostic in &diagnostics {
+        match diagnostic.severity {
+            Severity::Error => log_error!("{}", diagnostic),
+            Severity::Warning => log_warn!("{}", diagnostic),
+        }
+    }
+    !ConfigValidator::has_errors(&diagnostics)
+}
+
+/// 校验配置，存在错误时退出
+fn require_valid(config: &RequestConfig) {
+    if !report_diagnostics(config) {
+        log_error!("Configuration is invalid, aborting (run `validate` for details)");
+        logger::exit(1);
+    }
+}
+
+/// 解析凭据引用（env:/keyring:/secret:）并交互式输入缺少的密码，失败时退出
+fn resolve_credentials(config: &mut RequestConfig) {
+    if let Err(e) = SecretResolver::from_config(config)
+        .and_then(|resolver| resolver.resolve_config(config))
+        .and_then(|()| prompt_missing_passwords(config))
+    {
+        log_error!("Failed to resolve credentials: {:#}", e);
+        logger::exit(1);
+    }
+}
+
+/// 内置示例配置，包含POST请求和digest认证
+fn builtin_config() -> RequestConfig {
+    RequestConfig {
         request_a: HttpRequestConfig {
             method: "POST".to_string(),
             url: "https://10.41.131.87/ISAPI/System/AlgoPackageScheduling/AddTask?format=json".to_string(),
@@ -74,6 +1382,7 @@ async fn main() {
                     }
                 ]
             }"#.to_string()),
+            ..Default::default()
         },
         request_b: HttpRequestConfig {
             method: "PUT".to_string(),
@@ -87,6 +1396,7 @@ async fn main() {
                     }
                 ]
             }"#.to_string()),
+            ..Default::default()
         },
         delay_between_a_and_b_ms: 500,
         delay_between_a_requests_ms: 3000,
@@ -96,18 +1406,23 @@ async fn main() {
             password: "backend15".to_string(),
             realm: None,
             nonce: None,
+            auth_type: None,
+            preemptive: None,
         }),
         generated_fields: Some(vec![
             GeneratedField {
                 name: "taskID".to_string(),
                 generator: "uuid".to_string(),
                 field_type: "body".to_string(),
-                value: None,
+                ..Default::default()
             },
         ]),
-    };
+        ..Default::default()
+    }
+}
 
-    // 打印配置信息
+/// 打印配置信息
+fn print_config(config: &RequestConfig) {
     log_info!("📋 Configuration:");
     log_info!("  Request A:");
     log_info!("    Method: {}", config.request_a.method);
@@ -126,6 +1441,20 @@ async fn main() {
     } else {
         log_info!("    None");
     }
+    if let Some(proxy) = &config.proxy {
+        log_info!("  Proxy: {}", proxy.url);
+        if let Some(username) = &proxy.username {
+            log_info!(
+                "    Username: {} ({})",
+                username,
+                if proxy.uses_digest() {
+                    "digest"
+                } else {
+                    "basic"
+                }
+            );
+        }
+    }
     log_info!("  Generated Fields:");
     if let Some(fields) = &config.generated_fields {
         for field in fields {
@@ -140,10 +1469,4 @@ async fn main() {
         log_info!("    None");
     }
     log_info!("");
-
-    // 运行并发请求
-    let stats = RequestHandler::run_concurrent_requests(config).await;
-
-    // 打印最终统计信息
-    StatsHandler::print_final_stats(&stats);
 }
@@ -0,0 +1,208 @@
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::UdpSocket;
+use tokio::task::JoinHandle;
+
+use crate::config::MetricsConfig;
+use crate::observer::RequestObserver;
+use crate::stats::RequestResult;
+
+// Import logger macros from crate root
+use crate::{log_debug, log_warn};
+
+/// 指标导出器：缓存每个请求的测量值，按推送间隔写入InfluxDB或statsd
+pub struct MetricsExporter {
+    protocol: String,
+    address: String,
+    prefix: String,
+    tags: HashMap<String, String>,
+    flush_interval: Duration,
+    buffer: Mutex<Vec<String>>,
+    client: reqwest::Client,
+}
+
+impl MetricsExporter {
+    pub fn new(config: &MetricsConfig) -> Self {
+        Self {
+            protocol: config.protocol.clone(),
+            address: config.address.clone(),
+            prefix: config
+                .prefix
+                .clone()
+                .unwrap_or_else(|| "remote_task".to_string()),
+            tags: config.tags.clone().unwrap_or_default(),
+            flush_interval: Duration::from_millis(config.flush_interval_ms.unwrap_or(1000)),
+            buffer: Mutex::new(Vec::new()),
+            client: reqwest::Client::new(),
+        }
+    }
+
+    /// 启动后台任务，按推送间隔定期推送缓存的指标
+    pub fn spawn_flusher(self: &Arc<Self>) -> JoinHandle<()> {
+        let exporter = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(exporter.flush_interval);
+            interval.tick().await;
+            loop {
+                interval.tick().await;
+                exporter.flush().await;
+            }
+        })
+    }
+
+    /// 立即推送缓存的指标，推送失败时丢弃并告警
+    pub async fn flush(&self) {
+        let lines = std::mem::take(&mut *self.buffer.lock().unwrap());
+        if lines.is_empty() {
+            return;
+        }
+
+        let result = match self.protocol.as_str() {
+            "statsd" => self.send_statsd(&lines).await,
+            _ => self.send_influx(&lines).await,
+        };
+        match result {
+            Ok(()) => log_debug!("📈 Exported {} metrics to {}", lines.len(), self.address),
+            Err(e) => log_warn!(
+                "Failed to export {} metrics to {}: {}",
+                lines.len(),
+                self.address,
+                e
+            ),
+        }
+    }
+
+    async fn send_influx(&self, lines: &[String]) -> anyhow::Result<()> {
+        let response = self
+            .client
+            .post(&self.address)
+            .body(lines.join("\n"))
+            .send()
+            .await?;
+        if !response.status().is_success() {
+            return Err(anyhow::anyhow!("status {}", response.status()));
+        }
+        Ok(())
+    }
+
+    async fn send_statsd(&self, lines: &[String]) -> anyhow::Result<()> {
+        let socket = UdpSocket::bind("0.0.0.0:0").await?;
+        socket.connect(&self.address).await?;
+        // 每个数据包控制在常见MTU以内
+        let mut packet = String::new();
+        for line in lines {
+            if !packet.is_empty() && packet.len() + line.len() + 1 > 1400 {
+                socket.send(packet.as_bytes()).await?;
+                packet.clear();
+            }
+            if !packet.is_empty() {
+                packet.push('\n');
+            }
+            packet.push_str(line);
+        }
+        socket.send(packet.as_bytes()).await?;
+        Ok(())
+    }
+
+    /// InfluxDB行协议：标签为label/method/target/status，字段为耗时、成功与否、接收字节数和URL
+    fn influx_line(&self, result: &RequestResult) -> String {
+        let mut line = escape_influx(&self.prefix, false);
+        let mut tags: Vec<(&str, String)> = self
+            .tags
+            .iter()
+            .map(|(key, value)| (key.as_str(), value.clone()))
+            .collect();
+        tags.push(("label", result.label.clone()));
+        tags.push(("method", result.method.clone()));
+        if let Some(target) = &result.target {
+            tags.push(("target", target.clone()));
+        }
+        if let Some(status) = result.status {
+            tags.push(("status", status.to_string()));
+        }
+        tags.sort();
+        for (key, value) in tags {
+            line.push_str(&format!(
+                ",{}={}",
+                escape_influx(key, true),
+                escape_influx(&value, true)
+            ));
+        }
+
+        let bytes = result
+            .response
+            .as_ref()
+            .map_or(0, |response| response.body_len());
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_nanos();
+        line.push_str(&format!(
+            " elapsed_ms={},success={},bytes_received={}i,cycle={}i,url=\"{}\" {}",
+            result.elapsed.as_secs_f64() * 1000.0,
+            result.success,
+            bytes,
+            result.cycle,
+            result.url.replace('\\', "\\\\").replace('"', "\\\""),
+            timestamp
+        ));
+        line
+    }
+
+    /// statsd：`<prefix>.<label>.latency` 计时，`<prefix>.<label>.success|failure` 计数
+    fn statsd_lines(&self, result: &RequestResult) -> Vec<String> {
+        let name = format!("{}.{}", self.prefix, sanitize_statsd(&result.label));
+        vec![
+            format!(
+                "{}.latency:{:.3}|ms",
+                name,
+                result.elapsed.as_secs_f64() * 1000.0
+            ),
+            format!(
+                "{}.{}:1|c",
+                name,
+                if result.success { "success" } else { "failure" }
+            ),
+        ]
+    }
+}
+
+impl RequestObserver for MetricsExporter {
+    fn on_result(&self, result: &RequestResult) {
+        // 预热请求不计入指标
+        if result.warmup {
+            return;
+        }
+        let lines = match self.protocol.as_str() {
+            "statsd" => self.statsd_lines(result),
+            _ => vec![self.influx_line(result)],
+        };
+        self.buffer.lock().unwrap().extend(lines);
+    }
+}
+
+/// 转义行协议中的measurement名（逗号、空格）和标签键值（额外转义等号）
+fn escape_influx(text: &str, is_tag: bool) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if c == ',' || c == ' ' || (is_tag && c == '=') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// statsd指标名中只保留字母、数字、下划线和连字符
+fn sanitize_statsd(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '_' || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
@@ -0,0 +1,679 @@
+use anyhow::{Context, Result};
+use base64::prelude::*;
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use md5::{Digest, Md5};
+use rand::Rng;
+use serde_json::{Value, json};
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+use tokio::net::TcpListener;
+use tokio::task::JoinHandle;
+
+use crate::cron::format_timestamp;
+use crate::isapi::{
+    ADD_TASK_PATH, CAPABILITIES_PATH, DELETE_TASK_PATH, SESSION_HEARTBEAT_PATH,
+    SESSION_LOGIN_CAPABILITIES_PATH, SESSION_LOGIN_PATH, SessionChallenge, TASK_LIST_PATH,
+    TASK_STATUS_PATH, TIME_PATH, session_password_hash, xml_value,
+};
+use crate::listener;
+use crate::template::percent_decode;
+
+// Import logger macros from crate root
+use crate::log_debug;
+
+const SESSION_COOKIE: &str = "WebSession";
+const LOGIN_ITERATIONS: u32 = 100;
+
+/// 模拟服务器配置
+#[derive(Debug, Clone, Default)]
+pub struct MockServerConfig {
+    /// 每个请求的固定响应延迟
+    pub latency: Duration,
+    /// 随机返回500错误的概率（0-1）
+    pub failure_rate: f64,
+    /// AddTask/DeleteTask返回成功但实际不生效的概率（0-1），模拟设备静默失败
+    pub silent_failure_rate: f64,
+    /// 要求认证的用户名和密码（None表示无需认证，挑战为Digest，也接受Basic），sessionLogin也使用这组凭据
+    pub digest_auth: Option<(String, String)>,
+    /// 模拟设备时钟相对本机时钟的偏差（毫秒），影响 `/ISAPI/System/time`（+08:00时区）和响应的Date头
+    pub clock_skew_ms: i64,
+    /// 作为HTTP代理时要求的代理凭据（None表示无需代理认证），按 `proxy_digest` 要求Basic或Digest
+    pub proxy_auth: Option<(String, String)>,
+    pub proxy_digest: bool,
+    /// 设备同时存在的任务数上限（None表示不限），已满时AddTask返回错误，用于调试容量搜索
+    pub max_tasks: Option<usize>,
+}
+
+/// 模拟服务器的请求计数
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct MockServerStats {
+    pub requests: usize,
+    pub auth_challenges: usize,
+    pub proxy_challenges: usize,    // 要求代理认证（407）的次数
+    pub challenged_payloads: usize, // 被要求认证（401）的请求中带body的数量，正确的Digest流程不会发送
+    pub challenged_writes: usize, // 被要求认证（401）的POST/PUT/PATCH/DELETE请求数，认证探测只用HEAD/GET
+    pub injected_failures: usize,
+    pub tasks_added: usize,
+    pub tasks_deleted: usize,
+}
+
+struct MockState {
+    config: MockServerConfig,
+    realm: String,
+    nonce: String,
+    tasks: Mutex<HashSet<String>>,
+    login_challenges: Mutex<HashMap<String, (String, SessionChallenge)>>, // sessionID → (用户名, 待登录的挑战参数)
+    sessions: Mutex<HashSet<String>>,                                     // 已登录的sessionID
+    requests: AtomicUsize,
+    auth_challenges: AtomicUsize,
+    proxy_challenges: AtomicUsize,
+    challenged_payloads: AtomicUsize,
+    challenged_writes: AtomicUsize,
+    injected_failures: AtomicUsize,
+    tasks_added: AtomicUsize,
+    tasks_deleted: AtomicUsize,
+}
+
+/// 模拟AddTask/DeleteTask接口的本地HTTP服务器，用于无实验室设备时开发和测试
+pub struct MockServer {
+    addr: SocketAddr,
+    state: Arc<MockState>,
+    task: JoinHandle<()>,
+}
+
+impl MockServer {
+    /// 在指定地址启动服务器（端口为0时自动分配）
+    pub async fn start(addr: SocketAddr, config: MockServerConfig) -> Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind mock server to {}", addr))?;
+        let addr = listener.local_addr()?;
+        let nonce = random_hex(32);
+        let state = Arc::new(MockState {
+            config,
+            realm: "RemoteTask Mock".to_string(),
+            nonce,
+            tasks: Mutex::new(HashSet::new()),
+            login_challenges: Mutex::new(HashMap::new()),
+            sessions: Mutex::new(HashSet::new()),
+            requests: AtomicUsize::new(0),
+            auth_challenges: AtomicUsize::new(0),
+            proxy_challenges: AtomicUsize::new(0),
+            challenged_payloads: AtomicUsize::new(0),
+            challenged_writes: AtomicUsize::new(0),
+            injected_failures: AtomicUsize::new(0),
+            tasks_added: AtomicUsize::new(0),
+            tasks_deleted: AtomicUsize::new(0),
+        });
+
+        let server_state = Arc::clone(&state);
+        let task = listener::serve(listener, move |stream| {
+            let state = Arc::clone(&server_state);
+            async move {
+                let service = service_fn(move |request| {
+                    let state = Arc::clone(&state);
+                    async move {
+                        let mut response = handle(&state, request).await;
+                        let date = device_now(&state).div_euclid(1000);
+                        response
+                            .headers_mut()
+                            .insert("Date", http_date(date).parse().expect("valid header value"));
+                        Ok::<_, Infallible>(response)
+                    }
+                });
+                let _ = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .await;
+            }
+        });
+
+        Ok(Self { addr, state, task })
+    }
+
+    /// 实际监听地址
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// 服务器根URL，如 `http://127.0.0.1:8080`
+    pub fn base_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// 当前已添加且未删除的任务ID
+    pub fn tasks(&self) -> Vec<String> {
+        let mut tasks: Vec<_> = self.state.tasks.lock().unwrap().iter().cloned().collect();
+        tasks.sort();
+        tasks
+    }
+
+    /// 请求计数
+    pub fn stats(&self) -> MockServerStats {
+        MockServerStats {
+            requests: self.state.requests.load(Ordering::Relaxed),
+            auth_challenges: self.state.auth_challenges.load(Ordering::Relaxed),
+            proxy_challenges: self.state.proxy_challenges.load(Ordering::Relaxed),
+            challenged_payloads: self.state.challenged_payloads.load(Ordering::Relaxed),
+            challenged_writes: self.state.challenged_writes.load(Ordering::Relaxed),
+            injected_failures: self.state.injected_failures.load(Ordering::Relaxed),
+            tasks_added: self.state.tasks_added.load(Ordering::Relaxed),
+            tasks_deleted: self.state.tasks_deleted.load(Ordering::Relaxed),
+        }
+    }
+}
+
+impl Drop for MockServer {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 处理单个请求：代理认证 → 认证 → 延迟 → 故障注入 → 路由
+async fn handle(state: &MockState, request: Request<Incoming>) -> Response<Full<Bytes>> {
+    state.requests.fetch_add(1, Ordering::Relaxed);
+    let method = request.method().clone();
+    let path = request.uri().path().to_string();
+    let query = request.uri().query().unwrap_or_default().to_string();
+    log_debug!("🧪 Mock server: {} {}", method, request.uri());
+
+    // 作为代理时，绝对形式的请求目标直接由本服务器应答
+    if let Some((username, password)) = &state.config.proxy_auth {
+        let authorized = request
+            .headers()
+            .get("Proxy-Authorization")
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|header| {
+                if state.config.proxy_digest {
+                    verify_digest(header, method.as_str(), username, password, state)
+                } else {
+                    verify_basic(header, username, password)
+                }
+            });
+        if !authorized {
+            state.proxy_challenges.fetch_add(1, Ordering::Relaxed);
+            let challenge = if state.config.proxy_digest {
+                format!(
+                    "Digest realm=\"{} Proxy\", qop=\"auth\", nonce=\"{}\", algorithm=MD5",
+                    state.realm, state.nonce
+                )
+            } else {
+                format!("Basic realm=\"{} Proxy\"", state.realm)
+            };
+            let mut response = Response::builder()
+                .status(StatusCode::PROXY_AUTHENTICATION_REQUIRED)
+                .body(Full::new(Bytes::new()))
+                .expect("valid response");
+            response.headers_mut().insert(
+                "Proxy-Authenticate",
+                challenge.parse().expect("valid header value"),
+            );
+            return response;
+        }
+    }
+
+    // sessionLogin流程本身无需认证，已登录的会话Cookie可代替Digest认证
+    let session_path = path == SESSION_LOGIN_CAPABILITIES_PATH || path == SESSION_LOGIN_PATH;
+    let has_session = session_cookie(&request)
+        .is_some_and(|session_id| state.sessions.lock().unwrap().contains(&session_id));
+
+    if let Some((username, password)) = &state.config.digest_auth
+        && !session_path
+        && !has_session
+    {
+        let authorized = request
+            .headers()
+            .get("Authorization")
+            .and_then(|value| value.to_str().ok())
+            .is_some_and(|header| {
+                verify_digest(header, method.as_str(), username, password, state)
+                    || verify_basic(header, username, password)
+            });
+        if !authorized {
+            state.auth_challenges.fetch_add(1, Ordering::Relaxed);
+            let has_payload = request
+                .headers()
+                .get("Content-Length")
+                .and_then(|value| value.to_str().ok())
+                .and_then(|len| len.parse::<u64>().ok())
+                .is_some_and(|len| len > 0);
+            if has_payload {
+                state.challenged_payloads.fetch_add(1, Ordering::Relaxed);
+            }
+            if !matches!(method, Method::GET | Method::HEAD) {
+                state.challenged_writes.fetch_add(1, Ordering::Relaxed);
+            }
+            let mut response = isapi_response(StatusCode::UNAUTHORIZED, 4, "Unauthorized", None);
+            response.headers_mut().insert(
+                "WWW-Authenticate",
+                format!(
+                    "Digest realm=\"{}\", qop=\"auth\", nonce=\"{}\", algorithm=MD5",
+                    state.realm, state.nonce
+                )
+                .parse()
+                .expect("valid header value"),
+            );
+            return response;
+        }
+    }
+
+    let if_none_match = request
+        .headers()
+        .get("If-None-Match")
+        .and_then(|value| value.to_str().ok())
+        .map(str::to_string);
+    let body = match request.into_body().collect().await {
+        Ok(collected) => collected.to_bytes(),
+        Err(_) => Bytes::new(),
+    };
+
+    if !state.config.latency.is_zero() {
+        tokio::time::sleep(state.config.latency).await;
+    }
+
+    if state.config.failure_rate > 0.0
+        && rand::thread_rng().gen_bool(state.config.failure_rate.clamp(0.0, 1.0))
+    {
+        state.injected_failures.fetch_add(1, Ordering::Relaxed);
+        return isapi_response(StatusCode::INTERNAL_SERVER_ERROR, 6, "Device Error", None);
+    }
+
+    match (method, path.as_str()) {
+        (Method::GET, SESSION_LOGIN_CAPABILITIES_PATH) => login_capabilities(state, &query),
+        (Method::POST, SESSION_LOGIN_PATH) => session_login(state, &body),
+        (Method::PUT, SESSION_HEARTBEAT_PATH) if has_session => {
+            xml_response(StatusCode::OK, &xml_status(1, "OK"))
+        }
+        (Method::PUT, SESSION_HEARTBEAT_PATH) => {
+            xml_response(StatusCode::UNAUTHORIZED, &xml_status(4, "Invalid Session"))
+        }
+        (Method::GET, TIME_PATH) => xml_response(
+            StatusCode::OK,
+            &format!(
+                "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<Time version=\"2.0\">\n<timeMode>NTP</timeMode>\n<localTime>{}</localTime>\n<timeZone>CST-8:00:00</timeZone>\n</Time>\n",
+                format_timestamp(device_now(state).div_euclid(1000), 8 * 3600)
+            ),
+        ),
+        (Method::GET, CAPABILITIES_PATH) => json_response(
+            StatusCode::OK,
+            json!({
+                "AlgoPackageSchedulingCap": {
+                    "maxTaskNum": 64,
+                    "isSupportTaskStatus": true,
+                    "isSupportTaskList": true,
+                }
+            }),
+        ),
+        (Method::POST, ADD_TASK_PATH) => add_task(state, &body),
+        // 部分固件也接受以DELETE方法删除任务
+        (Method::PUT | Method::PATCH | Method::DELETE, DELETE_TASK_PATH) => {
+            delete_task(state, &body)
+        }
+        (Method::POST, TASK_STATUS_PATH) => task_status(state, &body),
+        (Method::GET, TASK_LIST_PATH) => task_list(state, if_none_match.as_deref()),
+        _ => isapi_response(StatusCode::NOT_FOUND, 4, "Invalid Operation", None),
+    }
+}
+
+/// 任务列表带ETag，If-None-Match与当前列表一致时返回304
+fn task_list(state: &MockState, if_none_match: Option<&str>) -> Response<Full<Bytes>> {
+    let mut tasks: Vec<_> = state.tasks.lock().unwrap().iter().cloned().collect();
+    tasks.sort();
+    let list: Vec<Value> = tasks
+        .into_iter()
+        .map(|task_id| json!({ "taskID": task_id }))
+        .collect();
+    let body = json!({ "TaskList": list });
+    let etag = format!("\"{}\"", md5_hex(&body.to_string()));
+
+    let mut response = if if_none_match == Some(etag.as_str()) {
+        Response::builder()
+            .status(StatusCode::NOT_MODIFIED)
+            .body(Full::new(Bytes::new()))
+            .expect("valid response")
+    } else {
+        json_response(StatusCode::OK, body)
+    };
+    response
+        .headers_mut()
+        .insert("ETag", etag.parse().expect("valid header value"));
+    response
+}
+
+fn add_task(state: &MockState, body: &[u8]) -> Response<Full<Bytes>> {
+    let Some(task_id) = serde_json::from_slice::<Value>(body)
+        .ok()
+        .and_then(|value| value["taskID"].as_str().map(str::to_string))
+    else {
+        return isapi_response(StatusCode::BAD_REQUEST, 6, "Invalid Content", None);
+    };
+
+    if state.tasks.lock().unwrap().contains(&task_id) {
+        return isapi_response(
+            StatusCode::BAD_REQUEST,
+            6,
+            "Task Already Exists",
+            Some(&task_id),
+        );
+    }
+    if let Some(max_tasks) = state.config.max_tasks
+        && state.tasks.lock().unwrap().len() >= max_tasks
+    {
+        return isapi_response(
+            StatusCode::BAD_REQUEST,
+            6,
+            "Task Count Exceeded",
+            Some(&task_id),
+        );
+    }
+    if silently_fail(state) {
+        return isapi_response(StatusCode::OK, 1, "OK", Some(&task_id));
+    }
+    state.tasks.lock().unwrap().insert(task_id.clone());
+    state.tasks_added.fetch_add(1, Ordering::Relaxed);
+    isapi_response(StatusCode::OK, 1, "OK", Some(&task_id))
+}
+
+fn delete_task(state: &MockState, body: &[u8]) -> Response<Full<Bytes>> {
+    let Some(task_ids) = parse_task_id_list(body) else {
+        return isapi_response(StatusCode::BAD_REQUEST, 6, "Invalid Content", None);
+    };
+
+    let mut tasks = state.tasks.lock().unwrap();
+    if let Some(missing) = task_ids.iter().find(|id| !tasks.contains(*id)) {
+        return isapi_response(StatusCode::BAD_REQUEST, 6, "Task Not Found", Some(missing));
+    }
+    if silently_fail(state) {
+        return isapi_response(StatusCode::OK, 1, "OK", None);
+    }
+    for task_id in &task_ids {
+        tasks.remove(task_id);
+    }
+    state
+        .tasks_deleted
+        .fetch_add(task_ids.len(), Ordering::Relaxed);
+    isapi_response(StatusCode::OK, 1, "OK", None)
+}
+
+/// 按概率决定是否"假装成功"
+fn silently_fail(state: &MockState) -> bool {
+    state.config.silent_failure_rate > 0.0
+        && rand::thread_rng().gen_bool(state.config.silent_failure_rate.clamp(0.0, 1.0))
+}
+
+fn task_status(state: &MockState, body: &[u8]) -> Response<Full<Bytes>> {
+    let Some(task_ids) = parse_task_id_list(body) else {
+        return isapi_response(StatusCode::BAD_REQUEST, 6, "Invalid Content", None);
+    };
+    let tasks = state.tasks.lock().unwrap();
+    let list: Vec<Value> = task_ids
+        .iter()
+        .map(|task_id| {
+            let status = if tasks.contains(task_id) {
+                "running"
+            } else {
+                "notExist"
+            };
+            json!({ "taskID": task_id, "status": status })
+        })
+        .collect();
+    json_response(StatusCode::OK, json!({ "TaskStatusList": list }))
+}
+
+/// 解析 `{"TaskIDList": [{"taskID": ...}]}`，列表为空时返回None
+fn parse_task_id_list(body: &[u8]) -> Option<Vec<String>> {
+    let value = serde_json::from_slice::<Value>(body).ok()?;
+    let task_ids: Vec<String> = value["TaskIDList"]
+        .as_array()?
+        .iter()
+        .filter_map(|item| item["taskID"].as_str().map(str::to_string))
+        .collect();
+    (!task_ids.is_empty()).then_some(task_ids)
+}
+
+/// 为登录用户生成挑战参数
+fn login_capabilities(state: &MockState, query: &str) -> Response<Full<Bytes>> {
+    let username = query
+        .split('&')
+        .find_map(|pair| pair.strip_prefix("username="))
+        .map(percent_decode)
+        .unwrap_or_default();
+    let challenge = SessionChallenge {
+        session_id: random_hex(32),
+        challenge: random_hex(32),
+        iterations: LOGIN_ITERATIONS,
+        is_irreversible: true,
+        salt: random_hex(32),
+        session_id_version: Some("2".to_string()),
+    };
+    let xml = format!(
+        "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n<SessionLoginCap>\
+         <username>{}</username><sessionID>{}</sessionID><challenge>{}</challenge>\
+         <iterations>{}</iterations><isIrreversible>true</isIrreversible><salt>{}</salt>\
+         <sessionIDVersion>2</sessionIDVersion></SessionLoginCap>",
+        username, challenge.session_id, challenge.challenge, challenge.iterations, challenge.salt
+    );
+    state
+        .login_challenges
+        .lock()
+        .unwrap()
+        .insert(challenge.session_id.clone(), (username, challenge));
+    xml_response(StatusCode::OK, &xml)
+}
+
+/// 校验sessionLogin提交的密码哈希，成功后下发会话Cookie
+fn session_login(state: &MockState, body: &[u8]) -> Response<Full<Bytes>> {
+    let xml = String::from_utf8_lossy(body);
+    let username = xml_value(&xml, "userName").unwrap_or_default();
+    let session_id = xml_value(&xml, "sessionID").unwrap_or_default();
+    let Some((challenged_user, challenge)) =
+        state.login_challenges.lock().unwrap().remove(&session_id)
+    else {
+        return xml_response(StatusCode::UNAUTHORIZED, &xml_status(4, "Invalid Session"));
+    };
+
+    let authorized = match &state.config.digest_auth {
+        Some((expected_user, password)) => {
+            username == *expected_user
+                && challenged_user == *expected_user
+                && xml_value(&xml, "password").as_deref()
+                    == Some(session_password_hash(&username, password, &challenge).as_str())
+        }
+        None => true,
+    };
+    if !authorized {
+        state.auth_challenges.fetch_add(1, Ordering::Relaxed);
+        return xml_response(StatusCode::UNAUTHORIZED, &xml_status(4, "Invalid Password"));
+    }
+
+    state.sessions.lock().unwrap().insert(session_id.clone());
+    let mut response = xml_response(
+        StatusCode::OK,
+        &format!(
+            "<SessionLogin><statusValue>200</statusValue><statusString>OK</statusString>\
+             <sessionID>{}</sessionID></SessionLogin>",
+            session_id
+        ),
+    );
+    response.headers_mut().insert(
+        "Set-Cookie",
+        format!("{}={}; path=/", SESSION_COOKIE, session_id)
+            .parse()
+            .expect("valid header value"),
+    );
+    response
+}
+
+/// 请求携带的会话Cookie
+fn session_cookie(request: &Request<Incoming>) -> Option<String> {
+    request
+        .headers()
+        .get_all("Cookie")
+        .iter()
+        .filter_map(|value| value.to_str().ok())
+        .flat_map(|cookies| cookies.split(';'))
+        .find_map(|cookie| {
+            let (name, value) = cookie.trim().split_once('=')?;
+            (name == SESSION_COOKIE).then(|| value.to_string())
+        })
+}
+
+fn random_hex(len: usize) -> String {
+    let mut rng = rand::thread_rng();
+    (0..len)
+        .map(|_| format!("{:x}", rng.gen_range(0..16)))
+        .collect()
+}
+
+/// XML格式的ResponseStatus
+fn xml_status(status_code: u32, status_string: &str) -> String {
+    format!(
+        "<ResponseStatus><statusCode>{}</statusCode><statusString>{}</statusString></ResponseStatus>",
+        status_code, status_string
+    )
+}
+
+/// 模拟设备的当前时间（Unix毫秒）：本机时间加上配置的时钟偏差
+fn device_now(state: &MockState) -> i64 {
+    let local = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64);
+    local + state.config.clock_skew_ms
+}
+
+/// HTTP `Date` 头格式（IMF-fixdate），如 `Fri, 16 Oct 2026 13:07:30 GMT`
+fn http_date(unix_secs: i64) -> String {
+    const WEEKDAYS: [&str; 7] = ["Thu", "Fri", "Sat", "Sun", "Mon", "Tue", "Wed"];
+    const MONTHS: [&str; 12] = [
+        "Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec",
+    ];
+    // 2026-10-16T13:07:30Z
+    let timestamp = format_timestamp(unix_secs, 0);
+    let field = |range: std::ops::Range<usize>| timestamp[range].parse::<usize>().unwrap_or(0);
+    format!(
+        "{}, {:02} {} {} {} GMT",
+        WEEKDAYS[unix_secs.div_euclid(86_400).rem_euclid(7) as usize],
+        field(8..10),
+        MONTHS[field(5..7).saturating_sub(1).min(11)],
+        &timestamp[..4],
+        &timestamp[11..19]
+    )
+}
+
+fn xml_response(status: StatusCode, body: &str) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/xml")
+        .body(Full::new(Bytes::from(body.to_string())))
+        .expect("valid response")
+}
+
+/// ISAPI风格的响应体
+fn isapi_response(
+    status: StatusCode,
+    status_code: u32,
+    status_string: &str,
+    task_id: Option<&str>,
+) -> Response<Full<Bytes>> {
+    let mut body = json!({
+        "statusCode": status_code,
+        "statusString": status_string,
+        "subStatusCode": if status.is_success() { "ok" } else { "error" },
+    });
+    if let Some(task_id) = task_id {
+        body["taskID"] = json!(task_id);
+    }
+    json_response(status, body)
+}
+
+fn json_response(status: StatusCode, body: Value) -> Response<Full<Bytes>> {
+    Response::builder()
+        .status(status)
+        .header("Content-Type", "application/json")
+        .body(Full::new(Bytes::from(body.to_string())))
+        .expect("valid response")
+}
+
+/// 校验Digest认证头（MD5，支持qop=auth）
+fn verify_basic(header: &str, username: &str, password: &str) -> bool {
+    header.strip_prefix("Basic ").is_some_and(|encoded| {
+        BASE64_STANDARD.decode(encoded).ok()
+            == Some(format!("{}:{}", username, password).into_bytes())
+    })
+}
+
+fn verify_digest(
+    header: &str,
+    method: &str,
+    username: &str,
+    password: &str,
+    state: &MockState,
+) -> bool {
+    let Some(params) = header.strip_prefix("Digest ") else {
+        return false;
+    };
+    let params = parse_digest_params(params);
+    let param = |name: &str| params.get(name).map(String::as_str).unwrap_or_default();
+
+    if param("username") != username || param("nonce") != state.nonce {
+        return false;
+    }
+
+    let ha1 = md5_hex(&format!("{}:{}:{}", username, param("realm"), password));
+    let ha2 = md5_hex(&format!("{}:{}", method, param("uri")));
+    let expected = match params.get("qop") {
+        Some(qop) => md5_hex(&format!(
+            "{}:{}:{}:{}:{}:{}",
+            ha1,
+            param("nonce"),
+            param("nc"),
+            param("cnonce"),
+            qop,
+            ha2
+        )),
+        None => md5_hex(&format!("{}:{}:{}", ha1, param("nonce"), ha2)),
+    };
+    expected == param("response")
+}
+
+/// 解析 `key="value", key=value` 形式的Digest参数
+fn parse_digest_params(params: &str) -> HashMap<String, String> {
+    let mut parsed = HashMap::new();
+    let mut rest = params.trim();
+    while !rest.is_empty() {
+        let Some((key, after)) = rest.split_once('=') else {
+            break;
+        };
+        let key = key.trim().trim_start_matches(',').trim().to_string();
+        let (value, remaining) = if let Some(quoted) = after.strip_prefix('"') {
+            match quoted.split_once('"') {
+                Some((value, remaining)) => (value.to_string(), remaining),
+                None => (quoted.to_string(), ""),
+            }
+        } else {
+            match after.split_once(',') {
+                Some((value, remaining)) => (value.trim().to_string(), remaining),
+                None => (after.trim().to_string(), ""),
+            }
+        };
+        parsed.insert(key, value);
+        rest = remaining.trim_start_matches([',', ' ']);
+    }
+    parsed
+}
+
+fn md5_hex(input: &str) -> String {
+    Md5::digest(input.as_bytes())
+        .iter()
+        .map(|byte| format!("{:02x}", byte))
+        .collect()
+}
@@ -0,0 +1,140 @@
+use serde_json::json;
+use std::sync::Mutex;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use tokio::task::JoinHandle;
+
+use crate::config::NotificationConfig;
+use crate::observer::RequestObserver;
+use crate::stats::{RequestResult, RequestStats};
+
+// Import logger macros from crate root
+use crate::{log_debug, log_warn};
+
+/// Webhook通知：错误率越过阈值、运行结束或因停止条件提前结束时发送消息
+pub struct Notifier {
+    config: NotificationConfig,
+    client: reqwest::Client,
+    total: AtomicUsize,
+    failed: AtomicUsize,
+    above_threshold: AtomicBool,
+    pending: Mutex<Vec<JoinHandle<()>>>,
+}
+
+impl Notifier {
+    pub fn new(config: &NotificationConfig) -> Self {
+        Self {
+            config: config.clone(),
+            client: reqwest::Client::new(),
+            total: AtomicUsize::new(0),
+            failed: AtomicUsize::new(0),
+            above_threshold: AtomicBool::new(false),
+            pending: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// 运行结束时发送通知，并等待所有未完成的通知发送完毕
+    pub async fn notify_finished(&self, stats: &RequestStats) {
+        let message = match &stats.stop_reason {
+            Some(reason) if self.config.on_stop.unwrap_or(true) => Some(format!(
+                "🛑 RemoteTask run stopped: {}\n{}",
+                reason,
+                Self::summary(stats)
+            )),
+            None if self.config.on_complete.unwrap_or(true) => Some(format!(
+                "✅ RemoteTask run completed\n{}",
+                Self::summary(stats)
+            )),
+            _ => None,
+        };
+        if let Some(message) = message {
+            self.send(message);
+        }
+
+        let pending = std::mem::take(&mut *self.pending.lock().unwrap());
+        for handle in pending {
+            let _ = handle.await;
+        }
+    }
+
+    /// 最终统计摘要
+    fn summary(stats: &RequestStats) -> String {
+        let p95 = stats
+            .latency_percentile(95.0)
+            .map(|latency| format!("{}ms", latency.as_millis()))
+            .unwrap_or_else(|| "-".to_string());
+        let mut summary = format!(
+            "Requests: {} total, {} successful, {} failed ({:.2}% errors), p95 {}, duration {:.1}s",
+            stats.total_requests,
+            stats.successful_requests,
+            stats.failed_requests,
+            stats.error_rate(),
+            p95,
+            stats.elapsed.as_secs_f64()
+        );
+        if let Some(error) = &stats.last_error {
+            summary.push_str(&format!("\nLast error: {}", error));
+        }
+        summary
+    }
+
+    /// 在后台发送消息，失败时仅告警
+    fn send(&self, text: String) {
+        let client = self.client.clone();
+        let url = self.config.webhook_url.clone();
+        let handle = tokio::spawn(async move {
+            let result = client
+                .post(&url)
+                .json(&json!({ "text": text }))
+                .send()
+                .await;
+            match result {
+                Ok(response) if response.status().is_success() => {
+                    log_debug!("📣 Notification sent to {}", url);
+                }
+                Ok(response) => log_warn!(
+                    "Notification webhook {} returned {}",
+                    url,
+                    response.status()
+                ),
+                Err(e) => log_warn!("Failed to send notification to {}: {}", url, e),
+            }
+        });
+        self.pending.lock().unwrap().push(handle);
+    }
+}
+
+impl RequestObserver for Notifier {
+    fn on_result(&self, result: &RequestResult) {
+        let Some(threshold) = self.config.error_rate_threshold else {
+            return;
+        };
+        if result.warmup {
+            return;
+        }
+        let total = self.total.fetch_add(1, Ordering::Relaxed) + 1;
+        let failed = if result.success {
+            self.failed.load(Ordering::Relaxed)
+        } else {
+            self.failed.fetch_add(1, Ordering::Relaxed) + 1
+        };
+        if total < self.config.min_requests.unwrap_or(10) {
+            return;
+        }
+
+        // 仅在越过阈值时通知一次，回落到阈值以下后重新生效
+        let error_rate = failed as f64 * 100.0 / total as f64;
+        let above = error_rate > threshold;
+        if above && !self.above_threshold.swap(true, Ordering::Relaxed) {
+            self.send(format!(
+                "⚠️ RemoteTask error rate {:.2}% exceeded {}% ({} of {} requests failed)\nLast error: {}",
+                error_rate,
+                threshold,
+                failed,
+                total,
+                result.error.as_deref().unwrap_or("-")
+            ));
+        } else if !above {
+            self.above_threshold.store(false, Ordering::Relaxed);
+        }
+    }
+}
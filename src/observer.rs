@@ -0,0 +1,37 @@
+use std::sync::Arc;
+use std::time::Duration;
+
+use crate::config::HttpRequestConfig;
+use crate::cycle_context::CycleContext;
+use crate::stats::RequestResult;
+
+/// 请求事件回调接口
+///
+/// 库使用者可实现该trait以接入自定义指标、日志或中止逻辑，所有方法均有空的默认实现。
+pub trait RequestObserver: Send + Sync {
+    /// A+B周期开始时调用
+    fn on_cycle_start(&self, _cycle: usize) {}
+
+    /// 周期内每个目标的请求渲染完成、发送之前调用，携带该目标的周期上下文
+    fn on_cycle_context(&self, _context: &CycleContext) {}
+
+    /// 请求即将发送时调用
+    fn on_request_sent(&self, _cycle: usize, _label: &str, _request: &HttpRequestConfig) {}
+
+    /// 收到响应时调用（包括非2xx状态码）
+    fn on_response(&self, _cycle: usize, _label: &str, _status: u16, _elapsed: Duration) {}
+
+    /// 请求未能得到响应时调用（连接失败、超时、认证错误等）
+    fn on_error(&self, _cycle: usize, _label: &str, _error: &str, _elapsed: Duration) {}
+
+    /// 每个请求完成后调用，携带结构化结果
+    fn on_result(&self, _result: &RequestResult) {}
+
+    /// 返回true时，在下一个周期开始前停止运行
+    fn should_abort(&self) -> bool {
+        false
+    }
+}
+
+/// 共享的观察者列表
+pub type Observers = Arc<Vec<Arc<dyn RequestObserver>>>;
@@ -0,0 +1,187 @@
+use anyhow::{Context, Result, anyhow, bail};
+use std::collections::HashMap;
+use std::io::ErrorKind;
+use std::time::Duration;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, UdpSocket, lookup_host};
+
+use crate::config::{PortProbeConfig, PortProbesConfig};
+use crate::template;
+
+// Import logger macros from crate root
+use crate::{log_error, log_info, log_warn};
+
+const DEFAULT_TIMEOUT_MS: u64 = 3000;
+
+/// 运行开始前的TCP/UDP端口探测
+pub struct PortProbe;
+
+impl PortProbe {
+    /// 依次执行所有探测，返回失败的原因（全部通过时为None）
+    ///
+    /// `on_failure` 为 "warn" 时失败只告警，同样返回None。
+    pub async fn check_all(
+        config: &PortProbesConfig,
+        vars: &HashMap<String, String>,
+    ) -> Option<String> {
+        let warn_only = config.on_failure.as_deref() == Some("warn");
+        let mut failures = Vec::new();
+        for probe in &config.probes {
+            let host = template::render(&probe.host, vars);
+            let protocol = probe.protocol.as_deref().unwrap_or("tcp");
+            let target = format!("{}/{}:{}", protocol, host, probe.port);
+            match Self::check(probe, &host).await {
+                Ok(()) => log_info!("🔌 Port probe {} passed", target),
+                Err(e) => {
+                    if warn_only {
+                        log_warn!("🔌 Port probe {} failed: {:#}", target, e);
+                    } else {
+                        log_error!("🔌 Port probe {} failed: {:#}", target, e);
+                    }
+                    failures.push(format!("{}: {:#}", target, e));
+                }
+            }
+        }
+        if failures.is_empty() || warn_only {
+            None
+        } else {
+            Some(failures.join("; "))
+        }
+    }
+
+    /// 执行单个探测
+    pub async fn check(probe: &PortProbeConfig, host: &str) -> Result<()> {
+        let timeout = Duration::from_millis(probe.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+        let encoding = probe.encoding.as_deref();
+        let payload = probe
+            .payload
+            .as_deref()
+            .map(|payload| decode(payload, encoding))
+            .transpose()
+            .context("invalid payload")?;
+        let expect = probe
+            .expect
+            .as_deref()
+            .map(|expect| decode(expect, encoding))
+            .transpose()
+            .context("invalid expect")?;
+
+        match probe.protocol.as_deref().unwrap_or("tcp") {
+            "tcp" => Self::check_tcp(host, probe.port, payload, expect, timeout).await,
+            "udp" => Self::check_udp(host, probe.port, payload, expect, timeout).await,
+            protocol => bail!("unknown protocol '{}'", protocol),
+        }
+    }
+
+    /// TCP：能建立连接即开放；配置了expect时发送payload后需收到期望数据
+    async fn check_tcp(
+        host: &str,
+        port: u16,
+        payload: Option<Vec<u8>>,
+        expect: Option<Vec<u8>>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let probe = async {
+            let mut stream = TcpStream::connect((host, port))
+                .await
+                .context("connection failed")?;
+            if let Some(payload) = &payload {
+                stream.write_all(payload).await.context("send failed")?;
+            }
+            let Some(expect) = &expect else {
+                return Ok(());
+            };
+            let mut received = Vec::new();
+            let mut chunk = [0u8; 4096];
+            while !contains(&received, expect) {
+                let read = stream.read(&mut chunk).await.context("receive failed")?;
+                if read == 0 {
+                    bail!("connection closed before expected data was received");
+                }
+                received.extend_from_slice(&chunk[..read]);
+            }
+            Ok(())
+        };
+        tokio::time::timeout(timeout, probe)
+            .await
+            .map_err(|_| anyhow!("timed out after {}ms", timeout.as_millis()))?
+    }
+
+    /// UDP：发送payload后等待响应；未配置expect时超时视为开放（无ICMP端口不可达）
+    async fn check_udp(
+        host: &str,
+        port: u16,
+        payload: Option<Vec<u8>>,
+        expect: Option<Vec<u8>>,
+        timeout: Duration,
+    ) -> Result<()> {
+        let address = lookup_host((host, port))
+            .await
+            .context("address lookup failed")?
+            .next()
+            .ok_or_else(|| anyhow!("no address for {}", host))?;
+        let bind = if address.is_ipv4() {
+            "0.0.0.0:0"
+        } else {
+            "[::]:0"
+        };
+        let socket = UdpSocket::bind(bind).await?;
+        socket.connect(address).await?;
+        socket
+            .send(payload.as_deref().unwrap_or_default())
+            .await
+            .context("send failed")?;
+
+        let mut buffer = vec![0u8; 65536];
+        let receive = async {
+            loop {
+                let read = socket.recv(&mut buffer).await?;
+                if expect
+                    .as_ref()
+                    .is_none_or(|expect| contains(&buffer[..read], expect))
+                {
+                    return Ok::<_, std::io::Error>(());
+                }
+            }
+        };
+        match tokio::time::timeout(timeout, receive).await {
+            Ok(Ok(())) => Ok(()),
+            Ok(Err(e)) if e.kind() == ErrorKind::ConnectionRefused => bail!("port unreachable"),
+            Ok(Err(e)) => Err(anyhow!("receive failed: {}", e)),
+            Err(_) if expect.is_none() => Ok(()),
+            Err(_) => bail!(
+                "expected data not received within {}ms",
+                timeout.as_millis()
+            ),
+        }
+    }
+}
+
+/// 按编码解析payload/expect
+pub fn decode(text: &str, encoding: Option<&str>) -> Result<Vec<u8>> {
+    match encoding.unwrap_or("text") {
+        "text" => Ok(text.as_bytes().to_vec()),
+        "hex" => {
+            let digits: Vec<char> = text.chars().filter(|c| !c.is_whitespace()).collect();
+            if !digits.len().is_multiple_of(2) {
+                bail!("odd number of hex digits");
+            }
+            digits
+                .chunks(2)
+                .map(|pair| {
+                    let byte: String = pair.iter().collect();
+                    u8::from_str_radix(&byte, 16)
+                        .map_err(|_| anyhow!("'{}' is not a hex byte", byte))
+                })
+                .collect()
+        }
+        encoding => bail!("unknown encoding '{}'", encoding),
+    }
+}
+
+fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+    needle.is_empty()
+        || haystack
+            .windows(needle.len())
+            .any(|window| window == needle)
+}
@@ -0,0 +1,147 @@
+use indicatif::{ProgressDrawTarget, ProgressStyle};
+use std::io::IsTerminal;
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use crate::logger;
+use crate::observer::RequestObserver;
+use crate::stats::RequestResult;
+
+/// 进度条的刻度数：进度按已完成的比例（千分比）显示，周期数和时长两种上限可同时生效
+const PROGRESS_SCALE: u64 = 1000;
+
+struct ProgressState {
+    first_cycle: Option<usize>,
+    last_cycle: usize,
+    requests: usize,
+    successes: usize,
+}
+
+/// 有界运行（配置了max_requests或max_duration_ms）的终端进度条，显示已完成周期、当前成功率和预计剩余时间
+///
+/// 进度取周期数和运行时长两者中完成比例较大的一个（先到达的上限结束运行）。
+/// 进度条输出到stderr，日志输出前会清除进度条所在行，之后由进度条定时重新绘制。
+pub struct ProgressBar {
+    bar: indicatif::ProgressBar,
+    total_cycles: Option<usize>,
+    max_duration: Option<Duration>,
+    started_at: Instant,
+    state: Mutex<ProgressState>,
+}
+
+impl ProgressBar {
+    /// 没有周期数和时长上限，或stderr不是终端（如输出被重定向）时返回None
+    pub fn new(total_cycles: Option<usize>, max_duration: Option<Duration>) -> Option<Self> {
+        if total_cycles.is_none() && max_duration.is_none() {
+            return None;
+        }
+        if !std::io::stderr().is_terminal() {
+            return None;
+        }
+        // 日志也输出到终端时，打印前需清除进度条所在行
+        logger::set_progress_active(logger::log_stream_is_terminal());
+        let progress = Self::with_target(
+            total_cycles,
+            max_duration,
+            ProgressDrawTarget::stderr_with_hz(10),
+        );
+        progress.bar.enable_steady_tick(Duration::from_millis(100));
+        Some(progress)
+    }
+
+    /// 不输出到终端的进度条，只跟踪进度
+    pub fn hidden(total_cycles: Option<usize>, max_duration: Option<Duration>) -> Self {
+        Self::with_target(total_cycles, max_duration, ProgressDrawTarget::hidden())
+    }
+
+    fn with_target(
+        total_cycles: Option<usize>,
+        max_duration: Option<Duration>,
+        target: ProgressDrawTarget,
+    ) -> Self {
+        let bar = indicatif::ProgressBar::with_draw_target(Some(PROGRESS_SCALE), target);
+        bar.set_style(
+            ProgressStyle::with_template("[{bar:30}] {msg}  ETA {eta}")
+                .expect("valid progress template")
+                .progress_chars("#>-"),
+        );
+        let progress = Self {
+            bar,
+            total_cycles: total_cycles.map(|total| total.max(1)),
+            max_duration,
+            started_at: Instant::now(),
+            state: Mutex::new(ProgressState {
+                first_cycle: None,
+                last_cycle: 0,
+                requests: 0,
+                successes: 0,
+            }),
+        };
+        progress.update(&progress.state.lock().unwrap());
+        progress
+    }
+
+    /// 已完成的比例（0..=1）
+    pub fn fraction(&self) -> f64 {
+        self.bar.position() as f64 / PROGRESS_SCALE as f64
+    }
+
+    /// 进度条上的说明文字（已完成周期和成功率）
+    pub fn message(&self) -> String {
+        self.bar.message()
+    }
+
+    /// 绘制最终状态并换行，之后的日志不再清除该行
+    pub fn finish(&self) {
+        self.update(&self.state.lock().unwrap());
+        self.bar.abandon();
+        logger::set_progress_active(false);
+    }
+
+    fn completed_cycles(state: &ProgressState) -> usize {
+        state
+            .first_cycle
+            .map_or(0, |first| state.last_cycle + 1 - first)
+    }
+
+    fn update(&self, state: &ProgressState) {
+        let completed = Self::completed_cycles(state);
+        let by_cycles = self
+            .total_cycles
+            .map_or(0.0, |total| completed.min(total) as f64 / total as f64);
+        let by_time = self.max_duration.map_or(0.0, |max_duration| {
+            (self.started_at.elapsed().as_secs_f64() / max_duration.as_secs_f64().max(0.001))
+                .min(1.0)
+        });
+        self.bar
+            .set_position((by_cycles.max(by_time) * PROGRESS_SCALE as f64) as u64);
+
+        let success_rate = if state.requests == 0 {
+            100.0
+        } else {
+            state.successes as f64 * 100.0 / state.requests as f64
+        };
+        let cycles = match self.total_cycles {
+            Some(total) => format!("{}/{} cycles", completed.min(total), total),
+            None => format!("{} cycles", completed),
+        };
+        self.bar
+            .set_message(format!("{}  {:.1}% ok", cycles, success_rate));
+    }
+}
+
+impl RequestObserver for ProgressBar {
+    fn on_result(&self, result: &RequestResult) {
+        if result.warmup {
+            return;
+        }
+        let mut state = self.state.lock().unwrap();
+        state.first_cycle.get_or_insert(result.cycle);
+        state.last_cycle = state.last_cycle.max(result.cycle);
+        state.requests += 1;
+        if result.success {
+            state.successes += 1;
+        }
+        self.update(&state);
+    }
+}
@@ -0,0 +1,204 @@
+use anyhow::{Result, bail};
+use serde_json::{Value, json};
+use std::collections::HashMap;
+
+use crate::capture::{Capturer, VariableStore};
+use crate::config::{HttpRequestConfig, ProvisioningConfig, RequestConfig};
+use crate::cycle_context::CycleContext;
+use crate::http_client::{HttpClient, ResponseData};
+use crate::request_handler::RequestHandler;
+use crate::template;
+
+// Import logger macros from crate root
+use crate::{log_info, log_warn};
+
+/// 数据预置的结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct ProvisioningReport {
+    pub created: usize,           // 创建成功的对象数
+    pub setup_failures: usize,    // 创建失败的请求数
+    pub deleted: usize,           // 删除成功的对象数
+    pub teardown_failures: usize, // 删除失败的对象数
+}
+
+impl ProvisioningReport {
+    /// JSON报告中的 `provisioning`
+    pub fn to_json(&self) -> Value {
+        json!({
+            "created": self.created,
+            "setup_failures": self.setup_failures,
+            "deleted": self.deleted,
+            "teardown_failures": self.teardown_failures,
+        })
+    }
+}
+
+/// 数据预置：主循环前依次发送 `count` 个创建请求，提取的对象ID组成变量池写入运行作用域变量，
+/// 运行结束后对每个对象发送删除请求
+pub struct Provisioner {
+    config: ProvisioningConfig,
+    http_client: HttpClient,
+    variables: HashMap<String, String>, // 运行和worker作用域的变量
+    objects: Vec<(usize, String)>,      // 已创建的对象 (序号, ID)
+    report: ProvisioningReport,
+}
+
+impl Provisioner {
+    pub fn new(config: &ProvisioningConfig, run: &RequestConfig) -> Result<Self> {
+        let variables = CycleContext::new(run, 0, None, Vec::new(), None).variables;
+        let http_client = HttpClient::new(RequestHandler::http_client_config(
+            run.digest_auth.as_ref(),
+            run.unix_socket.as_deref(),
+            run.proxy.as_ref(),
+            &variables,
+        ))?;
+        Ok(Self {
+            config: config.clone(),
+            http_client,
+            variables,
+            objects: Vec::new(),
+            report: ProvisioningReport::default(),
+        })
+    }
+
+    /// 依次创建对象，任一创建失败时停止并返回原因（已创建的对象仍在运行结束时删除）
+    pub async fn setup(&mut self) -> Option<String> {
+        log_info!(
+            "🏗️  Provisioning {} object(s) into pool {}",
+            self.config.count,
+            self.config.pool
+        );
+        for index in 1..=self.config.count {
+            let mut variables = self.variables.clone();
+            variables.insert("index".to_string(), index.to_string());
+            match self.send(&self.config.setup, &variables).await {
+                Ok(captures) => match captures.get(&self.config.pool) {
+                    Some(id) => {
+                        self.objects.push((index, id));
+                        self.report.created += 1;
+                    }
+                    None => {
+                        self.report.setup_failures += 1;
+                        return Some(format!(
+                            "setup request {} did not capture {}",
+                            index, self.config.pool
+                        ));
+                    }
+                },
+                Err(e) => {
+                    self.report.setup_failures += 1;
+                    return Some(format!("setup request {} failed: {:#}", index, e));
+                }
+            }
+        }
+        log_info!(
+            "🏗️  Provisioned {} object(s) into pool {}",
+            self.objects.len(),
+            self.config.pool
+        );
+        None
+    }
+
+    /// 将变量池写入运行作用域变量
+    pub fn apply(&self, run: &mut RequestConfig) {
+        let pool = &self.config.pool;
+        let variables = run.variables.get_or_insert_with(HashMap::new);
+        for (position, (_, id)) in self.objects.iter().enumerate() {
+            variables.insert(format!("{}.{}", pool, position + 1), id.clone());
+        }
+        variables.insert(format!("{}.count", pool), self.objects.len().to_string());
+        variables.insert(
+            format!("{}.all", pool),
+            self.objects
+                .iter()
+                .map(|(_, id)| id.as_str())
+                .collect::<Vec<_>>()
+                .join(","),
+        );
+    }
+
+    /// 周期使用的池对象：按周期序号轮流取池中的对象，写入 `{{<pool>}}`
+    pub fn select(pool: &str, cycle: usize, variables: &mut HashMap<String, String>) {
+        let Some(count) = variables
+            .get(&format!("{}.count", pool))
+            .and_then(|count| count.parse::<usize>().ok())
+            .filter(|count| *count > 0)
+        else {
+            return;
+        };
+        let position = (cycle.max(1) - 1) % count + 1;
+        if let Some(id) = variables.get(&format!("{}.{}", pool, position)).cloned() {
+            variables.insert(pool.to_string(), id);
+        }
+    }
+
+    /// 删除已创建的对象（未配置teardown时只输出对象数）
+    pub async fn teardown(&mut self) {
+        let Some(teardown) = self.config.teardown.clone() else {
+            if !self.objects.is_empty() {
+                log_info!(
+                    "🏗️  {} provisioned object(s) left in place (no teardown configured)",
+                    self.objects.len()
+                );
+            }
+            return;
+        };
+        for (index, id) in std::mem::take(&mut self.objects) {
+            let mut variables = self.variables.clone();
+            variables.insert("index".to_string(), index.to_string());
+            variables.insert(self.config.pool.clone(), id.clone());
+            match self.send(&teardown, &variables).await {
+                Ok(_) => self.report.deleted += 1,
+                Err(e) => {
+                    self.report.teardown_failures += 1;
+                    log_warn!("Teardown of {} {} failed: {:#}", self.config.pool, id, e);
+                }
+            }
+        }
+        log_info!(
+            "🧹 Removed {} provisioned object(s) from pool {}",
+            self.report.deleted,
+            self.config.pool
+        );
+    }
+
+    pub fn report(&self) -> ProvisioningReport {
+        self.report.clone()
+    }
+
+    /// 渲染并发送一个预置请求，返回成功响应中捕获的变量
+    async fn send(
+        &self,
+        step: &HttpRequestConfig,
+        variables: &HashMap<String, String>,
+    ) -> Result<VariableStore> {
+        let mut request = step.clone();
+        let mut scoped = variables.clone();
+        for (name, value) in step.variables.iter().flatten() {
+            scoped.insert(name.clone(), template::render(value, variables));
+        }
+        Capturer::render_with(&mut request, &scoped);
+        let headers = request.headers.as_ref().map(|headers| {
+            headers
+                .iter()
+                .map(|(name, value)| (name.as_str(), value.as_str()))
+                .collect::<Vec<_>>()
+        });
+        let response = self
+            .http_client
+            .send_request(&request.method, &request.url, request.body.clone(), headers)
+            .await?;
+        let response = ResponseData::read(response).await?;
+        if !response.status.is_success() {
+            bail!("HTTP {}", response.status);
+        }
+        let captures = VariableStore::new();
+        if let Some(capture_configs) = &request.captures
+            && let Some(failure) =
+                Capturer::capture(capture_configs, &response, &captures, &captures)
+        {
+            bail!("{}", failure);
+        }
+        Ok(captures)
+    }
+}
@@ -0,0 +1,89 @@
+use reqwest::Url;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::time::{Instant, sleep};
+
+use crate::config::RateLimitConfig;
+
+// Import logger macros from crate root
+use crate::log_trace;
+
+/// 令牌桶的状态：令牌按 `rate` 每秒补充，最多积累 `burst` 个
+///
+/// 请求限速（按请求计）和带宽限制（按字节计）共用。
+pub(crate) struct TokenBucket {
+    tokens: f64,
+    updated_at: Instant,
+}
+
+impl TokenBucket {
+    /// 初始令牌数为 `tokens` 的桶
+    pub(crate) fn new(tokens: f64) -> Self {
+        Self {
+            tokens,
+            updated_at: Instant::now(),
+        }
+    }
+
+    /// 预占 `amount` 个令牌，返回需要等待的时间。令牌不足时按欠额计算等待时间，
+    /// 并发的调用方依次排队
+    pub(crate) fn reserve(&mut self, rate: f64, burst: f64, amount: f64) -> Duration {
+        let now = Instant::now();
+        let elapsed = now.duration_since(self.updated_at).as_secs_f64();
+        self.tokens = (self.tokens + elapsed * rate).min(burst);
+        self.updated_at = now;
+        self.tokens -= amount;
+        if self.tokens < 0.0 {
+            Duration::from_secs_f64(-self.tokens / rate)
+        } else {
+            Duration::ZERO
+        }
+    }
+}
+
+/// 按主机的令牌桶限速器，保证发往同一设备的请求速率不超过配置值
+pub struct RateLimiter {
+    rate: f64,
+    burst: f64,
+    buckets: Mutex<HashMap<String, TokenBucket>>,
+}
+
+impl RateLimiter {
+    pub fn new(config: &RateLimitConfig) -> Self {
+        Self {
+            rate: config.requests_per_second,
+            burst: config.burst.unwrap_or(1).max(1) as f64,
+            buckets: Mutex::new(HashMap::new()),
+        }
+    }
+
+    /// 为发往 `url` 所在主机的请求获取一个令牌，必要时等待
+    pub async fn acquire(&self, url: &str) {
+        let host = Self::host_key(url);
+        let wait = self
+            .buckets
+            .lock()
+            .await
+            .entry(host.clone())
+            .or_insert_with(|| TokenBucket::new(self.burst))
+            .reserve(self.rate, self.burst, 1.0);
+
+        if !wait.is_zero() {
+            log_trace!("🚦 Rate limit for {}: waiting {}ms", host, wait.as_millis());
+            sleep(wait).await;
+        }
+    }
+
+    /// 限速的分组键：host[:port]，无法解析时使用整个URL
+    fn host_key(url: &str) -> String {
+        match Url::parse(url) {
+            Ok(parsed) => match (parsed.host_str(), parsed.port()) {
+                (Some(host), Some(port)) => format!("{}:{}", host, port),
+                (Some(host), None) => host.to_string(),
+                _ => url.to_string(),
+            },
+            Err(_) => url.to_string(),
+        }
+    }
+}
@@ -0,0 +1,193 @@
+use anyhow::{Context, Result, anyhow, bail};
+use bytes::Bytes;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{StatusCode, Url};
+use std::net::SocketAddr;
+use std::time::Duration;
+use tokio::io::{AsyncRead, AsyncReadExt, AsyncWrite, AsyncWriteExt};
+use tokio::net::{TcpStream, lookup_host};
+
+use crate::http_client::{IpFamily, ResponseData};
+
+const MAX_RESPONSE_SIZE: usize = 64 * 1024 * 1024;
+const DEFAULT_IDLE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// 原始请求：把body中的字节原样写入TCP/TLS连接，并读取原始响应
+///
+/// 用于复现reqwest拒绝发送的畸形请求（非法请求行、重复或错误的Content-Length等）。
+/// URL形如 `tcp://host:port` 或 `tls://host:port`（`http://`、`https://` 视为同义），
+/// 不处理认证和Cookie。
+pub struct RawRequest;
+
+impl RawRequest {
+    /// 发送原始字节，读取到连接关闭、按Content-Length读满或空闲超时为止
+    pub async fn send(
+        url: &str,
+        payload: &[u8],
+        ip_family: IpFamily,
+        timeout: Duration,
+    ) -> Result<ResponseData> {
+        let (host, port, tls) = Self::parse_url(url)?;
+        let mut remote_addr = None;
+        let connect = async {
+            let addresses: Vec<SocketAddr> = lookup_host((host.as_str(), port))
+                .await
+                .with_context(|| format!("Failed to resolve {}", host))?
+                .filter(|address| ip_family.allows(address))
+                .collect();
+            if addresses.is_empty() {
+                bail!("{} has no {:?} address", host, ip_family);
+            }
+            let stream = TcpStream::connect(&addresses[..])
+                .await
+                .with_context(|| format!("Failed to connect to {}:{}", host, port))?;
+            remote_addr = stream.peer_addr().ok();
+            if !tls {
+                return Self::exchange(stream, payload).await;
+            }
+            // 与HttpClient一致，不校验设备证书
+            let connector = tokio_native_tls::TlsConnector::from(
+                tokio_native_tls::native_tls::TlsConnector::builder()
+                    .danger_accept_invalid_certs(true)
+                    .danger_accept_invalid_hostnames(true)
+                    .build()?,
+            );
+            let stream = connector
+                .connect(&host, stream)
+                .await
+                .with_context(|| format!("TLS handshake with {}:{} failed", host, port))?;
+            Self::exchange(stream, payload).await
+        };
+        let raw = tokio::time::timeout(timeout, connect)
+            .await
+            .map_err(|_| anyhow!("raw request timed out after {}ms", timeout.as_millis()))??;
+        let mut response = Self::parse_response(&raw)?;
+        response.remote_addr = remote_addr;
+        Ok(response)
+    }
+
+    /// 解析 `tcp://` / `tls://` URL，返回主机、端口和是否使用TLS
+    pub fn parse_url(url: &str) -> Result<(String, u16, bool)> {
+        let parsed = Url::parse(url).with_context(|| format!("Invalid raw request URL {}", url))?;
+        let (tls, default_port) = match parsed.scheme() {
+            "tcp" | "http" => (false, 80),
+            "tls" | "https" => (true, 443),
+            scheme => bail!(
+                "unsupported scheme '{}' (expected tcp:// or tls://)",
+                scheme
+            ),
+        };
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| anyhow!("raw request URL has no host"))?
+            .trim_start_matches('[')
+            .trim_end_matches(']')
+            .to_string();
+        Ok((host, parsed.port().unwrap_or(default_port), tls))
+    }
+
+    async fn exchange<S: AsyncRead + AsyncWrite + Unpin>(
+        mut stream: S,
+        payload: &[u8],
+    ) -> Result<Vec<u8>> {
+        stream.write_all(payload).await?;
+        stream.flush().await?;
+
+        let mut buffer = Vec::with_capacity(4096);
+        let mut chunk = [0u8; 8192];
+        loop {
+            let read =
+                match tokio::time::timeout(DEFAULT_IDLE_TIMEOUT, stream.read(&mut chunk)).await {
+                    Ok(read) => read?,
+                    // 设备既不关闭连接也不再发送数据
+                    Err(_) if !buffer.is_empty() => break,
+                    Err(_) => bail!("no response within {}ms", DEFAULT_IDLE_TIMEOUT.as_millis()),
+                };
+            if read == 0 {
+                break;
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+            if buffer.len() > MAX_RESPONSE_SIZE {
+                bail!("raw response exceeds {} bytes", MAX_RESPONSE_SIZE);
+            }
+            if Self::is_complete(&buffer) {
+                break;
+            }
+        }
+        Ok(buffer)
+    }
+
+    /// 响应头部已收齐且body已按Content-Length读满（或分块传输已结束）
+    fn is_complete(buffer: &[u8]) -> bool {
+        let Some(header_end) = find(buffer, b"\r\n\r\n") else {
+            return false;
+        };
+        let head = String::from_utf8_lossy(&buffer[..header_end]);
+        let body = &buffer[header_end + 4..];
+        let header = |name: &str| {
+            head.lines().skip(1).find_map(|line| {
+                let (key, value) = line.split_once(':')?;
+                key.trim()
+                    .eq_ignore_ascii_case(name)
+                    .then(|| value.trim().to_string())
+            })
+        };
+        if let Some(length) = header("Content-Length").and_then(|length| length.parse().ok()) {
+            return body.len() >= length;
+        }
+        if header("Transfer-Encoding").is_some_and(|value| value.eq_ignore_ascii_case("chunked")) {
+            return body.ends_with(b"0\r\n\r\n");
+        }
+        // 1xx/204/304没有body
+        head.split_whitespace()
+            .nth(1)
+            .is_some_and(|status| status.starts_with('1') || status == "204" || status == "304")
+    }
+
+    /// 将原始响应解析为状态、头部和body（body保持原样，不解码分块传输）
+    fn parse_response(raw: &[u8]) -> Result<ResponseData> {
+        let preview = || String::from_utf8_lossy(&raw[..raw.len().min(200)]).into_owned();
+        if raw.is_empty() {
+            bail!("connection closed without a response");
+        }
+        let Some(header_end) = find(raw, b"\r\n\r\n") else {
+            bail!("malformed response: {:?}", preview());
+        };
+        let head = String::from_utf8_lossy(&raw[..header_end]);
+        let mut lines = head.lines();
+        let status_line = lines.next().unwrap_or_default();
+        let status = status_line
+            .strip_prefix("HTTP/")
+            .and_then(|rest| rest.split_whitespace().nth(1))
+            .and_then(|status| status.parse::<u16>().ok())
+            .and_then(|status| StatusCode::from_u16(status).ok())
+            .ok_or_else(|| anyhow!("malformed status line: {:?}", status_line))?;
+
+        let mut headers = HeaderMap::new();
+        for line in lines {
+            if let Some((name, value)) = line.split_once(':')
+                && let (Ok(name), Ok(value)) = (
+                    HeaderName::from_bytes(name.trim().as_bytes()),
+                    HeaderValue::from_str(value.trim()),
+                )
+            {
+                headers.append(name, value);
+            }
+        }
+
+        Ok(ResponseData {
+            status,
+            headers,
+            body: Bytes::copy_from_slice(&raw[header_end + 4..]),
+            remote_addr: None,
+            download: None,
+            overflow: None,
+        })
+    }
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
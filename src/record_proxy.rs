@@ -0,0 +1,464 @@
+use anyhow::{Context, Result, bail};
+use bytes::Bytes;
+use http_body_util::{BodyExt, Full};
+use hyper::body::Incoming;
+use hyper::server::conn::http1;
+use hyper::service::service_fn;
+use hyper::{Method, Request, Response, StatusCode};
+use hyper_util::rt::TokioIo;
+use regex::Regex;
+use serde_json::Value;
+use std::collections::{HashMap, HashSet};
+use std::convert::Infallible;
+use std::net::SocketAddr;
+use std::sync::{Arc, LazyLock, Mutex};
+use std::time::Instant;
+use tokio::net::{TcpListener, TcpStream};
+use tokio::task::JoinHandle;
+
+use crate::config::{CaptureConfig, DigestAuthConfig, HttpRequestConfig, RequestConfig};
+use crate::listener;
+
+// Import logger macros from crate root
+use crate::{log_info, log_warn};
+
+/// Digest认证头中的用户名
+static DIGEST_USERNAME: LazyLock<Regex> =
+    LazyLock::new(|| Regex::new(r#"^Digest .*username="([^"]*)""#).expect("valid regex"));
+
+/// 逐跳头部及由客户端重新生成的头部，不转发也不写入场景
+const SKIPPED_HEADERS: &[&str] = &[
+    "connection",
+    "keep-alive",
+    "proxy-authenticate",
+    "proxy-authorization",
+    "proxy-connection",
+    "te",
+    "trailer",
+    "transfer-encoding",
+    "upgrade",
+    "host",
+    "content-length",
+];
+
+/// 写入场景时额外去掉的头部（会话相关，回放时由客户端重新协商）
+const SESSION_HEADERS: &[&str] = &["authorization", "cookie", "accept-encoding"];
+
+/// 参与变量推断的最短取值，避免把 "1"、"true" 之类的常见值当作变量
+const MIN_VARIABLE_LEN: usize = 6;
+
+/// 录制代理配置
+#[derive(Debug, Clone, Default)]
+pub struct RecordProxyConfig {
+    /// 只录制URL匹配该正则的请求（其余请求照常转发），None表示全部录制
+    pub include: Option<Regex>,
+}
+
+/// 经代理转发并录制的一次请求/响应
+#[derive(Debug, Clone)]
+pub struct RecordedExchange {
+    pub method: String,
+    pub url: String,
+    pub headers: Vec<(String, String)>,
+    pub body: Bytes,
+    pub status: u16,
+    pub response_body: Bytes,
+    pub started: Instant,
+}
+
+struct ProxyState {
+    config: RecordProxyConfig,
+    client: reqwest::Client,
+    exchanges: Mutex<Vec<RecordedExchange>>,
+    tunneled_hosts: Mutex<HashSet<String>>,
+}
+
+/// 本地HTTP正向代理：浏览器或其他工具经由它发出的请求被转发并录制，最后转换为场景配置
+pub struct RecordProxy {
+    addr: SocketAddr,
+    state: Arc<ProxyState>,
+    task: JoinHandle<()>,
+}
+
+impl RecordProxy {
+    /// 在指定地址启动代理（端口为0时自动分配）
+    pub async fn start(addr: SocketAddr, config: RecordProxyConfig) -> Result<Self> {
+        let listener = TcpListener::bind(addr)
+            .await
+            .with_context(|| format!("Failed to bind record proxy to {}", addr))?;
+        let addr = listener.local_addr()?;
+        // 原样转发：不跟随重定向、不解压，响应头部和body与上游一致
+        let client = reqwest::Client::builder()
+            .no_proxy()
+            .no_gzip()
+            .no_brotli()
+            .redirect(reqwest::redirect::Policy::none())
+            .build()?;
+        let state = Arc::new(ProxyState {
+            config,
+            client,
+            exchanges: Mutex::new(Vec::new()),
+            tunneled_hosts: Mutex::new(HashSet::new()),
+        });
+
+        let server_state = Arc::clone(&state);
+        let task = listener::serve(listener, move |stream| {
+            let state = Arc::clone(&server_state);
+            async move {
+                let service = service_fn(move |request| {
+                    let state = Arc::clone(&state);
+                    async move { Ok::<_, Infallible>(handle(&state, request).await) }
+                });
+                let _ = http1::Builder::new()
+                    .serve_connection(TokioIo::new(stream), service)
+                    .with_upgrades()
+                    .await;
+            }
+        });
+
+        Ok(Self { addr, state, task })
+    }
+
+    /// 实际监听地址
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// 代理URL，如 `http://127.0.0.1:8888`
+    pub fn proxy_url(&self) -> String {
+        format!("http://{}", self.addr)
+    }
+
+    /// 已录制的请求（按发出顺序）
+    pub fn exchanges(&self) -> Vec<RecordedExchange> {
+        let mut exchanges = self.state.exchanges.lock().unwrap().clone();
+        exchanges.sort_by_key(|exchange| exchange.started);
+        exchanges
+    }
+
+    /// 将录制的请求转换为场景配置
+    pub fn scenario(&self) -> Result<RequestConfig> {
+        ScenarioBuilder::build(&self.exchanges())
+    }
+}
+
+impl Drop for RecordProxy {
+    fn drop(&mut self) {
+        self.task.abort();
+    }
+}
+
+/// 处理单个代理请求：CONNECT建立隧道（不录制），其余请求转发并录制
+async fn handle(state: &ProxyState, request: Request<Incoming>) -> Response<Full<Bytes>> {
+    if request.method() == Method::CONNECT {
+        return tunnel(state, request);
+    }
+    match forward(state, request).await {
+        Ok(response) => response,
+        Err(e) => {
+            log_warn!("Record proxy: {:#}", e);
+            Response::builder()
+                .status(StatusCode::BAD_GATEWAY)
+                .body(Full::new(Bytes::from(format!("{:#}", e))))
+                .expect("valid response")
+        }
+    }
+}
+
+/// HTTPS请求经CONNECT隧道原样转发，内容加密无法录制
+fn tunnel(state: &ProxyState, request: Request<Incoming>) -> Response<Full<Bytes>> {
+    let authority = request.uri().to_string();
+    if state
+        .tunneled_hosts
+        .lock()
+        .unwrap()
+        .insert(authority.clone())
+    {
+        log_warn!(
+            "HTTPS traffic to {} is tunneled but not recorded (use http:// URLs to record)",
+            authority
+        );
+    }
+    tokio::spawn(async move {
+        let Ok(upgraded) = hyper::upgrade::on(request).await else {
+            return;
+        };
+        let Ok(mut upstream) = TcpStream::connect(&authority).await else {
+            return;
+        };
+        let _ = tokio::io::copy_bidirectional(&mut TokioIo::new(upgraded), &mut upstream).await;
+    });
+    Response::new(Full::new(Bytes::new()))
+}
+
+/// 转发普通HTTP请求（请求行为绝对URL），匹配 `include` 时录制
+async fn forward(state: &ProxyState, request: Request<Incoming>) -> Result<Response<Full<Bytes>>> {
+    let started = Instant::now();
+    let method = request.method().as_str().to_string();
+    let url = request.uri().to_string();
+    if request.uri().scheme().is_none() {
+        bail!(
+            "{} is not an absolute URL (configure this server as an HTTP proxy)",
+            url
+        );
+    }
+    let headers: Vec<(String, String)> = request
+        .headers()
+        .iter()
+        .filter(|(name, _)| !SKIPPED_HEADERS.contains(&name.as_str()))
+        .filter_map(|(name, value)| Some((name.to_string(), value.to_str().ok()?.to_string())))
+        .collect();
+    let body = request.into_body().collect().await?.to_bytes();
+
+    let mut upstream = state.client.request(
+        reqwest::Method::from_bytes(method.as_bytes())?,
+        url.as_str(),
+    );
+    for (name, value) in &headers {
+        upstream = upstream.header(name, value);
+    }
+    let upstream = upstream
+        .body(body.clone())
+        .send()
+        .await
+        .with_context(|| format!("{} {}", method, url))?;
+
+    let status = upstream.status().as_u16();
+    let mut response = Response::builder().status(status);
+    for (name, value) in upstream.headers() {
+        if !SKIPPED_HEADERS.contains(&name.as_str()) {
+            response = response.header(name.as_str(), value.as_bytes());
+        }
+    }
+    let response_body = upstream.bytes().await?;
+
+    if state
+        .config
+        .include
+        .as_ref()
+        .is_none_or(|include| include.is_match(&url))
+    {
+        log_info!("⏺️  Recorded {} {} → {}", method, url, status);
+        state.exchanges.lock().unwrap().push(RecordedExchange {
+            method,
+            url,
+            headers,
+            body,
+            status,
+            response_body: response_body.clone(),
+            started,
+        });
+    }
+    Ok(response.body(Full::new(response_body))?)
+}
+
+/// 由录制的请求生成场景：第一个请求为A，最后一个为B
+///
+/// A请求body中的取值若在B中再次出现，提取为 `variables`；A响应中新出现的取值若在B中使用，
+/// 生成A的 `captures` 并在B中替换为 `{{name}}` 占位符。
+pub struct ScenarioBuilder;
+
+impl ScenarioBuilder {
+    pub fn build(exchanges: &[RecordedExchange]) -> Result<RequestConfig> {
+        let (Some(first), Some(last)) = (exchanges.first(), exchanges.last()) else {
+            bail!("no requests were recorded");
+        };
+        if exchanges.len() < 2 {
+            bail!("recorded 1 request, a scenario needs at least 2 (request A and request B)");
+        }
+        for skipped in &exchanges[1..exchanges.len() - 1] {
+            log_warn!(
+                "Skipping recorded {} {} (scenario uses the first and last request)",
+                skipped.method,
+                skipped.url
+            );
+        }
+
+        let mut request_a = Self::request(first);
+        let mut request_b = Self::request(last);
+        let mut variables = HashMap::new();
+        let mut used_names = HashSet::new();
+
+        // A请求body中的取值在B中再次出现：两者共用一个变量
+        let request_values = Self::json_leaves(&first.body);
+        for (path, value) in &request_values {
+            if Self::contains(&request_b, value) {
+                let name = Self::variable_name(path, &mut used_names);
+                Self::replace(&mut request_a, value, &name);
+                Self::replace(&mut request_b, value, &name);
+                variables.insert(name, value.clone());
+            }
+        }
+
+        // A响应中新出现的取值在B中使用：从A的响应中捕获
+        let mut captures = Vec::new();
+        for (path, value) in Self::json_leaves(&first.response_body) {
+            if request_values.iter().any(|(_, known)| *known == value)
+                || !Self::contains(&request_b, &value)
+            {
+                continue;
+            }
+            let name = Self::variable_name(&path, &mut used_names);
+            Self::replace(&mut request_b, &value, &name);
+            captures.push(CaptureConfig {
+                name,
+                path: Some(path),
+                ..Default::default()
+            });
+        }
+        if !captures.is_empty() {
+            request_a.captures = Some(captures);
+        }
+
+        Ok(RequestConfig {
+            request_a,
+            request_b,
+            delay_between_a_and_b_ms: last.started.duration_since(first.started).as_millis() as u64,
+            digest_auth: Self::digest_auth(first),
+            variables: (!variables.is_empty()).then_some(variables),
+            ..Default::default()
+        })
+    }
+
+    /// 场景配置的JSON表示（省略未设置的字段）
+    pub fn to_json(config: &RequestConfig) -> Result<Value> {
+        let mut value = serde_json::to_value(config)?;
+        strip_nulls(&mut value);
+        Ok(value)
+    }
+
+    fn request(exchange: &RecordedExchange) -> HttpRequestConfig {
+        let headers: HashMap<String, String> = exchange
+            .headers
+            .iter()
+            .filter(|(name, _)| !SESSION_HEADERS.contains(&name.to_lowercase().as_str()))
+            .cloned()
+            .collect();
+        let body = match std::str::from_utf8(&exchange.body) {
+            Ok(body) if !body.is_empty() => Some(body.to_string()),
+            Ok(_) => None,
+            Err(_) => {
+                log_warn!(
+                    "Body of {} {} is not UTF-8 text and was left out",
+                    exchange.method,
+                    exchange.url
+                );
+                None
+            }
+        };
+        HttpRequestConfig {
+            method: exchange.method.clone(),
+            url: exchange.url.clone(),
+            headers: (!headers.is_empty()).then_some(headers),
+            body,
+            ..Default::default()
+        }
+    }
+
+    /// 从Digest认证头中取用户名，密码留空（运行时交互式输入）
+    fn digest_auth(exchange: &RecordedExchange) -> Option<DigestAuthConfig> {
+        let header = exchange
+            .headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case("authorization"))
+            .map(|(_, value)| value)?;
+        let username = DIGEST_USERNAME
+            .captures(header)?
+            .get(1)?
+            .as_str()
+            .to_string();
+        Some(DigestAuthConfig {
+            username,
+            password: String::new(),
+            realm: None,
+            nonce: None,
+            auth_type: None,
+            preemptive: None,
+        })
+    }
+
+    /// JSON body中所有足够长的标量取值及其路径（如 `$.TaskList[0].taskID`）
+    fn json_leaves(body: &[u8]) -> Vec<(String, String)> {
+        let mut leaves = Vec::new();
+        if let Ok(value) = serde_json::from_slice::<Value>(body) {
+            collect_leaves(&value, "$".to_string(), &mut leaves);
+        }
+        leaves.retain(|(_, value)| value.len() >= MIN_VARIABLE_LEN);
+        leaves
+    }
+
+    fn contains(request: &HttpRequestConfig, value: &str) -> bool {
+        request.url.contains(value)
+            || request
+                .body
+                .as_deref()
+                .is_some_and(|body| body.contains(value))
+            || request
+                .headers
+                .iter()
+                .flatten()
+                .any(|(_, header)| header.contains(value))
+    }
+
+    fn replace(request: &mut HttpRequestConfig, value: &str, name: &str) {
+        let placeholder = format!("{{{{{}}}}}", name);
+        request.url = request.url.replace(value, &placeholder);
+        if let Some(body) = request.body.as_mut() {
+            *body = body.replace(value, &placeholder);
+        }
+        for header in request
+            .headers
+            .iter_mut()
+            .flat_map(|headers| headers.values_mut())
+        {
+            *header = header.replace(value, &placeholder);
+        }
+    }
+
+    /// 以路径最后一个字段名作为变量名，重名时追加序号
+    fn variable_name(path: &str, used: &mut HashSet<String>) -> String {
+        let base: String = path
+            .rsplit(['.', '['])
+            .find(|segment| segment.chars().next().is_some_and(char::is_alphabetic))
+            .unwrap_or("value")
+            .chars()
+            .filter(|c| c.is_alphanumeric() || *c == '_')
+            .collect();
+        let mut name = base.clone();
+        let mut index = 2;
+        while !used.insert(name.clone()) {
+            name = format!("{}{}", base, index);
+            index += 1;
+        }
+        name
+    }
+}
+
+fn collect_leaves(value: &Value, path: String, leaves: &mut Vec<(String, String)>) {
+    match value {
+        Value::Object(map) => {
+            for (key, value) in map {
+                collect_leaves(value, format!("{}.{}", path, key), leaves);
+            }
+        }
+        Value::Array(items) => {
+            for (index, value) in items.iter().enumerate() {
+                collect_leaves(value, format!("{}[{}]", path, index), leaves);
+            }
+        }
+        Value::String(text) => leaves.push((path, text.clone())),
+        Value::Number(number) => leaves.push((path, number.to_string())),
+        Value::Bool(_) | Value::Null => {}
+    }
+}
+
+fn strip_nulls(value: &mut Value) {
+    match value {
+        Value::Object(map) => {
+            map.retain(|_, value| !value.is_null());
+            map.values_mut().for_each(strip_nulls);
+        }
+        Value::Array(items) => items.iter_mut().for_each(strip_nulls),
+        _ => {}
+    }
+}
@@ -0,0 +1,130 @@
+use serde_json::{Map, Value, json};
+use std::path::{Path, PathBuf};
+
+use crate::observer::RequestObserver;
+use crate::stats::RequestResult;
+
+// Import logger macros from crate root
+use crate::log_warn;
+
+/// 记录模式：将每个响应（状态、头部、body）保存为 `<dir>/<cycle>/<label>.json`
+pub struct ResponseRecorder {
+    dir: PathBuf,
+}
+
+impl ResponseRecorder {
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self { dir: dir.into() }
+    }
+
+    /// 单个结果对应的文件路径，多目标时文件名附加目标名
+    pub fn path_for(&self, result: &RequestResult) -> PathBuf {
+        let file_name = match &result.target {
+            Some(target) => format!("{}-{}.json", result.label, sanitize(target)),
+            None => format!("{}.json", result.label),
+        };
+        self.dir.join(result.cycle.to_string()).join(file_name)
+    }
+
+    /// 将结果转换为记录文件内容，JSON格式的body保持结构，其余按文本保存
+    pub fn to_record(result: &RequestResult) -> Value {
+        let mut record = json!({
+            "cycle": result.cycle,
+            "label": result.label,
+            "target": result.target,
+            "method": result.method,
+            "url": result.url,
+            "elapsed_ms": result.elapsed.as_secs_f64() * 1000.0,
+            "error": result.error,
+        });
+        if let Some(correlation_id) = &result.correlation_id {
+            record["correlation_id"] = json!(correlation_id);
+        }
+        if !result.tags.is_empty() {
+            record["tags"] = json!(result.tags);
+        }
+        if let Some(skew) = result.send_skew {
+            record["send_skew_us"] = json!(skew.as_micros() as u64);
+        }
+
+        if let Some(response) = &result.response {
+            // 同名头部（如多个Set-Cookie）出现多次时保存为数组
+            let headers: Map<String, Value> = response
+                .headers
+                .keys()
+                .map(|name| {
+                    let mut values: Vec<Value> = response
+                        .headers
+                        .get_all(name)
+                        .iter()
+                        .map(|value| {
+                            Value::String(String::from_utf8_lossy(value.as_bytes()).into_owned())
+                        })
+                        .collect();
+                    let value = if values.len() == 1 {
+                        values.remove(0)
+                    } else {
+                        Value::Array(values)
+                    };
+                    (name.to_string(), value)
+                })
+                .collect();
+            let body = serde_json::from_slice(&response.body)
+                .unwrap_or_else(|_| Value::String(response.text()));
+
+            record["status"] = json!(response.status.as_u16());
+            if let Some(download) = &response.download {
+                record["download"] = json!({
+                    "path": download.path,
+                    "bytes": download.bytes,
+                    "sha256": download.sha256,
+                    "elapsed_ms": download.elapsed.as_secs_f64() * 1000.0,
+                    "resumes": download.resumes,
+                });
+            }
+            if let Some(overflow) = &response.overflow {
+                record["body_overflow"] = json!({
+                    "bytes": overflow.bytes,
+                    "buffered_bytes": response.body.len(),
+                    "spilled_to": overflow.spilled_to,
+                });
+            }
+            if let Some(remote_addr) = response.remote_addr {
+                record["remote_addr"] = json!(remote_addr.to_string());
+            }
+            record["headers"] = Value::Object(headers);
+            record["body"] = body;
+        }
+
+        record
+    }
+
+    fn write(path: &Path, record: &Value) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        std::fs::write(path, serde_json::to_vec_pretty(record)?)
+    }
+}
+
+impl RequestObserver for ResponseRecorder {
+    fn on_result(&self, result: &RequestResult) {
+        let path = self.path_for(result);
+        if let Err(e) = Self::write(&path, &Self::to_record(result)) {
+            log_warn!("Failed to record response to {}: {}", path.display(), e);
+        }
+    }
+}
+
+/// 将目标名转换为可用作文件名的形式
+pub(crate) fn sanitize(name: &str) -> String {
+    name.chars()
+        .map(|c| {
+            if c.is_ascii_alphanumeric() || c == '-' {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect()
+}
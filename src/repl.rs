@@ -0,0 +1,337 @@
+use anyhow::{Result, anyhow, bail};
+use std::collections::HashMap;
+use std::io::Write;
+use tokio::io::{AsyncBufReadExt, BufReader};
+use tokio::time::Instant;
+
+use crate::capture::{Capturer, VariableStore};
+use crate::config::{HttpRequestConfig, RequestConfig};
+use crate::cycle_context::CycleContext;
+use crate::http_client::{HttpClient, IpFamily, ResponseData};
+use crate::raw_request::RawRequest;
+use crate::request_handler::RequestHandler;
+
+// Import logger macros from crate root
+use crate::{log_error, log_info};
+
+/// body超过该长度时截断显示
+const MAX_BODY_DISPLAY: usize = 4096;
+
+const HELP: &str = "\
+Commands:
+  show [a|b]          print the rendered request(s) of the current cycle
+  send a|b            send request A (all batch copies) or B and print the response
+  vars                list variables, including captured ones
+  set <name> <value>  set a placeholder value (replaces a generated field of the same name)
+  unset <name>        remove a variable set with `set`
+  next                start the next cycle (regenerates fields, clears cycle captures)
+  help                show this help
+  quit                leave the REPL";
+
+/// 渲染后的单个周期（请求A的全部副本和请求B）
+struct RenderedCycle {
+    context: CycleContext,
+    requests_a: Vec<HttpRequestConfig>,
+    request_b: HttpRequestConfig,
+    http_client: HttpClient,
+}
+
+/// 交互模式：按需渲染和发送单个请求，查看捕获的变量、修改占位符取值，用于在运行循环前调试场景
+pub struct Repl {
+    config: RequestConfig,
+    cycle: usize,
+    rendered: Option<RenderedCycle>,
+    cycle_captures: VariableStore,
+    run_captures: VariableStore,
+}
+
+impl Repl {
+    pub fn new(config: RequestConfig) -> Self {
+        Self {
+            config,
+            cycle: 1,
+            rendered: None,
+            cycle_captures: VariableStore::new(),
+            run_captures: VariableStore::new(),
+        }
+    }
+
+    /// 从stdin逐行读取命令直到 `quit` 或输入结束
+    pub async fn run(mut self) {
+        println!("{}", HELP);
+        let mut lines = BufReader::new(tokio::io::stdin()).lines();
+        loop {
+            print!("remote-task[{}]> ", self.cycle);
+            let _ = std::io::stdout().flush();
+            let line = match lines.next_line().await {
+                Ok(Some(line)) => line,
+                Ok(None) => break,
+                Err(e) => {
+                    log_error!("Failed to read input: {}", e);
+                    break;
+                }
+            };
+            let line = line.trim();
+            if line.is_empty() {
+                continue;
+            }
+            if matches!(line, "quit" | "exit") {
+                break;
+            }
+            if let Err(e) = self.execute(line).await {
+                log_error!("{:#}", e);
+            }
+        }
+    }
+
+    /// 执行一条命令
+    pub async fn execute(&mut self, line: &str) -> Result<()> {
+        let mut words = line.split_whitespace();
+        let command = words.next().unwrap_or_default();
+        let argument = words.next();
+        match command {
+            "help" => println!("{}", HELP),
+            "show" => {
+                self.render()?;
+                let rendered = self.current();
+                let labels = match argument {
+                    Some(label) => vec![label],
+                    None => vec!["a", "b"],
+                };
+                for label in labels {
+                    for (label, request) in Self::requests(rendered, label)? {
+                        Self::print_request(label, &self.with_captures(request));
+                    }
+                }
+            }
+            "send" => {
+                let label = argument.ok_or_else(|| anyhow!("usage: send a|b"))?;
+                self.render()?;
+                let requests: Vec<_> = Self::requests(self.current(), label)?
+                    .into_iter()
+                    .map(|(label, request)| (label, self.with_captures(request)))
+                    .collect();
+                for (label, request) in requests {
+                    self.send(label, &request).await?;
+                }
+            }
+            "vars" => self.print_vars()?,
+            "set" => {
+                let name = argument.ok_or_else(|| anyhow!("usage: set <name> <value>"))?;
+                let value = line
+                    .splitn(3, char::is_whitespace)
+                    .nth(2)
+                    .map(str::trim)
+                    .unwrap_or_default();
+                self.set(name, value);
+            }
+            "unset" => {
+                let name = argument.ok_or_else(|| anyhow!("usage: unset <name>"))?;
+                if let Some(variables) = self.config.variables.as_mut() {
+                    variables.remove(name);
+                }
+                self.rendered = None;
+            }
+            "next" => {
+                self.cycle += 1;
+                self.rendered = None;
+                self.cycle_captures = VariableStore::new();
+                log_info!("🔄 Cycle {}", self.cycle);
+            }
+            _ => bail!("unknown command '{}' (type `help`)", command),
+        }
+        Ok(())
+    }
+
+    /// 设置占位符取值，同名的生成字段不再生成
+    fn set(&mut self, name: &str, value: &str) {
+        if let Some(fields) = self.config.generated_fields.as_mut() {
+            let before = fields.len();
+            fields.retain(|field| field.name != name);
+            if fields.len() != before {
+                log_info!("Generated field {} replaced by a fixed value", name);
+            }
+        }
+        // 设置的值写入运行作用域，去掉同名的worker变量以免被覆盖
+        if let Some(worker_variables) = self.config.worker_variables.as_mut() {
+            worker_variables.remove(name);
+        }
+        self.config
+            .variables
+            .get_or_insert_with(HashMap::new)
+            .insert(name.to_string(), value.to_string());
+        self.rendered = None;
+    }
+
+    /// 渲染当前周期（已渲染时不重复渲染）
+    fn render(&mut self) -> Result<()> {
+        if self.rendered.is_none() {
+            let cycle = RequestHandler::render_cycle(&self.config, self.cycle)
+                .into_iter()
+                .next()
+                .ok_or_else(|| anyhow!("no target to render"))?;
+            let digest_auth = cycle
+                .target
+                .and_then(|target| target.digest_auth.as_ref())
+                .or(self.config.digest_auth.as_ref());
+            let unix_socket = cycle
+                .target
+                .and_then(|target| target.unix_socket.as_deref())
+                .or(self.config.unix_socket.as_deref());
+            let http_client = RequestHandler::create_http_client(
+                digest_auth,
+                unix_socket,
+                self.config.proxy.as_ref(),
+                &cycle.context.variables,
+            )?;
+            self.rendered = Some(RenderedCycle {
+                context: cycle.context,
+                requests_a: cycle.requests_a,
+                request_b: cycle.request_b,
+                http_client,
+            });
+        }
+        Ok(())
+    }
+
+    /// 当前周期渲染后的请求，须先调用 `render`
+    fn current(&self) -> &RenderedCycle {
+        self.rendered.as_ref().expect("cycle rendered")
+    }
+
+    fn requests<'a>(
+        rendered: &'a RenderedCycle,
+        label: &str,
+    ) -> Result<Vec<(&'static str, &'a HttpRequestConfig)>> {
+        match label.to_lowercase().as_str() {
+            "a" => Ok(rendered
+                .requests_a
+                .iter()
+                .map(|request| ("A", request))
+                .collect()),
+            "b" => Ok(vec![("B", &rendered.request_b)]),
+            _ => bail!("unknown request '{}' (expected a or b)", label),
+        }
+    }
+
+    fn with_captures(&self, request: &HttpRequestConfig) -> HttpRequestConfig {
+        let mut request = request.clone();
+        Capturer::render(&mut request, &self.cycle_captures, &self.run_captures);
+        request
+    }
+
+    async fn send(&self, label: &str, request: &HttpRequestConfig) -> Result<()> {
+        let http_client = &self.current().http_client;
+        let method = request.method.to_uppercase();
+        println!("→ [{}] {} {}", label, method, request.url);
+
+        let start = Instant::now();
+        let response = if method == "RAW" {
+            let ip_family = request
+                .ip_family
+                .as_deref()
+                .and_then(IpFamily::parse)
+                .unwrap_or_default();
+            RawRequest::send(
+                &request.url,
+                request.body.as_deref().unwrap_or_default().as_bytes(),
+                ip_family,
+                http_client.timeout(),
+            )
+            .await?
+        } else {
+            let headers = request.headers.as_ref().map(|headers| {
+                headers
+                    .iter()
+                    .map(|(name, value)| (name.as_str(), value.as_str()))
+                    .collect()
+            });
+            let response = http_client
+                .send_request(&method, &request.url, request.body.clone(), headers)
+                .await?;
+            ResponseData::read(response).await?
+        };
+        let elapsed = start.elapsed();
+
+        println!("← {} in {}ms", response.status, elapsed.as_millis());
+        let mut headers: Vec<_> = response.headers.iter().collect();
+        headers.sort_by_key(|(name, _)| name.as_str());
+        for (name, value) in headers {
+            println!("{}: {}", name, value.to_str().unwrap_or("<binary>"));
+        }
+        let body = match serde_json::from_slice::<serde_json::Value>(&response.body) {
+            Ok(json) => serde_json::to_string_pretty(&json)?,
+            Err(_) => response.text(),
+        };
+        if !body.is_empty() {
+            println!();
+            if body.len() > MAX_BODY_DISPLAY {
+                let end = (0..=MAX_BODY_DISPLAY)
+                    .rev()
+                    .find(|index| body.is_char_boundary(*index))
+                    .unwrap_or_default();
+                println!("{}… ({} bytes)", &body[..end], body.len());
+            } else {
+                println!("{}", body);
+            }
+        }
+        println!();
+
+        if let Some(captures) = &request.captures {
+            if let Some(failure) = Capturer::capture(
+                captures,
+                &response,
+                &self.cycle_captures,
+                &self.run_captures,
+            ) {
+                log_error!("{}", failure);
+            }
+            let cycle = self.cycle_captures.snapshot();
+            let run = self.run_captures.snapshot();
+            for capture in captures {
+                if let Some(value) = cycle.get(&capture.name).or(run.get(&capture.name)) {
+                    println!("📌 {} = {}", capture.name, value);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    fn print_vars(&mut self) -> Result<()> {
+        self.render()?;
+        let mut vars: Vec<_> = self.current().context.variables.iter().collect();
+        vars.sort();
+        for (name, value) in vars {
+            println!("{} = {}", name, value);
+        }
+        for (name, value) in self.run_captures.snapshot() {
+            println!("{} = {}  (captured, run)", name, value);
+        }
+        for (name, value) in self.cycle_captures.snapshot() {
+            println!("{} = {}  (captured, cycle)", name, value);
+        }
+        for field in self.config.generated_fields.iter().flatten() {
+            println!("{} = <{}>  (generated)", field.name, field.generator);
+        }
+        Ok(())
+    }
+
+    fn print_request(label: &str, request: &HttpRequestConfig) {
+        println!(
+            "[{}] {} {}",
+            label,
+            request.method.to_uppercase(),
+            request.url
+        );
+        let mut headers: Vec<_> = request.headers.iter().flatten().collect();
+        headers.sort();
+        for (name, value) in headers {
+            println!("{}: {}", name, value);
+        }
+        if let Some(body) = &request.body {
+            println!();
+            println!("{}", body.trim());
+        }
+        println!();
+    }
+}
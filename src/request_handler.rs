@@ -1,205 +1,2185 @@
+use rand::Rng;
+use reqwest::StatusCode;
+use std::collections::HashMap;
 use std::sync::Arc;
 use std::time::Duration;
-use tokio::sync::Mutex;
-use tokio::time::{Instant, sleep};
+use tokio::sync::{Mutex, mpsc, watch};
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, sleep, sleep_until};
+use tokio_stream::Stream;
+use tokio_stream::wrappers::UnboundedReceiverStream;
+use tokio_util::sync::CancellationToken;
+use tracing::field::Empty;
+use tracing::{Instrument, Span, info_span};
+use uuid::Builder;
 
-use crate::config::{HttpRequestConfig, RequestConfig};
-use crate::field_generator::FieldGenerator;
-use crate::http_client::{AuthConfig, AuthType, HttpClient, HttpClientConfig};
-use crate::stats::{RequestStats, StatsHandler};
+use crate::adaptive_pacing::AdaptivePacer;
+use crate::auth_recovery::AuthRecovery;
+use crate::bandwidth::BandwidthThrottle;
+use crate::capture::{Capturer, VariableStore};
+use crate::circuit_breaker::CircuitBreaker;
+use crate::conditional::ConditionalCache;
+use crate::config::{
+    BranchConfig, DigestAuthConfig, HttpRequestConfig, IdempotencyKeyConfig, LiveSettings,
+    ProxyConfig, RepeatConfig, RequestConfig, StepDelayConfig, TargetConfig, UserAgentConfig,
+};
+use crate::cookies::CookieSteps;
+use crate::csrf::CsrfSession;
+use crate::cycle_context::{CycleContext, SendBarrier};
+use crate::delay::DelaySampler;
+use crate::device_clock::{DeviceClock, DeviceTimeSync};
+use crate::download::Downloader;
+use crate::drift::DriftDetector;
+use crate::fault_injection::FaultInjector;
+use crate::field_generator::{FieldGenerator, UniqueValues};
+use crate::heartbeat::Heartbeat;
+use crate::http_client::{
+    AuthConfig, AuthType, DEFAULT_USER_AGENT, HttpClient, HttpClientCache, HttpClientConfig,
+    IpFamily, ProxyError, ProxySettings, RequestCancelled, ResponseData, ResponseLimit,
+};
+use crate::isapi::task_ids_in_body;
+use crate::job_queue::{Job, JobQueue};
+use crate::json_expectation::JsonExpectation;
+use crate::metrics::MetricsExporter;
+use crate::notifier::Notifier;
+use crate::observer::{Observers, RequestObserver};
+use crate::port_probe::PortProbe;
+use crate::provisioning::Provisioner;
+use crate::rate_limiter::RateLimiter;
+use crate::raw_request::RawRequest;
+use crate::rng;
+use crate::rtsp::RtspPrecheck;
+use crate::run_metadata::RunMetadata;
+use crate::self_monitor::SelfMonitor;
+use crate::slo::SloTracker;
+use crate::snapshot::SnapshotChecker;
+use crate::stats::{RequestResult, RequestStats, StatsHandler};
+use crate::status_expectation::{StatusExpectation, StatusOutcome};
+use crate::task_sweeper::TaskSweeper;
+use crate::task_verifier::TaskVerifier;
+use crate::time_window::TimeWindows;
+use crate::{logger, template};
 
 // Import logger macros from crate root
-use crate::{log_debug, log_error, log_info, log_trace};
+use crate::{log_debug, log_error, log_info, log_trace, log_warn};
+
+/// 将每个请求结果转发到通道的观察者，用于 `run_stream`
+struct ResultForwarder {
+    sender: mpsc::UnboundedSender<RequestResult>,
+    shutdown: CancellationToken, // 接收端被丢弃后中断运行
+}
+
+impl RequestObserver for ResultForwarder {
+    fn on_result(&self, result: &RequestResult) {
+        if self.sender.send(result.clone()).is_err() && !self.shutdown.is_cancelled() {
+            log_info!("🛑 Result stream dropped, stopping the run");
+            self.shutdown.cancel();
+        }
+    }
+}
+
+/// 运行选项：配置文件之外、由调用方提供的运行期输入
+#[derive(Default)]
+pub struct RunOptions {
+    /// 各阶段回调的观察者
+    pub observers: Vec<Arc<dyn RequestObserver>>,
+    /// 可热更新的运行参数，每个周期开始时读取最新值
+    pub live_settings: Option<watch::Receiver<LiveSettings>>,
+    /// 用实际响应重写快照文件，而不是与之比较
+    pub update_snapshots: bool,
+    /// 取消时中断运行：不再开始新周期，进行中的请求超过 `shutdown_grace_ms` 仍未完成则取消
+    pub shutdown: Option<CancellationToken>,
+}
+
+/// 一次运行中所有请求共享的资源
+pub struct RunShared {
+    pub stats: Arc<Mutex<RequestStats>>,
+    pub warmup_stats: Arc<Mutex<RequestStats>>, // 预热期间的统计，不计入最终结果
+    pub rate_limiter: Option<RateLimiter>,
+    pub bandwidth: Option<BandwidthThrottle>,
+    pub fault_injector: Option<FaultInjector>,
+    pub task_verifier: Option<TaskVerifier>,
+    pub rtsp_precheck: Option<RtspPrecheck>,
+    pub captures: VariableStore, // 运行作用域的捕获变量
+    pub conditional: ConditionalCache,
+    pub observers: Observers,
+    pub update_snapshots: bool,
+    pub http_clients: HttpClientCache, // 各周期复用的底层客户端（连接池、TLS上下文）
+    pub stopping: CancellationToken,   // 中断后触发：不再开始新周期，步骤延迟不再等待
+    pub cancellation: CancellationToken, // 中断后超过宽限期时取消进行中的请求
+    pub response_limit: Option<ResponseLimit>, // 响应body缓冲上限
+    pub idempotency: Option<IdempotencyKeyConfig>, // 幂等键和失败重试
+    pub strict_json: bool,             // 发送前校验渲染后的body是否为合法JSON
+    pub unique_values: UniqueValues,   // unique生成字段在本次运行中已生成的值
+    pub device_clock: Arc<DeviceClock>, // 生成字段使用的时钟（配置了device_time时按设备时间校正）
+    pub auth_recovery: Option<AuthRecovery>, // 收到401时重新登录并重发一次
+    pub csrf: Option<CsrfSession>,     // 修改状态的请求加上CSRF令牌头部
+}
+
+/// 一个周期内针对单个目标渲染完成的A和B请求
+#[derive(Debug)]
+pub struct CycleRequests<'a> {
+    /// 目标设备（未配置targets时为None）
+    pub target: Option<&'a TargetConfig>,
+    /// 渲染时使用的周期上下文
+    pub context: CycleContext,
+    /// 请求A（批量模式下为本周期的全部副本，各自使用新生成的字段）
+    pub requests_a: Vec<HttpRequestConfig>,
+    pub request_b: HttpRequestConfig,
+    /// 分支（分支请求已按本周期渲染），未配置branches时为空
+    pub branches: Vec<BranchConfig>,
+    /// 重复步骤（请求已按本周期渲染，`{{iteration}}` 在每次发送时替换）
+    pub repeat: Option<RepeatConfig>,
+    /// 批量模式下请求B的任务ID列表（`request_b` 中已填入全部副本的任务ID）
+    pub batch: Option<BatchTaskList>,
+}
+
+/// 批量模式下请求B的任务ID列表：按请求A副本记录任务ID，请求A完成后只列出创建成功的任务
+#[derive(Debug, Clone)]
+pub struct BatchTaskList {
+    request_b: HttpRequestConfig, // 尚未填入列表占位符的请求B
+    list_variable: String,
+    task_id_field: String,
+    task_ids: Vec<Vec<String>>, // 每个请求A副本body中的任务ID
+}
+
+impl BatchTaskList {
+    /// 填入 `created(副本序号)` 为true的副本中的任务ID，没有任何任务时返回None
+    pub fn request_b(&self, created: impl Fn(usize) -> bool) -> Option<HttpRequestConfig> {
+        let task_id_list: Vec<serde_json::Value> = self
+            .task_ids
+            .iter()
+            .enumerate()
+            .filter(|(copy, _)| created(*copy))
+            .flat_map(|(_, task_ids)| task_ids)
+            .map(|task_id| serde_json::json!({ self.task_id_field.as_str(): task_id }))
+            .collect();
+        if task_id_list.is_empty() {
+            return None;
+        }
+        let mut request_b = self.request_b.clone();
+        Capturer::render_with(
+            &mut request_b,
+            &HashMap::from([(
+                self.list_variable.clone(),
+                serde_json::Value::Array(task_id_list).to_string(),
+            )]),
+        );
+        Some(request_b)
+    }
+}
+
+/// 分支或重复步骤模式下一个目标在请求A之后的流程
+struct FollowUp {
+    a_handles: Vec<JoinHandle<Option<u16>>>,
+    configs_a: Vec<HttpRequestConfig>,
+    config_b: HttpRequestConfig,
+    repeat: Option<RepeatConfig>,
+    branches: Vec<BranchConfig>,
+    /// 请求B（或分支）最早的发送时刻
+    b_not_before: Instant,
+    /// 运行中断时不再等待，尽快发送请求B
+    stopping: CancellationToken,
+}
 
 /// 请求处理器
 pub struct RequestHandler;
 
 impl RequestHandler {
-    /// 使用共享HttpClient发送请求（认证复用）
+    /// 使用本周期该目标共享的HttpClient发送请求（认证复用），返回响应状态码（未收到响应时为None）。
+    /// 步骤配置的pre_delay在发送前等待，post_delay在完成后等待，都不计入请求耗时，运行中断时不再等待
     pub async fn send_request_with_shared_client(
         config: HttpRequestConfig,
-        http_client: Arc<HttpClient>,
-        _request_type: String,
-        stats: Arc<Mutex<RequestStats>>,
+        request_type: String,
+        context: Arc<CycleContext>,
+        cycle_client: Arc<HttpClient>,
+        shared: Arc<RunShared>,
+    ) -> Option<u16> {
+        let post_delay = config.post_delay.clone();
+        if let Some(delay) = &config.pre_delay {
+            Self::step_delay(delay, "before", &request_type, &shared).await;
+        }
+        let status = Self::send_step(
+            config,
+            request_type.clone(),
+            context,
+            cycle_client,
+            Arc::clone(&shared),
+        )
+        .await;
+        if let Some(delay) = &post_delay {
+            Self::step_delay(delay, "after", &request_type, &shared).await;
+        }
+        status
+    }
+
+    /// 步骤延迟
+    async fn step_delay(
+        delay: &StepDelayConfig,
+        position: &str,
+        request_type: &str,
+        shared: &RunShared,
     ) {
-        let start_time = Instant::now();
+        let duration = DelaySampler::sample(delay.ms, delay.distribution.as_ref());
+        log_debug!(
+            "⏳ Waiting {}ms {} {} request",
+            duration.as_millis(),
+            position,
+            request_type
+        );
+        shared.stopping.run_until_cancelled(sleep(duration)).await;
+    }
+
+    async fn send_step(
+        mut config: HttpRequestConfig,
+        request_type: String,
+        context: Arc<CycleContext>,
+        cycle_client: Arc<HttpClient>,
+        shared: Arc<RunShared>,
+    ) -> Option<u16> {
+        let (cycle, warmup, target) = (context.cycle, context.warmup, &context.target);
+
+        // 限定地址族时使用对应的客户端（与周期客户端共享Cookie和认证缓存）
+        let ip_family = config
+            .ip_family
+            .as_deref()
+            .and_then(IpFamily::parse)
+            .unwrap_or_default();
+        let family_client;
+        let http_client = match ip_family {
+            IpFamily::Auto => &cycle_client,
+            family => match cycle_client.with_ip_family(family) {
+                Ok(client) => {
+                    family_client = Arc::new(client);
+                    &family_client
+                }
+                Err(e) => {
+                    log_error!("Failed to create {:?} HTTP client: {}", family, e);
+                    &cycle_client
+                }
+            },
+        };
+
+        // 带宽限制：上传限速通过限速客户端发送body，下载限速在读取响应时生效
+        let bandwidth = shared
+            .bandwidth
+            .as_ref()
+            .map(BandwidthThrottle::for_request)
+            .unwrap_or_default();
+        let limited_client;
+        let http_client = match &bandwidth.upload {
+            Some(limiter) => {
+                limited_client = Arc::new(http_client.with_upload_limit(Arc::clone(limiter)));
+                &limited_client
+            }
+            None => http_client,
+        };
+
+        // 渲染之前请求捕获的变量（配置了auth_recovery时保留渲染前的请求，重新登录后按新捕获的变量重新渲染）
+        let unrendered = shared.auth_recovery.as_ref().map(|_| config.clone());
+        Capturer::render(&mut config, &context.captures, &shared.captures);
+        shared.conditional.apply(&request_type, &mut config);
+        if let Some(csrf) = &shared.csrf {
+            csrf.apply(&mut config, &shared.captures);
+        }
+
+        // 严格JSON校验：渲染后的body不是合法JSON时不发送，直接计为失败（在故障注入截断body之前检查）
+        let invalid_body = config
+            .body
+            .as_deref()
+            .filter(|_| shared.strict_json && !config.method.eq_ignore_ascii_case("RAW"))
+            .and_then(Self::json_body_error);
+
+        // 按主机限速，等待时间不计入请求耗时
+        if let Some(rate_limiter) = &shared.rate_limiter {
+            rate_limiter.acquire(&config.url).await;
+        }
+
+        // 故障注入：发送前延迟、截断body
+        let mut fault_plan = shared
+            .fault_injector
+            .as_ref()
+            .map(|injector| injector.plan(config.body.as_deref()))
+            .unwrap_or_default();
+        if let Some(delay) = fault_plan.delay {
+            log_debug!(
+                "💥 Injecting {}ms delay before {} request",
+                delay.as_millis(),
+                request_type
+            );
+            sleep(delay).await;
+        }
+        if let (Some(length), Some(body)) = (fault_plan.truncate_to, config.body.as_mut()) {
+            log_debug!(
+                "💥 Truncating {} request body from {} to {} bytes",
+                request_type,
+                body.len(),
+                length
+            );
+            body.truncate(length);
+        }
+
         let method = config.method.to_uppercase();
 
-        // 转换HashMap头为Vec元组用于http_client
-        let headers = config.headers.as_ref().map(|headers| {
-            headers
+        // 发送前设置或删除Cookie（原始请求不经过Cookie罐）
+        if let Some(cookies) = &config.cookies
+            && method != "RAW"
+            && let Err(e) = CookieSteps::prepare(cookies, http_client, &config.url)
+        {
+            log_warn!("Cookie setup for {} request failed: {:#}", request_type, e);
+        }
+
+        // 屏障模式：先完成认证探测，再等本周期的全部请求就绪后同时放行
+        let send_skew = match &context.barrier {
+            Some(barrier) => {
+                if method != "RAW"
+                    && let Err(e) = http_client.get_auth_header(&config.url, &method).await
+                {
+                    log_debug!("Auth preflight before barrier failed: {}", e);
+                }
+                Some(barrier.wait().await)
+            }
+            None => None,
+        };
+
+        let start_time = Instant::now();
+        let observers = &shared.observers;
+
+        for observer in observers.iter() {
+            observer.on_request_sent(cycle, &request_type, &config);
+        }
+
+        // 失败（连接错误、中止或5xx）时按 idempotency_key.retries 重试，重试沿用或更换幂等键
+        let retries = shared
+            .idempotency
+            .as_ref()
+            .and_then(|idempotency| idempotency.retries)
+            .unwrap_or(0);
+        let idempotency_key = shared.idempotency.as_ref().and_then(|idempotency| {
+            let header = idempotency.header_name();
+            config
+                .headers
                 .iter()
-                .map(|(k, v)| (k.as_str(), v.as_str()))
-                .collect::<Vec<_>>()
+                .flatten()
+                .find(|(name, _)| name.eq_ignore_ascii_case(header))
+                .map(|(_, key)| key.clone())
         });
+        let mut attempt = 0;
+        let mut reauthenticated = false;
+        let result = loop {
+            if let Some(error) = &invalid_body {
+                break Err(anyhow::anyhow!("{}", error));
+            }
 
-        let result = match method.as_str() {
-            "POST" => {
-                if let Some(body) = &config.body {
+            // 转换HashMap头为Vec元组用于http_client
+            let headers = config.headers.as_ref().map(|headers| {
+                headers
+                    .iter()
+                    .map(|(k, v)| (k.as_str(), v.as_str()))
+                    .collect::<Vec<_>>()
+            });
+
+            // 下载续传时在原请求头上追加Range
+            let resume_headers = headers.clone();
+            let resend = |offset: u64| {
+                let (http_client, method, config) = (&http_client, &method, &config);
+                let resume_headers = &resume_headers;
+                async move {
+                    let range = format!("bytes={}-", offset);
+                    let mut headers = resume_headers.clone().unwrap_or_default();
+                    headers.push(("Range", range.as_str()));
                     http_client
-                        .post_json(&config.url, body, headers)
+                        .send_request(method, &config.url, config.body.clone(), Some(headers))
                         .await
-                        .map_err(|e| anyhow::anyhow!("{}", e))
-                } else {
-                    Err(anyhow::anyhow!("POST request requires a body"))
+                }
+            };
+
+            let send = async {
+                // 原始请求：body按字节原样发送，不经过reqwest
+                if method == "RAW" {
+                    let payload = config.body.as_deref().unwrap_or_default();
+                    return RawRequest::send(
+                        &config.url,
+                        payload.as_bytes(),
+                        ip_family,
+                        http_client.timeout(),
+                    )
+                    .await;
+                }
+                let response = match method.as_str() {
+                    "POST" => {
+                        if let Some(body) = &config.body {
+                            http_client.post_json(&config.url, body, headers).await
+                        } else {
+                            Err(anyhow::anyhow!("POST request requires a body"))
+                        }
+                    }
+                    "PUT" | "PATCH" | "GET" | "DELETE" => {
+                        http_client
+                            .send_request(&method, &config.url, config.body.clone(), headers)
+                            .await
+                    }
+                    _ => Err(anyhow::anyhow!("Unsupported HTTP method: {}", method)),
+                }?;
+
+                // 下载模式下成功响应的body写入文件，其余读取响应（受缓冲上限约束）
+                match &config.download {
+                    Some(download) if response.status().is_success() => {
+                        Downloader::save(response, download, bandwidth.download.as_deref(), resend)
+                            .await
+                    }
+                    _ => {
+                        let body_limit = shared.response_limit.as_ref().map(|limit| {
+                            let name = match target {
+                                Some(target) => format!("{}-{}-{}", cycle, request_type, target),
+                                None => format!("{}-{}", cycle, request_type),
+                            };
+                            limit.for_request(&name)
+                        });
+                        ResponseData::read_with(
+                            response,
+                            bandwidth.download.as_deref(),
+                            body_limit.as_ref(),
+                        )
+                        .await
+                    }
+                }
+            };
+
+            // 运行中断且超过宽限期时取消进行中的请求
+            let send = async {
+                shared
+                    .cancellation
+                    .run_until_cancelled(send)
+                    .await
+                    .unwrap_or_else(|| Err(RequestCancelled.into()))
+            };
+
+            // 故障注入：请求发出后中途中止
+            let result = match fault_plan.abort_after {
+                Some(abort_after) => match tokio::time::timeout(abort_after, send).await {
+                    Ok(result) => result,
+                    Err(_) => {
+                        if let Some(injector) = &shared.fault_injector {
+                            injector.record_abort();
+                        }
+                        Err(anyhow::anyhow!(
+                            "fault injection: request aborted after {}ms",
+                            abort_after.as_millis()
+                        ))
+                    }
+                },
+                None => send.await,
+            };
+
+            // 认证失效（会话或nonce过期）：重新登录后重发一次，不计为重试
+            if let (Some(recovery), Some(unrendered)) = (&shared.auth_recovery, &unrendered)
+                && !reauthenticated
+                && AuthRecovery::is_auth_failure(&result)
+            {
+                reauthenticated = true;
+                match recovery
+                    .recover(http_client, &context, &shared.captures, start_time)
+                    .await
+                {
+                    Ok(logged_in) => {
+                        if logged_in && !warmup {
+                            shared.stats.lock().await.reauth_events += 1;
+                        }
+                        log_debug!(
+                            "🔑 Resending {} request to {} after re-authentication",
+                            request_type,
+                            config.url
+                        );
+                        config = unrendered.clone();
+                        Capturer::render(&mut config, &context.captures, &shared.captures);
+                        shared.conditional.apply(&request_type, &mut config);
+                        if let Some(csrf) = &shared.csrf {
+                            csrf.apply(&mut config, &shared.captures);
+                        }
+                        continue;
+                    }
+                    Err(e) => {
+                        log_warn!("🔑 Re-authentication failed: {:#}", e);
+                        if !warmup {
+                            shared.stats.lock().await.reauth_failures += 1;
+                        }
+                    }
                 }
             }
-            "PUT" | "GET" => http_client
-                .send_request(&method, &config.url, config.body.clone(), headers)
+
+            if attempt >= retries || !Self::is_retryable(&config, &result) {
+                break result;
+            }
+            attempt += 1;
+            // 重试前指数退避；运行中断时不再重试
+            let backoff = shared
+                .idempotency
+                .as_ref()
+                .map_or(Duration::ZERO, |idempotency| {
+                    idempotency.retry_backoff(attempt)
+                });
+            if shared
+                .stopping
+                .run_until_cancelled(sleep(backoff))
                 .await
-                .map_err(|e| anyhow::anyhow!("{}", e)),
-            _ => Err(anyhow::anyhow!("Unsupported HTTP method: {}", method)),
+                .is_none()
+            {
+                break result;
+            }
+            log_warn!(
+                "🔁 Retrying {} request to {} ({}/{}): {}",
+                request_type,
+                config.url,
+                attempt,
+                retries,
+                match &result {
+                    Ok(response) => format!("status {}", response.status),
+                    Err(e) => e.to_string(),
+                }
+            );
+            if let (Some(idempotency), Some(key)) = (&shared.idempotency, &idempotency_key)
+                && idempotency.retry_key.as_deref() == Some("new")
+            {
+                Self::set_header(
+                    &mut config,
+                    idempotency.header_name(),
+                    format!("{}-r{}", key, attempt),
+                );
+            }
+            // 每次重试重新抽取延迟和中止故障
+            if let Some(injector) = &shared.fault_injector {
+                fault_plan = injector.plan(None);
+                if let Some(delay) = fault_plan.delay {
+                    shared.cancellation.run_until_cancelled(sleep(delay)).await;
+                }
+            }
+            if !warmup {
+                shared.stats.lock().await.retries += 1;
+            }
         };
 
-        StatsHandler::handle_response(result, &config, start_time, &stats).await;
-    }
+        // 在请求span上记录结果
+        let span = Span::current();
+        match &result {
+            Ok(response) => {
+                span.record("http.response.status_code", response.status.as_u16());
+                if let Some(remote_addr) = response.remote_addr {
+                    span.record("network.peer.address", remote_addr.ip().to_string());
+                    log_debug!("🌐 {} request connected to {}", request_type, remote_addr);
+                }
+                if let Some(overflow) = &response.overflow {
+                    match &overflow.spilled_to {
+                        Some(path) => log_warn!(
+                            "📦 {} response from {} exceeded {} buffered bytes, {} bytes written to {}",
+                            request_type,
+                            config.url,
+                            response.body.len(),
+                            overflow.bytes,
+                            path.display()
+                        ),
+                        None => log_warn!(
+                            "📦 {} response from {} truncated to {} bytes",
+                            request_type,
+                            config.url,
+                            response.body.len()
+                        ),
+                    }
+                }
+            }
+            Err(e) => {
+                span.record("otel.status_code", "ERROR");
+                span.record("error", e.to_string());
+            }
+        }
 
-    /// 运行并发请求
-    pub async fn run_concurrent_requests(config: RequestConfig) -> RequestStats {
-        let stats = Arc::new(Mutex::new(RequestStats::new()));
-        let config = Arc::new(config);
+        // 请求耗时不包含之后的快照比较和任务校验
+        let elapsed = start_time.elapsed();
+        let cancelled = matches!(&result, Err(e) if e.is::<RequestCancelled>());
 
-        let stats_clone = Arc::clone(&stats);
-        let config_clone = Arc::clone(&config);
+        // 与快照比较，不匹配时计为失败
+        let mut check_failure = match (&result, &config.snapshot) {
+            (Ok(response), Some(snapshot)) => {
+                SnapshotChecker::check(snapshot, response, shared.update_snapshots)
+            }
+            _ => None,
+        };
 
-        let request_task = tokio::spawn(async move {
-            let mut request_count = 0;
-            let mut last_a_request_time = Instant::now();
+        // 与期望的响应JSON比较
+        if check_failure.is_none()
+            && let (Ok(response), Some(expect_json)) = (&result, &config.expect_json)
+        {
+            check_failure = JsonExpectation::check(expect_json, response);
+        }
 
-            loop {
-                // 检查是否达到最大请求数
-                if let Some(max) = config_clone.max_requests {
-                    if request_count >= max {
-                        log_info!("🎯 Reached maximum request count of {}", max);
-                        break;
+        // jq断言
+        if check_failure.is_none()
+            && let (Ok(response), Some(expect_jq)) = (&result, &config.expect_jq)
+        {
+            check_failure = JsonExpectation::check_jq(expect_jq, response);
+        }
+
+        // 断言响应Set-Cookie的属性
+        if check_failure.is_none()
+            && let (Ok(response), Some(expect)) = (
+                &result,
+                config
+                    .cookies
+                    .as_ref()
+                    .and_then(|cookies| cookies.expect.as_ref()),
+            )
+        {
+            check_failure = CookieSteps::check(expect, response);
+        }
+
+        // 校验下载文件的SHA-256
+        if check_failure.is_none()
+            && let (Ok(response), Some(download)) = (&result, &config.download)
+        {
+            check_failure = Downloader::verify(download, response);
+        }
+
+        // 在设备任务列表中确认任务已创建/删除
+        if check_failure.is_none()
+            && let (Ok(response), Some(verifier)) = (&result, &shared.task_verifier)
+            && response.status.is_success()
+        {
+            check_failure = verifier
+                .verify(&request_type, &config, Arc::clone(http_client), !warmup)
+                .await;
+        }
+
+        if let Ok(response) = &result {
+            shared
+                .conditional
+                .remember(&request_type, &config, response);
+        }
+
+        // 从成功的响应中提取变量，提取失败时计为失败
+        if check_failure.is_none()
+            && let (Ok(response), Some(captures)) = (&result, &config.captures)
+            && response.status.is_success()
+        {
+            check_failure =
+                Capturer::capture(captures, response, &context.captures, &shared.captures);
+        }
+
+        // 从Cookie罐读取Cookie存为本周期的变量
+        if check_failure.is_none()
+            && let (Ok(response), Some(read)) = (
+                &result,
+                config
+                    .cookies
+                    .as_ref()
+                    .and_then(|cookies| cookies.read.as_ref()),
+            )
+            && response.status.is_success()
+        {
+            check_failure = CookieSteps::read(read, http_client, &config.url, &context.captures);
+        }
+
+        // 通知观察者请求结果
+        let request_result = match &result {
+            Ok(response) => {
+                let status = response.status.as_u16();
+                for observer in observers.iter() {
+                    observer.on_response(cycle, &request_type, status, elapsed);
+                }
+                let outcome = StatusExpectation::classify(&config, response.status);
+                RequestResult {
+                    cycle,
+                    label: request_type.clone(),
+                    method: method.clone(),
+                    url: config.url.clone(),
+                    url_template: config.url_template.clone(),
+                    target: target.clone(),
+                    status: Some(status),
+                    success: outcome != StatusOutcome::Failure && check_failure.is_none(),
+                    expected_failure: outcome == StatusOutcome::ExpectedFailure
+                        && check_failure.is_none(),
+                    elapsed,
+                    error: check_failure.clone(),
+                    warmup,
+                    request_body: config.body.clone(),
+                    response: Some(response.clone()),
+                    correlation_id: context.correlation_id.clone(),
+                    tags: config.tags.clone().unwrap_or_default(),
+                    cancelled: false,
+                    proxy_error: false,
+                    send_skew,
+                }
+            }
+            Err(e) => {
+                let error = e.to_string();
+                if !cancelled {
+                    for observer in observers.iter() {
+                        observer.on_error(cycle, &request_type, &error, elapsed);
                     }
                 }
+                RequestResult {
+                    cycle,
+                    label: request_type.clone(),
+                    method: method.clone(),
+                    url: config.url.clone(),
+                    url_template: config.url_template.clone(),
+                    target: target.clone(),
+                    status: None,
+                    success: false,
+                    expected_failure: false,
+                    elapsed,
+                    error: Some(error),
+                    warmup,
+                    request_body: config.body.clone(),
+                    response: None,
+                    correlation_id: context.correlation_id.clone(),
+                    tags: config.tags.clone().unwrap_or_default(),
+                    cancelled,
+                    proxy_error: e.is::<ProxyError>(),
+                    send_skew,
+                }
+            }
+        };
+        for observer in observers.iter() {
+            observer.on_result(&request_result);
+        }
+        let status = request_result.status;
+        let stats = if warmup {
+            &shared.warmup_stats
+        } else {
+            &shared.stats
+        };
+
+        // 被取消的请求单独计数，不计入成功或失败
+        if cancelled {
+            log_warn!(
+                "🛑 {} request to {} cancelled after {}ms",
+                request_type,
+                config.url,
+                elapsed.as_millis()
+            );
+            StatsHandler::handle_response(&request_result, stats).await;
+            return None;
+        }
 
-                request_count += 1;
-                log_debug!("\n--- Request Cycle {} ---", request_count);
+        StatsHandler::handle_response(&request_result, stats).await;
+        status
+    }
 
-                // 按类型分离字段（header vs body）
+    /// 单个请求的追踪span，挂在所属周期的span下
+    fn request_span(
+        parent: &Span,
+        label: &str,
+        config: &HttpRequestConfig,
+        context: &CycleContext,
+    ) -> Span {
+        info_span!(
+            parent: parent,
+            "request",
+            otel.name = format!("{} {}", label, config.method),
+            label,
+            http.request.method = %config.method,
+            url.full = %config.url,
+            target = context.target.as_deref().unwrap_or_default(),
+            correlation_id = context.correlation_id.as_deref().unwrap_or_default(),
+            http.response.status_code = Empty,
+            network.peer.address = Empty,
+            otel.status_code = Empty,
+            error = Empty,
+        )
+    }
+
+    /// 生成本周期的字段，并为本周期访问的每个目标渲染A和B请求
+    ///
+    /// 不发送任何请求，可用于预览（dry-run）。
+    pub fn render_cycle(config: &RequestConfig, cycle: usize) -> Vec<CycleRequests<'_>> {
+        Self::render_cycle_with(config, cycle, &UniqueValues::new(), &DeviceClock::new())
+    }
+
+    /// 同 [`Self::render_cycle`]，`unique` 字段在 `unique_values` 记录的整个运行范围内去重，
+    /// 时间相关的字段按 `clock`（同步后为设备时间）生成
+    pub fn render_cycle_with<'a>(
+        config: &'a RequestConfig,
+        cycle: usize,
+        unique_values: &UniqueValues,
+        clock: &DeviceClock,
+    ) -> Vec<CycleRequests<'a>> {
+        // 按类型分离字段（header vs body），批量模式下每个请求A副本单独生成。
+        // 两类字段都可在URL、头部和body中引用，只有body字段会组成缺省的JSON body
+        let batch_size = config.batch.as_ref().map_or(1, |batch| batch.size.max(1));
+        let batch_fields: Vec<(HashMap<String, String>, HashMap<String, String>)> = (0..batch_size)
+            .map(|_| {
                 let (header_fields, body_fields) = FieldGenerator::separate_fields_by_type(
-                    &config_clone.generated_fields,
-                    request_count,
+                    &config.generated_fields,
+                    cycle,
+                    unique_values,
+                    clock,
                 );
-
                 if !header_fields.is_empty() {
                     log_trace!("🎲 Generated header fields: {:?}", header_fields);
                 }
                 if !body_fields.is_empty() {
                     log_trace!("📝 Generated body fields: {:?}", body_fields);
                 }
+                (header_fields, body_fields)
+            })
+            .collect();
+
+        // 本周期的关联ID，作为默认头部加到请求A和B中
+        let correlation_id = config.correlation_id.as_ref().map(|correlation| {
+            format!(
+                "{}{}",
+                correlation.prefix.as_deref().unwrap_or_default(),
+                rng::with(|rng| Builder::from_random_bytes(rng.r#gen()).into_uuid())
+            )
+        });
+        let mut default_headers = config.default_headers.clone();
+        if let (Some(correlation), Some(correlation_id)) = (&config.correlation_id, &correlation_id)
+        {
+            let header = correlation.header.as_deref().unwrap_or("X-Request-ID");
+            let headers = default_headers.get_or_insert_with(HashMap::new);
+            headers.retain(|name, _| !name.eq_ignore_ascii_case(header));
+            headers.insert(header.to_string(), correlation_id.clone());
+        }
+        let user_agent = Self::cycle_user_agent(config.user_agent.as_ref(), cycle);
+        let request_a = Self::with_default_headers(
+            Self::with_user_agent(config.request_a.clone(), user_agent),
+            &default_headers,
+        );
+        let request_b = Self::with_default_headers(
+            Self::with_user_agent(config.request_b.clone(), user_agent),
+            &default_headers,
+        );
+
+        // 确定本周期访问的目标（未配置targets时直接使用请求中的URL）
+        let cycle_targets: Vec<Option<&TargetConfig>> = match &config.targets {
+            Some(targets) if !targets.is_empty() => {
+                if config.target_mode.as_deref() == Some("concurrent") {
+                    targets.iter().map(Some).collect()
+                } else {
+                    vec![Some(&targets[(cycle - 1) % targets.len()])]
+                }
+            }
+            _ => vec![None],
+        };
+
+        // 为每个目标创建动态请求内容
+        cycle_targets
+            .into_iter()
+            .map(|target| {
+                let mut context = CycleContext::new(
+                    config,
+                    cycle,
+                    target,
+                    batch_fields.clone(),
+                    correlation_id.clone(),
+                );
+                let padding =
+                    FieldGenerator::padding_fields(&config.generated_fields, &context.variables);
+                let requests_a: Vec<HttpRequestConfig> = (0..batch_size)
+                    .map(|copy| {
+                        let mut request_a =
+                            Self::render_request(&request_a, &context, copy, &padding);
+                        if let Some(idempotency) = &config.idempotency_key {
+                            let name = if batch_size > 1 {
+                                format!("a{}", copy + 1)
+                            } else {
+                                "a".to_string()
+                            };
+                            let key = Self::idempotency_key(idempotency, &context, &name);
+                            Self::set_header(&mut request_a, idempotency.header_name(), key);
+                        }
+                        request_a
+                    })
+                    .collect();
+                for request_a in &requests_a {
+                    log_trace!("📝 Dynamic body for A: {:?}", request_a.body);
+                }
+
+                let mut request_b = Self::render_request(&request_b, &context, 0, &padding);
+                if let Some(idempotency) = &config.idempotency_key {
+                    let key = Self::idempotency_key(idempotency, &context, "b");
+                    Self::set_header(&mut request_b, idempotency.header_name(), key);
+                }
 
-                // 为A和B请求创建动态body内容
-                let config_a = {
-                    let mut config = config_clone.request_a.clone();
-                    if !body_fields.is_empty() {
-                        config.body =
-                            FieldGenerator::generate_dynamic_body(&config.body, &body_fields);
-                        log_trace!("📝 Dynamic body for A: {:?}", config.body);
+                // 批量模式：请求B的列表占位符渲染为请求A中的任务ID（运行时只列出创建成功的任务）
+                let batch = config.batch.as_ref().map(|batch| {
+                    let task_id_field = batch.task_id_field.as_deref().unwrap_or("taskID");
+                    let list = BatchTaskList {
+                        request_b: request_b.clone(),
+                        list_variable: batch
+                            .list_variable
+                            .clone()
+                            .unwrap_or_else(|| "task_id_list".to_string()),
+                        task_id_field: task_id_field.to_string(),
+                        task_ids: requests_a
+                            .iter()
+                            .map(|request_a| {
+                                request_a
+                                    .body
+                                    .as_deref()
+                                    .map(|body| task_ids_in_body(body, task_id_field))
+                                    .unwrap_or_default()
+                            })
+                            .collect(),
+                    };
+                    if let Some(all) = list.request_b(|_| true) {
+                        request_b = all;
+                    }
+                    let all_ids = list
+                        .task_ids
+                        .iter()
+                        .flatten()
+                        .map(|task_id| serde_json::json!({ task_id_field: task_id }));
+                    context.variables.insert(
+                        list.list_variable.clone(),
+                        serde_json::Value::Array(all_ids.collect()).to_string(),
+                    );
+                    list
+                });
+                log_trace!("📝 Dynamic body for B: {:?}", request_b.body);
+                let branches = config
+                    .branches
+                    .iter()
+                    .flatten()
+                    .map(|branch| {
+                        let mut branch = branch.clone();
+                        branch.equals = branch.equals.map(|equals| {
+                            template::render(&equals, &context.scoped_variables(0, None))
+                        });
+                        branch.request = branch.request.map(|request| {
+                            let request = Self::with_default_headers(
+                                Self::with_user_agent(request, user_agent),
+                                &default_headers,
+                            );
+                            Self::render_request(&request, &context, 0, &padding)
+                        });
+                        branch
+                    })
+                    .collect();
+                let repeat = config.repeat.as_ref().map(|repeat| {
+                    let mut repeat = repeat.clone();
+                    let request = Self::with_default_headers(
+                        Self::with_user_agent(repeat.request, user_agent),
+                        &default_headers,
+                    );
+                    repeat.request = Self::render_request(&request, &context, 0, &padding);
+                    repeat.until_equals = repeat.until_equals.map(|equals| {
+                        template::render(&equals, &context.scoped_variables(0, None))
+                    });
+                    repeat
+                });
+                CycleRequests {
+                    target,
+                    context,
+                    requests_a,
+                    request_b,
+                    branches,
+                    repeat,
+                    batch,
+                }
+            })
+            .collect()
+    }
+
+    /// 分支或重复步骤模式下一个目标在请求A之后的流程：等待本目标的请求A完成，执行重复步骤，
+    /// 到请求B的发送时刻后按请求A的状态码或捕获的变量选择第一个匹配的分支，未匹配时发送请求B。
+    /// 返回最后一个请求的状态码
+    async fn follow_up(
+        follow_up: FollowUp,
+        context: Arc<CycleContext>,
+        http_client: Arc<HttpClient>,
+        shared: Arc<RunShared>,
+    ) -> Option<u16> {
+        let FollowUp {
+            a_handles,
+            configs_a,
+            config_b,
+            repeat,
+            branches,
+            b_not_before,
+            stopping,
+        } = follow_up;
+        let send = |config: HttpRequestConfig, label: String| {
+            Self::send_request_with_shared_client(
+                config,
+                label,
+                Arc::clone(&context),
+                Arc::clone(&http_client),
+                Arc::clone(&shared),
+            )
+        };
+        // 批量模式下按最后一个请求A副本的结果选择
+        let mut status = None;
+        for handle in a_handles {
+            status = handle.await.ok().flatten();
+        }
+
+        // 重复步骤：达到次数或满足until条件后结束，运行中断时跳过剩余的重复
+        if let Some(repeat) = &repeat {
+            let label = repeat.name.clone().unwrap_or_else(|| "repeat".to_string());
+            let has_until = repeat.until_status.is_some() || repeat.until_variable.is_some();
+            for iteration in 1..=repeat.count {
+                if iteration > 1
+                    && let Some(interval_ms) = repeat.interval_ms
+                {
+                    stopping
+                        .run_until_cancelled(sleep(Duration::from_millis(interval_ms)))
+                        .await;
+                }
+                if stopping.is_cancelled() {
+                    break;
+                }
+                let mut request = repeat.request.clone();
+                Capturer::render_with(
+                    &mut request,
+                    &HashMap::from([("iteration".to_string(), iteration.to_string())]),
+                );
+                let repeat_status = send(request, label.clone()).await;
+                if has_until
+                    && Self::condition_matches(
+                        repeat.until_status.as_deref(),
+                        repeat.until_variable.as_deref(),
+                        repeat.until_equals.as_deref(),
+                        repeat_status,
+                        &context,
+                        &shared,
+                    )
+                {
+                    log_debug!("🔁 {} condition met after {} iterations", label, iteration);
+                    break;
+                }
+            }
+        }
+        stopping
+            .run_until_cancelled(sleep_until(b_not_before))
+            .await;
+
+        let mut retries = 0;
+        loop {
+            let Some((index, branch)) = branches
+                .iter()
+                .enumerate()
+                .find(|(_, branch)| Self::branch_matches(branch, status, &context, &shared))
+            else {
+                return send(config_b, "B".to_string()).await;
+            };
+            let label = branch
+                .name
+                .clone()
+                .unwrap_or_else(|| format!("branch{}", index + 1));
+            log_debug!(
+                "🔀 Request A returned {}, taking branch {}",
+                status.map_or_else(|| "no response".to_string(), |status| status.to_string()),
+                label
+            );
+            if !context.warmup {
+                *shared
+                    .stats
+                    .lock()
+                    .await
+                    .branches
+                    .entry(label.clone())
+                    .or_default() += 1;
+            }
+            if let Some(wait_ms) = branch.wait_ms
+                && shared
+                    .cancellation
+                    .run_until_cancelled(sleep(Duration::from_millis(wait_ms)))
+                    .await
+                    .is_none()
+            {
+                return None;
+            }
+            if let Some(request) = &branch.request {
+                status = send(request.clone(), label.clone()).await;
+            }
+            match branch.then.as_deref() {
+                Some("b") => return send(config_b, "B".to_string()).await,
+                Some("retry") => {
+                    let max_retries = branch.max_retries.unwrap_or(3);
+                    if retries >= max_retries {
+                        log_warn!(
+                            "🔀 Branch {} retried request A {} times, sending request B",
+                            label,
+                            retries
+                        );
+                        return send(config_b, "B".to_string()).await;
+                    }
+                    retries += 1;
+                    // 重发前清除请求A上次捕获的周期变量，捕获失败时不会用旧值匹配分支
+                    for capture in configs_a
+                        .iter()
+                        .flat_map(|config_a| config_a.captures.iter().flatten())
+                        .filter(|capture| capture.scope.as_deref() != Some("run"))
+                    {
+                        context.captures.remove(&capture.name);
                     }
-                    config
+                    for config_a in &configs_a {
+                        status = send(config_a.clone(), "A".to_string()).await;
+                    }
+                }
+                _ => return status,
+            }
+        }
+    }
+
+    /// 分支是否匹配请求A的结果
+    fn branch_matches(
+        branch: &BranchConfig,
+        status: Option<u16>,
+        context: &CycleContext,
+        shared: &RunShared,
+    ) -> bool {
+        Self::condition_matches(
+            branch.status.as_deref(),
+            branch.variable.as_deref(),
+            branch.equals.as_deref(),
+            status,
+            context,
+            shared,
+        )
+    }
+
+    /// 状态码（"error" 匹配未收到响应）和捕获的变量（周期变量优先）都满足条件，未配置的条件视为满足。
+    /// 运行作用域中只有声明为 `scope: "run"` 的捕获（及CSRF令牌），周期变量不会沿用到之后的周期
+    fn condition_matches(
+        status_patterns: Option<&[String]>,
+        variable: Option<&str>,
+        equals: Option<&str>,
+        status: Option<u16>,
+        context: &CycleContext,
+        shared: &RunShared,
+    ) -> bool {
+        let status_matches = status_patterns.is_none_or(|patterns| {
+            patterns.iter().any(|pattern| match status {
+                None => pattern == "error",
+                Some(status) => StatusCode::from_u16(status)
+                    .is_ok_and(|status| StatusExpectation::matches(pattern, status)),
+            })
+        });
+        let variable_matches = variable.is_none_or(|name| {
+            context
+                .captures
+                .get(name)
+                .or_else(|| shared.captures.get(name))
+                .is_some_and(|value| equals.is_none_or(|equals| value == equals))
+        });
+        status_matches && variable_matches
+    }
+
+    /// 按周期生成请求的幂等键，如 `<prefix>12-a`（批量模式为 `12-a1`、`12-a2`…，多目标时附加目标名）；
+    /// 运行时 `prefix` 已附加本次运行的标识
+    fn idempotency_key(
+        idempotency: &IdempotencyKeyConfig,
+        context: &CycleContext,
+        request: &str,
+    ) -> String {
+        let prefix = context.render(idempotency.prefix.as_deref().unwrap_or_default());
+        match &context.target {
+            Some(target) => format!("{}{}-{}-{}", prefix, context.cycle, request, target),
+            None => format!("{}{}-{}", prefix, context.cycle, request),
+        }
+    }
+
+    /// 设置头部，替换同名（不区分大小写）的已有头部
+    fn set_header(config: &mut HttpRequestConfig, name: &str, value: String) {
+        let headers = config.headers.get_or_insert_with(HashMap::new);
+        headers.retain(|existing, _| !existing.eq_ignore_ascii_case(name));
+        headers.insert(name.to_string(), value);
+    }
+
+    /// 是否值得重试：连接错误、超时、注入的中止和5xx响应（运行中断取消的请求、
+    /// `expect_status` 中计为成功或预期失败的状态码不重试）
+    fn is_retryable(config: &HttpRequestConfig, result: &anyhow::Result<ResponseData>) -> bool {
+        match result {
+            Ok(response) => {
+                response.status.is_server_error()
+                    && !StatusExpectation::is_success(config, response.status)
+            }
+            Err(e) => !e.is::<RequestCancelled>(),
+        }
+    }
+
+    /// 渲染后的body不是合法JSON时返回错误说明，附带出错位置附近的内容（`»` 标记出错位置）
+    pub fn json_body_error(body: &str) -> Option<String> {
+        let error = serde_json::from_str::<serde::de::IgnoredAny>(body).err()?;
+        let line = body
+            .lines()
+            .nth(error.line().saturating_sub(1))
+            .unwrap_or_default();
+        // serde_json的列号从1开始、按字节计
+        let position = line.floor_char_boundary(error.column().saturating_sub(1));
+        let start = line.floor_char_boundary(position.saturating_sub(24));
+        let end = line.ceil_char_boundary(position + 24);
+        Some(format!(
+            "rendered body is not valid JSON: {} near `{}»{}`",
+            error,
+            &line[start..position],
+            &line[position..end]
+        ))
+    }
+
+    /// 本周期使用的User-Agent：轮换列表按周期依次或随机选取，否则为固定值
+    fn cycle_user_agent(user_agent: Option<&UserAgentConfig>, cycle: usize) -> Option<&str> {
+        let user_agent = user_agent?;
+        match user_agent.rotation.as_deref() {
+            Some(rotation) if !rotation.is_empty() => {
+                let index = match user_agent.mode.as_deref() {
+                    Some("random") => rng::with(|rng| rng.gen_range(0..rotation.len())),
+                    _ => (cycle.max(1) - 1) % rotation.len(),
+                };
+                Some(&rotation[index])
+            }
+            _ => user_agent.value.as_deref(),
+        }
+    }
+
+    /// 设置User-Agent头部：headers中已配置的优先，其次为请求的user_agent，最后为本周期的全局值
+    fn with_user_agent(
+        mut config: HttpRequestConfig,
+        cycle_user_agent: Option<&str>,
+    ) -> HttpRequestConfig {
+        let Some(user_agent) = config
+            .user_agent
+            .clone()
+            .or(cycle_user_agent.map(str::to_string))
+        else {
+            return config;
+        };
+        let headers = config.headers.get_or_insert_with(HashMap::new);
+        if !headers
+            .keys()
+            .any(|key| key.eq_ignore_ascii_case("User-Agent"))
+        {
+            headers.insert("User-Agent".to_string(), user_agent);
+        }
+        config
+    }
+
+    /// 合并全局默认头部，请求中已有的同名头部（不区分大小写）优先
+    fn with_default_headers(
+        mut config: HttpRequestConfig,
+        default_headers: &Option<HashMap<String, String>>,
+    ) -> HttpRequestConfig {
+        let Some(default_headers) = default_headers else {
+            return config;
+        };
+        let headers = config.headers.get_or_insert_with(HashMap::new);
+        for (name, value) in default_headers {
+            if !headers.keys().any(|key| key.eq_ignore_ascii_case(name)) {
+                headers.insert(name.clone(), value.clone());
+            }
+        }
+        config
+    }
+
+    /// 渲染单个请求：按作用域合并第 `copy` 份生成字段、本周期变量和请求的步骤变量，
+    /// 替换URL、头部、body、下载路径、标签和要设置的Cookie中的占位符
+    ///
+    /// 配置了padding字段时，填充内容按渲染结果计算，使body达到目标大小。
+    fn render_request(
+        base: &HttpRequestConfig,
+        context: &CycleContext,
+        copy: usize,
+        padding: &[(String, usize)],
+    ) -> HttpRequestConfig {
+        let variables = context.scoped_variables(copy, base.variables.as_ref());
+        let mut config = base.clone();
+        config.body = match &base.body {
+            Some(base_body) => {
+                // 兼容旧版body中生成字段的 `{name}` 占位符
+                let base_body = &template::upgrade_legacy(base_body, context.fields(copy).keys());
+                // JSON子模板渲染后作为本步骤的变量，body中可用 `{{ name | json_escape }}` 嵌入
+                let mut body_variables = variables.clone();
+                body_variables.extend(
+                    context
+                        .json_templates(&variables)
+                        .map(|(name, text)| (name.to_string(), text)),
+                );
+                let base_body = if padding.is_empty() {
+                    base_body.clone()
+                } else {
+                    FieldGenerator::fill_padding(base_body, padding, &mut body_variables)
                 };
+                Some(template::render(&base_body, &body_variables))
+            }
+            None => {
+                // 没有基础body时由body字段组成JSON，不包含padding字段
+                let mut fields = context.fields(copy).clone();
+                fields.retain(|name, _| !padding.iter().any(|(padding, _)| padding == name));
+                FieldGenerator::generate_dynamic_body(&None, &fields)
+            }
+        };
+        config.url_template.get_or_insert_with(|| base.url.clone());
+        config.url = template::render(&config.url, &variables);
+        if let Some(download) = config.download.as_mut() {
+            download.path = template::render(&download.path, &variables);
+        }
+        if let Some(headers) = config.headers.as_mut() {
+            for value in headers.values_mut() {
+                *value = template::render(value, &variables);
+            }
+        }
+        for tag in config.tags.iter_mut().flatten() {
+            *tag = template::render(tag, &variables);
+        }
+        if let Some(cookies) = config.cookies.as_mut() {
+            for value in cookies.set.iter_mut().flat_map(HashMap::values_mut) {
+                *value = template::render(value, &variables);
+            }
+        }
+        config
+    }
+
+    /// 创建HttpClient，配置了digest凭据时启用Digest认证，配置了unix_socket时通过Unix域套接字连接，
+    /// 配置了proxy时经代理连接（凭据、套接字路径和代理地址中的占位符按变量渲染）
+    pub(crate) fn create_http_client(
+        digest_auth: Option<&DigestAuthConfig>,
+        unix_socket: Option<&str>,
+        proxy: Option<&ProxyConfig>,
+        vars: &HashMap<String, String>,
+    ) -> anyhow::Result<HttpClient> {
+        HttpClient::new(Self::http_client_config(
+            digest_auth,
+            unix_socket,
+            proxy,
+            vars,
+        ))
+    }
 
-                let config_b = {
-                    let mut config = config_clone.request_b.clone();
-                    if !body_fields.is_empty() {
-                        config.body =
-                            FieldGenerator::generate_dynamic_body(&config.body, &body_fields);
-                        log_trace!("📝 Dynamic body for B: {:?}", config.body);
+    pub(crate) fn http_client_config(
+        digest_auth: Option<&DigestAuthConfig>,
+        unix_socket: Option<&str>,
+        proxy: Option<&ProxyConfig>,
+        vars: &HashMap<String, String>,
+    ) -> HttpClientConfig {
+        let auth_config = digest_auth.map(|digest_auth| {
+            AuthConfig::from_config(digest_auth, |value| template::render(value, vars))
+        });
+        let proxy = proxy.map(|proxy| ProxySettings {
+            url: template::render(&proxy.url, vars),
+            auth: proxy.username.as_ref().map(|username| AuthConfig {
+                username: template::render(username, vars),
+                password: template::render(proxy.password.as_deref().unwrap_or_default(), vars),
+                auth_type: if proxy.uses_digest() {
+                    AuthType::Digest
+                } else {
+                    AuthType::Basic
+                },
+                preemptive: false,
+                known_challenge: None,
+            }),
+        });
+
+        HttpClientConfig {
+            timeout: Duration::from_secs(30),
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            auth: auth_config,
+            unix_socket: unix_socket.map(|path| template::render(path, vars)),
+            proxy,
+        }
+    }
+
+    /// 运行并发请求
+    pub async fn run_concurrent_requests(config: RequestConfig) -> RequestStats {
+        Self::run_with_observers(config, Vec::new()).await
+    }
+
+    /// 以异步流的形式运行并发请求，每个请求完成时产出一个结果
+    ///
+    /// 运行结束后流随之结束；流被丢弃时运行按中断处理（同 `RunOptions::shutdown`）。
+    /// 结果经无界通道转发，消费者跟不上时未读取的结果缓存在内存中。需要在tokio运行时中调用。
+    pub fn run_stream(config: RequestConfig) -> impl Stream<Item = RequestResult> {
+        Self::run_stream_with_observers(config, Vec::new())
+    }
+
+    /// 同 `run_stream`，并额外回调给定的观察者
+    pub fn run_stream_with_observers(
+        config: RequestConfig,
+        observers: Vec<Arc<dyn RequestObserver>>,
+    ) -> impl Stream<Item = RequestResult> {
+        let (stream, _) = Self::run_stream_with_options(
+            config,
+            RunOptions {
+                observers,
+                ..Default::default()
+            },
+        );
+        stream
+    }
+
+    /// 同 `run_stream`，使用给定的运行选项，另外返回运行任务的句柄，用于取得最终统计
+    pub fn run_stream_with_options(
+        config: RequestConfig,
+        mut options: RunOptions,
+    ) -> (impl Stream<Item = RequestResult>, JoinHandle<RequestStats>) {
+        let (sender, receiver) = mpsc::unbounded_channel();
+        // 调用方的shutdown仍然有效，流被丢弃时只中断本次运行
+        let shutdown = options
+            .shutdown
+            .as_ref()
+            .map_or_else(CancellationToken::new, CancellationToken::child_token);
+        options.shutdown = Some(shutdown.clone());
+        options
+            .observers
+            .push(Arc::new(ResultForwarder { sender, shutdown }));
+        let run = tokio::spawn(Self::run_with_options(config, options));
+        (UnboundedReceiverStream::new(receiver), run)
+    }
+
+    /// 运行并发请求，并在各阶段回调给定的观察者
+    pub async fn run_with_observers(
+        config: RequestConfig,
+        observers: Vec<Arc<dyn RequestObserver>>,
+    ) -> RequestStats {
+        Self::run_with_options(
+            config,
+            RunOptions {
+                observers,
+                ..Default::default()
+            },
+        )
+        .await
+    }
+
+    /// 使用给定的运行选项运行并发请求
+    pub async fn run_with_options(mut config: RequestConfig, options: RunOptions) -> RequestStats {
+        let stats = Arc::new(Mutex::new(RequestStats::new()));
+        let run_start = Instant::now();
+        let mut metadata = RunMetadata::new(&config);
+        // 幂等键带上本次运行的随机标识（不受 --seed 影响），重跑同一配置时服务器不会返回上次运行缓存的响应
+        if let Some(idempotency) = config.idempotency_key.as_mut() {
+            let prefix = format!(
+                "{}{:08x}-",
+                idempotency.prefix.as_deref().unwrap_or_default(),
+                rand::random::<u32>()
+            );
+            log_info!(
+                "🔑 Idempotency keys for this run: {}<cycle>-<request>",
+                prefix
+            );
+            idempotency.prefix = Some(prefix);
+        }
+        let seed = rng::run_seed(config.seed);
+        metadata.seed = Some(seed);
+        log_info!("🎲 Random seed: {} (reproduce with --seed {})", seed, seed);
+        let mut settings = LiveSettings::from_config(&config);
+        let mut observers = options.observers;
+
+        // 配置驱动的内置观察者
+        let drift_detector = config
+            .drift_detection
+            .as_ref()
+            .map(|drift| Arc::new(DriftDetector::new(drift)));
+        if let Some(detector) = &drift_detector {
+            observers.push(Arc::clone(detector) as Arc<dyn RequestObserver>);
+        }
+        let metrics_exporter = config
+            .metrics
+            .as_ref()
+            .map(|metrics| Arc::new(MetricsExporter::new(metrics)));
+        let metrics_flusher = metrics_exporter.as_ref().map(|exporter| {
+            observers.push(Arc::clone(exporter) as Arc<dyn RequestObserver>);
+            exporter.spawn_flusher()
+        });
+        let circuit_breaker = config
+            .circuit_breaker
+            .as_ref()
+            .map(|breaker| Arc::new(CircuitBreaker::new(breaker)));
+        if let Some(breaker) = &circuit_breaker {
+            observers.push(Arc::clone(breaker) as Arc<dyn RequestObserver>);
+        }
+        let pacer = config.adaptive_pacing.as_ref().map(|pacing| {
+            Arc::new(AdaptivePacer::new(
+                pacing,
+                Duration::from_millis(config.delay_between_a_requests_ms),
+            ))
+        });
+        if let Some(pacer) = &pacer {
+            observers.push(Arc::clone(pacer) as Arc<dyn RequestObserver>);
+        }
+        let slo_tracker = SloTracker::new(&config.request_a, &config.request_b).map(Arc::new);
+        if let Some(tracker) = &slo_tracker {
+            observers.push(Arc::clone(tracker) as Arc<dyn RequestObserver>);
+        }
+        let notifier = config
+            .notifications
+            .as_ref()
+            .map(|notifications| Arc::new(Notifier::new(notifications)));
+        if let Some(notifier) = &notifier {
+            observers.push(Arc::clone(notifier) as Arc<dyn RequestObserver>);
+        }
+        let task_sweeper = config
+            .task_cleanup
+            .as_ref()
+            .and_then(|cleanup| match TaskSweeper::new(cleanup) {
+                Ok(sweeper) => Some(Arc::new(sweeper)),
+                Err(e) => {
+                    log_warn!("Task cleanup disabled: {:#}", e);
+                    None
+                }
+            });
+        if let Some(sweeper) = &task_sweeper {
+            observers.push(Arc::clone(sweeper) as Arc<dyn RequestObserver>);
+        }
+        let job_queue = config
+            .job_queue
+            .as_ref()
+            .map(|queue| Arc::new(JobQueue::new(queue)));
+        if let Some(queue) = &job_queue {
+            observers.push(Arc::clone(queue) as Arc<dyn RequestObserver>);
+        }
+        let time_windows = config.time_windows.as_ref().and_then(|windows| {
+            let fallback_offset = config
+                .daemon
+                .as_ref()
+                .and_then(|daemon| daemon.utc_offset.as_deref());
+            match TimeWindows::new(windows, fallback_offset) {
+                Ok(windows) => Some(windows),
+                Err(e) => {
+                    log_warn!("Time windows disabled: {:#}", e);
+                    None
+                }
+            }
+        });
+        let heartbeat = config.heartbeat.as_ref().and_then(|heartbeat| {
+            match Heartbeat::new(heartbeat, &config) {
+                Ok(heartbeat) => Some(Arc::new(heartbeat)),
+                Err(e) => {
+                    log_warn!("Heartbeat disabled: {:#}", e);
+                    None
+                }
+            }
+        });
+        let self_monitor = config
+            .self_monitor
+            .as_ref()
+            .map(|monitor| Arc::new(SelfMonitor::new(monitor, Arc::clone(&stats))));
+        // 运行开始前与设备对时，失败时按本机时间生成
+        let device_clock = Arc::new(DeviceClock::new());
+        let device_time_sync = match &config.device_time {
+            Some(device_time) => match DeviceTimeSync::new(device_time, &config) {
+                Ok(sync) => {
+                    if let Err(e) = sync.sync(&device_clock).await {
+                        log_warn!("Device time sync failed, using the local clock: {:#}", e);
                     }
-                    config
+                    Some(Arc::new(sync))
+                }
+                Err(e) => {
+                    log_warn!("Device time sync disabled: {:#}", e);
+                    None
+                }
+            },
+            None => None,
+        };
+        let sweep_on_shutdown = task_sweeper.is_some()
+            && config
+                .task_cleanup
+                .as_ref()
+                .and_then(|cleanup| cleanup.on_shutdown)
+                .unwrap_or(true);
+
+        // 数据预置：主循环前创建前置对象并将变量池写入运行作用域变量，失败时不进入主循环
+        let mut config = config;
+        let mut provisioner = None;
+        let mut provisioning_failure = None;
+        if let Some(provisioning) = config.provisioning.clone() {
+            match Provisioner::new(&provisioning, &config) {
+                Ok(mut created) => {
+                    provisioning_failure = created.setup().await;
+                    created.apply(&mut config);
+                    provisioner = Some(created);
+                }
+                Err(e) => provisioning_failure = Some(format!("{:#}", e)),
+            }
+        }
+
+        let config = Arc::new(config);
+        let observers: Observers = Arc::new(observers);
+        let mut live_settings = options.live_settings;
+
+        let warmup_stats = Arc::new(Mutex::new(RequestStats::new()));
+        let shared = Arc::new(RunShared {
+            stats: Arc::clone(&stats),
+            warmup_stats: Arc::clone(&warmup_stats),
+            rate_limiter: config.rate_limit.as_ref().map(RateLimiter::new),
+            bandwidth: config.bandwidth.as_ref().map(BandwidthThrottle::new),
+            fault_injector: config.fault_injection.as_ref().map(FaultInjector::new),
+            task_verifier: config.task_verification.as_ref().map(TaskVerifier::new),
+            rtsp_precheck: config.rtsp_precheck.as_ref().map(RtspPrecheck::new),
+            captures: VariableStore::new(),
+            conditional: ConditionalCache::new(),
+            observers: Arc::clone(&observers),
+            update_snapshots: options.update_snapshots,
+            http_clients: HttpClientCache::new(),
+            stopping: CancellationToken::new(),
+            cancellation: CancellationToken::new(),
+            response_limit: config.response_limit.as_ref().map(ResponseLimit::new),
+            idempotency: config.idempotency_key.clone(),
+            strict_json: config.strict_json.unwrap_or(false),
+            unique_values: UniqueValues::new(),
+            device_clock: Arc::clone(&device_clock),
+            auth_recovery: config.auth_recovery.clone().map(|mut recovery| {
+                if recovery.login.is_none() {
+                    recovery.login = config.csrf.as_ref().map(CsrfSession::login_request);
+                }
+                AuthRecovery::new(recovery)
+            }),
+            csrf: config.csrf.as_ref().map(CsrfSession::new),
+        });
+
+        // 运行开始前登录并取得CSRF令牌，失败时请求不带令牌发送
+        if let Some(csrf) = &config.csrf {
+            let context = CycleContext::new(&config, 0, None, Vec::new(), None);
+            let login = async {
+                let http_client = shared.http_clients.session(Self::http_client_config(
+                    config.digest_auth.as_ref(),
+                    config.unix_socket.as_deref(),
+                    config.proxy.as_ref(),
+                    &context.variables,
+                ))?;
+                AuthRecovery::login(
+                    &http_client,
+                    &CsrfSession::login_request(csrf),
+                    &context,
+                    &shared.captures,
+                )
+                .await
+            };
+            match login.await {
+                Ok(()) => log_info!("🔑 CSRF token obtained from {}", csrf.login.url),
+                Err(e) => log_warn!(
+                    "CSRF login failed, sending requests without a token: {:#}",
+                    e
+                ),
+            }
+        }
+        let config_clone = Arc::clone(&config);
+        let observers_clone = Arc::clone(&observers);
+
+        let warmup_cycles = config.warmup_cycles.unwrap_or(0);
+        let warmup_duration = Duration::from_millis(config.warmup_duration_ms.unwrap_or(0));
+
+        let breaker_clone = circuit_breaker.clone();
+        let slo_clone = slo_tracker.clone();
+        let pacer_clone = pacer.clone();
+        let queue_clone = job_queue.clone();
+        let shared_clone = Arc::clone(&shared);
+        let stopping = shared.stopping.clone();
+        let stopping_clone = stopping.clone();
+
+        // 心跳在主循环运行期间于后台发送
+        let heartbeat_stop = CancellationToken::new();
+        let heartbeat_task = heartbeat
+            .as_ref()
+            .map(|heartbeat| heartbeat.spawn(heartbeat_stop.clone()));
+        let clock_refresh_task = device_time_sync
+            .as_ref()
+            .and_then(|sync| sync.spawn_refresh(Arc::clone(&device_clock), heartbeat_stop.clone()));
+        let self_monitor_task = self_monitor
+            .as_ref()
+            .map(|monitor| monitor.spawn(heartbeat_stop.clone()));
+
+        let mut request_task = tokio::spawn(rng::with_seed(seed, async move {
+            let mut request_count = 0;
+            let mut measured_count = 0;
+            let mut measure_start = run_start;
+            let mut warming_up = warmup_cycles > 0 || !warmup_duration.is_zero();
+            // 请求A的计划发出时刻按上一个计划时刻（而非实际发出时刻）累加间隔，
+            // 睡眠精度和周期准备耗时不会累积成漂移
+            let mut last_a_scheduled = Instant::now();
+            let mut last_a_sent: Option<Instant> = None;
+
+            // 发送请求前确认设备的辅助端口已开放
+            if let Some(port_probes) = &config_clone.port_probes
+                && let Some(reason) = PortProbe::check_all(
+                    port_probes,
+                    &config_clone.variables.clone().unwrap_or_default(),
+                )
+                .await
+            {
+                return (
+                    Some(format!("port probe failed: {}", reason)),
+                    measure_start,
+                );
+            }
+            if let Some(reason) = provisioning_failure {
+                return (
+                    Some(format!("provisioning failed: {}", reason)),
+                    measure_start,
+                );
+            }
+
+            loop {
+                // 中断后不再开始新周期
+                if stopping_clone.is_cancelled() {
+                    break (Some("interrupted".to_string()), measure_start);
+                }
+
+                // 应用热更新的运行参数
+                if let Some(receiver) = live_settings.as_mut()
+                    && receiver.has_changed().unwrap_or(false)
+                {
+                    settings = receiver.borrow_and_update().clone();
+                }
+
+                // 预热结束后开始计入统计
+                if warming_up
+                    && request_count >= warmup_cycles
+                    && run_start.elapsed() >= warmup_duration
+                {
+                    warming_up = false;
+                    measure_start = Instant::now();
+                    log_info!("🔥 Warm-up finished after {} cycles", request_count);
+                }
+
+                // 检查是否达到最大请求数（不含预热周期）
+                if let Some(max) = settings.max_requests
+                    && measured_count >= max
+                {
+                    log_info!("🎯 Reached maximum request count of {}", max);
+                    break (None, measure_start);
+                }
+
+                // SLO违规预算耗尽时停止
+                if let Some(tracker) = &slo_clone
+                    && let Some(reason) = tracker.exhausted_reason()
+                {
+                    log_warn!("📉 {}", reason);
+                    break (Some(reason), measure_start);
+                }
+
+                // 检查观察者是否要求中止
+                if observers_clone
+                    .iter()
+                    .any(|observer| observer.should_abort())
+                {
+                    log_info!("🛑 Run aborted by observer");
+                    break (Some("aborted by observer".to_string()), measure_start);
+                }
+
+                // 熔断期间暂停发送
+                if let Some(breaker) = &breaker_clone
+                    && stopping_clone
+                        .run_until_cancelled(breaker.wait_until_allowed())
+                        .await
+                        .is_none()
+                {
+                    continue;
+                }
+
+                // 时间窗外暂停，进入允许的时段后再开始新周期（进行中的周期不受影响）
+                if let Some(windows) = &time_windows
+                    && stopping_clone
+                        .run_until_cancelled(windows.wait_until_open())
+                        .await
+                        .is_none()
+                {
+                    continue;
+                }
+
+                // 任务队列模式下每个周期取一个任务
+                let job = match &queue_clone {
+                    Some(queue) => match stopping_clone
+                        .run_until_cancelled(queue.next_message())
+                        .await
+                    {
+                        None => continue,
+                        Some(Ok(Some(message))) => match Job::parse(&message) {
+                            Ok(job) => Some(job),
+                            Err(e) => {
+                                log_warn!("Skipping invalid job: {:#}", e);
+                                if let Err(e) = queue.reject(&message, &e).await {
+                                    log_warn!("Failed to publish job result: {:#}", e);
+                                }
+                                continue;
+                            }
+                        },
+                        Some(Ok(None)) => {
+                            log_info!("📭 No new job within the idle timeout");
+                            break (None, measure_start);
+                        }
+                        Some(Err(e)) => {
+                            log_error!("Job queue failed: {:#}", e);
+                            break (Some(format!("job queue failed: {:#}", e)), measure_start);
+                        }
+                    },
+                    None => None,
                 };
+                let job_config = job.as_ref().map(|job| job.apply(&config_clone));
+                let cycle_config = job_config.as_ref().unwrap_or(&config_clone);
 
-                // 计算距离上次A请求的时间以确保适当间隔
-                let time_since_last_a = last_a_request_time.elapsed();
-                let required_delay =
-                    Duration::from_millis(config_clone.delay_between_a_requests_ms);
+                // RTSP预检通过后才计入周期，被跳过的周期不占用请求数
+                let cycle = request_count + 1;
 
-                if time_since_last_a < required_delay {
-                    let remaining_delay = required_delay - time_since_last_a;
-                    log_trace!(
-                        "⏳ Waiting {}ms to ensure proper A request spacing",
-                        remaining_delay.as_millis()
+                // 生成字段并为每个目标渲染A和B请求
+                let rendered = Self::render_cycle_with(
+                    cycle_config,
+                    cycle,
+                    &shared_clone.unique_values,
+                    &shared_clone.device_clock,
+                );
+
+                // unique字段出现重复：已重新生成的只计数，无法避免的重复停止运行
+                let duplicates = shared_clone.unique_values.take_duplicates();
+                if !duplicates.is_empty() && !warming_up {
+                    shared_clone.stats.lock().await.duplicate_values += duplicates.len();
+                }
+                if let Some(duplicate) = duplicates.iter().find(|duplicate| !duplicate.regenerated)
+                {
+                    log_error!(
+                        "🔁 Generated field {} repeated value {} in cycle {}",
+                        duplicate.field,
+                        duplicate.value,
+                        cycle
+                    );
+                    break (
+                        Some(format!(
+                            "duplicate value for unique field {}: {}",
+                            duplicate.field, duplicate.value
+                        )),
+                        measure_start,
+                    );
+                }
+                for duplicate in &duplicates {
+                    log_warn!(
+                        "🔁 Generated field {} repeated value {}, regenerated",
+                        duplicate.field,
+                        duplicate.value
+                    );
+                }
+                if let Some(correlation_id) = rendered
+                    .first()
+                    .and_then(|cycle_request| cycle_request.context.correlation_id.as_deref())
+                {
+                    log_debug!("🔗 Cycle {} correlation ID: {}", cycle, correlation_id);
+                }
+
+                // 自适应节奏启用时以其当前间隔为基准
+                let a_interval_ms = pacer_clone
+                    .as_ref()
+                    .map_or(settings.delay_between_a_requests_ms, |pacer| {
+                        pacer.interval().as_millis() as u64
+                    });
+                let required_delay = DelaySampler::sample(
+                    a_interval_ms,
+                    config_clone.delay_between_a_requests_distribution.as_ref(),
+                );
+                let a_scheduled = last_a_scheduled + required_delay;
+
+                // 检查请求A引用的RTSP视频流是否可达
+                let bodies_a: Vec<&str> = rendered
+                    .iter()
+                    .flat_map(|cycle_request| &cycle_request.requests_a)
+                    .filter_map(|request_a| request_a.body.as_deref())
+                    .collect();
+                if let Some(precheck) = &shared_clone.rtsp_precheck
+                    && let Some(reason) = precheck.check(bodies_a).await
+                {
+                    if precheck.skips_cycle() {
+                        // 跳过的周期同样按A的间隔占位，避免缓存的失败结果使循环空转
+                        last_a_scheduled = a_scheduled;
+                        let _ = stopping_clone
+                            .run_until_cancelled(sleep_until(a_scheduled))
+                            .await;
+                        continue;
+                    }
+                    break (
+                        Some(format!("RTSP precheck failed: {}", reason)),
+                        measure_start,
                     );
-                    sleep(remaining_delay).await;
                 }
 
-                // 更新上次A请求时间
-                last_a_request_time = Instant::now();
+                request_count = cycle;
+                if !warming_up {
+                    measured_count += 1;
+                }
+                log_debug!(
+                    "\n--- Request Cycle {}{} ---",
+                    request_count,
+                    if warming_up { " (warm-up)" } else { "" }
+                );
 
-                let stats_a = Arc::clone(&stats_clone);
-                let stats_b = Arc::clone(&stats_clone);
+                for observer in observers_clone.iter() {
+                    observer.on_cycle_start(request_count);
+                }
+                let cycle_span = info_span!("cycle", cycle = request_count);
 
-                // 创建共享HttpClient用于认证复用
-                let http_client = {
-                    let auth_config =
-                        config_clone
-                            .digest_auth
-                            .as_ref()
-                            .map(|digest_auth| AuthConfig {
-                                username: digest_auth.username.clone(),
-                                password: digest_auth.password.clone(),
-                                auth_type: AuthType::Digest,
-                            });
-
-                    let http_client_config = HttpClientConfig {
-                        timeout: Duration::from_secs(30),
-                        user_agent: "RemoteTask-HTTP-Client/1.0".to_string(),
-                        auth: auth_config,
-                    };
+                // 为每个目标取得本周期共享的HttpClient会话用于认证复用（目标凭据优先于全局凭据），
+                // 底层客户端在整个运行中复用
+                // 屏障模式下本周期的全部请求（各目标的A副本和B）同时放行
+                let barrier = (cycle_config.send_mode.as_deref() == Some("barrier")).then(|| {
+                    let requests = rendered
+                        .iter()
+                        .map(|cycle_request| cycle_request.requests_a.len() + 1)
+                        .sum();
+                    Arc::new(SendBarrier::new(requests))
+                });
+                let mut prepared = Vec::with_capacity(rendered.len());
+                let mut batches = Vec::with_capacity(rendered.len());
+                for cycle_request in rendered {
+                    let CycleRequests {
+                        target,
+                        mut context,
+                        requests_a: configs_a,
+                        request_b: config_b,
+                        branches,
+                        repeat,
+                        batch,
+                    } = cycle_request;
+                    batches.push(batch);
+                    let digest_auth = target
+                        .and_then(|target| target.digest_auth.as_ref())
+                        .or(config_clone.digest_auth.as_ref());
+                    let unix_socket = target
+                        .and_then(|target| target.unix_socket.as_deref())
+                        .or(config_clone.unix_socket.as_deref());
+                    let http_client =
+                        match shared_clone.http_clients.session(Self::http_client_config(
+                            digest_auth,
+                            unix_socket,
+                            config_clone.proxy.as_ref(),
+                            &context.variables,
+                        )) {
+                            Ok(client) => Arc::new(
+                                client.with_cancellation(shared_clone.cancellation.clone()),
+                            ),
+                            Err(e) => {
+                                log_error!("Failed to create HTTP client: {}", e);
+                                return (
+                                    Some(format!("failed to create HTTP client: {}", e)),
+                                    measure_start,
+                                );
+                            }
+                        };
+                    context.warmup = warming_up;
+                    context.barrier = barrier.clone();
+                    for observer in shared_clone.observers.iter() {
+                        observer.on_cycle_context(&context);
+                    }
+                    prepared.push((
+                        Arc::new(context),
+                        http_client,
+                        configs_a,
+                        config_b,
+                        branches,
+                        repeat,
+                    ));
+                }
 
-                    match HttpClient::new(http_client_config) {
-                        Ok(client) => Arc::new(client),
-                        Err(e) => {
-                            log_error!("Failed to create HTTP client: {}", e);
-                            return;
-                        }
+                // 等到计划时刻再发送请求A（准备本周期的耗时已包含在间隔内）
+                let remaining_delay = a_scheduled.saturating_duration_since(Instant::now());
+                if !remaining_delay.is_zero() {
+                    log_trace!(
+                        "⏳ Waiting {}ms to ensure proper A request spacing",
+                        remaining_delay.as_millis()
+                    );
+                    if stopping_clone
+                        .run_until_cancelled(sleep_until(a_scheduled))
+                        .await
+                        .is_none()
+                    {
+                        continue;
                     }
+                }
+                let a_sent = Instant::now();
+                if !warming_up {
+                    let mut stats = shared_clone.stats.lock().await;
+                    if let Some(last_a_sent) = last_a_sent {
+                        stats.a_spacing.intervals.push(a_sent - last_a_sent);
+                    }
+                    stats.a_spacing.lateness.push(a_sent - a_scheduled);
+                }
+                // 落后超过一个间隔（如周期耗时长于间隔）时从当前时刻重新计时，不集中补发
+                last_a_scheduled = if a_sent - a_scheduled > required_delay {
+                    a_sent
+                } else {
+                    a_scheduled
                 };
+                last_a_sent = Some(a_sent);
 
                 // 使用共享HttpClient发送请求A（认证复用）
-                let a_handle = {
-                    let http_client_clone = Arc::clone(&http_client);
-                    let config_a_clone = config_a.clone();
-                    let stats_a_clone = Arc::clone(&stats_a);
-
-                    tokio::spawn(async move {
-                        Self::send_request_with_shared_client(
-                            config_a_clone,
-                            http_client_clone,
-                            "A".to_string(),
-                            stats_a_clone,
+                let mut a_handles: Vec<_> = prepared
+                    .iter()
+                    .flat_map(|(context, http_client, configs_a, _, _, _)| {
+                        configs_a
+                            .iter()
+                            .map(move |config_a| (context, http_client, config_a))
+                    })
+                    .map(|(context, http_client, config_a)| {
+                        let span = Self::request_span(&cycle_span, "A", config_a, context);
+                        tokio::spawn(
+                            logger::with_correlation_id(
+                                context.correlation_id.clone(),
+                                rng::with_seed(
+                                    rng::child_seed(),
+                                    Self::send_request_with_shared_client(
+                                        config_a.clone(),
+                                        "A".to_string(),
+                                        Arc::clone(context),
+                                        Arc::clone(http_client),
+                                        Arc::clone(&shared_clone),
+                                    ),
+                                ),
+                            )
+                            .instrument(span),
                         )
-                        .await;
                     })
+                    .collect();
+
+                // 分支或重复步骤：每个目标等自己的请求A完成后再继续（屏障模式下不生效）
+                let following = barrier.is_none()
+                    && (cycle_config
+                        .branches
+                        .as_ref()
+                        .is_some_and(|branches| !branches.is_empty())
+                        || cycle_config.repeat.is_some());
+
+                // 请求A的post_delay或请求B的pre_delay取代全局A→B延迟：B等A完成后按步骤延迟发送
+                let step_timed = cycle_config.request_a.post_delay.is_some()
+                    || cycle_config.request_b.pre_delay.is_some();
+
+                // 发送请求B前等待（中断时不再等待，尽快发送B以删除A创建的任务；屏障模式下不等待；
+                // 分支或重复步骤模式下在各目标的流程中等待）
+                let b_delay = if step_timed {
+                    Duration::ZERO
+                } else {
+                    DelaySampler::sample(
+                        settings.delay_between_a_and_b_ms,
+                        config_clone.delay_between_a_and_b_distribution.as_ref(),
+                    )
                 };
+                let b_not_before = Instant::now() + b_delay;
+                if barrier.is_none() && !following {
+                    stopping_clone.run_until_cancelled(sleep(b_delay)).await;
+                }
 
-                // 发送请求B前等待
-                sleep(Duration::from_millis(config_clone.delay_between_a_and_b_ms)).await;
+                // 请求B引用请求A捕获的变量、使用步骤延迟、需先校验任务已创建或为批量删除时，
+                // 需等A完成后再发送（屏障模式下校验会拒绝前者）
+                if barrier.is_none()
+                    && !following
+                    && (step_timed
+                        || cycle_config.batch.is_some()
+                        || shared_clone
+                            .task_verifier
+                            .as_ref()
+                            .is_some_and(TaskVerifier::verifies_create)
+                        || Capturer::names(&cycle_config.request_a)
+                            .any(|name| Capturer::references(&cycle_config.request_b, name)))
+                {
+                    let mut a_statuses = Vec::with_capacity(a_handles.len());
+                    for handle in a_handles.drain(..) {
+                        a_statuses.push(handle.await.ok().flatten());
+                    }
+
+                    // 批量模式：请求B只删除创建成功的任务，没有任务创建成功的目标不发送请求B
+                    let mut a_statuses = a_statuses.into_iter();
+                    let mut batches = batches.drain(..);
+                    prepared.retain_mut(|(_, _, configs_a, config_b, _, _)| {
+                        let statuses: Vec<_> = a_statuses.by_ref().take(configs_a.len()).collect();
+                        let Some(batch) = batches.next().flatten() else {
+                            return true;
+                        };
+                        let created = |copy: usize| {
+                            statuses[copy]
+                                .and_then(|status| StatusCode::from_u16(status).ok())
+                                .is_some_and(|status| {
+                                    StatusExpectation::is_success(&configs_a[copy], status)
+                                })
+                        };
+                        match batch.request_b(created) {
+                            Some(request_b) => {
+                                *config_b = request_b;
+                                true
+                            }
+                            None => {
+                                log_warn!(
+                                    "No task created in cycle {}, skipping request B",
+                                    request_count
+                                );
+                                false
+                            }
+                        }
+                    });
+                }
 
                 // 使用共享HttpClient发送请求B（认证复用）
-                let b_handle = {
-                    let http_client_clone = Arc::clone(&http_client);
-                    let config_b_clone = config_b.clone();
-                    let stats_b_clone = Arc::clone(&stats_b);
-
-                    tokio::spawn(async move {
-                        Self::send_request_with_shared_client(
-                            config_b_clone,
-                            http_client_clone,
-                            "B".to_string(),
-                            stats_b_clone,
+                let b_handles: Vec<_> = if following {
+                    let mut target_a_handles = a_handles.drain(..);
+                    prepared
+                        .into_iter()
+                        .map(
+                            |(context, http_client, configs_a, config_b, branches, repeat)| {
+                                let span =
+                                    Self::request_span(&cycle_span, "B", &config_b, &context);
+                                let follow_up = FollowUp {
+                                    a_handles: target_a_handles
+                                        .by_ref()
+                                        .take(configs_a.len())
+                                        .collect(),
+                                    configs_a,
+                                    config_b,
+                                    repeat,
+                                    branches,
+                                    b_not_before,
+                                    stopping: stopping_clone.clone(),
+                                };
+                                tokio::spawn(
+                                    logger::with_correlation_id(
+                                        context.correlation_id.clone(),
+                                        rng::with_seed(
+                                            rng::child_seed(),
+                                            Self::follow_up(
+                                                follow_up,
+                                                context,
+                                                http_client,
+                                                Arc::clone(&shared_clone),
+                                            ),
+                                        ),
+                                    )
+                                    .instrument(span),
+                                )
+                            },
                         )
-                        .await;
-                    })
+                        .collect()
+                } else {
+                    prepared
+                        .iter()
+                        .map(|(context, http_client, _, config_b, _, _)| {
+                            let span = Self::request_span(&cycle_span, "B", config_b, context);
+                            tokio::spawn(
+                                logger::with_correlation_id(
+                                    context.correlation_id.clone(),
+                                    rng::with_seed(
+                                        rng::child_seed(),
+                                        Self::send_request_with_shared_client(
+                                            config_b.clone(),
+                                            "B".to_string(),
+                                            Arc::clone(context),
+                                            Arc::clone(http_client),
+                                            Arc::clone(&shared_clone),
+                                        ),
+                                    ),
+                                )
+                                .instrument(span),
+                            )
+                        })
+                        .collect()
                 };
 
-                // 等待两个请求完成
-                let _ = tokio::try_join!(a_handle, b_handle);
+                // 等待所有请求完成
+                for handle in a_handles.into_iter().chain(b_handles) {
+                    let _ = handle.await;
+                }
+
+                // 将本周期的结果推送回队列
+                if let (Some(queue), Some(job)) = (&queue_clone, &job)
+                    && let Err(e) = queue.publish(request_count, job).await
+                {
+                    log_warn!("Failed to publish job result: {:#}", e);
+                }
             }
-        });
+        }));
 
         // 等待请求任务完成
         log_info!("🚀 Concurrent HTTP requests started!");
@@ -212,13 +2192,130 @@ impl RequestHandler {
         log_trace!("  ✅ Precise delay control");
         log_info!("Press Ctrl+C to stop...");
 
-        match request_task.await {
-            Ok(_) => log_info!("\n✅ All request cycles completed!"),
-            Err(e) => log_error!("\n❌ Request task failed: {}", e),
+        // 调用方触发shutdown时中断运行（信号由调用方处理，如命令行在Ctrl+C/SIGTERM时触发）
+        let shutdown = options.shutdown;
+        let interrupted = async {
+            match &shutdown {
+                Some(shutdown) => shutdown.cancelled().await,
+                None => std::future::pending::<()>().await,
+            }
+        };
+        let joined = tokio::select! {
+            result = &mut request_task => result,
+            _ = interrupted => {
+                // 不再开始新周期，给进行中的请求留出宽限期，超时后取消
+                let grace = Duration::from_millis(config.shutdown_grace_ms.unwrap_or(5000));
+                log_warn!(
+                    "\n🛑 Interrupted, waiting up to {}ms for in-flight requests",
+                    grace.as_millis()
+                );
+                stopping.cancel();
+                let finished = match tokio::time::timeout(grace, &mut request_task).await {
+                    Ok(result) => Some(result),
+                    Err(_) => {
+                        log_warn!("⏱️  Grace period expired, cancelling in-flight requests");
+                        shared.cancellation.cancel();
+                        tokio::time::timeout(Duration::from_secs(1), &mut request_task)
+                            .await
+                            .ok()
+                    }
+                };
+                match finished {
+                    Some(Ok((_, measure_start))) => {
+                        Ok((Some("interrupted".to_string()), measure_start))
+                    }
+                    _ => {
+                        request_task.abort();
+                        Ok((Some("interrupted".to_string()), run_start))
+                    }
+                }
+            }
+        };
+
+        // 主循环结束（含中断后的宽限期）后停止心跳、设备对时和自身资源监测
+        heartbeat_stop.cancel();
+        for task in heartbeat_task
+            .into_iter()
+            .chain(clock_refresh_task)
+            .chain(self_monitor_task)
+        {
+            let _ = task.await;
+        }
+
+        let (stop_reason, measure_start) = match joined {
+            Ok(result) => {
+                log_info!("\n✅ All request cycles completed!");
+                result
+            }
+            Err(e) => {
+                log_error!("\n❌ Request task failed: {}", e);
+                (Some(format!("request task failed: {}", e)), run_start)
+            }
+        };
+
+        // 推送剩余的指标
+        if let Some(flusher) = metrics_flusher {
+            flusher.abort();
+        }
+        if let Some(exporter) = &metrics_exporter {
+            exporter.flush().await;
         }
 
         // 返回最终统计信息
-        let stats_guard = stats.lock().await;
-        stats_guard.clone()
+        let mut stats_guard = stats.lock().await;
+        stats_guard.elapsed = measure_start.elapsed();
+        stats_guard.warmup_requests = warmup_stats.lock().await.total_requests;
+        stats_guard.stop_reason = stop_reason;
+        metadata.finish();
+        stats_guard.metadata = Some(metadata);
+        if let Some(detector) = &drift_detector {
+            stats_guard.drift_events = detector.drift_events();
+        }
+        if let Some(tracker) = &slo_tracker {
+            stats_guard.slo = tracker.reports();
+        }
+        if let Some(pacer) = &pacer {
+            stats_guard.pacing = Some(pacer.report());
+        }
+        if let Some(breaker) = &circuit_breaker {
+            stats_guard.circuit_transitions = breaker.transitions();
+        }
+        if let Some(heartbeat) = &heartbeat {
+            stats_guard.heartbeat = Some(heartbeat.stats());
+        }
+        if let Some(monitor) = &self_monitor {
+            stats_guard.self_monitor = Some(monitor.report().await);
+        }
+        if let Some(injector) = &shared.fault_injector {
+            stats_guard.injected_faults = injector.injected();
+        }
+        if let Some(verifier) = &shared.task_verifier {
+            stats_guard.task_verification_failures = verifier.failures();
+        }
+        if let Some(precheck) = &shared.rtsp_precheck {
+            stats_guard.rtsp_precheck_failures = precheck.failures();
+        }
+        stats_guard.captured_variables = shared.captures.snapshot();
+        drop(stats_guard);
+
+        // 删除预置的对象
+        if let Some(provisioner) = &mut provisioner {
+            provisioner.teardown().await;
+            stats.lock().await.provisioning = Some(provisioner.report());
+        }
+
+        // 清理本次运行遗留在设备上的任务
+        if let Some(sweeper) = &task_sweeper
+            && sweep_on_shutdown
+        {
+            let report = TaskSweeper::sweep(sweeper.ledger(), &config).await;
+            stats.lock().await.leftover_tasks_deleted = report.deleted;
+        }
+        let stats = stats.lock().await.clone();
+
+        if let Some(notifier) = &notifier {
+            notifier.notify_finished(&stats).await;
+        }
+        stats
     }
 }
@@ -0,0 +1,422 @@
+use anyhow::{Context, Result};
+use rusqlite::{Connection, params};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::time::Duration;
+use tokio::sync::{mpsc, oneshot};
+
+use crate::observer::RequestObserver;
+use crate::run_metadata::RunMetadata;
+use crate::stats::{LabelStats, RequestResult, RequestStats};
+
+// Import logger macros from crate root
+use crate::log_warn;
+
+const SCHEMA: &str = "
+CREATE TABLE IF NOT EXISTS runs (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    started_at TEXT NOT NULL DEFAULT (datetime('now')),
+    finished_at TEXT,
+    description TEXT,
+    total_requests INTEGER,
+    successful_requests INTEGER,
+    failed_requests INTEGER,
+    elapsed_ms REAL,
+    stop_reason TEXT,
+    metadata TEXT
+);
+CREATE TABLE IF NOT EXISTS cycles (
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    cycle INTEGER NOT NULL,
+    started_at TEXT NOT NULL DEFAULT (strftime('%Y-%m-%d %H:%M:%f', 'now')),
+    PRIMARY KEY (run_id, cycle)
+);
+CREATE TABLE IF NOT EXISTS requests (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    run_id INTEGER NOT NULL REFERENCES runs(id),
+    cycle INTEGER NOT NULL,
+    label TEXT NOT NULL,
+    method TEXT NOT NULL,
+    url TEXT NOT NULL,
+    target TEXT,
+    status INTEGER,
+    success INTEGER NOT NULL,
+    elapsed_ms REAL NOT NULL,
+    bytes_received INTEGER,
+    error TEXT,
+    correlation_id TEXT
+);
+CREATE INDEX IF NOT EXISTS requests_run ON requests(run_id, label, url);
+";
+
+/// 后台写入线程每个事务最多写入的记录数
+const WRITE_BATCH: usize = 500;
+
+/// 单次运行的汇总
+#[derive(Debug, Clone)]
+pub struct RunSummary {
+    pub id: i64,
+    pub started_at: String,
+    pub description: Option<String>,
+    pub stats: LabelStats,
+    pub elapsed: Option<Duration>,
+    pub stop_reason: Option<String>,
+    pub metadata: Option<RunMetadata>, // 运行元数据（旧版本记录的运行为None）
+}
+
+/// SQLite结果库：按 runs / cycles / requests 三张表持久化每个请求的结果。
+/// 运行期间的写入经通道交给后台的阻塞写入任务，按批在事务中提交，不阻塞请求任务
+pub struct ResultStore {
+    writes: mpsc::UnboundedSender<Write>,
+    run_id: i64,
+}
+
+/// 交给后台写入任务的记录
+enum Write {
+    Cycle(usize),
+    Request(StoredRequest),
+    Finish(FinishedRun, oneshot::Sender<Result<(), String>>),
+}
+
+struct StoredRequest {
+    cycle: usize,
+    label: String,
+    method: String,
+    url: String,
+    target: Option<String>,
+    status: Option<u16>,
+    success: bool,
+    elapsed_ms: f64,
+    bytes_received: Option<i64>,
+    error: Option<String>,
+    correlation_id: Option<String>,
+}
+
+struct FinishedRun {
+    total_requests: i64,
+    successful_requests: i64,
+    failed_requests: i64,
+    elapsed_ms: f64,
+    stop_reason: Option<String>,
+    metadata: Option<String>,
+}
+
+impl ResultStore {
+    /// 打开（或创建）结果库并登记一次新的运行，需要在tokio运行时中调用
+    pub fn start_run(path: impl AsRef<Path>, description: Option<&str>) -> Result<Self> {
+        let connection = Self::open(path)?;
+        connection.execute(
+            "INSERT INTO runs (description) VALUES (?1)",
+            params![description],
+        )?;
+        let run_id = connection.last_insert_rowid();
+        let (writes, receiver) = mpsc::unbounded_channel();
+        tokio::task::spawn_blocking(move || Self::write_loop(connection, run_id, receiver));
+        Ok(Self { writes, run_id })
+    }
+
+    /// 本次运行的ID
+    pub fn run_id(&self) -> i64 {
+        self.run_id
+    }
+
+    /// 写入尚未提交的记录，并记录运行结束时间和最终统计
+    pub async fn finish_run(&self, stats: &RequestStats) -> Result<()> {
+        let finished = FinishedRun {
+            total_requests: stats.total_requests as i64,
+            successful_requests: stats.successful_requests as i64,
+            failed_requests: stats.failed_requests as i64,
+            elapsed_ms: stats.elapsed.as_secs_f64() * 1000.0,
+            stop_reason: stats.stop_reason.clone(),
+            metadata: stats
+                .metadata
+                .as_ref()
+                .and_then(|metadata| serde_json::to_string(metadata).ok()),
+        };
+        let (done, result) = oneshot::channel();
+        self.writes
+            .send(Write::Finish(finished, done))
+            .ok()
+            .context("Results database writer stopped")?;
+        result
+            .await
+            .context("Results database writer stopped")?
+            .map_err(|e| anyhow::anyhow!("Failed to record the finished run: {}", e))
+    }
+
+    /// 后台写入循环：取出通道中已排队的记录（每批最多 [`WRITE_BATCH`] 条），在一个事务中写入
+    fn write_loop(
+        mut connection: Connection,
+        run_id: i64,
+        mut receiver: mpsc::UnboundedReceiver<Write>,
+    ) {
+        let mut batch = Vec::with_capacity(WRITE_BATCH);
+        while let Some(write) = receiver.blocking_recv() {
+            batch.push(write);
+            while batch.len() < WRITE_BATCH {
+                match receiver.try_recv() {
+                    Ok(write) => batch.push(write),
+                    Err(_) => break,
+                }
+            }
+            let mut finished = Vec::new();
+            let committed = Self::write_batch(&mut connection, run_id, &mut batch, &mut finished)
+                .map_err(|e| e.to_string());
+            if let Err(e) = &committed {
+                log_warn!("Failed to store results: {}", e);
+            }
+            for done in finished {
+                let _ = done.send(committed.clone());
+            }
+        }
+    }
+
+    fn write_batch(
+        connection: &mut Connection,
+        run_id: i64,
+        batch: &mut Vec<Write>,
+        finished: &mut Vec<oneshot::Sender<Result<(), String>>>,
+    ) -> rusqlite::Result<()> {
+        let transaction = connection.transaction()?;
+        for write in batch.drain(..) {
+            match write {
+                Write::Cycle(cycle) => {
+                    transaction.execute(
+                        "INSERT OR IGNORE INTO cycles (run_id, cycle) VALUES (?1, ?2)",
+                        params![run_id, cycle as i64],
+                    )?;
+                }
+                Write::Request(request) => {
+                    transaction.execute(
+                        "INSERT INTO requests (run_id, cycle, label, method, url, target, status,
+                            success, elapsed_ms, bytes_received, error, correlation_id)
+                         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12)",
+                        params![
+                            run_id,
+                            request.cycle as i64,
+                            request.label,
+                            request.method,
+                            request.url,
+                            request.target,
+                            request.status,
+                            request.success,
+                            request.elapsed_ms,
+                            request.bytes_received,
+                            request.error,
+                            request.correlation_id
+                        ],
+                    )?;
+                }
+                Write::Finish(run, done) => {
+                    transaction.execute(
+                        "UPDATE runs SET finished_at = datetime('now'), total_requests = ?1,
+                            successful_requests = ?2, failed_requests = ?3, elapsed_ms = ?4,
+                            stop_reason = ?5, metadata = ?6
+                         WHERE id = ?7",
+                        params![
+                            run.total_requests,
+                            run.successful_requests,
+                            run.failed_requests,
+                            run.elapsed_ms,
+                            run.stop_reason,
+                            run.metadata,
+                            run_id
+                        ],
+                    )?;
+                    finished.push(done);
+                }
+            }
+        }
+        transaction.commit()
+    }
+
+    /// 所有运行的汇总，按ID升序
+    pub fn run_summaries(path: impl AsRef<Path>) -> Result<Vec<RunSummary>> {
+        let connection = Self::open(path)?;
+        let mut statement = connection.prepare(
+            "SELECT id, started_at, description, elapsed_ms, stop_reason, metadata FROM runs ORDER BY id",
+        )?;
+        let runs = statement
+            .query_map([], |row| {
+                Ok((
+                    row.get::<_, i64>(0)?,
+                    row.get::<_, String>(1)?,
+                    row.get::<_, Option<String>>(2)?,
+                    row.get::<_, Option<f64>>(3)?,
+                    row.get::<_, Option<String>>(4)?,
+                    row.get::<_, Option<String>>(5)?,
+                ))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+
+        let mut summaries = Vec::with_capacity(runs.len());
+        for (id, started_at, description, elapsed_ms, stop_reason, metadata) in runs {
+            let mut stats = LabelStats::default();
+            for label_stats in Self::query_label_stats(&connection, id)?.into_values() {
+                stats.total_requests += label_stats.total_requests;
+                stats.successful_requests += label_stats.successful_requests;
+                stats.failed_requests += label_stats.failed_requests;
+                stats.latencies.merge(&label_stats.latencies);
+                stats.bytes_received += label_stats.bytes_received;
+            }
+            summaries.push(RunSummary {
+                id,
+                started_at,
+                description,
+                stats,
+                elapsed: elapsed_ms.map(|ms| Duration::from_secs_f64(ms / 1000.0)),
+                stop_reason,
+                metadata: metadata.and_then(|metadata| serde_json::from_str(&metadata).ok()),
+            });
+        }
+        Ok(summaries)
+    }
+
+    /// 指定运行按 (请求标签, URL) 的统计
+    pub fn label_stats(
+        path: impl AsRef<Path>,
+        run_id: i64,
+    ) -> Result<BTreeMap<(String, String), LabelStats>> {
+        let connection = Self::open(path)?;
+        Self::query_label_stats(&connection, run_id)
+    }
+
+    /// 最近一次运行的ID，结果库为空时返回None
+    pub fn latest_run_id(path: impl AsRef<Path>) -> Result<Option<i64>> {
+        let connection = Self::open(path)?;
+        Ok(connection.query_row("SELECT MAX(id) FROM runs", [], |row| row.get(0))?)
+    }
+
+    /// 按保留策略删除旧的运行及其周期和请求记录，返回删除的运行数。
+    /// `description` 指定时只清理该描述（如守护模式的计划名）的运行；
+    /// `keep_runs` 保留最近的N次运行，`max_age` 删除开始时间早于该时长之前的运行
+    pub fn prune(
+        path: impl AsRef<Path>,
+        description: Option<&str>,
+        keep_runs: Option<usize>,
+        max_age: Option<Duration>,
+    ) -> Result<usize> {
+        let connection = Self::open(path)?;
+        let mut statement = connection.prepare(
+            "SELECT id, started_at < datetime('now', ?2) FROM runs
+             WHERE ?1 IS NULL OR description = ?1 ORDER BY id DESC",
+        )?;
+        let cutoff = format!("-{} seconds", max_age.map_or(0, |age| age.as_secs()));
+        let runs = statement
+            .query_map(params![description, cutoff], |row| {
+                Ok((row.get::<_, i64>(0)?, row.get::<_, bool>(1)?))
+            })?
+            .collect::<rusqlite::Result<Vec<_>>>()?;
+        drop(statement);
+        let expired: Vec<i64> = runs
+            .into_iter()
+            .enumerate()
+            .filter(|&(index, (_, old))| {
+                keep_runs.is_some_and(|keep_runs| index >= keep_runs) || (max_age.is_some() && old)
+            })
+            .map(|(_, (id, _))| id)
+            .collect();
+        if expired.is_empty() {
+            return Ok(0);
+        }
+        let transaction = connection.unchecked_transaction()?;
+        for id in &expired {
+            transaction.execute("DELETE FROM requests WHERE run_id = ?1", params![id])?;
+            transaction.execute("DELETE FROM cycles WHERE run_id = ?1", params![id])?;
+            transaction.execute("DELETE FROM runs WHERE id = ?1", params![id])?;
+        }
+        transaction.commit()?;
+        // 删除的记录不会自动归还磁盘空间
+        connection.execute_batch("VACUUM")?;
+        Ok(expired.len())
+    }
+
+    fn open(path: impl AsRef<Path>) -> Result<Connection> {
+        let path = path.as_ref();
+        let connection = Connection::open(path)
+            .with_context(|| format!("Failed to open results database {}", path.display()))?;
+        connection
+            .execute_batch(SCHEMA)
+            .with_context(|| format!("Failed to initialize {}", path.display()))?;
+        Self::migrate(&connection)
+            .with_context(|| format!("Failed to migrate {}", path.display()))?;
+        Ok(connection)
+    }
+
+    /// 为旧版本创建的结果库补充新增的列
+    fn migrate(connection: &Connection) -> Result<()> {
+        let has_correlation_id: bool = connection.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('requests') WHERE name = 'correlation_id'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_correlation_id {
+            connection.execute("ALTER TABLE requests ADD COLUMN correlation_id TEXT", [])?;
+        }
+        let has_metadata: bool = connection.query_row(
+            "SELECT COUNT(*) > 0 FROM pragma_table_info('runs') WHERE name = 'metadata'",
+            [],
+            |row| row.get(0),
+        )?;
+        if !has_metadata {
+            connection.execute("ALTER TABLE runs ADD COLUMN metadata TEXT", [])?;
+        }
+        Ok(())
+    }
+
+    fn query_label_stats(
+        connection: &Connection,
+        run_id: i64,
+    ) -> Result<BTreeMap<(String, String), LabelStats>> {
+        let mut statement = connection.prepare(
+            "SELECT label, url, success, elapsed_ms, bytes_received, error
+             FROM requests WHERE run_id = ?1 ORDER BY id",
+        )?;
+        let mut rows = statement.query(params![run_id])?;
+        let mut per_label: BTreeMap<(String, String), LabelStats> = Default::default();
+        while let Some(row) = rows.next()? {
+            let label_stats = per_label.entry((row.get(0)?, row.get(1)?)).or_default();
+            label_stats.total_requests += 1;
+            if row.get::<_, bool>(2)? {
+                label_stats.successful_requests += 1;
+            } else {
+                label_stats.failed_requests += 1;
+                label_stats.last_error = row.get(5)?;
+            }
+            label_stats
+                .latencies
+                .push(Duration::from_secs_f64(row.get::<_, f64>(3)? / 1000.0));
+            label_stats.bytes_received += row.get::<_, Option<i64>>(4)?.unwrap_or(0) as u64;
+        }
+        Ok(per_label)
+    }
+}
+
+impl RequestObserver for ResultStore {
+    fn on_cycle_start(&self, cycle: usize) {
+        let _ = self.writes.send(Write::Cycle(cycle));
+    }
+
+    fn on_result(&self, result: &RequestResult) {
+        // 预热请求不计入结果
+        if result.warmup {
+            return;
+        }
+        let _ = self.writes.send(Write::Request(StoredRequest {
+            cycle: result.cycle,
+            label: result.label.clone(),
+            method: result.method.clone(),
+            url: result.url.clone(),
+            target: result.target.clone(),
+            status: result.status,
+            success: result.success,
+            elapsed_ms: result.elapsed.as_secs_f64() * 1000.0,
+            bytes_received: result
+                .response
+                .as_ref()
+                .map(|response| response.body_len() as i64),
+            error: result.error.clone(),
+            correlation_id: result.correlation_id.clone(),
+        }));
+    }
+}
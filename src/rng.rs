@@ -0,0 +1,35 @@
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use std::cell::RefCell;
+use std::future::Future;
+
+tokio::task_local! {
+    static RNG: RefCell<StdRng>;
+}
+
+/// 本次运行的随机数种子：配置（或 `--seed`）指定时使用该值，否则随机选取，
+/// 两种情况下都输出到日志和报告，失败的运行可以用同一种子重现
+pub fn run_seed(seed: Option<u64>) -> u64 {
+    seed.unwrap_or_else(|| rand::thread_rng().r#gen())
+}
+
+/// 在以 `seed` 初始化的随机数生成器下运行future，期间随机字段、延迟抖动、随机轮换和故障注入都从中取值
+pub async fn with_seed<F: Future>(seed: u64, future: F) -> F::Output {
+    RNG.scope(RefCell::new(StdRng::seed_from_u64(seed)), future)
+        .await
+}
+
+/// 从当前任务的生成器中派生子任务的种子：子任务按创建顺序取得种子，
+/// 与各任务的实际执行顺序无关
+pub fn child_seed() -> u64 {
+    with(|rng| rng.next_u64())
+}
+
+/// 使用当前任务的生成器；不在 `with_seed` 范围内（如交互模式、渲染预览）时使用线程随机数生成器
+pub fn with<R>(f: impl FnOnce(&mut dyn RngCore) -> R) -> R {
+    let mut f = Some(f);
+    match RNG.try_with(|rng| (f.take().unwrap())(&mut *rng.borrow_mut())) {
+        Ok(value) => value,
+        Err(_) => (f.take().unwrap())(&mut rand::thread_rng()),
+    }
+}
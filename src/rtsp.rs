@@ -0,0 +1,319 @@
+use anyhow::{Context, Result, anyhow};
+use base64::prelude::*;
+use digest_auth::{AuthContext, HttpMethod, WwwAuthenticateHeader};
+use reqwest::Url;
+use std::borrow::Cow;
+use std::collections::{BTreeSet, HashMap};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::TcpStream;
+use tokio::task::JoinSet;
+
+use crate::config::RtspPrecheckConfig;
+
+// Import logger macros from crate root
+use crate::{log_debug, log_error, log_warn};
+
+const DEFAULT_RTSP_PORT: u16 = 554;
+const MAX_RESPONSE_HEADER: usize = 16 * 1024;
+
+/// RTSP响应的状态和头部
+#[derive(Debug, Clone)]
+struct RtspResponse {
+    status: u16,
+    reason: String,
+    www_authenticate: Option<String>,
+}
+
+/// 对单个RTSP流发送 OPTIONS/DESCRIBE 请求，检查摄像机是否可达
+pub struct RtspProbe;
+
+impl RtspProbe {
+    /// 检查流地址，2xx视为可达；URL中带凭据时自动处理401（Basic/Digest）
+    pub async fn check(url: &str, method: &str, timeout: Duration) -> Result<()> {
+        tokio::time::timeout(timeout, Self::probe(url, method))
+            .await
+            .map_err(|_| anyhow!("timed out after {}ms", timeout.as_millis()))?
+    }
+
+    async fn probe(url: &str, method: &str) -> Result<()> {
+        let parsed = Url::parse(url).with_context(|| format!("Invalid RTSP URL {}", url))?;
+        let host = parsed
+            .host_str()
+            .ok_or_else(|| anyhow!("RTSP URL has no host"))?
+            .to_string();
+        let port = parsed.port().unwrap_or(DEFAULT_RTSP_PORT);
+
+        // 请求行中的URI不带凭据
+        let mut request_uri = parsed.clone();
+        let _ = request_uri.set_username("");
+        let _ = request_uri.set_password(None);
+        let request_uri = request_uri.to_string();
+
+        let response = Self::send(&host, port, method, &request_uri, 1, None).await?;
+        let response = match (response.status, &response.www_authenticate) {
+            (401, Some(challenge)) if !parsed.username().is_empty() => {
+                let authorization = Self::authorization(
+                    challenge,
+                    parsed.username(),
+                    parsed.password().unwrap_or_default(),
+                    method,
+                    &request_uri,
+                )?;
+                Self::send(&host, port, method, &request_uri, 2, Some(&authorization)).await?
+            }
+            _ => response,
+        };
+
+        if (200..300).contains(&response.status) {
+            Ok(())
+        } else {
+            Err(anyhow!("RTSP {} {}", response.status, response.reason))
+        }
+    }
+
+    /// 建立连接并发送一个请求，只读取状态行和头部
+    async fn send(
+        host: &str,
+        port: u16,
+        method: &str,
+        uri: &str,
+        cseq: u32,
+        authorization: Option<&str>,
+    ) -> Result<RtspResponse> {
+        let mut stream = TcpStream::connect((host, port))
+            .await
+            .with_context(|| format!("Failed to connect to {}:{}", host, port))?;
+
+        let mut request = format!(
+            "{} {} RTSP/1.0\r\nCSeq: {}\r\nUser-Agent: RemoteTask-rs\r\n",
+            method, uri, cseq
+        );
+        if method == "DESCRIBE" {
+            request.push_str("Accept: application/sdp\r\n");
+        }
+        if let Some(authorization) = authorization {
+            request.push_str(&format!("Authorization: {}\r\n", authorization));
+        }
+        request.push_str("\r\n");
+        stream.write_all(request.as_bytes()).await?;
+
+        let mut buffer = Vec::with_capacity(1024);
+        let mut chunk = [0u8; 1024];
+        while !buffer.windows(4).any(|window| window == b"\r\n\r\n") {
+            let read = stream.read(&mut chunk).await?;
+            if read == 0 {
+                break;
+            }
+            buffer.extend_from_slice(&chunk[..read]);
+            if buffer.len() > MAX_RESPONSE_HEADER {
+                return Err(anyhow!("RTSP response header too large"));
+            }
+        }
+
+        let text = String::from_utf8_lossy(&buffer);
+        let mut lines = text.lines();
+        let status_line = lines
+            .next()
+            .filter(|line| line.starts_with("RTSP/"))
+            .ok_or_else(|| anyhow!("Not an RTSP response"))?;
+        let mut parts = status_line.splitn(3, ' ');
+        parts.next();
+        let status = parts
+            .next()
+            .and_then(|status| status.parse().ok())
+            .ok_or_else(|| anyhow!("Invalid RTSP status line: {}", status_line))?;
+        let reason = parts.next().unwrap_or_default().to_string();
+
+        // 摄像机可能同时返回Digest和Basic挑战，优先使用Digest
+        let challenges: Vec<&str> = lines
+            .filter_map(|line| line.split_once(':'))
+            .filter(|(name, _)| name.trim().eq_ignore_ascii_case("WWW-Authenticate"))
+            .map(|(_, value)| value.trim())
+            .collect();
+        let www_authenticate = challenges
+            .iter()
+            .find(|challenge| challenge.starts_with("Digest"))
+            .or(challenges.first())
+            .map(|challenge| challenge.to_string());
+
+        Ok(RtspResponse {
+            status,
+            reason,
+            www_authenticate,
+        })
+    }
+
+    /// 按挑战生成Authorization头
+    fn authorization(
+        challenge: &str,
+        username: &str,
+        password: &str,
+        method: &str,
+        uri: &str,
+    ) -> Result<String> {
+        if challenge.starts_with("Basic") {
+            let credentials = BASE64_STANDARD.encode(format!("{}:{}", username, password));
+            return Ok(format!("Basic {}", credentials));
+        }
+        let mut header = WwwAuthenticateHeader::parse(challenge)
+            .map_err(|e| anyhow!("Invalid RTSP digest challenge: {}", e))?;
+        let context = AuthContext::new_with_method(
+            username,
+            password,
+            uri,
+            None::<Vec<u8>>,
+            HttpMethod(Cow::Owned(method.to_string())),
+        );
+        Ok(header
+            .respond(&context)
+            .map_err(|e| anyhow!("RTSP digest response failed: {}", e))?
+            .to_string())
+    }
+}
+
+/// 周期开始前的RTSP流可达性预检
+///
+/// 每个流的检查结果缓存 `interval_ms`：首次出现的流在周期开始前检查，之后各周期沿用缓存的结果，
+/// 结果过期时在后台重新检查，不阻塞周期调度
+pub struct RtspPrecheck {
+    urls: Option<Vec<String>>,
+    method: String,
+    timeout: Duration,
+    interval: Duration,
+    skip_cycle: bool,
+    streams: Arc<Mutex<HashMap<String, StreamStatus>>>,
+    failures: AtomicUsize,
+}
+
+/// 单个流最近一次检查的结果
+struct StreamStatus {
+    checked: Instant,
+    failure: Option<String>, // 不可达的原因，可达时为None
+    refreshing: bool,        // 是否正在后台重新检查
+}
+
+impl RtspPrecheck {
+    pub fn new(config: &RtspPrecheckConfig) -> Self {
+        Self {
+            urls: config.urls.clone(),
+            method: config.method.as_deref().unwrap_or("OPTIONS").to_uppercase(),
+            timeout: Duration::from_millis(config.timeout_ms.unwrap_or(3000)),
+            interval: Duration::from_millis(config.interval_ms.unwrap_or(30000)),
+            skip_cycle: config.on_failure.as_deref() == Some("skip_cycle"),
+            streams: Arc::new(Mutex::new(HashMap::new())),
+            failures: AtomicUsize::new(0),
+        }
+    }
+
+    /// 预检失败时是否只跳过本周期（否则停止运行）
+    pub fn skips_cycle(&self) -> bool {
+        self.skip_cycle
+    }
+
+    /// 检查配置的流地址（缺省为请求body中出现的 rtsp:// 地址），返回第一个不可达的流及原因
+    pub async fn check<'a>(&self, bodies: impl IntoIterator<Item = &'a str>) -> Option<String> {
+        let urls: BTreeSet<String> = match &self.urls {
+            Some(urls) => urls.iter().cloned().collect(),
+            None => bodies.into_iter().flat_map(rtsp_urls).collect(),
+        };
+
+        // 首次出现的流并发检查；结果过期的流在后台重新检查，本周期沿用上次结果
+        let mut probes = JoinSet::new();
+        {
+            let mut streams = self.streams.lock().unwrap();
+            let probe = |url: &String| {
+                Self::probe(
+                    Arc::clone(&self.streams),
+                    url.clone(),
+                    self.method.clone(),
+                    self.timeout,
+                )
+            };
+            for url in &urls {
+                match streams.get_mut(url) {
+                    None => {
+                        probes.spawn(probe(url));
+                    }
+                    Some(status)
+                        if !status.refreshing && status.checked.elapsed() >= self.interval =>
+                    {
+                        status.refreshing = true;
+                        tokio::spawn(probe(url));
+                    }
+                    Some(_) => {}
+                }
+            }
+        }
+        probes.join_all().await;
+
+        let streams = self.streams.lock().unwrap();
+        let reason = urls
+            .iter()
+            .find_map(|url| streams.get(url).and_then(|status| status.failure.clone()))?;
+        self.failures.fetch_add(1, Ordering::Relaxed);
+        if self.skip_cycle {
+            log_warn!("📹 RTSP precheck failed, skipping cycle: {}", reason);
+        } else {
+            log_error!("📹 RTSP precheck failed: {}", reason);
+        }
+        Some(reason)
+    }
+
+    /// 检查一个流并更新缓存的结果
+    async fn probe(
+        streams: Arc<Mutex<HashMap<String, StreamStatus>>>,
+        url: String,
+        method: String,
+        timeout: Duration,
+    ) {
+        let failure = match RtspProbe::check(&url, &method, timeout).await {
+            Ok(()) => {
+                log_debug!("📹 RTSP {} {} reachable", method, redact(&url));
+                None
+            }
+            Err(e) => Some(format!("{} unreachable: {:#}", redact(&url), e)),
+        };
+        streams.lock().unwrap().insert(
+            url,
+            StreamStatus {
+                checked: Instant::now(),
+                failure,
+                refreshing: false,
+            },
+        );
+    }
+
+    /// 预检失败次数
+    pub fn failures(&self) -> usize {
+        self.failures.load(Ordering::Relaxed)
+    }
+}
+
+/// 提取文本中的所有 rtsp:// 地址
+pub fn rtsp_urls(text: &str) -> Vec<String> {
+    let mut urls = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("rtsp://") {
+        let candidate = &rest[start..];
+        let end = candidate
+            .find(|c: char| c.is_whitespace() || matches!(c, '"' | '\'' | '\\' | '<' | '>'))
+            .unwrap_or(candidate.len());
+        urls.push(candidate[..end].to_string());
+        rest = &candidate[end..];
+    }
+    urls
+}
+
+/// 日志中隐藏URL里的密码
+fn redact(url: &str) -> String {
+    match Url::parse(url) {
+        Ok(mut parsed) if parsed.password().is_some() => {
+            let _ = parsed.set_password(Some("***"));
+            parsed.to_string()
+        }
+        _ => url.to_string(),
+    }
+}
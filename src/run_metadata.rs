@@ -0,0 +1,121 @@
+use reqwest::Url;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use crate::config::RequestConfig;
+use crate::cron::format_timestamp;
+use crate::cycle_context::CycleContext;
+use crate::template;
+
+/// 运行元数据：写入每个结果文件和报告头部，数月后仍能确定结果来自哪个配置、哪个版本的工具和哪台设备
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RunMetadata {
+    pub tool_version: String,         // 工具版本（Cargo包版本）
+    pub git_describe: Option<String>, // 构建时工具源码的 `git describe`
+    pub config_hash: String,          // 生效配置（应用环境、覆盖项和凭据之后）的SHA-256
+    pub hostname: String,             // 运行本工具的主机
+    pub started_at: String,           // 开始时间（RFC 3339，UTC）
+    pub finished_at: Option<String>,  // 结束时间（运行中为None）
+    pub args: Vec<String>,            // 命令行参数
+    pub seed: Option<u64>,            // 随机数种子（`--seed` 可重现本次运行）
+    pub targets: Vec<String>, // 目标设备（targets的名称和地址，未配置targets时为请求A的主机）
+}
+
+impl RunMetadata {
+    /// 在运行开始时记录
+    pub fn new(config: &RequestConfig) -> Self {
+        Self {
+            tool_version: env!("CARGO_PKG_VERSION").to_string(),
+            git_describe: option_env!("REMOTE_TASK_GIT_DESCRIBE").map(str::to_string),
+            config_hash: Self::config_hash(config),
+            hostname: Self::hostname(),
+            started_at: Self::now(),
+            finished_at: None,
+            args: std::env::args().collect(),
+            seed: None,
+            targets: Self::targets(config),
+        }
+    }
+
+    /// 记录结束时间
+    pub fn finish(&mut self) {
+        self.finished_at = Some(Self::now());
+    }
+
+    /// 配置的SHA-256（十六进制），按规范化的JSON（对象键排序）计算，与键的书写顺序无关
+    pub fn config_hash(config: &RequestConfig) -> String {
+        let canonical = serde_json::to_value(config)
+            .map(|value| value.to_string())
+            .unwrap_or_default();
+        Sha256::digest(canonical.as_bytes())
+            .iter()
+            .map(|byte| format!("{:02x}", byte))
+            .collect()
+    }
+
+    /// 报告头部展示的 (名称, 值)
+    pub fn entries(&self) -> Vec<(&'static str, String)> {
+        let mut entries = vec![
+            (
+                "Tool version",
+                match &self.git_describe {
+                    Some(describe) => format!("{} ({})", self.tool_version, describe),
+                    None => self.tool_version.clone(),
+                },
+            ),
+            ("Config SHA-256", self.config_hash.clone()),
+            ("Host", self.hostname.clone()),
+            ("Started", self.started_at.clone()),
+        ];
+        if let Some(finished_at) = &self.finished_at {
+            entries.push(("Finished", finished_at.clone()));
+        }
+        entries.push(("Command line", self.args.join(" ")));
+        if let Some(seed) = self.seed {
+            entries.push(("Seed", seed.to_string()));
+        }
+        if !self.targets.is_empty() {
+            entries.push(("Targets", self.targets.join(", ")));
+        }
+        entries
+    }
+
+    fn now() -> String {
+        let seconds = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs() as i64;
+        format_timestamp(seconds, 0)
+    }
+
+    fn hostname() -> String {
+        std::fs::read_to_string("/proc/sys/kernel/hostname")
+            .ok()
+            .map(|hostname| hostname.trim().to_string())
+            .filter(|hostname| !hostname.is_empty())
+            .or_else(|| std::env::var("HOSTNAME").ok())
+            .or_else(|| std::env::var("COMPUTERNAME").ok())
+            .unwrap_or_else(|| "unknown".to_string())
+    }
+
+    fn targets(config: &RequestConfig) -> Vec<String> {
+        match &config.targets {
+            Some(targets) if !targets.is_empty() => targets
+                .iter()
+                .map(|target| match &target.name {
+                    Some(name) => format!("{} ({})", name, target.base_url),
+                    None => target.base_url.clone(),
+                })
+                .collect(),
+            _ => {
+                let variables = CycleContext::new(config, 0, None, Vec::new(), None).variables;
+                let url = template::render(&config.request_a.url, &variables);
+                match Url::parse(&url) {
+                    Ok(url) => vec![url.origin().ascii_serialization()],
+                    Err(_) => vec![url],
+                }
+            }
+        }
+    }
+}
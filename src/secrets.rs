@@ -0,0 +1,185 @@
+use anyhow::{Context, Result, anyhow};
+use std::collections::HashMap;
+use std::io::{IsTerminal, Read};
+
+use crate::config::{DigestAuthConfig, RequestConfig, SecretsConfig};
+
+/// 凭据后端：根据引用键解析出明文凭据
+pub trait SecretBackend {
+    /// 引用前缀，例如 `keyring` 对应 `keyring:<key>`
+    fn scheme(&self) -> &str;
+
+    /// 解析引用键对应的明文
+    fn resolve(&self, key: &str) -> Result<String>;
+}
+
+/// 环境变量后端：`env:NAME`
+pub struct EnvBackend;
+
+impl SecretBackend for EnvBackend {
+    fn scheme(&self) -> &str {
+        "env"
+    }
+
+    fn resolve(&self, key: &str) -> Result<String> {
+        std::env::var(key).map_err(|_| anyhow!("environment variable {} is not set", key))
+    }
+}
+
+/// 系统钥匙串后端：`keyring:<service>/<account>`
+pub struct KeyringBackend;
+
+impl SecretBackend for KeyringBackend {
+    fn scheme(&self) -> &str {
+        "keyring"
+    }
+
+    fn resolve(&self, key: &str) -> Result<String> {
+        let (service, account) = key
+            .split_once('/')
+            .ok_or_else(|| anyhow!("keyring reference must be <service>/<account>, got {}", key))?;
+        keyring::Entry::new(service, account)
+            .and_then(|entry| entry.get_password())
+            .map_err(|e| anyhow!("keyring lookup {} failed: {}", key, e))
+    }
+}
+
+/// age加密的凭据文件后端：`secret:<name>`
+///
+/// 文件解密后为JSON对象 `{"name": "value", ...}`，启动时一次性解密。
+pub struct AgeFileBackend {
+    secrets: HashMap<String, String>,
+}
+
+impl AgeFileBackend {
+    /// 使用身份文件解密凭据文件
+    pub fn open(config: &SecretsConfig) -> Result<Self> {
+        let identities = age::IdentityFile::from_file(config.identity.clone())
+            .with_context(|| format!("Failed to read age identity {}", config.identity))?
+            .into_identities()
+            .map_err(|e| anyhow!("Invalid age identity {}: {}", config.identity, e))?;
+
+        let encrypted = std::fs::read(&config.file)
+            .with_context(|| format!("Failed to read secrets file {}", config.file))?;
+        let decryptor = age::Decryptor::new_buffered(&encrypted[..])
+            .map_err(|e| anyhow!("Invalid secrets file {}: {}", config.file, e))?;
+
+        let mut plaintext = String::new();
+        decryptor
+            .decrypt(identities.iter().map(|identity| identity.as_ref()))
+            .map_err(|e| anyhow!("Failed to decrypt {}: {}", config.file, e))?
+            .read_to_string(&mut plaintext)
+            .with_context(|| format!("Failed to decrypt {}", config.file))?;
+
+        let secrets = serde_json::from_str(&plaintext)
+            .with_context(|| format!("Secrets file {} must contain a JSON object", config.file))?;
+        Ok(Self { secrets })
+    }
+}
+
+impl SecretBackend for AgeFileBackend {
+    fn scheme(&self) -> &str {
+        "secret"
+    }
+
+    fn resolve(&self, key: &str) -> Result<String> {
+        self.secrets
+            .get(key)
+            .cloned()
+            .ok_or_else(|| anyhow!("secret {} not found in secrets file", key))
+    }
+}
+
+/// 凭据解析器，按引用前缀分派到对应后端，无前缀的值视为字面量
+pub struct SecretResolver {
+    backends: Vec<Box<dyn SecretBackend>>,
+}
+
+impl SecretResolver {
+    /// 根据配置创建解析器（配置了 `secrets` 时会立即解密凭据文件）
+    pub fn from_config(config: &RequestConfig) -> Result<Self> {
+        let mut backends: Vec<Box<dyn SecretBackend>> =
+            vec![Box::new(EnvBackend), Box::new(KeyringBackend)];
+        if let Some(secrets) = &config.secrets {
+            backends.push(Box::new(AgeFileBackend::open(secrets)?));
+        }
+        Ok(Self { backends })
+    }
+
+    /// 解析单个凭据值
+    pub fn resolve(&self, value: &str) -> Result<String> {
+        if let Some((scheme, key)) = value.split_once(':') {
+            if let Some(backend) = self.backends.iter().find(|b| b.scheme() == scheme) {
+                return backend.resolve(key);
+            }
+            if scheme == "secret" {
+                return Err(anyhow!(
+                    "{} references a secrets file but none is configured",
+                    value
+                ));
+            }
+        }
+        Ok(value.to_string())
+    }
+
+    /// 解析配置中所有digest凭据（全局和各目标）和代理凭据
+    pub fn resolve_config(&self, config: &mut RequestConfig) -> Result<()> {
+        if let Some(auth) = config.digest_auth.as_mut() {
+            self.resolve_auth(auth).context("digest_auth")?;
+        }
+        for (index, target) in config.targets.iter_mut().flatten().enumerate() {
+            if let Some(auth) = target.digest_auth.as_mut() {
+                self.resolve_auth(auth)
+                    .with_context(|| format!("targets[{}].digest_auth", index))?;
+            }
+        }
+        if let Some(proxy) = config.proxy.as_mut() {
+            for value in [proxy.username.as_mut(), proxy.password.as_mut()]
+                .into_iter()
+                .flatten()
+            {
+                *value = self.resolve(value).context("proxy")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn resolve_auth(&self, auth: &mut DigestAuthConfig) -> Result<()> {
+        auth.username = self.resolve(&auth.username)?;
+        auth.password = self.resolve(&auth.password)?;
+        Ok(())
+    }
+}
+
+/// 为配置了用户名但未提供密码的digest凭据交互式输入密码（输入不回显）
+pub fn prompt_missing_passwords(config: &mut RequestConfig) -> Result<()> {
+    let mut auths: Vec<(String, &mut DigestAuthConfig)> = Vec::new();
+    if let Some(auth) = config.digest_auth.as_mut() {
+        auths.push(("digest_auth".to_string(), auth));
+    }
+    for target in config.targets.iter_mut().flatten() {
+        let name = target.display_name().to_string();
+        if let Some(auth) = target.digest_auth.as_mut() {
+            auths.push((format!("target {}", name), auth));
+        }
+    }
+
+    for (context, auth) in auths {
+        if auth.username.is_empty() || !auth.password.is_empty() {
+            continue;
+        }
+        if !std::io::stdin().is_terminal() {
+            return Err(anyhow!(
+                "{}: password for {} is missing and stdin is not a terminal",
+                context,
+                auth.username
+            ));
+        }
+        auth.password = rpassword::prompt_password(format!(
+            "🔑 Password for {} ({}): ",
+            auth.username, context
+        ))
+        .with_context(|| format!("Failed to read password for {}", auth.username))?;
+    }
+    Ok(())
+}
@@ -0,0 +1,219 @@
+use serde_json::{Value, json};
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::Mutex;
+use tokio::task::JoinHandle;
+use tokio::time::{Instant, MissedTickBehavior};
+use tokio_util::sync::CancellationToken;
+
+use crate::config::SelfMonitorConfig;
+use crate::stats::RequestStats;
+
+// Import logger macros from crate root
+use crate::{log_info, log_warn};
+
+const DEFAULT_INTERVAL_MS: u64 = 60_000;
+const DEFAULT_GROWTH_SAMPLES: usize = 5;
+
+/// 一次自身资源采样
+#[derive(Debug, Clone, PartialEq)]
+pub struct ResourceSample {
+    pub elapsed: Duration,       // 相对运行开始的时间
+    pub rss_bytes: Option<u64>,  // 常驻内存（仅Linux，读取 /proc/self/status）
+    pub open_fds: Option<usize>, // 打开的文件描述符数（仅Linux，统计 /proc/self/fd）
+    pub tasks: usize,            // 存活的tokio任务数
+}
+
+impl ResourceSample {
+    /// 采样当前进程
+    pub fn current(elapsed: Duration) -> Self {
+        Self {
+            elapsed,
+            rss_bytes: Self::rss_bytes(),
+            open_fds: Self::open_fds(),
+            tasks: tokio::runtime::Handle::current()
+                .metrics()
+                .num_alive_tasks(),
+        }
+    }
+
+    /// 各指标的名称和取值，用于增长检测
+    fn metrics(&self) -> [(&'static str, Option<u64>); 3] {
+        [
+            ("rss", self.rss_bytes),
+            ("open_fds", self.open_fds.map(|fds| fds as u64)),
+            ("tasks", Some(self.tasks as u64)),
+        ]
+    }
+
+    fn rss_bytes() -> Option<u64> {
+        let status = std::fs::read_to_string("/proc/self/status").ok()?;
+        let kilobytes = status
+            .lines()
+            .find_map(|line| line.strip_prefix("VmRSS:"))?
+            .trim()
+            .trim_end_matches("kB")
+            .trim()
+            .parse::<u64>()
+            .ok()?;
+        Some(kilobytes * 1024)
+    }
+
+    fn open_fds() -> Option<usize> {
+        // 读取目录本身占用一个描述符，不计入
+        let entries = std::fs::read_dir("/proc/self/fd").ok()?;
+        Some(entries.count().saturating_sub(1))
+    }
+}
+
+/// 自身资源监测的结果
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct SelfMonitorReport {
+    pub samples: Vec<ResourceSample>,
+    pub growth_warnings: BTreeMap<String, usize>, // 各指标（rss/open_fds/tasks）持续增长告警的次数
+}
+
+impl SelfMonitorReport {
+    pub fn peak_rss_bytes(&self) -> Option<u64> {
+        self.samples
+            .iter()
+            .filter_map(|sample| sample.rss_bytes)
+            .max()
+    }
+
+    pub fn peak_open_fds(&self) -> Option<usize> {
+        self.samples
+            .iter()
+            .filter_map(|sample| sample.open_fds)
+            .max()
+    }
+
+    pub fn peak_tasks(&self) -> Option<usize> {
+        self.samples.iter().map(|sample| sample.tasks).max()
+    }
+
+    /// JSON报告中的 `self_monitor`
+    pub fn to_json(&self) -> Value {
+        json!({
+            "samples": self
+                .samples
+                .iter()
+                .map(|sample| {
+                    json!({
+                        "elapsed_s": sample.elapsed.as_secs_f64(),
+                        "rss_bytes": sample.rss_bytes,
+                        "open_fds": sample.open_fds,
+                        "tasks": sample.tasks,
+                    })
+                })
+                .collect::<Vec<_>>(),
+            "peak_rss_bytes": self.peak_rss_bytes(),
+            "peak_open_fds": self.peak_open_fds(),
+            "peak_tasks": self.peak_tasks(),
+            "growth_warnings": self.growth_warnings,
+        })
+    }
+}
+
+#[derive(Default)]
+struct MonitorState {
+    report: SelfMonitorReport,
+    streaks: BTreeMap<&'static str, usize>, // 各指标连续增长的采样次数
+}
+
+/// 自身资源监测：长时间运行期间定期采样本工具的RSS、打开的文件描述符和tokio任务数，
+/// 每次采样输出一行区间报告；某项指标连续多次采样持续增长时告警，避免客户端泄漏被误认为设备变慢
+pub struct SelfMonitor {
+    interval: Duration,
+    growth_samples: usize,
+    started_at: Instant,
+    stats: Arc<Mutex<RequestStats>>,
+    state: Mutex<MonitorState>,
+}
+
+impl SelfMonitor {
+    pub fn new(config: &SelfMonitorConfig, stats: Arc<Mutex<RequestStats>>) -> Self {
+        Self {
+            interval: Duration::from_millis(
+                config.interval_ms.unwrap_or(DEFAULT_INTERVAL_MS).max(1),
+            ),
+            growth_samples: config
+                .growth_samples
+                .unwrap_or(DEFAULT_GROWTH_SAMPLES)
+                .max(1),
+            started_at: Instant::now(),
+            stats,
+            state: Mutex::new(MonitorState::default()),
+        }
+    }
+
+    /// 启动后台任务，运行开始时和之后每个间隔采样一次，直到 `stop` 取消
+    pub fn spawn(self: &Arc<Self>, stop: CancellationToken) -> JoinHandle<()> {
+        let monitor = Arc::clone(self);
+        tokio::spawn(async move {
+            let mut interval = tokio::time::interval(monitor.interval);
+            interval.set_missed_tick_behavior(MissedTickBehavior::Delay);
+            while stop.run_until_cancelled(interval.tick()).await.is_some() {
+                let sample = ResourceSample::current(monitor.started_at.elapsed());
+                monitor.record(sample).await;
+            }
+        })
+    }
+
+    /// 记录一次采样：输出区间报告并检测持续增长
+    pub async fn record(&self, sample: ResourceSample) {
+        let (requests, failed) = {
+            let stats = self.stats.lock().await;
+            (stats.total_requests, stats.failed_requests)
+        };
+        log_info!(
+            "🩺 [{}s] {} requests ({} failed) | RSS {} | {} open FDs | {} tokio tasks",
+            sample.elapsed.as_secs(),
+            requests,
+            failed,
+            sample.rss_bytes.map_or("-".to_string(), |rss| format!(
+                "{:.1} MB",
+                rss as f64 / 1_048_576.0
+            )),
+            sample
+                .open_fds
+                .map_or("-".to_string(), |fds| fds.to_string()),
+            sample.tasks
+        );
+
+        let mut guard = self.state.lock().await;
+        let state = &mut *guard;
+        if let Some(previous) = state.report.samples.last() {
+            let metrics = previous.metrics().into_iter().zip(sample.metrics());
+            for (index, ((name, before), (_, after))) in metrics.enumerate() {
+                let grew = matches!((before, after), (Some(before), Some(after)) if after > before);
+                let streak = state.streaks.entry(name).or_default();
+                *streak = if grew { *streak + 1 } else { 0 };
+                // 连续增长达到阈值时告警一次，回落后重新计数
+                if *streak == self.growth_samples {
+                    *state
+                        .report
+                        .growth_warnings
+                        .entry(name.to_string())
+                        .or_default() += 1;
+                    let first = state.report.samples.len() - self.growth_samples;
+                    let (_, from) = state.report.samples[first].metrics()[index];
+                    log_warn!(
+                        "📈 {} grew in each of the last {} samples ({} → {}): possible client-side leak, \
+                         not necessarily device slowness",
+                        name,
+                        self.growth_samples,
+                        from.unwrap_or_default(),
+                        after.unwrap_or_default()
+                    );
+                }
+            }
+        }
+        state.report.samples.push(sample);
+    }
+
+    pub async fn report(&self) -> SelfMonitorReport {
+        self.state.lock().await.report.clone()
+    }
+}
@@ -0,0 +1,377 @@
+use anyhow::{Context, Result, bail};
+use std::fs::{File, OpenOptions, TryLockError};
+use std::io::{Read, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+// Import logger macros from crate root
+use crate::{log_debug, log_info, log_warn};
+
+/// systemd通知（sd_notify协议）：以 `Type=notify` 运行时告知就绪、状态、看门狗心跳和退出
+///
+/// 只在设置了 `NOTIFY_SOCKET` 的环境中生效，其他平台或直接运行时 [`SystemdNotifier::from_env`] 返回None。
+#[derive(Debug, Clone)]
+pub struct SystemdNotifier {
+    #[cfg_attr(not(unix), allow(dead_code))]
+    socket: String,
+    watchdog_interval: Option<Duration>,
+}
+
+impl SystemdNotifier {
+    /// 向指定的通知套接字发送（`@` 开头为抽象命名空间），不启用看门狗
+    pub fn new(socket: impl Into<String>) -> Self {
+        Self {
+            socket: socket.into(),
+            watchdog_interval: None,
+        }
+    }
+
+    pub fn from_env() -> Option<Self> {
+        let socket = std::env::var("NOTIFY_SOCKET")
+            .ok()
+            .filter(|s| !s.is_empty())?;
+        if !cfg!(unix) {
+            return None;
+        }
+        // WATCHDOG_PID 存在时只对该进程生效（避免子进程误发心跳）
+        let for_us = std::env::var("WATCHDOG_PID")
+            .ok()
+            .and_then(|pid| pid.parse::<u32>().ok())
+            .is_none_or(|pid| pid == std::process::id());
+        let watchdog_interval = std::env::var("WATCHDOG_USEC")
+            .ok()
+            .and_then(|usec| usec.parse::<u64>().ok())
+            .filter(|usec| *usec > 0 && for_us)
+            .map(Duration::from_micros);
+        Some(Self {
+            watchdog_interval,
+            ..Self::new(socket)
+        })
+    }
+
+    /// 启用看门狗，超时为 `interval`（对应 `WatchdogSec`）
+    pub fn with_watchdog_interval(mut self, interval: Duration) -> Self {
+        self.watchdog_interval = Some(interval);
+        self
+    }
+
+    /// 看门狗超时（`WatchdogSec`），未启用时为None
+    pub fn watchdog_interval(&self) -> Option<Duration> {
+        self.watchdog_interval
+    }
+
+    pub fn ready(&self) {
+        self.notify("READY=1");
+    }
+
+    pub fn stopping(&self) {
+        self.notify("STOPPING=1");
+    }
+
+    /// 更新 `systemctl status` 中显示的状态行
+    pub fn status(&self, status: &str) {
+        self.notify(&format!("STATUS={}", status.replace('\n', " ")));
+    }
+
+    /// 按看门狗超时的一半检查一次 `alive`，只在其返回true（主循环仍在推进）时发送心跳，直到返回的任务被中止
+    ///
+    /// 主循环卡住时不再发送心跳，由systemd在看门狗超时后重启服务。
+    pub fn spawn_watchdog(
+        &self,
+        alive: impl Fn() -> bool + Send + 'static,
+    ) -> Option<tokio::task::JoinHandle<()>> {
+        let interval = self.watchdog_interval? / 2;
+        let notifier = self.clone();
+        log_debug!("🐶 Sending watchdog heartbeats every {:?}", interval);
+        Some(tokio::spawn(async move {
+            let mut ticker = tokio::time::interval(interval);
+            let mut stalled = false;
+            loop {
+                ticker.tick().await;
+                if alive() {
+                    stalled = false;
+                    notifier.notify("WATCHDOG=1");
+                } else if !stalled {
+                    stalled = true;
+                    log_warn!("🐶 Main loop made no progress, withholding watchdog heartbeat");
+                }
+            }
+        }))
+    }
+
+    /// 发送一条通知；失败只记录警告，不影响运行
+    pub fn notify(&self, message: &str) {
+        if let Err(e) = self.send(message) {
+            log_warn!("sd_notify '{}' failed: {:#}", message, e);
+        }
+    }
+
+    #[cfg(unix)]
+    fn send(&self, message: &str) -> Result<()> {
+        use std::os::unix::net::UnixDatagram;
+
+        let socket = UnixDatagram::unbound()?;
+        // 以 @ 开头的是Linux抽象命名空间套接字
+        if let Some(name) = self.socket.strip_prefix('@') {
+            #[cfg(target_os = "linux")]
+            {
+                use std::os::linux::net::SocketAddrExt;
+                let address = std::os::unix::net::SocketAddr::from_abstract_name(name)?;
+                socket.send_to_addr(message.as_bytes(), &address)?;
+                return Ok(());
+            }
+            #[cfg(not(target_os = "linux"))]
+            bail!("abstract socket {} is only supported on Linux", name);
+        }
+        socket.send_to(message.as_bytes(), &self.socket)?;
+        Ok(())
+    }
+
+    #[cfg(not(unix))]
+    fn send(&self, _message: &str) -> Result<()> {
+        bail!("sd_notify is only supported on Unix")
+    }
+}
+
+/// 等待Ctrl+C，Unix上还包括SIGTERM（`systemctl stop` 发送的信号）
+pub async fn shutdown_signal() {
+    #[cfg(unix)]
+    {
+        use tokio::signal::unix::{SignalKind, signal};
+        if let Ok(mut terminate) = signal(SignalKind::terminate()) {
+            tokio::select! {
+                _ = tokio::signal::ctrl_c() => {}
+                _ = terminate.recv() => {}
+            }
+            return;
+        }
+    }
+    let _ = tokio::signal::ctrl_c().await;
+}
+
+/// PID文件与单实例锁：持有期间对文件加排他锁，第二个实例获取失败；释放时删除文件
+#[derive(Debug)]
+pub struct PidFile {
+    path: PathBuf,
+    file: File,
+}
+
+impl PidFile {
+    pub fn acquire(path: impl AsRef<Path>) -> Result<Self> {
+        let path = path.as_ref();
+        if let Some(parent) = path
+            .parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+        {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Failed to create {}", parent.display()))?;
+        }
+        let mut file = OpenOptions::new()
+            .read(true)
+            .write(true)
+            .create(true)
+            .truncate(false)
+            .open(path)
+            .with_context(|| format!("Failed to open PID file {}", path.display()))?;
+        match file.try_lock() {
+            Ok(()) => {}
+            Err(TryLockError::WouldBlock) => {
+                let mut pid = String::new();
+                let _ = file.read_to_string(&mut pid);
+                bail!(
+                    "another instance is already running (pid {}, lock {})",
+                    pid.trim(),
+                    path.display()
+                );
+            }
+            Err(TryLockError::Error(e)) => {
+                return Err(e).with_context(|| format!("Failed to lock {}", path.display()));
+            }
+        }
+        file.set_len(0)?;
+        file.seek(SeekFrom::Start(0))?;
+        writeln!(file, "{}", std::process::id())?;
+        file.flush()?;
+        log_debug!("🔒 Holding PID file {}", path.display());
+        Ok(Self {
+            path: path.to_path_buf(),
+            file,
+        })
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+}
+
+impl Drop for PidFile {
+    fn drop(&mut self) {
+        // 先释放锁再删除文件（Windows上不能删除仍被锁定的文件）
+        let _ = self.file.unlock();
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// 将守护模式注册为开机自动运行：Linux为systemd服务（Type=notify），Windows为开机启动的计划任务
+///
+/// Windows上注册的不是Windows服务：计划任务不受服务控制管理器（SCM）管理，没有就绪通知、看门狗和失败重启，
+/// `sc query` / `services.msc` 中也看不到，只能通过 `schtasks` 或任务计划程序查看和结束。
+pub struct ServiceInstaller {
+    pub name: String,
+    pub executable: PathBuf,
+    pub config: PathBuf,
+    pub env: Option<String>,
+    /// 命令行的 `--var` 覆盖，原样传给服务
+    pub vars: Vec<(String, String)>,
+    /// systemd的 `WatchdogSec`（秒），0表示不启用看门狗
+    pub watchdog_secs: u64,
+}
+
+impl ServiceInstaller {
+    /// 服务启动的命令行参数
+    fn arguments(&self) -> Vec<String> {
+        let mut arguments = vec!["--config".to_string(), self.config.display().to_string()];
+        if let Some(env) = &self.env {
+            arguments.extend(["--env".to_string(), env.clone()]);
+        }
+        for (key, value) in &self.vars {
+            arguments.extend(["--var".to_string(), format!("{}={}", key, value)]);
+        }
+        arguments.push("daemon".to_string());
+        arguments
+    }
+
+    /// systemd单元文件内容
+    pub fn systemd_unit(&self) -> String {
+        let command = std::iter::once(self.executable.display().to_string())
+            .chain(self.arguments())
+            .map(|argument| quote(&argument))
+            .collect::<Vec<_>>()
+            .join(" ");
+        let working_directory = self
+            .config
+            .parent()
+            .map_or_else(|| "/".to_string(), |dir| dir.display().to_string());
+        let mut unit = format!(
+            "[Unit]\n\
+             Description=RemoteTask scheduled runs ({name})\n\
+             After=network-online.target\n\
+             Wants=network-online.target\n\
+             \n\
+             [Service]\n\
+             Type=notify\n\
+             ExecStart={command}\n\
+             WorkingDirectory={working_directory}\n\
+             Restart=on-failure\n\
+             RestartSec=10\n",
+            name = self.name,
+        );
+        if self.watchdog_secs > 0 {
+            unit.push_str(&format!("WatchdogSec={}\n", self.watchdog_secs));
+        }
+        unit.push_str("\n[Install]\nWantedBy=multi-user.target\n");
+        unit
+    }
+
+    /// 单元文件路径
+    pub fn systemd_unit_path(&self) -> PathBuf {
+        PathBuf::from("/etc/systemd/system").join(format!("{}.service", self.name))
+    }
+
+    /// Windows开机启动计划任务的创建命令（schtasks参数）
+    pub fn startup_task_arguments(&self) -> Vec<String> {
+        let command = std::iter::once(self.executable.display().to_string())
+            .chain(self.arguments())
+            .map(|argument| quote(&argument))
+            .collect::<Vec<_>>()
+            .join(" ");
+        [
+            "/Create", "/F", "/TN", &self.name, "/SC", "ONSTART", "/RU", "SYSTEM", "/RL",
+            "HIGHEST", "/TR", &command,
+        ]
+        .iter()
+        .map(|argument| argument.to_string())
+        .collect()
+    }
+
+    /// 注册并启动systemd服务或Windows开机启动任务（需要root/管理员权限）
+    pub fn install(&self) -> Result<()> {
+        if cfg!(windows) {
+            run("schtasks", &self.startup_task_arguments())?;
+            run(
+                "schtasks",
+                &["/Run".to_string(), "/TN".to_string(), self.name.clone()],
+            )?;
+            log_info!("✅ Registered startup task {}", self.name);
+        } else {
+            let path = self.systemd_unit_path();
+            std::fs::write(&path, self.systemd_unit())
+                .with_context(|| format!("Failed to write {}", path.display()))?;
+            log_info!("📝 Wrote {}", path.display());
+            run("systemctl", &["daemon-reload".to_string()])?;
+            run(
+                "systemctl",
+                &["enable".to_string(), "--now".to_string(), self.name.clone()],
+            )?;
+            log_info!("✅ Enabled and started {}.service", self.name);
+        }
+        Ok(())
+    }
+
+    /// 停止并注销systemd服务或Windows开机启动任务
+    pub fn uninstall(&self) -> Result<()> {
+        if cfg!(windows) {
+            // 任务未在运行时 /End 会失败，忽略
+            let _ = run(
+                "schtasks",
+                &["/End".to_string(), "/TN".to_string(), self.name.clone()],
+            );
+            run(
+                "schtasks",
+                &[
+                    "/Delete".to_string(),
+                    "/F".to_string(),
+                    "/TN".to_string(),
+                    self.name.clone(),
+                ],
+            )?;
+            log_info!("🗑️  Removed startup task {}", self.name);
+        } else {
+            run(
+                "systemctl",
+                &[
+                    "disable".to_string(),
+                    "--now".to_string(),
+                    self.name.clone(),
+                ],
+            )?;
+            let path = self.systemd_unit_path();
+            std::fs::remove_file(&path)
+                .with_context(|| format!("Failed to remove {}", path.display()))?;
+            run("systemctl", &["daemon-reload".to_string()])?;
+            log_info!("🗑️  Removed {}.service", self.name);
+        }
+        Ok(())
+    }
+}
+
+fn run(program: &str, arguments: &[String]) -> Result<()> {
+    log_debug!("$ {} {}", program, arguments.join(" "));
+    let status = std::process::Command::new(program)
+        .args(arguments)
+        .status()
+        .with_context(|| format!("Failed to run {}", program))?;
+    if !status.success() {
+        bail!("{} {} exited with {}", program, arguments.join(" "), status);
+    }
+    Ok(())
+}
+
+/// 含空白或引号的参数加引号
+fn quote(argument: &str) -> String {
+    if argument.is_empty() || argument.contains(|c: char| c.is_whitespace() || c == '"') {
+        format!("\"{}\"", argument.replace('"', "\\\""))
+    } else {
+        argument.to_string()
+    }
+}
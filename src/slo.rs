@@ -0,0 +1,142 @@
+use std::collections::BTreeMap;
+use std::sync::Mutex;
+use std::time::Duration;
+
+use crate::config::{HttpRequestConfig, SloConfig};
+use crate::observer::RequestObserver;
+use crate::stats::RequestResult;
+
+// Import logger macros from crate root
+use crate::log_warn;
+
+const DEFAULT_MIN_REQUESTS: usize = 10;
+
+/// 单个请求标签的SLO达成情况
+#[derive(Debug, Clone)]
+pub struct SloReport {
+    pub label: String,
+    pub latency_ms: u64,
+    pub target_percent: f64,
+    pub total: usize,
+    pub violations: usize, // 失败或超过延迟目标的请求数
+}
+
+impl SloReport {
+    /// 达标请求的百分比
+    pub fn compliance_percent(&self) -> f64 {
+        if self.total == 0 {
+            return 100.0;
+        }
+        (self.total - self.violations) as f64 * 100.0 / self.total as f64
+    }
+
+    /// 按当前请求数允许的违规次数
+    pub fn budget(&self) -> f64 {
+        self.total as f64 * (100.0 - self.target_percent) / 100.0
+    }
+
+    /// 已消耗的违规预算百分比
+    pub fn consumed_percent(&self) -> f64 {
+        let budget = self.budget();
+        if budget > 0.0 {
+            self.violations as f64 * 100.0 / budget
+        } else if self.violations > 0 {
+            f64::INFINITY
+        } else {
+            0.0
+        }
+    }
+
+    /// 违规次数超出预算
+    pub fn exhausted(&self) -> bool {
+        self.violations as f64 > self.budget()
+    }
+}
+
+struct LabelSlo {
+    config: SloConfig,
+    report: SloReport,
+}
+
+/// 响应时间SLO跟踪：按请求标签持续统计违规次数和预算消耗
+pub struct SloTracker {
+    labels: Mutex<BTreeMap<String, LabelSlo>>,
+}
+
+impl SloTracker {
+    /// 请求A和B均未配置SLO时返回None
+    pub fn new(request_a: &HttpRequestConfig, request_b: &HttpRequestConfig) -> Option<Self> {
+        let labels: BTreeMap<String, LabelSlo> = [("A", request_a), ("B", request_b)]
+            .into_iter()
+            .filter_map(|(label, request)| {
+                let config = request.slo.clone()?;
+                let report = SloReport {
+                    label: label.to_string(),
+                    latency_ms: config.latency_ms,
+                    target_percent: config.target_percent,
+                    total: 0,
+                    violations: 0,
+                };
+                Some((label.to_string(), LabelSlo { config, report }))
+            })
+            .collect();
+        (!labels.is_empty()).then(|| Self {
+            labels: Mutex::new(labels),
+        })
+    }
+
+    /// 配置了 `stop_on_exhausted` 的SLO预算耗尽时返回停止原因
+    pub fn exhausted_reason(&self) -> Option<String> {
+        let labels = self.labels.lock().unwrap();
+        labels.values().find_map(|slo| {
+            let report = &slo.report;
+            (slo.config.stop_on_exhausted.unwrap_or(false)
+                && report.total >= slo.config.min_requests.unwrap_or(DEFAULT_MIN_REQUESTS)
+                && report.exhausted())
+            .then(|| {
+                format!(
+                    "SLO budget exhausted for {} ({} of {} requests violated {}% ≤ {}ms)",
+                    report.label,
+                    report.violations,
+                    report.total,
+                    report.target_percent,
+                    report.latency_ms
+                )
+            })
+        })
+    }
+
+    /// 各标签的SLO达成情况
+    pub fn reports(&self) -> Vec<SloReport> {
+        let labels = self.labels.lock().unwrap();
+        labels.values().map(|slo| slo.report.clone()).collect()
+    }
+}
+
+impl RequestObserver for SloTracker {
+    fn on_result(&self, result: &RequestResult) {
+        if result.warmup {
+            return;
+        }
+        let mut labels = self.labels.lock().unwrap();
+        let Some(slo) = labels.get_mut(&result.label) else {
+            return;
+        };
+        let was_exhausted = slo.report.exhausted();
+        slo.report.total += 1;
+        if !result.success || result.elapsed > Duration::from_millis(slo.config.latency_ms) {
+            slo.report.violations += 1;
+        }
+        if !was_exhausted
+            && slo.report.exhausted()
+            && slo.report.total >= slo.config.min_requests.unwrap_or(DEFAULT_MIN_REQUESTS)
+        {
+            log_warn!(
+                "📉 SLO budget for {} exhausted: {} violations in {} requests",
+                slo.report.label,
+                slo.report.violations,
+                slo.report.total
+            );
+        }
+    }
+}
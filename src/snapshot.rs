@@ -0,0 +1,76 @@
+use serde_json::Value;
+use std::path::Path;
+
+use crate::config::SnapshotConfig;
+use crate::http_client::ResponseData;
+use crate::json_diff;
+
+// Import logger macros from crate root
+use crate::log_info;
+
+/// 快照（golden response）比较
+pub struct SnapshotChecker;
+
+impl SnapshotChecker {
+    /// 将响应body与快照文件比较，返回不匹配原因；`update` 为true时改为用响应重写快照
+    pub fn check(config: &SnapshotConfig, response: &ResponseData, update: bool) -> Option<String> {
+        let path = Path::new(&config.file);
+        let actual = Self::parse(&response.body);
+
+        if update {
+            return match Self::write(path, &actual) {
+                Ok(()) => {
+                    log_info!("📸 Snapshot updated: {}", path.display());
+                    None
+                }
+                Err(e) => Some(format!(
+                    "failed to update snapshot {}: {}",
+                    path.display(),
+                    e
+                )),
+            };
+        }
+
+        let expected = match std::fs::read(path) {
+            Ok(content) => Self::parse(&content),
+            Err(e) => {
+                return Some(format!(
+                    "snapshot {} unreadable ({}); run with --update-snapshots to create it",
+                    path.display(),
+                    e
+                ));
+            }
+        };
+
+        let ignore_paths = config.ignore_paths.clone().unwrap_or_default();
+        let differences = json_diff::diff(&expected, &actual, &ignore_paths);
+        if differences.is_empty() {
+            return None;
+        }
+
+        let shown: Vec<String> = differences.iter().take(5).map(|d| d.to_string()).collect();
+        Some(format!(
+            "snapshot {} mismatch ({} difference(s)): {}",
+            path.display(),
+            differences.len(),
+            shown.join("; ")
+        ))
+    }
+
+    /// JSON内容按结构比较，其余按去除首尾空白后的文本比较
+    fn parse(content: &[u8]) -> Value {
+        serde_json::from_slice(content)
+            .unwrap_or_else(|_| Value::String(String::from_utf8_lossy(content).trim().to_string()))
+    }
+
+    fn write(path: &Path, value: &Value) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let content = match value {
+            Value::String(text) => text.clone(),
+            _ => serde_json::to_string_pretty(value)?,
+        };
+        std::fs::write(path, content)
+    }
+}
@@ -1,92 +1,1055 @@
+use reqwest::StatusCode;
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value, json};
+use std::collections::BTreeMap;
 use std::sync::Arc;
+use std::time::Duration;
 use tokio::sync::Mutex;
-use tokio::time::Instant;
 
-use crate::config::HttpRequestConfig;
+use crate::adaptive_pacing::PacingReport;
+use crate::http_client::ResponseData;
+use crate::provisioning::ProvisioningReport;
+use crate::run_metadata::RunMetadata;
+use crate::self_monitor::SelfMonitorReport;
+use crate::slo::SloReport;
 
 // Import logger macros from crate root
-use crate::{log_error, log_info};
+use crate::{log_error, log_info, log_warn};
 
 /// 请求统计信息
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Default)]
 pub struct RequestStats {
     pub total_requests: usize,
     pub successful_requests: usize,
     pub failed_requests: usize,
     pub last_error: Option<String>,
+    pub per_target: BTreeMap<String, TargetStats>,
+    pub per_label: BTreeMap<(String, String), LabelStats>, // 按 (请求标签, 渲染前的URL) 统计
+    pub per_tag: BTreeMap<String, LabelStats>, // 按请求声明的tags统计，一个请求计入它的每个tag
+    pub drift_events: usize,
+    pub bytes_sent: u64,                                     // 请求body字节数
+    pub bytes_received: u64,                                 // 响应body字节数
+    pub elapsed: Duration,                                   // 运行总时长
+    pub stop_reason: Option<String>,                         // 提前停止的原因（正常结束时为None）
+    pub warmup_requests: usize,                              // 预热期间发送、未计入统计的请求数
+    pub circuit_transitions: BTreeMap<String, usize>,        // 熔断器状态转换次数
+    pub injected_faults: BTreeMap<String, usize>,            // 故障注入次数（按类型）
+    pub leftover_tasks_deleted: usize,                       // 运行结束时清理的遗留任务数
+    pub task_verification_failures: BTreeMap<String, usize>, // 任务存在性校验失败次数（create/delete）
+    pub rtsp_precheck_failures: usize,                       // RTSP流预检失败次数
+    pub captured_variables: BTreeMap<String, String>,        // 运行结束时的运行作用域捕获变量
+    pub not_modified: usize,                                 // 条件请求返回304的次数（计为成功）
+    pub remote_addresses: BTreeMap<String, usize>,           // 按实际连接的对端IP统计的响应数
+    pub download_resumes: usize,                             // 下载中断后续传的次数
+    pub slo: Vec<SloReport>,                                 // 各请求标签的SLO达成情况
+    pub pacing: Option<PacingReport>, // 自适应节奏的结果（未配置adaptive_pacing时为None）
+    pub cancelled_requests: usize,    // 中断时超过宽限期被取消的请求数（不计入成功或失败）
+    pub max_send_skew: Option<Duration>, // 屏障模式下同一周期内请求实际发送时间的最大偏差
+    pub a_spacing: SpacingStats,      // 请求A实际达到的发送间隔和相对计划时刻的延后
+    pub oversized_responses: usize,   // body超出缓冲上限被截断或写入磁盘的响应数
+    pub retries: usize,               // 失败后重试的次数（idempotency_key.retries）
+    pub duplicate_values: usize,      // unique生成字段检测到的重复取值次数（含已重新生成的）
+    pub expected_failures: usize, // 状态码属于expect_status.expected_failure的响应数（已计入成功）
+    pub branches: BTreeMap<String, usize>, // 各分支被选中的次数（未匹配分支、发送请求B的不计入）
+    pub self_monitor: Option<SelfMonitorReport>, // 自身资源采样（未配置self_monitor时为None）
+    pub provisioning: Option<ProvisioningReport>, // 数据预置的创建和删除结果（未配置provisioning时为None）
+    pub heartbeat: Option<LabelStats>, // 心跳请求的统计（未配置heartbeat时为None），不计入上面的请求统计
+    pub proxy_failures: usize, // 在代理处失败的请求数（无法连接代理或代理认证被拒绝），已计入失败
+    pub reauth_events: usize, // 收到401后重新登录的次数（auth_recovery），重发的请求按重发结果计入统计
+    pub reauth_failures: usize, // 重新登录失败的次数，此时原请求的401计为失败
+    pub metadata: Option<RunMetadata>, // 运行元数据（配置哈希、工具版本、主机、起止时间、命令行、目标设备）
+    pub failures: Vec<FailureDetail>, // 最近的失败请求（最多 MAX_RECORDED_FAILURES 个）的结构化上下文
+}
+
+/// 单个目标设备的统计信息
+#[derive(Debug, Clone, Default)]
+pub struct TargetStats {
+    pub total_requests: usize,
+    pub successful_requests: usize,
+    pub failed_requests: usize,
+}
+
+/// 单个请求标签+URL的统计信息
+#[derive(Debug, Clone, Default)]
+pub struct LabelStats {
+    pub total_requests: usize,
+    pub successful_requests: usize,
+    pub failed_requests: usize,
+    pub latencies: Samples,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub last_error: Option<String>,
+}
+
+impl LabelStats {
+    /// 成功率（百分比）
+    pub fn success_rate(&self) -> f64 {
+        if self.total_requests == 0 {
+            return 0.0;
+        }
+        self.successful_requests as f64 * 100.0 / self.total_requests as f64
+    }
+
+    /// 错误率（百分比）
+    pub fn error_rate(&self) -> f64 {
+        if self.total_requests == 0 {
+            return 0.0;
+        }
+        self.failed_requests as f64 * 100.0 / self.total_requests as f64
+    }
+
+    /// 延迟百分位（nearest-rank），`percentile` 取值 0-100
+    pub fn latency_percentile(&self, percentile: f64) -> Option<Duration> {
+        self.latencies.percentile(percentile)
+    }
+
+    /// 单个请求的传输速率（MB/s）：收发字节数除以请求耗时之和
+    pub fn throughput_mbps(&self) -> f64 {
+        throughput_mbps(self.bytes_sent + self.bytes_received, self.latencies.sum())
+    }
+}
+
+/// 请求A的发送间隔精度（不含预热周期）
+#[derive(Debug, Clone, Default)]
+pub struct SpacingStats {
+    /// 相邻周期请求A实际发出的间隔
+    pub intervals: Samples,
+    /// 请求A实际发出时刻晚于计划时刻的时间
+    pub lateness: Samples,
+}
+
+impl SpacingStats {
+    pub fn interval_percentile(&self, percentile: f64) -> Option<Duration> {
+        self.intervals.percentile(percentile)
+    }
+
+    pub fn lateness_percentile(&self, percentile: f64) -> Option<Duration> {
+        self.lateness.percentile(percentile)
+    }
+
+    /// 平均间隔
+    pub fn mean_interval(&self) -> Option<Duration> {
+        self.intervals.mean()
+    }
+}
+
+/// 每组样本最多保留的取值个数
+pub const MAX_SAMPLES: usize = 100_000;
+
+/// 一组时长样本（延迟、发送间隔等）
+///
+/// 个数、总和与最大值精确统计；取值最多保留 [`MAX_SAMPLES`] 个，超出后按蓄水池抽样替换，
+/// 保留的取值仍是全部样本的均匀抽样，百分位为近似值。长时间运行（soak测试）时内存不随请求数增长。
+#[derive(Debug, Clone, Default)]
+pub struct Samples {
+    values: Vec<Duration>,
+    count: usize,
+    sum: Duration,
+    max: Option<Duration>,
+}
+
+impl Samples {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, value: Duration) {
+        self.count += 1;
+        self.sum += value;
+        self.max = self.max.max(Some(value));
+        if self.values.len() < MAX_SAMPLES {
+            self.values.push(value);
+        } else {
+            // 第n个样本以 MAX_SAMPLES/n 的概率替换一个保留的取值；用样本序号派生伪随机数，
+            // 不消耗运行的随机数生成器（见 `rng`），不影响 --seed 的重现
+            let slot = (splitmix64(self.count as u64) % self.count as u64) as usize;
+            if slot < MAX_SAMPLES {
+                self.values[slot] = value;
+            }
+        }
+    }
+
+    /// 合并另一组样本（个数、总和与最大值精确合并，取值按蓄水池抽样）
+    pub fn merge(&mut self, other: &Samples) {
+        for value in &other.values {
+            self.push(*value);
+        }
+        // 对方未保留的样本只计入个数和总和
+        let retained: Duration = other.values.iter().sum();
+        self.count += other.count - other.values.len();
+        self.sum += other.sum.saturating_sub(retained);
+        self.max = self.max.max(other.max);
+    }
+
+    /// 样本总数（含未保留取值的样本）
+    pub fn len(&self) -> usize {
+        self.count
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// 全部样本之和
+    pub fn sum(&self) -> Duration {
+        self.sum
+    }
+
+    pub fn mean(&self) -> Option<Duration> {
+        let count = u32::try_from(self.count).ok().filter(|n| *n > 0)?;
+        Some(self.sum / count)
+    }
+
+    /// 百分位（nearest-rank），`percentile` 取值 0-100；100为精确的最大值
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if percentile >= 100.0 {
+            return self.max;
+        }
+        nearest_rank(&self.values, percentile)
+    }
+
+    /// 保留的样本取值（样本数超过 [`MAX_SAMPLES`] 时为均匀抽样）
+    pub fn values(&self) -> &[Duration] {
+        &self.values
+    }
+}
+
+impl FromIterator<Duration> for Samples {
+    fn from_iter<I: IntoIterator<Item = Duration>>(iter: I) -> Self {
+        let mut samples = Self::new();
+        for value in iter {
+            samples.push(value);
+        }
+        samples
+    }
+}
+
+/// SplitMix64，用于蓄水池抽样的确定性伪随机数
+fn splitmix64(mut x: u64) -> u64 {
+    x = x.wrapping_add(0x9e37_79b9_7f4a_7c15);
+    x = (x ^ (x >> 30)).wrapping_mul(0xbf58_476d_1ce4_e5b9);
+    x = (x ^ (x >> 27)).wrapping_mul(0x94d0_49bb_1331_11eb);
+    x ^ (x >> 31)
+}
+
+/// 百分位（nearest-rank），`percentile` 取值 0-100
+pub(crate) fn nearest_rank(values: &[Duration], percentile: f64) -> Option<Duration> {
+    if values.is_empty() {
+        return None;
+    }
+    let mut sorted = values.to_vec();
+    sorted.sort();
+    let rank = (percentile / 100.0 * sorted.len() as f64).ceil() as usize;
+    Some(sorted[rank.clamp(1, sorted.len()) - 1])
 }
 
 impl RequestStats {
     pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 所有请求的错误率（百分比）
+    pub fn error_rate(&self) -> f64 {
+        if self.total_requests == 0 {
+            return 0.0;
+        }
+        self.failed_requests as f64 * 100.0 / self.total_requests as f64
+    }
+
+    /// 所有请求的延迟百分位（nearest-rank），`percentile` 取值 0-100
+    pub fn latency_percentile(&self, percentile: f64) -> Option<Duration> {
+        let mut latencies = Samples::new();
+        for label_stats in self.per_label.values() {
+            latencies.merge(&label_stats.latencies);
+        }
+        latencies.percentile(percentile)
+    }
+
+    /// 整体吞吐量（MB/s）：收发字节数除以运行总时长
+    pub fn throughput_mbps(&self) -> f64 {
+        throughput_mbps(self.bytes_sent + self.bytes_received, self.elapsed)
+    }
+
+    /// 记录失败请求的上下文，只保留最近的 [`MAX_RECORDED_FAILURES`] 个
+    pub fn record_failure(&mut self, failure: FailureDetail) {
+        if self.failures.len() >= MAX_RECORDED_FAILURES {
+            self.failures.remove(0);
+        }
+        self.failures.push(failure);
+    }
+}
+
+/// 统计中保留的失败请求上下文个数
+pub const MAX_RECORDED_FAILURES: usize = 20;
+
+/// 失败上下文中保留的响应body字节数
+const FAILURE_BODY_BYTES: usize = 500;
+
+/// 失败请求的结构化上下文，失败时按块输出到日志，并写入JSON报告的 `failures`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct FailureDetail {
+    pub cycle: usize,
+    pub label: String,
+    pub method: String,
+    pub url: String, // 渲染后的URL
+    pub target: Option<String>,
+    pub status: Option<u16>,
+    pub elapsed_ms: f64,
+    pub reason: String,       // 不满足的断言、错误或意外的状态码
+    pub body: Option<String>, // 响应body的前500字节（没有响应时为None）
+}
+
+impl FailureDetail {
+    /// 失败请求的结果对应的上下文（成功、预热或被取消的请求为None）
+    pub fn from_result(result: &RequestResult) -> Option<Self> {
+        if result.success || result.warmup || result.cancelled {
+            return None;
+        }
+        let reason = match (&result.error, result.status) {
+            (Some(error), None) if result.proxy_error => format!("proxy error: {}", error),
+            (Some(error), _) => error.clone(),
+            (None, Some(status)) => format!("unexpected status {}", status_text(status)),
+            (None, None) => "request failed".to_string(),
+        };
+        Some(Self {
+            cycle: result.cycle,
+            label: result.label.clone(),
+            method: result.method.clone(),
+            url: result.url.clone(),
+            target: result.target.clone(),
+            status: result.status,
+            elapsed_ms: result.elapsed.as_secs_f64() * 1000.0,
+            reason,
+            body: result
+                .response
+                .as_ref()
+                .map(|response| Self::body_excerpt(&response.body)),
+        })
+    }
+
+    /// 响应body的开头部分，按UTF-8显示，超出部分以 `…` 表示
+    pub fn body_excerpt(body: &[u8]) -> String {
+        let end = body.len().min(FAILURE_BODY_BYTES);
+        let mut excerpt = String::from_utf8_lossy(&body[..end]).into_owned();
+        if body.len() > end {
+            excerpt.push('…');
+        }
+        excerpt
+    }
+
+    /// 按块输出到日志
+    pub fn log(&self) {
+        let mut lines = vec![
+            format!("❌ {} request failed (cycle {})", self.label, self.cycle),
+            format!("     request: {} {}", self.method, self.url),
+        ];
+        if let Some(target) = &self.target {
+            lines.push(format!("     target:  {}", target));
+        }
+        if let Some(status) = self.status {
+            lines.push(format!("     status:  {}", status_text(status)));
+        }
+        lines.push(format!("     elapsed: {:.2}ms", self.elapsed_ms));
+        lines.push(format!("     reason:  {}", self.reason));
+        if let Some(body) = self.body.as_deref().filter(|body| !body.is_empty()) {
+            lines.push(format!(
+                "     body:    {}",
+                body.replace('\n', "\n              ")
+            ));
+        }
+        log_error!("{}", lines.join("\n"));
+    }
+}
+
+/// 按字节数和耗时计算吞吐量（MB/s）
+fn throughput_mbps(bytes: u64, duration: Duration) -> f64 {
+    let seconds = duration.as_secs_f64();
+    if seconds == 0.0 {
+        return 0.0;
+    }
+    bytes as f64 / 1_000_000.0 / seconds
+}
+
+/// 单个请求的结构化结果
+#[derive(Debug, Clone)]
+pub struct RequestResult {
+    pub cycle: usize,
+    pub label: String,
+    pub method: String,
+    pub url: String,
+    /// 渲染前的URL，按请求统计时以此分组（未经模板渲染的请求为None）
+    pub url_template: Option<String>,
+    pub target: Option<String>,
+    pub status: Option<u16>,
+    pub success: bool,
+    /// 状态码属于 `expect_status.expected_failure`（已计为成功）
+    pub expected_failure: bool,
+    pub elapsed: Duration,
+    pub error: Option<String>,
+    /// 是否为预热期间的请求（不计入统计）
+    pub warmup: bool,
+    /// 实际发送的请求body
+    pub request_body: Option<String>,
+    /// 完整响应（请求失败时为None）
+    pub response: Option<ResponseData>,
+    /// 所属周期的关联ID（未配置correlation_id时为None）
+    pub correlation_id: Option<String>,
+    /// 请求声明的统计分组标签（已渲染变量）
+    pub tags: Vec<String>,
+    /// 中断时超过宽限期被取消（未计入统计）
+    pub cancelled: bool,
+    /// 请求在代理处失败（[`ProxyError`](crate::http_client::ProxyError)），而非目标
+    pub proxy_error: bool,
+    /// 屏障模式下本请求相对本周期最早放行的请求的实际发送时间差
+    pub send_skew: Option<Duration>,
+}
+
+/// 计入统计的单个请求结果（不含响应内容），可序列化，分布式worker和任务队列以此上报结果
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub cycle: usize,
+    pub label: String,
+    pub method: String,
+    pub url: String,
+    #[serde(default)]
+    pub url_template: Option<String>, // 渲染前的URL
+    pub target: Option<String>,
+    pub status: Option<u16>,
+    pub success: bool,
+    #[serde(default)]
+    pub expected_failure: bool,
+    pub elapsed_us: u64,
+    pub error: Option<String>,
+    pub warmup: bool,
+    pub bytes_sent: u64,
+    pub bytes_received: u64,
+    pub tags: Vec<String>,
+    #[serde(default)]
+    pub cancelled: bool,
+    #[serde(default)]
+    pub send_skew_us: Option<u64>,
+    #[serde(default)]
+    pub proxy_error: bool,
+    #[serde(default)]
+    pub remote_addr: Option<String>, // 实际连接的对端IP
+    #[serde(default)]
+    pub download_resumes: usize,
+    #[serde(default)]
+    pub oversized: bool, // body超出缓冲上限
+    #[serde(default)]
+    pub failure: Option<FailureDetail>, // 失败请求的结构化上下文
+}
+
+impl RecordedRequest {
+    /// 按请求统计的分组URL：渲染前的URL模板（多目标时附带目标名称），使含周期占位符的URL归入同一组
+    pub fn stats_url(&self) -> String {
+        match (&self.url_template, &self.target) {
+            (Some(template), Some(target)) => format!("{} ({})", template, target),
+            (Some(template), None) => template.clone(),
+            (None, _) => self.url.clone(),
+        }
+    }
+
+    pub fn from_result(result: &RequestResult) -> Self {
+        let response = result.response.as_ref();
         Self {
-            total_requests: 0,
-            successful_requests: 0,
-            failed_requests: 0,
-            last_error: None,
+            cycle: result.cycle,
+            label: result.label.clone(),
+            method: result.method.clone(),
+            url: result.url.clone(),
+            url_template: result.url_template.clone(),
+            target: result.target.clone(),
+            status: result.status,
+            success: result.success,
+            expected_failure: result.expected_failure,
+            elapsed_us: result.elapsed.as_micros() as u64,
+            error: result.error.clone(),
+            warmup: result.warmup,
+            bytes_sent: result
+                .request_body
+                .as_ref()
+                .map_or(0, |body| body.len() as u64),
+            bytes_received: response.map_or(0, |response| response.body_len()),
+            tags: result.tags.clone(),
+            cancelled: result.cancelled,
+            send_skew_us: result.send_skew.map(|skew| skew.as_micros() as u64),
+            proxy_error: result.proxy_error,
+            remote_addr: response
+                .and_then(|response| response.remote_addr)
+                .map(|remote_addr| remote_addr.ip().to_string()),
+            download_resumes: response
+                .and_then(|response| response.download.as_ref())
+                .map_or(0, |download| download.resumes),
+            oversized: response.is_some_and(|response| response.overflow.is_some()),
+            failure: FailureDetail::from_result(result),
+        }
+    }
+
+    /// 失败的单行描述（`last_error` 中）
+    fn failure_description(&self) -> String {
+        match (&self.error, self.status) {
+            (Some(error), Some(_)) => format!("failed check: {}", error),
+            (None, Some(status)) => format!("failed with status: {}", status_text(status)),
+            (Some(error), None) if self.proxy_error => format!("failed at the proxy: {}", error),
+            (Some(error), None) => format!("failed with error: {}", error),
+            (None, None) => "failed".to_string(),
         }
     }
+
+    fn count(&self, group: &mut LabelStats, elapsed: Duration, last_error: Option<&String>) {
+        group.total_requests += 1;
+        if self.success {
+            group.successful_requests += 1;
+        } else {
+            group.failed_requests += 1;
+            group.last_error = last_error.cloned();
+        }
+        group.latencies.push(elapsed);
+        group.bytes_sent += self.bytes_sent;
+        group.bytes_received += self.bytes_received;
+    }
+}
+
+/// 状态码及其原因短语，如 `404 Not Found`
+fn status_text(status: u16) -> String {
+    StatusCode::from_u16(status).map_or_else(|_| status.to_string(), |status| status.to_string())
 }
 
 /// 统计处理器
 pub struct StatsHandler;
 
 impl StatsHandler {
-    /// 处理响应并更新统计信息
-    pub async fn handle_response(
-        result: Result<reqwest::Response, anyhow::Error>,
-        config: &HttpRequestConfig,
-        start_time: Instant,
-        stats: &Arc<Mutex<RequestStats>>,
-    ) {
-        let duration = start_time.elapsed();
-        let mut stats_guard = stats.lock().await;
-        stats_guard.total_requests += 1;
-
-        match &result {
-            Ok(response) => {
-                if response.status().is_success() {
-                    stats_guard.successful_requests += 1;
-                    log_info!(
-                        "✅ {} request to {} succeeded in {:.2}ms (Status: {})",
-                        config.method,
-                        config.url,
-                        duration.as_millis(),
-                        response.status()
-                    );
-                } else {
-                    stats_guard.failed_requests += 1;
-                    let error_msg = format!(
-                        "❌ {} request to {} failed with status: {} in {:.2}ms",
-                        config.method,
-                        config.url,
-                        response.status(),
-                        duration.as_millis()
-                    );
-                    log_error!("🎯 request failed:  {}", error_msg);
-                    stats_guard.last_error = Some(error_msg);
-                }
-            }
-            Err(e) => {
-                stats_guard.failed_requests += 1;
-                let error_msg = format!(
-                    "❌ {} request to {} failed with error: {} in {:.2}ms",
-                    config.method,
-                    config.url,
-                    e,
-                    duration.as_millis()
-                );
-                log_error!("🎯 request failed:  {}", error_msg);
-                stats_guard.last_error = Some(error_msg);
+    /// 输出请求结果并计入统计
+    pub async fn handle_response(request: &RequestResult, stats: &Arc<Mutex<RequestStats>>) {
+        let recorded = RecordedRequest::from_result(request);
+        Self::log(&recorded);
+        Self::record(&mut *stats.lock().await, &recorded);
+    }
+
+    /// 单个请求结果的日志：成功和预期失败各一行，失败时输出结构化块
+    fn log(request: &RecordedRequest) {
+        if request.cancelled {
+            return;
+        }
+        let elapsed = Duration::from_micros(request.elapsed_us);
+        let status = request.status.map(status_text).unwrap_or_default();
+        if let Some(failure) = &request.failure {
+            failure.log();
+        } else if request.expected_failure {
+            log_warn!(
+                "⚠️  {} request to {} returned expected failure status {} in {:.2}ms",
+                request.method,
+                request.url,
+                status,
+                elapsed.as_millis()
+            );
+        } else if request.success {
+            log_info!(
+                "✅ {} request to {} succeeded in {:.2}ms (Status: {})",
+                request.method,
+                request.url,
+                elapsed.as_millis(),
+                status
+            );
+        }
+    }
+
+    /// 将请求结果计入统计；本地运行和分布式coordinator汇总worker结果都经由这里
+    pub fn record(stats: &mut RequestStats, request: &RecordedRequest) {
+        // 被取消的请求单独计数，不计入成功或失败
+        if request.cancelled {
+            stats.cancelled_requests += 1;
+            return;
+        }
+        if let Some(skew) = request.send_skew_us.map(Duration::from_micros) {
+            stats.max_send_skew = stats.max_send_skew.max(Some(skew));
+        }
+        let elapsed = Duration::from_micros(request.elapsed_us);
+        stats.total_requests += 1;
+        if request.success {
+            stats.successful_requests += 1;
+            if request.expected_failure {
+                stats.expected_failures += 1;
+            }
+            if request.status == Some(StatusCode::NOT_MODIFIED.as_u16()) {
+                stats.not_modified += 1;
+            }
+        } else {
+            stats.failed_requests += 1;
+            if request.proxy_error {
+                stats.proxy_failures += 1;
+            }
+            if let Some(failure) = &request.failure {
+                stats.record_failure(failure.clone());
+            }
+            stats.last_error = Some(format!(
+                "❌ {} request to {} {} in {:.2}ms",
+                request.method,
+                request.url,
+                request.failure_description(),
+                elapsed.as_millis()
+            ));
+        }
+        if let Some(remote_addr) = &request.remote_addr {
+            *stats
+                .remote_addresses
+                .entry(remote_addr.clone())
+                .or_default() += 1;
+        }
+        stats.download_resumes += request.download_resumes;
+        if request.oversized {
+            stats.oversized_responses += 1;
+        }
+        stats.bytes_sent += request.bytes_sent;
+        stats.bytes_received += request.bytes_received;
+
+        // 按请求标签和URL、按tag统计，失败时记录该分组最近一次的失败原因
+        let last_error = stats.last_error.clone();
+        let label_stats = stats
+            .per_label
+            .entry((request.label.clone(), request.stats_url()))
+            .or_default();
+        request.count(label_stats, elapsed, last_error.as_ref());
+        for tag in &request.tags {
+            let tag_stats = stats.per_tag.entry(tag.clone()).or_default();
+            request.count(tag_stats, elapsed, last_error.as_ref());
+        }
+
+        // 按目标统计
+        if let Some(target) = &request.target {
+            let target_stats = stats.per_target.entry(target.clone()).or_default();
+            target_stats.total_requests += 1;
+            if request.success {
+                target_stats.successful_requests += 1;
+            } else {
+                target_stats.failed_requests += 1;
             }
         }
     }
 
+    /// 最终统计的JSON文档（`--output json`），延迟单位为毫秒
+    pub fn to_json(stats: &RequestStats) -> Value {
+        let millis =
+            |latency: Option<Duration>| latency.map(|latency| latency.as_secs_f64() * 1000.0);
+        let label_json = |label_stats: &LabelStats| {
+            json!({
+                "total_requests": label_stats.total_requests,
+                "successful_requests": label_stats.successful_requests,
+                "failed_requests": label_stats.failed_requests,
+                "success_rate": label_stats.success_rate(),
+                "p50_ms": millis(label_stats.latency_percentile(50.0)),
+                "p95_ms": millis(label_stats.latency_percentile(95.0)),
+                "p99_ms": millis(label_stats.latency_percentile(99.0)),
+                "bytes_sent": label_stats.bytes_sent,
+                "bytes_received": label_stats.bytes_received,
+                "last_error": label_stats.last_error,
+            })
+        };
+
+        let mut document = json!({
+            "total_requests": stats.total_requests,
+            "successful_requests": stats.successful_requests,
+            "failed_requests": stats.failed_requests,
+            "error_rate": stats.error_rate(),
+            "warmup_requests": stats.warmup_requests,
+            "elapsed_ms": stats.elapsed.as_secs_f64() * 1000.0,
+            "stop_reason": stats.stop_reason,
+            "last_error": stats.last_error,
+            "failures": stats.failures,
+            "latency_ms": {
+                "p50": millis(stats.latency_percentile(50.0)),
+                "p95": millis(stats.latency_percentile(95.0)),
+                "p99": millis(stats.latency_percentile(99.0)),
+            },
+            "bytes_sent": stats.bytes_sent,
+            "bytes_received": stats.bytes_received,
+            "throughput_mbps": stats.throughput_mbps(),
+            "per_label": stats
+                .per_label
+                .iter()
+                .map(|((label, url), label_stats)| {
+                    let mut entry = label_json(label_stats);
+                    entry["label"] = json!(label);
+                    entry["url"] = json!(url);
+                    entry
+                })
+                .collect::<Vec<_>>(),
+            "per_target": stats
+                .per_target
+                .iter()
+                .map(|(target, target_stats)| {
+                    (
+                        target.clone(),
+                        json!({
+                            "total_requests": target_stats.total_requests,
+                            "successful_requests": target_stats.successful_requests,
+                            "failed_requests": target_stats.failed_requests,
+                        }),
+                    )
+                })
+                .collect::<Map<_, _>>(),
+            "per_tag": stats
+                .per_tag
+                .iter()
+                .map(|(tag, tag_stats)| (tag.clone(), label_json(tag_stats)))
+                .collect::<Map<_, _>>(),
+            "slo": stats
+                .slo
+                .iter()
+                .map(|report| {
+                    json!({
+                        "label": report.label,
+                        "latency_ms": report.latency_ms,
+                        "target_percent": report.target_percent,
+                        "total_requests": report.total,
+                        "violations": report.violations,
+                        "compliance_percent": report.compliance_percent(),
+                        "budget_consumed_percent": report.consumed_percent(),
+                        "exhausted": report.exhausted(),
+                    })
+                })
+                .collect::<Vec<_>>(),
+            "pacing": stats.pacing.as_ref().map(|pacing| {
+                json!({
+                    "interval_ms": pacing.interval.as_millis() as u64,
+                    "sustainable_interval_ms": pacing
+                        .sustainable_interval
+                        .map(|interval| interval.as_millis() as u64),
+                    "sustainable_cycles_per_second": pacing.sustainable_cycles_per_second(),
+                    "slowdowns": pacing.slowdowns,
+                    "speedups": pacing.speedups,
+                })
+            }),
+            "not_modified": stats.not_modified,
+            "cancelled_requests": stats.cancelled_requests,
+            "proxy_failures": stats.proxy_failures,
+            "max_send_skew_us": stats.max_send_skew.map(|skew| skew.as_micros() as u64),
+            "a_spacing": {
+                "intervals": stats.a_spacing.intervals.len(),
+                "mean_interval_ms": millis(stats.a_spacing.mean_interval()),
+                "interval_p50_ms": millis(stats.a_spacing.interval_percentile(50.0)),
+                "interval_p95_ms": millis(stats.a_spacing.interval_percentile(95.0)),
+                "interval_max_ms": millis(stats.a_spacing.interval_percentile(100.0)),
+                "lateness_p50_ms": millis(stats.a_spacing.lateness_percentile(50.0)),
+                "lateness_p95_ms": millis(stats.a_spacing.lateness_percentile(95.0)),
+                "lateness_max_ms": millis(stats.a_spacing.lateness_percentile(100.0)),
+            },
+            "drift_events": stats.drift_events,
+            "download_resumes": stats.download_resumes,
+            "oversized_responses": stats.oversized_responses,
+            "retries": stats.retries,
+            "reauth_events": stats.reauth_events,
+            "reauth_failures": stats.reauth_failures,
+            "duplicate_values": stats.duplicate_values,
+            "expected_failures": stats.expected_failures,
+            "rtsp_precheck_failures": stats.rtsp_precheck_failures,
+            "leftover_tasks_deleted": stats.leftover_tasks_deleted,
+            "remote_addresses": stats.remote_addresses,
+            "injected_faults": stats.injected_faults,
+            "circuit_transitions": stats.circuit_transitions,
+            "branches": stats.branches,
+            "heartbeat": stats.heartbeat.as_ref().map(label_json),
+            "self_monitor": stats.self_monitor.as_ref().map(SelfMonitorReport::to_json),
+            "task_verification_failures": stats.task_verification_failures,
+            "captured_variables": stats.captured_variables,
+        });
+        document["metadata"] = json!(stats.metadata);
+        document["provisioning"] =
+            json!(stats.provisioning.as_ref().map(ProvisioningReport::to_json));
+        document
+    }
+
     /// 打印最终统计信息
     pub fn print_final_stats(stats: &RequestStats) {
         log_info!("\n📊 Final Statistics:");
+        if let Some(metadata) = &stats.metadata {
+            for (name, value) in metadata.entries() {
+                log_info!("  {}: {}", name, value);
+            }
+        }
         log_info!("  Total requests: {}", stats.total_requests);
         log_info!("  Successful: {}", stats.successful_requests);
         log_info!("  Failed: {}", stats.failed_requests);
+        if stats.warmup_requests > 0 {
+            log_info!("  Warm-up requests (excluded): {}", stats.warmup_requests);
+        }
+        if stats.cancelled_requests > 0 {
+            log_warn!(
+                "  Cancelled on shutdown (excluded): {}",
+                stats.cancelled_requests
+            );
+        }
+        log_info!(
+            "  Bytes sent: {}, received: {}, throughput: {:.3} MB/s",
+            stats.bytes_sent,
+            stats.bytes_received,
+            stats.throughput_mbps()
+        );
+        if !stats.per_target.is_empty() {
+            log_info!("  Per target:");
+            for (target, target_stats) in &stats.per_target {
+                log_info!(
+                    "    {}: total {}, successful {}, failed {}",
+                    target,
+                    target_stats.total_requests,
+                    target_stats.successful_requests,
+                    target_stats.failed_requests
+                );
+            }
+        }
+        if !stats.per_label.is_empty() {
+            log_info!("  Per request:");
+            log_info!(
+                "    {:<8} {:>8} {:>10} {:>10} {:>12} {:>12} {:>10}  URL",
+                "Label",
+                "Count",
+                "Success%",
+                "p95(ms)",
+                "Sent(B)",
+                "Recv(B)",
+                "MB/s"
+            );
+            for ((label, url), label_stats) in &stats.per_label {
+                let p95 = label_stats
+                    .latency_percentile(95.0)
+                    .map(|latency| latency.as_millis().to_string())
+                    .unwrap_or_else(|| "-".to_string());
+                log_info!(
+                    "    {:<8} {:>8} {:>9.1}% {:>10} {:>12} {:>12} {:>10.3}  {}",
+                    label,
+                    label_stats.total_requests,
+                    label_stats.success_rate(),
+                    p95,
+                    label_stats.bytes_sent,
+                    label_stats.bytes_received,
+                    label_stats.throughput_mbps(),
+                    url
+                );
+            }
+        }
+        if !stats.per_tag.is_empty() {
+            log_info!("  Per tag:");
+            log_info!(
+                "    {:<24} {:>8} {:>10} {:>10} {:>10}",
+                "Tag",
+                "Count",
+                "Success%",
+                "p50(ms)",
+                "p95(ms)"
+            );
+            for (tag, tag_stats) in &stats.per_tag {
+                let percentile = |percentile| {
+                    tag_stats
+                        .latency_percentile(percentile)
+                        .map(|latency| latency.as_millis().to_string())
+                        .unwrap_or_else(|| "-".to_string())
+                };
+                log_info!(
+                    "    {:<24} {:>8} {:>9.1}% {:>10} {:>10}",
+                    tag,
+                    tag_stats.total_requests,
+                    tag_stats.success_rate(),
+                    percentile(50.0),
+                    percentile(95.0)
+                );
+            }
+        }
+        if !stats.circuit_transitions.is_empty() {
+            log_warn!("  Circuit breaker transitions:");
+            for (transition, count) in &stats.circuit_transitions {
+                log_warn!("    {}: {}", transition, count);
+            }
+        }
+        if !stats.injected_faults.is_empty() {
+            log_warn!("  Injected faults:");
+            for (fault, count) in &stats.injected_faults {
+                log_warn!("    {}: {}", fault, count);
+            }
+        }
+        if !stats.branches.is_empty() {
+            log_info!("  Branches taken:");
+            for (branch, count) in &stats.branches {
+                log_info!("    {}: {}", branch, count);
+            }
+        }
+        if let Some(heartbeat) = &stats.heartbeat {
+            let line = format!(
+                "  Heartbeat: {} sent, {} failed, p95 {}",
+                heartbeat.total_requests,
+                heartbeat.failed_requests,
+                heartbeat
+                    .latency_percentile(95.0)
+                    .map_or("-".to_string(), |p95| format!("{}ms", p95.as_millis()))
+            );
+            if heartbeat.failed_requests > 0 {
+                log_warn!("{}", line);
+            } else {
+                log_info!("{}", line);
+            }
+        }
+        if let Some(monitor) = &stats.self_monitor {
+            log_info!(
+                "  Self monitor ({} samples): peak RSS {}, peak open FDs {}, peak tokio tasks {}",
+                monitor.samples.len(),
+                monitor
+                    .peak_rss_bytes()
+                    .map_or("-".to_string(), |rss| format!(
+                        "{:.1} MB",
+                        rss as f64 / 1_048_576.0
+                    )),
+                monitor
+                    .peak_open_fds()
+                    .map_or("-".to_string(), |fds| fds.to_string()),
+                monitor
+                    .peak_tasks()
+                    .map_or("-".to_string(), |tasks| tasks.to_string())
+            );
+            for (metric, count) in &monitor.growth_warnings {
+                log_warn!("    Sustained {} growth warnings: {}", metric, count);
+            }
+        }
+        if let Some(provisioning) = &stats.provisioning {
+            let line = format!(
+                "  Provisioning: {} created ({} failed), {} removed ({} failed)",
+                provisioning.created,
+                provisioning.setup_failures,
+                provisioning.deleted,
+                provisioning.teardown_failures
+            );
+            if provisioning.setup_failures + provisioning.teardown_failures > 0 {
+                log_warn!("{}", line);
+            } else {
+                log_info!("{}", line);
+            }
+        }
+        if !stats.remote_addresses.is_empty() {
+            log_info!("  Remote addresses:");
+            for (address, count) in &stats.remote_addresses {
+                log_info!("    {}: {}", address, count);
+            }
+        }
+        if stats.not_modified > 0 {
+            log_info!("  Not modified (304): {}", stats.not_modified);
+        }
+        if let Some(skew) = stats.max_send_skew {
+            log_info!("  Barrier send skew (max): {}µs", skew.as_micros());
+        }
+        let spacing = &stats.a_spacing;
+        if let (Some(mean), Some(p50), Some(p95), Some(max)) = (
+            spacing.mean_interval(),
+            spacing.interval_percentile(50.0),
+            spacing.interval_percentile(95.0),
+            spacing.interval_percentile(100.0),
+        ) {
+            log_info!(
+                "  A spacing: mean {:.1}ms, p50 {:.1}ms, p95 {:.1}ms, max {:.1}ms",
+                mean.as_secs_f64() * 1000.0,
+                p50.as_secs_f64() * 1000.0,
+                p95.as_secs_f64() * 1000.0,
+                max.as_secs_f64() * 1000.0
+            );
+        }
+        if let (Some(p95), Some(max)) = (
+            spacing.lateness_percentile(95.0),
+            spacing.lateness_percentile(100.0),
+        ) {
+            log_info!(
+                "  A lateness vs schedule: p95 {:.2}ms, max {:.2}ms",
+                p95.as_secs_f64() * 1000.0,
+                max.as_secs_f64() * 1000.0
+            );
+        }
+        for report in &stats.slo {
+            let line = format!(
+                "  SLO {} ({}% ≤ {}ms): {:.2}% compliant, {} of {:.1} allowed violations ({:.0}% of budget)",
+                report.label,
+                report.target_percent,
+                report.latency_ms,
+                report.compliance_percent(),
+                report.violations,
+                report.budget(),
+                report.consumed_percent()
+            );
+            if report.exhausted() {
+                log_warn!("{} — budget exhausted", line);
+            } else {
+                log_info!("{}", line);
+            }
+        }
+        if let Some(pacing) = &stats.pacing {
+            log_info!(
+                "  Adaptive pacing: {} slowdown(s), {} speedup(s), final A interval {}ms",
+                pacing.slowdowns,
+                pacing.speedups,
+                pacing.interval.as_millis()
+            );
+            match (
+                pacing.sustainable_interval,
+                pacing.sustainable_cycles_per_second(),
+            ) {
+                (Some(interval), Some(rate)) => log_info!(
+                    "  Sustainable pace: A every {}ms ({:.2} cycles/s)",
+                    interval.as_millis(),
+                    rate
+                ),
+                _ => log_warn!("  Sustainable pace: no window stayed within the thresholds"),
+            }
+        }
+        if stats.download_resumes > 0 {
+            log_warn!("  Download resumes: {}", stats.download_resumes);
+        }
+        if stats.retries > 0 {
+            log_warn!("  Retries: {}", stats.retries);
+        }
+        if stats.reauth_events > 0 || stats.reauth_failures > 0 {
+            log_warn!(
+                "  Re-authentications after HTTP 401: {} ({} failed)",
+                stats.reauth_events,
+                stats.reauth_failures
+            );
+        }
+        if stats.expected_failures > 0 {
+            log_info!(
+                "  Expected failures (counted as successful): {}",
+                stats.expected_failures
+            );
+        }
+        if stats.duplicate_values > 0 {
+            log_warn!("  Duplicate generated values: {}", stats.duplicate_values);
+        }
+        if stats.oversized_responses > 0 {
+            log_warn!(
+                "  Oversized responses (truncated or spilled): {}",
+                stats.oversized_responses
+            );
+        }
+        if !stats.captured_variables.is_empty() {
+            log_info!("  Captured variables:");
+            for (name, value) in &stats.captured_variables {
+                log_info!("    {} = {}", name, value);
+            }
+        }
+        if stats.rtsp_precheck_failures > 0 {
+            log_warn!("  RTSP precheck failures: {}", stats.rtsp_precheck_failures);
+        }
+        if !stats.task_verification_failures.is_empty() {
+            log_warn!("  Task verification failures:");
+            for (kind, count) in &stats.task_verification_failures {
+                log_warn!("    {}: {}", kind, count);
+            }
+        }
+        if stats.leftover_tasks_deleted > 0 {
+            log_warn!("  Leftover tasks deleted: {}", stats.leftover_tasks_deleted);
+        }
+        if stats.proxy_failures > 0 {
+            log_warn!(
+                "  Proxy failures (connect or authentication): {}",
+                stats.proxy_failures
+            );
+        }
+        if stats.drift_events > 0 {
+            log_warn!("  Response drift events: {}", stats.drift_events);
+        }
         if let Some(error) = &stats.last_error {
             log_error!("  Last error: {}", error);
         }
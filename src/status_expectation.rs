@@ -0,0 +1,96 @@
+use reqwest::StatusCode;
+
+use crate::conditional::ConditionalCache;
+use crate::config::HttpRequestConfig;
+
+/// 按请求的 `expect_status` 对响应状态码的分类
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StatusOutcome {
+    /// 计为成功
+    Success,
+    /// 预期内的失败（如重复创建返回409）：计为成功，单独计数
+    ExpectedFailure,
+    /// 计为失败
+    Failure,
+}
+
+/// 每个请求的状态码期望：哪些状态码计为成功、预期失败或失败
+pub struct StatusExpectation;
+
+impl StatusExpectation {
+    /// 对状态码分类，优先级为 `failure` > `expected_failure` > `success`。
+    /// 未配置 `success` 时沿用默认判断（2xx，启用条件请求时包括304）
+    pub fn classify(config: &HttpRequestConfig, status: StatusCode) -> StatusOutcome {
+        let default = if ConditionalCache::is_success(config, status) {
+            StatusOutcome::Success
+        } else {
+            StatusOutcome::Failure
+        };
+        let Some(expect) = &config.expect_status else {
+            return default;
+        };
+        let listed = |patterns: &Option<Vec<String>>| {
+            patterns
+                .iter()
+                .flatten()
+                .any(|pattern| Self::matches(pattern, status))
+        };
+        if listed(&expect.failure) {
+            StatusOutcome::Failure
+        } else if listed(&expect.expected_failure) {
+            StatusOutcome::ExpectedFailure
+        } else if expect.success.is_some() {
+            if listed(&expect.success) {
+                StatusOutcome::Success
+            } else {
+                StatusOutcome::Failure
+            }
+        } else {
+            default
+        }
+    }
+
+    /// 状态码计为成功（含预期失败）
+    pub fn is_success(config: &HttpRequestConfig, status: StatusCode) -> bool {
+        Self::classify(config, status) != StatusOutcome::Failure
+    }
+
+    /// 状态码是否匹配模式：`"409"`、`"4xx"` 或 `"400-404"`
+    pub fn matches(pattern: &str, status: StatusCode) -> bool {
+        Self::range(pattern).is_some_and(|(low, high)| (low..=high).contains(&status.as_u16()))
+    }
+
+    /// 模式无效时返回错误说明
+    pub fn pattern_error(pattern: &str) -> Option<String> {
+        match Self::range(pattern) {
+            Some(_) => None,
+            None => Some(format!(
+                "invalid status pattern '{}' (expected e.g. \"409\", \"4xx\" or \"400-404\")",
+                pattern
+            )),
+        }
+    }
+
+    /// 解析模式为闭区间
+    fn range(pattern: &str) -> Option<(u16, u16)> {
+        let pattern = pattern.trim();
+        let valid = |code: u16| (100..=599).contains(&code);
+        if let Some(class) = pattern
+            .strip_suffix("xx")
+            .or_else(|| pattern.strip_suffix("XX"))
+        {
+            let class: u16 = class.parse().ok()?;
+            return (1..=5)
+                .contains(&class)
+                .then_some((class * 100, class * 100 + 99));
+        }
+        let (low, high) = match pattern.split_once('-') {
+            Some((low, high)) => (low.trim().parse().ok()?, high.trim().parse().ok()?),
+            None => {
+                let code = pattern.parse().ok()?;
+                (code, code)
+            }
+        };
+        (valid(low) && valid(high) && low <= high).then_some((low, high))
+    }
+}
@@ -0,0 +1,148 @@
+use anyhow::{Context, Result, anyhow, bail};
+use serde_json::Value;
+
+use crate::config::RequestConfig;
+use crate::request_handler::RequestHandler;
+use crate::stats::RequestStats;
+
+// Import logger macros from crate root
+use crate::log_info;
+
+/// 单次扫描允许的最多取值数，防止范围写错时无限运行
+const MAX_SWEEP_VALUES: usize = 1000;
+
+/// 参数扫描中单个取值的运行结果
+#[derive(Debug, Clone)]
+pub struct SweepPoint {
+    pub value: String,
+    pub stats: RequestStats,
+}
+
+/// 参数扫描：依次将配置中的一个参数设为列表/范围中的每个值，各运行一次短场景，用于容量摸底
+pub struct ParameterSweep;
+
+impl ParameterSweep {
+    /// 解析取值：逗号分隔的列表（`1,2,4,8`）或闭区间（`1..60`，可带步长 `1..60:5`）
+    pub fn parse_values(spec: &str) -> Result<Vec<String>> {
+        let spec = spec.trim();
+        if let Some((start, rest)) = spec.split_once("..") {
+            let (end, step) = match rest.split_once(':') {
+                Some((end, step)) => (end, step),
+                None => (rest, "1"),
+            };
+            let parse = |text: &str, name: &str| -> Result<i64> {
+                text.trim()
+                    .parse()
+                    .with_context(|| format!("Invalid range {} \"{}\"", name, text.trim()))
+            };
+            let (start, end, step) = (
+                parse(start, "start")?,
+                parse(end, "end")?,
+                parse(step, "step")?,
+            );
+            if step <= 0 {
+                bail!("Range step must be greater than 0");
+            }
+            if end < start {
+                bail!("Range end {} is less than start {}", end, start);
+            }
+            let count = ((end - start) / step + 1) as usize;
+            if count > MAX_SWEEP_VALUES {
+                bail!(
+                    "Range has {} values (at most {} allowed)",
+                    count,
+                    MAX_SWEEP_VALUES
+                );
+            }
+            return Ok((0..count as i64)
+                .map(|index| (start + index * step).to_string())
+                .collect());
+        }
+
+        let values: Vec<String> = spec
+            .split(',')
+            .map(|value| value.trim().to_string())
+            .filter(|value| !value.is_empty())
+            .collect();
+        if values.is_empty() {
+            bail!("No sweep values given");
+        }
+        if values.len() > MAX_SWEEP_VALUES {
+            bail!(
+                "{} sweep values given (at most {} allowed)",
+                values.len(),
+                MAX_SWEEP_VALUES
+            );
+        }
+        Ok(values)
+    }
+
+    /// 将点分路径（如 `variables.pollingTime`、`batch.size`）处的参数设为指定值
+    ///
+    /// 原值为字符串或位于 `variables` 下时按字符串设置，否则按JSON解析（数字、布尔等），
+    /// 路径中缺失的对象会自动创建。
+    pub fn apply(config: &RequestConfig, param: &str, value: &str) -> Result<RequestConfig> {
+        let mut root = serde_json::to_value(config)?;
+        let keys: Vec<&str> = param.split('.').collect();
+        if keys.iter().any(|key| key.is_empty()) {
+            bail!("Invalid parameter path \"{}\"", param);
+        }
+
+        let mut node = &mut root;
+        for key in &keys[..keys.len() - 1] {
+            let object = node
+                .as_object_mut()
+                .ok_or_else(|| anyhow!("\"{}\" is not an object in config", key))?;
+            let child = object.entry(key.to_string()).or_insert(Value::Null);
+            if child.is_null() {
+                *child = Value::Object(Default::default());
+            }
+            node = child;
+        }
+        let object = node
+            .as_object_mut()
+            .ok_or_else(|| anyhow!("Parent of \"{}\" is not an object in config", param))?;
+        let last = keys[keys.len() - 1];
+        let as_string =
+            keys[0] == "variables" || object.get(last).is_some_and(|current| current.is_string());
+        let new_value = if as_string {
+            Value::String(value.to_string())
+        } else {
+            serde_json::from_str(value).unwrap_or_else(|_| Value::String(value.to_string()))
+        };
+        object.insert(last.to_string(), new_value);
+
+        serde_json::from_value(root)
+            .with_context(|| format!("Cannot set {} to \"{}\"", param, value))
+    }
+
+    /// 依次以每个取值运行，返回各取值的统计
+    pub async fn run(
+        config: &RequestConfig,
+        param: &str,
+        values: &[String],
+    ) -> Result<Vec<SweepPoint>> {
+        // 先应用全部取值，任一取值无效时不发送请求
+        let configs = values
+            .iter()
+            .map(|value| Self::apply(config, param, value))
+            .collect::<Result<Vec<_>>>()?;
+
+        let mut points = Vec::with_capacity(values.len());
+        for (index, (value, config)) in values.iter().zip(configs).enumerate() {
+            log_info!(
+                "📐 Sweep {}/{}: {} = {}",
+                index + 1,
+                values.len(),
+                param,
+                value
+            );
+            let stats = RequestHandler::run_concurrent_requests(config).await;
+            points.push(SweepPoint {
+                value: value.clone(),
+                stats,
+            });
+        }
+        Ok(points)
+    }
+}
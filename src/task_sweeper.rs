@@ -0,0 +1,388 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fs::{File, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use crate::config::{
+    DigestAuthConfig, HttpRequestConfig, RequestConfig, TargetConfig, TaskCleanupConfig,
+};
+use crate::http_client::{AuthConfig, HttpClient, HttpClientConfig};
+use crate::isapi::{IsapiClient, device_origin, task_ids_in_body};
+use crate::observer::RequestObserver;
+use crate::stats::RequestResult;
+
+// Import logger macros from crate root
+use crate::{log_error, log_info, log_warn};
+
+pub const DEFAULT_LEDGER_FILE: &str = "remote_task_tasks.json";
+const DEFAULT_TASK_ID_FIELD: &str = "taskID";
+
+/// 记录文件中超过该行数且多于存活任务数的两倍时压缩
+const LEDGER_COMPACT_LINES: usize = 1000;
+
+/// 本工具创建、尚未确认删除的任务，按设备地址（scheme://host:port）分组并持久化到文件
+///
+/// 文件为追加写入的JSON Lines日志（每次记录或移除追加一行，不重写整个文件），加载时重放并压缩；
+/// 旧版本写入的整体JSON对象仍可读取。进程崩溃后文件仍保留，`cleanup` 子命令据此删除残留任务。
+pub struct TaskLedger {
+    path: PathBuf,
+    state: Mutex<LedgerState>,
+}
+
+struct LedgerState {
+    tasks: BTreeMap<String, BTreeSet<String>>,
+    log: Option<File>, // 追加写入的记录文件，首次写入时打开
+    lines: usize,      // 记录文件中的行数
+}
+
+/// 记录文件中的一行
+#[derive(Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "snake_case")]
+enum LedgerEntry {
+    Add {
+        origin: String,
+        task_id: String,
+    },
+    Remove {
+        origin: String,
+        task_ids: Vec<String>,
+    },
+}
+
+impl TaskLedger {
+    /// 加载记录文件，不存在时为空
+    pub fn load(path: impl Into<PathBuf>) -> Result<Self> {
+        let path = path.into();
+        let tasks = match std::fs::read_to_string(&path) {
+            Ok(content) => Self::replay(&content)
+                .with_context(|| format!("Invalid task ledger {}", path.display()))?,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => BTreeMap::new(),
+            Err(e) => {
+                return Err(e).with_context(|| format!("Failed to read {}", path.display()));
+            }
+        };
+        let ledger = Self {
+            path,
+            state: Mutex::new(LedgerState {
+                tasks,
+                log: None,
+                lines: 0,
+            }),
+        };
+        ledger.compact(&mut ledger.state.lock().unwrap());
+        Ok(ledger)
+    }
+
+    /// 记录文件路径
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// 当前记录的任务
+    pub fn tasks(&self) -> BTreeMap<String, BTreeSet<String>> {
+        self.state.lock().unwrap().tasks.clone()
+    }
+
+    /// 记录新创建的任务
+    pub fn record(&self, origin: &str, task_id: &str) {
+        let mut state = self.state.lock().unwrap();
+        if state
+            .tasks
+            .entry(origin.to_string())
+            .or_default()
+            .insert(task_id.to_string())
+        {
+            self.append(
+                &mut state,
+                &LedgerEntry::Add {
+                    origin: origin.to_string(),
+                    task_id: task_id.to_string(),
+                },
+            );
+        }
+    }
+
+    /// 移除已删除的任务
+    pub fn forget(&self, origin: &str, task_ids: &[String]) {
+        let mut state = self.state.lock().unwrap();
+        let Some(origin_tasks) = state.tasks.get_mut(origin) else {
+            return;
+        };
+        let removed: Vec<String> = task_ids
+            .iter()
+            .filter(|task_id| origin_tasks.remove(*task_id))
+            .cloned()
+            .collect();
+        if removed.is_empty() {
+            return;
+        }
+        if origin_tasks.is_empty() {
+            state.tasks.remove(origin);
+        }
+        if state.tasks.is_empty() {
+            // 全部清空时删除文件
+            self.compact(&mut state);
+            return;
+        }
+        self.append(
+            &mut state,
+            &LedgerEntry::Remove {
+                origin: origin.to_string(),
+                task_ids: removed,
+            },
+        );
+        let live: usize = state.tasks.values().map(BTreeSet::len).sum();
+        if state.lines > LEDGER_COMPACT_LINES && state.lines > live * 2 {
+            self.compact(&mut state);
+        }
+    }
+
+    /// 重放记录文件；整体为JSON对象时按旧版本的格式读取
+    fn replay(content: &str) -> Result<BTreeMap<String, BTreeSet<String>>> {
+        if let Ok(tasks) = serde_json::from_str(content) {
+            return Ok(tasks);
+        }
+        let mut tasks: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        for (number, line) in content.lines().enumerate() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let entry = match serde_json::from_str(line) {
+                Ok(entry) => entry,
+                // 崩溃时最后一行可能只写了一半
+                Err(_) if number + 1 == content.lines().count() => break,
+                Err(e) => return Err(e).with_context(|| format!("line {}", number + 1)),
+            };
+            match entry {
+                LedgerEntry::Add { origin, task_id } => {
+                    tasks.entry(origin).or_default().insert(task_id);
+                }
+                LedgerEntry::Remove { origin, task_ids } => {
+                    if let Some(origin_tasks) = tasks.get_mut(&origin) {
+                        for task_id in &task_ids {
+                            origin_tasks.remove(task_id);
+                        }
+                        if origin_tasks.is_empty() {
+                            tasks.remove(&origin);
+                        }
+                    }
+                }
+            }
+        }
+        Ok(tasks)
+    }
+
+    /// 在记录文件末尾追加一行
+    fn append(&self, state: &mut LedgerState, entry: &LedgerEntry) {
+        let result = (|| {
+            if state.log.is_none() {
+                state.log = Some(
+                    OpenOptions::new()
+                        .create(true)
+                        .append(true)
+                        .open(&self.path)?,
+                );
+            }
+            let line = serde_json::to_string(entry).map_err(std::io::Error::other)?;
+            writeln!(state.log.as_mut().unwrap(), "{}", line)
+        })();
+        match result {
+            Ok(()) => state.lines += 1,
+            Err(e) => log_warn!(
+                "Failed to update task ledger {}: {}",
+                self.path.display(),
+                e
+            ),
+        }
+    }
+
+    /// 按当前任务重写记录文件（先写临时文件再替换），全部清空时删除文件
+    fn compact(&self, state: &mut LedgerState) {
+        state.log = None;
+        state.lines = 0;
+        let result = if state.tasks.is_empty() {
+            match std::fs::remove_file(&self.path) {
+                Err(e) if e.kind() == std::io::ErrorKind::NotFound => Ok(()),
+                result => result,
+            }
+        } else {
+            let mut content = String::new();
+            for (origin, task_ids) in &state.tasks {
+                for task_id in task_ids {
+                    let entry = LedgerEntry::Add {
+                        origin: origin.clone(),
+                        task_id: task_id.clone(),
+                    };
+                    content.push_str(&serde_json::to_string(&entry).unwrap_or_default());
+                    content.push('\n');
+                    state.lines += 1;
+                }
+            }
+            let temporary = self.path.with_extension("tmp");
+            std::fs::write(&temporary, content)
+                .and_then(|_| std::fs::rename(&temporary, &self.path))
+        };
+        if let Err(e) = result {
+            log_warn!(
+                "Failed to update task ledger {}: {}",
+                self.path.display(),
+                e
+            );
+        }
+    }
+}
+
+/// 一次清理的结果
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SweepReport {
+    pub deleted: usize,      // 从设备上删除的残留任务数
+    pub already_gone: usize, // 记录中存在但设备上已不存在的任务数
+    pub failed_devices: usize,
+}
+
+/// 遗留任务清理器
+///
+/// 作为观察者在运行期间记录请求A创建的任务ID（请求发出即记录，以覆盖中途退出的情况），
+/// 请求B成功后移除；清理时查询设备任务列表，删除其中由本工具创建的任务。
+pub struct TaskSweeper {
+    ledger: Arc<TaskLedger>,
+    task_id_field: String,
+}
+
+impl TaskSweeper {
+    pub fn new(config: &TaskCleanupConfig) -> Result<Self> {
+        Ok(Self {
+            ledger: Arc::new(TaskLedger::load(Self::ledger_path(config))?),
+            task_id_field: config
+                .task_id_field
+                .clone()
+                .unwrap_or_else(|| DEFAULT_TASK_ID_FIELD.to_string()),
+        })
+    }
+
+    /// 配置的记录文件路径
+    pub fn ledger_path(config: &TaskCleanupConfig) -> PathBuf {
+        PathBuf::from(config.ledger_file.as_deref().unwrap_or(DEFAULT_LEDGER_FILE))
+    }
+
+    pub fn ledger(&self) -> &TaskLedger {
+        &self.ledger
+    }
+
+    /// 查询各设备的任务列表并删除记录中仍存在的任务，成功处理的设备从记录中移除
+    pub async fn sweep(ledger: &TaskLedger, config: &RequestConfig) -> SweepReport {
+        let mut report = SweepReport::default();
+        for (origin, task_ids) in ledger.tasks() {
+            match Self::sweep_device(&origin, &task_ids, config).await {
+                Ok((deleted, already_gone)) => {
+                    report.deleted += deleted.len();
+                    report.already_gone += already_gone.len();
+                    let task_ids: Vec<String> = task_ids.into_iter().collect();
+                    ledger.forget(&origin, &task_ids);
+                    if !deleted.is_empty() {
+                        log_info!(
+                            "🧹 Deleted {} leftover task(s) on {}: {}",
+                            deleted.len(),
+                            origin,
+                            deleted.join(", ")
+                        );
+                    }
+                }
+                Err(e) => {
+                    report.failed_devices += 1;
+                    log_error!("Task cleanup on {} failed: {:#}", origin, e);
+                }
+            }
+        }
+        report
+    }
+
+    /// 清理单个设备，返回 (已删除, 已不存在) 的任务ID
+    async fn sweep_device(
+        origin: &str,
+        task_ids: &BTreeSet<String>,
+        config: &RequestConfig,
+    ) -> Result<(Vec<String>, Vec<String>)> {
+        let http_client = HttpClient::new(HttpClientConfig {
+            auth: Self::credentials_for(origin, config)
+                .map(|auth| AuthConfig::from_config(auth, str::to_string)),
+            unix_socket: Self::unix_socket_for(origin, config).map(str::to_string),
+            ..Default::default()
+        })?;
+        let isapi = IsapiClient::new(origin, Arc::new(http_client));
+
+        let on_device: BTreeSet<String> = isapi.task_list().await?.into_iter().collect();
+        let (leftover, already_gone): (Vec<String>, Vec<String>) = task_ids
+            .iter()
+            .cloned()
+            .partition(|task_id| on_device.contains(task_id));
+        if !leftover.is_empty() {
+            let status = isapi.delete_tasks(&leftover).await?;
+            if !status.is_ok() {
+                anyhow::bail!("DeleteTask returned {}", status.status_string);
+            }
+        }
+        Ok((leftover, already_gone))
+    }
+
+    /// 设备对应的凭据：优先使用同地址目标的专属凭据，否则使用全局digest_auth
+    fn credentials_for<'a>(
+        origin: &str,
+        config: &'a RequestConfig,
+    ) -> Option<&'a DigestAuthConfig> {
+        Self::target_for(origin, config)
+            .and_then(|target| target.digest_auth.as_ref())
+            .or(config.digest_auth.as_ref())
+    }
+
+    /// 设备使用的Unix域套接字：目标配置优先，其次为全局配置
+    fn unix_socket_for<'a>(origin: &str, config: &'a RequestConfig) -> Option<&'a str> {
+        Self::target_for(origin, config)
+            .and_then(|target| target.unix_socket.as_deref())
+            .or(config.unix_socket.as_deref())
+    }
+
+    fn target_for<'a>(origin: &str, config: &'a RequestConfig) -> Option<&'a TargetConfig> {
+        config
+            .targets
+            .iter()
+            .flatten()
+            .find(|target| device_origin(&target.base_url).as_deref() == Some(origin))
+    }
+
+    /// 请求body中的任务ID
+    fn task_id(&self, request: &HttpRequestConfig) -> Option<(String, Vec<String>)> {
+        let origin = device_origin(&request.url)?;
+        let task_ids = task_ids_in_body(request.body.as_deref()?, &self.task_id_field);
+        (!task_ids.is_empty()).then_some((origin, task_ids))
+    }
+}
+
+impl RequestObserver for TaskSweeper {
+    fn on_request_sent(&self, _cycle: usize, label: &str, request: &HttpRequestConfig) {
+        if label == "A"
+            && let Some((origin, task_ids)) = self.task_id(request)
+        {
+            for task_id in &task_ids {
+                self.ledger.record(&origin, task_id);
+            }
+        }
+    }
+
+    fn on_result(&self, result: &RequestResult) {
+        if result.label != "B" || !result.success {
+            return;
+        }
+        let request = HttpRequestConfig {
+            url: result.url.clone(),
+            body: result.request_body.clone(),
+            ..Default::default()
+        };
+        if let Some((origin, task_ids)) = self.task_id(&request) {
+            self.ledger.forget(&origin, &task_ids);
+        }
+    }
+}
@@ -0,0 +1,100 @@
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use crate::config::{HttpRequestConfig, TaskVerificationConfig};
+use crate::http_client::HttpClient;
+use crate::isapi::{IsapiClient, device_origin, task_ids_in_body};
+
+// Import logger macros from crate root
+use crate::{log_debug, log_error};
+
+/// 任务存在性校验：请求A成功后确认任务出现在设备任务列表中，请求B成功后确认任务已消失
+///
+/// 校验失败的请求计为失败，并按 "create" / "delete" 单独计数。
+pub struct TaskVerifier {
+    task_id_field: String,
+    after_create: bool,
+    after_delete: bool,
+    settle: Duration,
+    failures: Mutex<BTreeMap<String, usize>>,
+}
+
+impl TaskVerifier {
+    pub fn new(config: &TaskVerificationConfig) -> Self {
+        Self {
+            task_id_field: config
+                .task_id_field
+                .clone()
+                .unwrap_or_else(|| "taskID".to_string()),
+            after_create: config.after_create.unwrap_or(true),
+            after_delete: config.after_delete.unwrap_or(true),
+            settle: Duration::from_millis(config.settle_ms.unwrap_or(0)),
+            failures: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// 是否在请求A成功后校验任务已创建（请求B需等该校验完成后再发送）
+    pub fn verifies_create(&self) -> bool {
+        self.after_create
+    }
+
+    /// 校验请求对应的任务状态，返回失败原因（无需校验或校验通过时为None）
+    pub async fn verify(
+        &self,
+        label: &str,
+        request: &HttpRequestConfig,
+        http_client: Arc<HttpClient>,
+        record: bool,
+    ) -> Option<String> {
+        let (kind, should_exist) = match label {
+            "A" if self.after_create => ("create", true),
+            "B" if self.after_delete => ("delete", false),
+            _ => return None,
+        };
+        let origin = device_origin(&request.url)?;
+        let task_ids = task_ids_in_body(request.body.as_deref()?, &self.task_id_field);
+        if task_ids.is_empty() {
+            return None;
+        }
+
+        if !self.settle.is_zero() {
+            tokio::time::sleep(self.settle).await;
+        }
+        let failure = match IsapiClient::new(origin, http_client).task_list().await {
+            Ok(on_device) => {
+                let mismatched: Vec<&String> = task_ids
+                    .iter()
+                    .filter(|task_id| on_device.contains(task_id) != should_exist)
+                    .collect();
+                if mismatched.is_empty() {
+                    log_debug!("🔎 Task {} verified: {}", kind, task_ids.join(", "));
+                    return None;
+                }
+                let ids: Vec<&str> = mismatched.iter().map(|id| id.as_str()).collect();
+                if should_exist {
+                    format!("task {} not found in device task list", ids.join(", "))
+                } else {
+                    format!("task {} still in device task list", ids.join(", "))
+                }
+            }
+            Err(e) => format!("task list query failed: {:#}", e),
+        };
+
+        log_error!("🔎 Task {} verification failed: {}", kind, failure);
+        if record {
+            *self
+                .failures
+                .lock()
+                .unwrap()
+                .entry(kind.to_string())
+                .or_default() += 1;
+        }
+        Some(format!("{} verification: {}", kind, failure))
+    }
+
+    /// 校验失败次数（按 "create" / "delete"）
+    pub fn failures(&self) -> BTreeMap<String, usize> {
+        self.failures.lock().unwrap().clone()
+    }
+}
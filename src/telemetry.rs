@@ -0,0 +1,80 @@
+use anyhow::{Context, Result};
+use opentelemetry::propagation::TextMapPropagator;
+use opentelemetry::trace::TracerProvider as _;
+use opentelemetry_otlp::{SpanExporter, WithExportConfig};
+use opentelemetry_sdk::propagation::TraceContextPropagator;
+use opentelemetry_sdk::trace::SdkTracerProvider;
+use opentelemetry_sdk::trace::span_processor_with_async_runtime::BatchSpanProcessor;
+use opentelemetry_sdk::{Resource, runtime};
+use std::collections::HashMap;
+use tracing_opentelemetry::OpenTelemetrySpanExt;
+use tracing_subscriber::layer::SubscriberExt;
+use tracing_subscriber::util::SubscriberInitExt;
+
+use crate::config::TracingConfig;
+
+// Import logger macros from crate root
+use crate::log_warn;
+
+/// OpenTelemetry追踪：将周期、请求、认证往返等span通过OTLP导出
+pub struct Telemetry {
+    provider: SdkTracerProvider,
+}
+
+impl Telemetry {
+    /// 创建OTLP导出器并安装为全局tracing订阅者
+    ///
+    /// span在tokio运行时上的后台任务中批量导出（异步HTTP客户端），不占用请求任务所在的线程。
+    /// 需要在tokio运行时内调用。
+    pub fn init(config: &TracingConfig) -> Result<Self> {
+        let exporter = SpanExporter::builder()
+            .with_http()
+            .with_endpoint(&config.otlp_endpoint)
+            .build()
+            .context("Failed to create OTLP exporter")?;
+        let provider = SdkTracerProvider::builder()
+            .with_span_processor(BatchSpanProcessor::builder(exporter, runtime::Tokio).build())
+            .with_resource(
+                Resource::builder()
+                    .with_service_name(
+                        config
+                            .service_name
+                            .clone()
+                            .unwrap_or_else(|| "remote-task".to_string()),
+                    )
+                    .build(),
+            )
+            .build();
+
+        let tracer = provider.tracer("remote_task");
+        tracing_subscriber::registry()
+            .with(tracing_opentelemetry::layer().with_tracer(tracer))
+            .try_init()
+            .context("Failed to install tracing subscriber")?;
+
+        Ok(Self { provider })
+    }
+
+    /// 导出剩余的span并关闭导出器
+    ///
+    /// 关闭时同步等待后台导出任务完成，放在阻塞线程池中执行，避免占住运行时的工作线程
+    /// （单工作线程的运行时上会死锁）。
+    pub async fn shutdown(self) {
+        let provider = self.provider;
+        match tokio::task::spawn_blocking(move || provider.shutdown()).await {
+            Ok(Ok(())) => {}
+            Ok(Err(e)) => log_warn!("Failed to flush traces: {}", e),
+            Err(e) => log_warn!("Failed to flush traces: {}", e),
+        }
+    }
+
+    /// 当前span的W3C trace context请求头（traceparent），用于与服务端追踪关联
+    ///
+    /// 未启用追踪时返回空。
+    pub fn trace_context_headers() -> Vec<(String, String)> {
+        let context = tracing::Span::current().context();
+        let mut headers = HashMap::new();
+        TraceContextPropagator::new().inject_context(&context, &mut headers);
+        headers.into_iter().collect()
+    }
+}
@@ -0,0 +1,359 @@
+use anyhow::{Result, anyhow, bail};
+use base64::prelude::*;
+use md5::Md5;
+use regex::Regex;
+use sha2::{Digest, Sha256};
+use std::collections::HashMap;
+use std::sync::{LazyLock, Mutex};
+
+/// 按表达式缓存的正则（模板过滤器和捕获每次渲染/匹配都会用到，只在首次使用某个表达式时编译）
+static REGEXES: LazyLock<Mutex<HashMap<String, Regex>>> = LazyLock::new(Mutex::default);
+/// 缓存的正则数上限，超过时清空（表达式通常来自配置，数量很少）
+const MAX_CACHED_REGEXES: usize = 1024;
+
+/// 将模板中的 `{{name}}` 占位符替换为变量值
+///
+/// 占位符两侧允许空白（`{{ name }}`），未找到对应变量的占位符保持原样。
+/// 变量后可接过滤器，如 `{{ name | regex_replace('\d+', 'N') }}`、`{{ name | sha256 | base64 }}`，
+/// 过滤器出错时占位符保持原样。
+pub fn render(template: &str, vars: &HashMap<String, String>) -> String {
+    let mut output = String::with_capacity(template.len());
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            // 没有闭合的占位符，原样输出剩余部分
+            output.push_str(&rest[start..]);
+            return output;
+        };
+
+        let (name, filters) = split_filters(&after[..end]);
+        let rendered = vars
+            .get(name)
+            .and_then(|value| apply_filters(value, &filters).ok());
+        match rendered {
+            Some(value) => output.push_str(&value),
+            None => output.push_str(&rest[start..start + end + 4]),
+        }
+        rest = &after[end + 2..];
+    }
+
+    output.push_str(rest);
+    output
+}
+
+/// 提取模板中所有 `{{name}}` 占位符的变量名（按出现顺序，含重复，不含过滤器）
+pub fn placeholders(template: &str) -> Vec<&str> {
+    placeholder_contents(template)
+        .into_iter()
+        .map(|content| split_filters(content).0)
+        .collect()
+}
+
+/// 为 `name` 的每次出现编号：出现多次时第k次改写为 `{{name#k}}`（保留过滤器），返回改写后的模板和各次出现的变量名
+///
+/// 用于同一占位符的各次出现需要渲染为不同值的场合（如padding平分余数）。
+pub fn number_occurrences(template: &str, name: &str) -> (String, Vec<String>) {
+    let count = placeholders(template)
+        .into_iter()
+        .filter(|placeholder| *placeholder == name)
+        .count();
+    if count <= 1 {
+        return (template.to_string(), vec![name.to_string(); count]);
+    }
+
+    let mut output = String::with_capacity(template.len());
+    let mut names = Vec::with_capacity(count);
+    let mut rest = template;
+    while let Some(start) = rest.find("{{") {
+        output.push_str(&rest[..start]);
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        let content = &after[..end];
+        if split_filters(content).0 == name {
+            let numbered = format!("{}#{}", name, names.len() + 1);
+            output.push_str("{{");
+            output.push_str(&content.replacen(name, &numbered, 1));
+            output.push_str("}}");
+            names.push(numbered);
+        } else {
+            output.push_str(&rest[start..start + end + 4]);
+        }
+        rest = &after[end + 2..];
+    }
+    output.push_str(rest);
+    (output, names)
+}
+
+/// 将旧版单花括号占位符 `{name}` 改写为 `{{name}}`，只改写 `names` 中的名称，已是 `{{name}}` 的部分不变
+///
+/// 生成字段的占位符原为 `{name}`，兼容旧配置的body。
+pub fn upgrade_legacy<'a>(template: &str, names: impl IntoIterator<Item = &'a String>) -> String {
+    let mut output = template.to_string();
+    for name in names {
+        let legacy = format!("{{{}}}", name);
+        let mut upgraded = String::with_capacity(output.len());
+        let mut rest = output.as_str();
+        while let Some(start) = rest.find(&legacy) {
+            let end = start + legacy.len();
+            let doubled = (start == 0 && upgraded.ends_with('{'))
+                || rest[..start].ends_with('{')
+                || rest[end..].starts_with('}');
+            upgraded.push_str(&rest[..start]);
+            if doubled {
+                upgraded.push_str(&legacy);
+            } else {
+                upgraded.push_str(&format!("{{{}}}", legacy));
+            }
+            rest = &rest[end..];
+        }
+        upgraded.push_str(rest);
+        output = upgraded;
+    }
+    output
+}
+
+/// 模板中引用了 `names` 中哪些名称的旧版 `{name}` 占位符（按 `names` 的顺序）
+pub fn legacy_placeholders<'a>(
+    template: &str,
+    names: impl IntoIterator<Item = &'a String>,
+) -> Vec<&'a String> {
+    names
+        .into_iter()
+        .filter(|name| upgrade_legacy(template, [*name]) != template)
+        .collect()
+}
+
+/// 检查模板中的过滤器（名称、参数个数、正则表达式），返回所有错误
+pub fn filter_errors(template: &str) -> Vec<String> {
+    let mut errors = Vec::new();
+    for content in placeholder_contents(template) {
+        let (name, filters) = split_filters(content);
+        for filter in filters {
+            if let Err(e) = parse_filter(filter).and_then(|filter| filter.check()) {
+                errors.push(format!("{{{{{}}}}}: {}", name, e));
+            }
+        }
+    }
+    errors
+}
+
+/// 对值依次应用 `|` 分隔的过滤器链，如 `"sha256 | base64"`
+pub fn apply_filter_chain(value: &str, chain: &str) -> Result<String> {
+    apply_filters(value, &split_pipes(chain))
+}
+
+/// 检查过滤器链，返回所有错误
+pub fn filter_chain_errors(chain: &str) -> Vec<String> {
+    split_pipes(chain)
+        .into_iter()
+        .filter_map(|filter| {
+            parse_filter(filter)
+                .and_then(|filter| filter.check())
+                .err()
+                .map(|e| e.to_string())
+        })
+        .collect()
+}
+
+fn placeholder_contents(template: &str) -> Vec<&str> {
+    let mut contents = Vec::new();
+    let mut rest = template;
+
+    while let Some(start) = rest.find("{{") {
+        let after = &rest[start + 2..];
+        let Some(end) = after.find("}}") else {
+            break;
+        };
+        contents.push(&after[..end]);
+        rest = &after[end + 2..];
+    }
+
+    contents
+}
+
+/// 拆分占位符内容为变量名和过滤器表达式
+fn split_filters(content: &str) -> (&str, Vec<&str>) {
+    let mut parts = split_pipes(content);
+    let name = parts.remove(0);
+    (name, parts)
+}
+
+/// 按 `|` 拆分（忽略引号内的 `|`），各部分去除两侧空白
+fn split_pipes(content: &str) -> Vec<&str> {
+    let mut parts = Vec::new();
+    let mut quote = None;
+    let mut start = 0;
+    for (index, c) in content.char_indices() {
+        match (quote, c) {
+            (Some(q), _) if c == q => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"') => quote = Some(c),
+            (None, '|') => {
+                parts.push(content[start..index].trim());
+                start = index + 1;
+            }
+            _ => {}
+        }
+    }
+    parts.push(content[start..].trim());
+    parts
+}
+
+/// 模板过滤器
+struct Filter {
+    name: String,
+    args: Vec<String>,
+}
+
+impl Filter {
+    fn check(&self) -> Result<()> {
+        match self.name.as_str() {
+            "regex_replace" => {
+                if self.args.len() != 2 {
+                    bail!("regex_replace expects 2 arguments (pattern, replacement)");
+                }
+                regex(&self.args[0])?;
+                Ok(())
+            }
+            "base64" | "base64url" | "hex" | "md5" | "sha256" | "urlencode" | "json_escape" => {
+                if !self.args.is_empty() {
+                    bail!("{} takes no arguments", self.name);
+                }
+                Ok(())
+            }
+            name => bail!("unknown filter \"{}\"", name),
+        }
+    }
+
+    fn apply(&self, value: &str) -> Result<String> {
+        self.check()?;
+        match self.name.as_str() {
+            "regex_replace" => Ok(regex(&self.args[0])?
+                .replace_all(value, self.args[1].as_str())
+                .into_owned()),
+            "base64" => Ok(BASE64_STANDARD.encode(value)),
+            // URL安全字母表，不带填充
+            "base64url" => Ok(BASE64_URL_SAFE_NO_PAD.encode(value)),
+            "hex" => Ok(hex(value.as_bytes())),
+            // 摘要输出为小写十六进制
+            "md5" => Ok(hex(&Md5::digest(value))),
+            "sha256" => Ok(hex(&Sha256::digest(value))),
+            "urlencode" => Ok(urlencode(value)),
+            // 转义为JSON字符串内容（不含两侧引号），用于把JSON文本嵌入字符串字段
+            "json_escape" => {
+                let quoted = serde_json::to_string(value)?;
+                Ok(quoted[1..quoted.len() - 1].to_string())
+            }
+            _ => unreachable!("checked above"),
+        }
+    }
+}
+
+/// 编译正则，已编译过的表达式直接取缓存
+pub(crate) fn regex(pattern: &str) -> Result<Regex> {
+    let mut regexes = REGEXES.lock().unwrap();
+    if let Some(regex) = regexes.get(pattern) {
+        return Ok(regex.clone());
+    }
+    let regex = Regex::new(pattern).map_err(|e| anyhow!("invalid regex: {}", e))?;
+    if regexes.len() >= MAX_CACHED_REGEXES {
+        regexes.clear();
+    }
+    regexes.insert(pattern.to_string(), regex.clone());
+    Ok(regex)
+}
+
+fn hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+/// 百分号编码，只保留RFC 3986的非保留字符（字母、数字、`-`、`_`、`.`、`~`）
+pub(crate) fn urlencode(value: &str) -> String {
+    let mut encoded = String::with_capacity(value.len());
+    for byte in value.bytes() {
+        if byte.is_ascii_alphanumeric() || matches!(byte, b'-' | b'_' | b'.' | b'~') {
+            encoded.push(byte as char);
+        } else {
+            encoded.push_str(&format!("%{:02X}", byte));
+        }
+    }
+    encoded
+}
+
+/// 解码 `%XX`（[`urlencode`] 的逆操作），无效的转义原样保留
+pub(crate) fn percent_decode(value: &str) -> String {
+    let bytes = value.as_bytes();
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let hex = value
+            .get(index + 1..index + 3)
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok());
+        match (bytes[index], hex) {
+            (b'%', Some(byte)) => {
+                decoded.push(byte);
+                index += 3;
+            }
+            (byte, _) => {
+                decoded.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&decoded).into_owned()
+}
+
+fn apply_filters(value: &str, filters: &[&str]) -> Result<String> {
+    let mut value = value.to_string();
+    for filter in filters {
+        value = parse_filter(filter)?.apply(&value)?;
+    }
+    Ok(value)
+}
+
+/// 解析 `name` 或 `name('arg', "arg")` 形式的过滤器表达式
+fn parse_filter(expression: &str) -> Result<Filter> {
+    let Some(open) = expression.find('(') else {
+        return Ok(Filter {
+            name: expression.trim().to_string(),
+            args: Vec::new(),
+        });
+    };
+    let name = expression[..open].trim().to_string();
+    let inner = expression[open + 1..]
+        .trim_end()
+        .strip_suffix(')')
+        .ok_or_else(|| anyhow!("missing ')' in filter \"{}\"", expression))?;
+
+    let mut args = Vec::new();
+    let mut chars = inner.chars().peekable();
+    loop {
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        let Some(quote) = chars.next() else {
+            break;
+        };
+        if quote != '\'' && quote != '"' {
+            bail!("filter arguments must be quoted in \"{}\"", expression);
+        }
+        let mut arg = String::new();
+        loop {
+            match chars.next() {
+                Some(c) if c == quote => break,
+                Some(c) => arg.push(c),
+                None => bail!("unterminated argument in filter \"{}\"", expression),
+            }
+        }
+        args.push(arg);
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match chars.next() {
+            Some(',') | None => {}
+            Some(c) => bail!("unexpected '{}' in filter \"{}\"", c, expression),
+        }
+    }
+    Ok(Filter { name, args })
+}
@@ -0,0 +1,176 @@
+use anyhow::{Result, anyhow, bail};
+use chrono::{Datelike, Timelike};
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use crate::config::TimeWindowsConfig;
+use crate::cron::{format_timestamp, local_time, parse_utc_offset};
+
+// Import logger macros from crate root
+use crate::log_info;
+
+const DAY: i64 = 86_400;
+const WEEK: i64 = 7 * DAY;
+
+/// 运行时间窗：按 `TimeList` 的每周时段判断当前是否允许开始新周期
+///
+/// 时段换算为本地时间下一周内的秒数区间（周一00:00为0），跨越午夜的时段延续到次日，
+/// 周日跨越午夜的时段延续到下周一。
+#[derive(Debug, Clone)]
+pub struct TimeWindows {
+    ranges: Vec<(i64, i64)>,
+    offset_secs: i64,
+}
+
+impl TimeWindows {
+    /// 解析时间窗，`fallback_offset` 为未配置 `utc_offset` 时使用的时区偏移（如 daemon.utc_offset）
+    pub fn new(config: &TimeWindowsConfig, fallback_offset: Option<&str>) -> Result<Self> {
+        let offset_secs = config
+            .utc_offset
+            .as_deref()
+            .or(fallback_offset)
+            .map(parse_utc_offset)
+            .transpose()?
+            .unwrap_or(0);
+        let mut ranges = Vec::new();
+        for entry in &config.time_list {
+            let days = parse_days(&entry.day)?;
+            for range in &entry.time_range {
+                let start = parse_time(&range.start_time)?;
+                let mut end = parse_time(&range.end_time)?;
+                // 与设备的TimeList一致，23:59:59 表示到当天结束
+                if end == DAY - 1 {
+                    end = DAY;
+                }
+                if start == end {
+                    bail!(
+                        "time range {}-{} is empty",
+                        range.start_time,
+                        range.end_time
+                    );
+                }
+                for &day in &days {
+                    let base = day * DAY;
+                    let end = if end > start { end } else { end + DAY };
+                    ranges.push((base + start, base + end));
+                }
+            }
+        }
+        if ranges.is_empty() {
+            bail!("time_list has no time ranges, the run would never start");
+        }
+        Ok(Self {
+            ranges,
+            offset_secs,
+        })
+    }
+
+    /// `unix_secs` 时刻是否在允许的时段内
+    pub fn is_open(&self, unix_secs: i64) -> bool {
+        let second = self.second_of_week(unix_secs);
+        self.ranges.iter().any(|&(start, end)| {
+            (start..end).contains(&second) || (start..end).contains(&(second + WEEK))
+        })
+    }
+
+    /// `unix_secs` 之后（含）最近一次进入允许时段的时刻（Unix秒）
+    pub fn next_open(&self, unix_secs: i64) -> i64 {
+        if self.is_open(unix_secs) {
+            return unix_secs;
+        }
+        let second = self.second_of_week(unix_secs);
+        let wait = self
+            .ranges
+            .iter()
+            .map(|&(start, _)| (start - second).rem_euclid(WEEK))
+            .min()
+            .unwrap_or(0);
+        unix_secs + wait
+    }
+
+    /// 时段外等待到下一个时段开始；已在时段内时立即返回
+    pub async fn wait_until_open(&self) {
+        loop {
+            let now = unix_millis();
+            let next = self.next_open(now.div_euclid(1000));
+            if next * 1000 <= now {
+                return;
+            }
+            log_info!(
+                "⏸️  Outside time windows, pausing until {}",
+                format_timestamp(next, self.offset_secs)
+            );
+            tokio::time::sleep(Duration::from_millis((next * 1000 - now) as u64)).await;
+            if self.is_open(unix_millis().div_euclid(1000)) {
+                log_info!("▶️  Time window open, resuming");
+                return;
+            }
+        }
+    }
+
+    /// 本地时间下一周内的秒数（周一00:00为0）
+    fn second_of_week(&self, unix_secs: i64) -> i64 {
+        local_time(unix_secs, self.offset_secs).map_or(0, |local| {
+            i64::from(local.weekday().num_days_from_monday()) * DAY
+                + i64::from(local.num_seconds_from_midnight())
+        })
+    }
+}
+
+fn unix_millis() -> i64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() as i64)
+}
+
+/// 日期名 → 周几（周一为0）
+pub fn parse_days(day: &str) -> Result<Vec<i64>> {
+    const NAMES: [&str; 7] = [
+        "monday",
+        "tuesday",
+        "wednesday",
+        "thursday",
+        "friday",
+        "saturday",
+        "sunday",
+    ];
+    let day = day.trim().to_lowercase();
+    match day.as_str() {
+        "weekdays" => Ok((0..5).collect()),
+        "weekends" => Ok(vec![5, 6]),
+        "everyday" => Ok((0..7).collect()),
+        name => NAMES
+            .iter()
+            .position(|candidate| *candidate == name)
+            .map(|index| vec![index as i64])
+            .ok_or_else(|| {
+                anyhow!(
+                    "unknown day '{}' (expected monday … sunday, weekdays, weekends or everyday)",
+                    name
+                )
+            }),
+    }
+}
+
+/// `HH:MM` 或 `HH:MM:SS` → 当天的秒数（允许 "24:00" 表示当天结束）
+pub fn parse_time(time: &str) -> Result<i64> {
+    let invalid = || anyhow!("invalid time '{}' (expected HH:MM or HH:MM:SS)", time);
+    let mut parts = time.trim().split(':');
+    let mut next = |max: i64| -> Result<Option<i64>> {
+        parts
+            .next()
+            .map(|part| {
+                part.parse::<i64>()
+                    .ok()
+                    .filter(|value| (0..=max).contains(value))
+                    .ok_or_else(invalid)
+            })
+            .transpose()
+    };
+    let hours = next(24)?.ok_or_else(invalid)?;
+    let minutes = next(59)?.ok_or_else(invalid)?;
+    let seconds = next(59)?.unwrap_or(0);
+    if parts.next().is_some() || (hours == 24 && (minutes, seconds) != (0, 0)) {
+        return Err(invalid());
+    }
+    Ok(hours * 3600 + minutes * 60 + seconds)
+}
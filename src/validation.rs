@@ -0,0 +1,2463 @@
+use regex::Regex;
+use reqwest::header::HeaderName;
+use std::collections::HashSet;
+use std::fmt;
+
+use crate::campaign::Campaign;
+use crate::capture::Capturer;
+use crate::config::{CaptureConfig, DelayDistribution, HttpRequestConfig, RequestConfig};
+use crate::cron::{CronSchedule, parse_utc_offset};
+use crate::field_generator::parse_byte_size;
+use crate::http_client::IpFamily;
+use crate::jq::JqFilter;
+use crate::logger::LogLevel;
+use crate::port_probe;
+use crate::raw_request::RawRequest;
+use crate::status_expectation::StatusExpectation;
+use crate::template;
+use crate::time_window;
+
+/// 支持的HTTP方法
+const SUPPORTED_METHODS: &[&str] = &["GET", "POST", "PUT", "PATCH", "DELETE", "RAW"];
+
+/// 支持的字段生成器
+const SUPPORTED_GENERATORS: &[&str] = &[
+    "random",
+    "timestamp",
+    "counter",
+    "uuid",
+    "ulid",
+    "nanoid",
+    "fixed",
+    "padding",
+];
+
+/// 诊断级别
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Severity {
+    Error,
+    Warning,
+}
+
+/// 单条配置诊断，`path` 为出错字段路径（如 `request_a.url`）
+#[derive(Debug, Clone)]
+pub struct Diagnostic {
+    pub severity: Severity,
+    pub path: String,
+    pub message: String,
+}
+
+impl fmt::Display for Diagnostic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let level = match self.severity {
+            Severity::Error => "error",
+            Severity::Warning => "warning",
+        };
+        write!(f, "{}: {}: {}", level, self.path, self.message)
+    }
+}
+
+/// 配置校验器，一次性收集所有问题
+pub struct ConfigValidator;
+
+impl ConfigValidator {
+    /// 校验配置，返回全部诊断（为空表示没有问题）
+    pub fn validate(config: &RequestConfig) -> Vec<Diagnostic> {
+        let mut diagnostics = Vec::new();
+        let known = Self::known_placeholders(config);
+
+        Self::validate_request(&config.request_a, "request_a", &known, &mut diagnostics);
+        Self::validate_request(&config.request_b, "request_b", &known, &mut diagnostics);
+        for (name, value) in config.default_headers.iter().flatten() {
+            let path = format!("default_headers.{}", name);
+            for placeholder in template::placeholders(value) {
+                if !known.contains(placeholder) {
+                    diagnostics.push(Self::error(
+                        path.clone(),
+                        format!(
+                            "placeholder {{{{{}}}}} has no matching generated field or variable",
+                            placeholder
+                        ),
+                    ));
+                }
+            }
+            for error in template::filter_errors(value) {
+                diagnostics.push(Self::error(path.clone(), error));
+            }
+        }
+        Self::validate_delays(config, &mut diagnostics);
+        Self::validate_step_delays(config, &mut diagnostics);
+        Self::validate_generated_fields(config, &mut diagnostics);
+        Self::validate_targets(config, &mut diagnostics);
+        Self::validate_metrics(config, &mut diagnostics);
+        Self::validate_notifications(config, &mut diagnostics);
+        Self::validate_circuit_breaker(config, &mut diagnostics);
+        Self::validate_adaptive_pacing(config, &mut diagnostics);
+        Self::validate_fault_injection(config, &mut diagnostics);
+        Self::validate_rtsp_precheck(config, &mut diagnostics);
+        Self::validate_port_probes(config, &known, &mut diagnostics);
+        Self::validate_batch(config, &mut diagnostics);
+        Self::validate_job_queue(config, &mut diagnostics);
+        Self::validate_daemon(config, &mut diagnostics);
+        Self::validate_time_windows(config, &mut diagnostics);
+        Self::validate_heartbeat(config, &mut diagnostics);
+        Self::validate_self_monitor(config, &mut diagnostics);
+        Self::validate_provisioning(config, &known, &mut diagnostics);
+        Self::validate_device_time(config, &mut diagnostics);
+        Self::validate_auth(config, &mut diagnostics);
+        Self::validate_proxy(config, &mut diagnostics);
+        Self::validate_auth_recovery(config, &mut diagnostics);
+        Self::validate_csrf(config, &mut diagnostics);
+        Self::validate_cookies(config, &mut diagnostics);
+        Self::validate_campaign(config, &mut diagnostics);
+        Self::validate_runtime(config, &mut diagnostics);
+        Self::validate_send_mode(config, &mut diagnostics);
+        Self::validate_response_limit(config, &mut diagnostics);
+        Self::validate_idempotency_key(config, &mut diagnostics);
+        Self::validate_json_templates(config, &known, &mut diagnostics);
+        Self::validate_branches(config, &known, &mut diagnostics);
+        Self::validate_repeat(config, &known, &mut diagnostics);
+        Self::validate_captures(config, &mut diagnostics);
+        Self::validate_user_agent(config, &known, &mut diagnostics);
+        if let Some(header) = config
+            .correlation_id
+            .as_ref()
+            .and_then(|correlation| correlation.header.as_deref())
+            && HeaderName::from_bytes(header.as_bytes()).is_err()
+        {
+            diagnostics.push(Self::error(
+                "correlation_id.header".to_string(),
+                format!("\"{}\" is not a valid header name", header),
+            ));
+        }
+        if let Some(cleanup) = &config.task_cleanup {
+            Self::validate_task_id_field(
+                config,
+                "task_cleanup",
+                cleanup.task_id_field.as_deref(),
+                &mut diagnostics,
+            );
+        }
+        if let Some(verification) = &config.task_verification {
+            Self::validate_task_id_field(
+                config,
+                "task_verification",
+                verification.task_id_field.as_deref(),
+                &mut diagnostics,
+            );
+        }
+        if let Some(rate_limit) = &config.rate_limit
+            && (rate_limit.requests_per_second.is_nan() || rate_limit.requests_per_second <= 0.0)
+        {
+            diagnostics.push(Self::error(
+                "rate_limit.requests_per_second".to_string(),
+                "requests_per_second must be greater than 0".to_string(),
+            ));
+        }
+        if let Some(bandwidth) = &config.bandwidth {
+            for (field, rate) in [
+                ("upload_bytes_per_second", bandwidth.upload_bytes_per_second),
+                (
+                    "download_bytes_per_second",
+                    bandwidth.download_bytes_per_second,
+                ),
+            ] {
+                if rate == Some(0) {
+                    diagnostics.push(Self::error(
+                        format!("bandwidth.{}", field),
+                        format!("{} must be greater than 0", field),
+                    ));
+                }
+            }
+            if let Some(scope) = &bandwidth.scope
+                && !matches!(scope.as_str(), "request" | "global")
+            {
+                diagnostics.push(Self::error(
+                    "bandwidth.scope".to_string(),
+                    format!("unknown scope '{}' (expected request or global)", scope),
+                ));
+            }
+        }
+
+        diagnostics
+    }
+
+    /// 是否存在错误级别的诊断
+    pub fn has_errors(diagnostics: &[Diagnostic]) -> bool {
+        diagnostics.iter().any(|d| d.severity == Severity::Error)
+    }
+
+    /// 模板中可以引用的变量名
+    fn known_placeholders(config: &RequestConfig) -> HashSet<String> {
+        let mut known = HashSet::new();
+        for field in config.generated_fields.iter().flatten() {
+            known.insert(field.name.clone());
+        }
+        for name in config
+            .variables
+            .iter()
+            .chain(&config.worker_variables)
+            .flat_map(|vars| vars.keys())
+        {
+            known.insert(name.clone());
+        }
+        for vars in config.environments.iter().flat_map(|envs| envs.values()) {
+            known.extend(vars.keys().cloned());
+        }
+        if config
+            .targets
+            .as_ref()
+            .is_some_and(|targets| !targets.is_empty())
+        {
+            known.insert("target.base_url".to_string());
+            known.insert("target.name".to_string());
+        }
+        let branch_requests = config
+            .branches
+            .iter()
+            .flatten()
+            .filter_map(|branch| branch.request.as_ref());
+        let repeat_request = config.repeat.as_ref().map(|repeat| &repeat.request);
+        for request in [&config.request_a, &config.request_b]
+            .into_iter()
+            .chain(branch_requests)
+            .chain(repeat_request)
+        {
+            for capture in request.captures.iter().flatten() {
+                known.insert(capture.name.clone());
+            }
+        }
+        if config.correlation_id.is_some() {
+            known.insert("correlation_id".to_string());
+        }
+        if let Some(batch) = &config.batch {
+            known.insert(
+                batch
+                    .list_variable
+                    .clone()
+                    .unwrap_or_else(|| "task_id_list".to_string()),
+            );
+        }
+        if let Some(queue) = &config.job_queue {
+            known.extend(queue.variables.iter().flatten().cloned());
+        }
+        if let Some(provisioning) = &config.provisioning {
+            let pool = &provisioning.pool;
+            known.insert(pool.clone());
+            known.insert(format!("{}.count", pool));
+            known.insert(format!("{}.all", pool));
+            known.extend((1..=provisioning.count).map(|position| format!("{}.{}", pool, position)));
+        }
+        known.extend(
+            config
+                .json_templates
+                .iter()
+                .flatten()
+                .map(|(name, _)| name.clone()),
+        );
+        known
+    }
+
+    fn validate_request(
+        request: &HttpRequestConfig,
+        path: &str,
+        known: &HashSet<String>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let method = request.method.to_uppercase();
+        if !SUPPORTED_METHODS.contains(&method.as_str()) {
+            diagnostics.push(Self::error(
+                format!("{}.method", path),
+                format!(
+                    "unsupported method '{}' (expected one of {})",
+                    request.method,
+                    SUPPORTED_METHODS.join(", ")
+                ),
+            ));
+        }
+
+        // 原始请求的URL为 tcp://host:port 或 tls://host:port，头部需写在body中
+        if method == "RAW" {
+            if !request.url.starts_with("{{")
+                && let Err(e) = RawRequest::parse_url(&request.url)
+            {
+                diagnostics.push(Self::error(format!("{}.url", path), e.to_string()));
+            }
+            if request
+                .headers
+                .as_ref()
+                .is_some_and(|headers| !headers.is_empty())
+                || request.user_agent.is_some()
+            {
+                diagnostics.push(Self::warning(
+                    format!("{}.headers", path),
+                    "headers are ignored by RAW requests, write them into the body".to_string(),
+                ));
+            }
+        }
+
+        // 以占位符开头的URL（如 {{target.base_url}}/...）在渲染后才能确定scheme
+        if method != "RAW"
+            && !request.url.starts_with("{{")
+            && !request.url.starts_with("http://")
+            && !request.url.starts_with("https://")
+        {
+            diagnostics.push(Self::error(
+                format!("{}.url", path),
+                format!("'{}' must start with http:// or https://", request.url),
+            ));
+        }
+
+        if (method == "POST" || method == "PUT" || method == "RAW")
+            && request
+                .body
+                .as_deref()
+                .is_none_or(|body| body.trim().is_empty())
+        {
+            diagnostics.push(Self::error(
+                format!("{}.body", path),
+                format!("{} request requires a body", method),
+            ));
+        }
+
+        if let Some(download) = &request.download {
+            if download.path.trim().is_empty() {
+                diagnostics.push(Self::error(
+                    format!("{}.download.path", path),
+                    "path is empty".to_string(),
+                ));
+            }
+            if let Some(sha256) = &download.sha256
+                && !(sha256.trim().len() == 64
+                    && sha256.trim().chars().all(|c| c.is_ascii_hexdigit()))
+            {
+                diagnostics.push(Self::error(
+                    format!("{}.download.sha256", path),
+                    "sha256 must be 64 hex digits".to_string(),
+                ));
+            }
+            if method == "RAW" {
+                diagnostics.push(Self::warning(
+                    format!("{}.download", path),
+                    "RAW requests are not streamed to disk".to_string(),
+                ));
+            }
+            if request.expect_json.is_some() {
+                diagnostics.push(Self::warning(
+                    format!("{}.expect_json", path),
+                    "downloaded bodies are written to disk and not compared".to_string(),
+                ));
+            }
+            if download.max_resumes.unwrap_or(0) > 0 && method != "GET" {
+                diagnostics.push(Self::warning(
+                    format!("{}.download.max_resumes", path),
+                    format!(
+                        "resuming re-sends the {} request with a Range header",
+                        method
+                    ),
+                ));
+            }
+        }
+
+        for (index, tag) in request.tags.iter().flatten().enumerate() {
+            if tag.trim().is_empty() {
+                diagnostics.push(Self::error(
+                    format!("{}.tags[{}]", path, index),
+                    "tag is empty".to_string(),
+                ));
+            }
+        }
+
+        for (index, filter) in request.expect_jq.iter().flatten().enumerate() {
+            if let Err(e) = JqFilter::check(filter) {
+                diagnostics.push(Self::error(
+                    format!("{}.expect_jq[{}]", path, index),
+                    e.to_string(),
+                ));
+            }
+        }
+
+        if let Some(slo) = &request.slo
+            && !(slo.target_percent > 0.0 && slo.target_percent < 100.0)
+        {
+            diagnostics.push(Self::error(
+                format!("{}.slo.target_percent", path),
+                "target_percent must be between 0 and 100 (exclusive)".to_string(),
+            ));
+        }
+
+        if let Some(ip_family) = &request.ip_family
+            && IpFamily::parse(ip_family).is_none()
+        {
+            diagnostics.push(Self::error(
+                format!("{}.ip_family", path),
+                format!(
+                    "unknown ip_family '{}' (expected auto, ipv4 or ipv6)",
+                    ip_family
+                ),
+            ));
+        }
+
+        if let Some(expect_status) = &request.expect_status {
+            let lists = [
+                ("success", &expect_status.success),
+                ("expected_failure", &expect_status.expected_failure),
+                ("failure", &expect_status.failure),
+            ];
+            for (name, patterns) in lists {
+                for (index, pattern) in patterns.iter().flatten().enumerate() {
+                    if let Some(error) = StatusExpectation::pattern_error(pattern) {
+                        diagnostics.push(Self::error(
+                            format!("{}.expect_status.{}[{}]", path, name, index),
+                            error,
+                        ));
+                    }
+                }
+            }
+            if expect_status
+                .success
+                .as_ref()
+                .is_some_and(|success| success.is_empty())
+            {
+                diagnostics.push(Self::warning(
+                    format!("{}.expect_status.success", path),
+                    "empty list counts every status as a failure".to_string(),
+                ));
+            }
+        }
+
+        if request.conditional == Some(true) && method != "GET" {
+            diagnostics.push(Self::warning(
+                format!("{}.conditional", path),
+                format!(
+                    "conditional requests only apply to GET, {} responses are rarely cached",
+                    method
+                ),
+            ));
+        }
+
+        // 步骤变量的值只按其他作用域渲染（可引用外层的同名变量），请求的其余部分还可以引用步骤变量
+        for (name, value) in request.variables.iter().flatten() {
+            for placeholder in template::placeholders(value) {
+                if !known.contains(placeholder) {
+                    diagnostics.push(Self::error(
+                        format!("{}.variables.{}", path, name),
+                        format!(
+                            "placeholder {{{{{}}}}} is not defined in the run, worker or cycle scope",
+                            placeholder
+                        ),
+                    ));
+                }
+            }
+        }
+        let step_known: HashSet<String> = known
+            .iter()
+            .cloned()
+            .chain(
+                request
+                    .variables
+                    .iter()
+                    .flat_map(|vars| vars.keys().cloned()),
+            )
+            .collect();
+        let mut check_placeholders = |text: &str, field_path: String| {
+            for name in template::placeholders(text) {
+                if !step_known.contains(name) {
+                    diagnostics.push(Self::error(
+                        field_path.clone(),
+                        format!(
+                            "placeholder {{{{{}}}}} has no matching generated field or variable",
+                            name
+                        ),
+                    ));
+                }
+            }
+            for error in template::filter_errors(text) {
+                diagnostics.push(Self::error(field_path.clone(), error));
+            }
+        };
+        check_placeholders(&request.url, format!("{}.url", path));
+        for (name, value) in request.headers.iter().flatten() {
+            check_placeholders(value, format!("{}.headers.{}", path, name));
+        }
+        if let Some(body) = &request.body {
+            check_placeholders(body, format!("{}.body", path));
+        }
+        if let Some(user_agent) = &request.user_agent {
+            check_placeholders(user_agent, format!("{}.user_agent", path));
+        }
+        if let Some(download) = &request.download {
+            check_placeholders(&download.path, format!("{}.download.path", path));
+        }
+        for (index, tag) in request.tags.iter().flatten().enumerate() {
+            check_placeholders(tag, format!("{}.tags[{}]", path, index));
+        }
+    }
+
+    fn validate_delays(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        if config.delay_between_a_and_b_ms >= config.delay_between_a_requests_ms
+            && config.delay_between_a_requests_ms > 0
+        {
+            diagnostics.push(Self::warning(
+                "delay_between_a_and_b_ms".to_string(),
+                format!(
+                    "A→B delay ({}ms) is not shorter than A→A delay ({}ms); cycles will run back to back",
+                    config.delay_between_a_and_b_ms, config.delay_between_a_requests_ms
+                ),
+            ));
+        }
+        if config.delay_between_a_requests_ms == 0 && config.max_requests.is_none() {
+            diagnostics.push(Self::warning(
+                "delay_between_a_requests_ms".to_string(),
+                "0ms A→A delay with no max_requests will send requests as fast as possible forever"
+                    .to_string(),
+            ));
+        }
+        if let Some(level) = &config.log_level
+            && LogLevel::parse(level).is_none()
+        {
+            diagnostics.push(Self::error(
+                "log_level".to_string(),
+                format!(
+                    "unknown log level '{}' (expected error, warn, info, debug or trace)",
+                    level
+                ),
+            ));
+        }
+        if let Some(max_error_rate) = config
+            .thresholds
+            .as_ref()
+            .and_then(|thresholds| thresholds.max_error_rate)
+            && !(0.0..=100.0).contains(&max_error_rate)
+        {
+            diagnostics.push(Self::error(
+                "thresholds.max_error_rate".to_string(),
+                format!(
+                    "max_error_rate {} is not a percentage between 0 and 100",
+                    max_error_rate
+                ),
+            ));
+        }
+        for (path, distribution) in [
+            (
+                "delay_between_a_and_b_distribution",
+                &config.delay_between_a_and_b_distribution,
+            ),
+            (
+                "delay_between_a_requests_distribution",
+                &config.delay_between_a_requests_distribution,
+            ),
+        ] {
+            if let Some(distribution) = distribution {
+                Self::validate_distribution(path, distribution, diagnostics);
+            }
+        }
+        if config.max_requests == Some(0) {
+            diagnostics.push(Self::warning(
+                "max_requests".to_string(),
+                "max_requests is 0, no requests will be sent".to_string(),
+            ));
+        }
+    }
+
+    fn validate_step_delays(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let steps = [
+            ("request_a".to_string(), Some(&config.request_a)),
+            ("request_b".to_string(), Some(&config.request_b)),
+            (
+                "repeat.request".to_string(),
+                config.repeat.as_ref().map(|repeat| &repeat.request),
+            ),
+        ]
+        .into_iter()
+        .chain(
+            config
+                .branches
+                .iter()
+                .flatten()
+                .enumerate()
+                .map(|(index, branch)| {
+                    (
+                        format!("branches[{}].request", index),
+                        branch.request.as_ref(),
+                    )
+                }),
+        );
+        for (path, request) in steps {
+            let Some(request) = request else {
+                continue;
+            };
+            for (name, delay) in [
+                ("pre_delay", &request.pre_delay),
+                ("post_delay", &request.post_delay),
+            ] {
+                if let Some(distribution) =
+                    delay.as_ref().and_then(|delay| delay.distribution.as_ref())
+                {
+                    Self::validate_distribution(
+                        &format!("{}.{}.distribution", path, name),
+                        distribution,
+                        diagnostics,
+                    );
+                }
+            }
+        }
+
+        // 步骤延迟取代全局A→B延迟
+        if (config.request_a.post_delay.is_some() || config.request_b.pre_delay.is_some())
+            && (config.delay_between_a_and_b_ms > 0
+                || config.delay_between_a_and_b_distribution.is_some())
+        {
+            diagnostics.push(Self::warning(
+                "delay_between_a_and_b_ms".to_string(),
+                "ignored because request_a.post_delay or request_b.pre_delay is set".to_string(),
+            ));
+        }
+        if config.send_mode.as_deref() == Some("barrier")
+            && (config.request_a.pre_delay.is_some() || config.request_b.pre_delay.is_some())
+        {
+            diagnostics.push(Self::warning(
+                "send_mode".to_string(),
+                "pre_delay of request_a/request_b only postpones the shared release in barrier mode"
+                    .to_string(),
+            ));
+        }
+    }
+
+    fn validate_distribution(
+        path: &str,
+        distribution: &DelayDistribution,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        match distribution.distribution.as_str() {
+            "uniform" if distribution.jitter_ms.is_none() => diagnostics.push(Self::warning(
+                format!("{}.jitter_ms", path),
+                "uniform distribution without jitter_ms behaves like a fixed delay".to_string(),
+            )),
+            "normal" if distribution.stddev_ms.is_none() => diagnostics.push(Self::warning(
+                format!("{}.stddev_ms", path),
+                "normal distribution without stddev_ms behaves like a fixed delay".to_string(),
+            )),
+            "uniform" | "normal" | "exponential" => {}
+            other => diagnostics.push(Self::error(
+                format!("{}.distribution", path),
+                format!(
+                    "unknown distribution '{}' (expected uniform, normal or exponential)",
+                    other
+                ),
+            )),
+        }
+        if let (Some(min), Some(max)) = (distribution.min_ms, distribution.max_ms)
+            && min > max
+        {
+            diagnostics.push(Self::error(
+                format!("{}.min_ms", path),
+                format!("min_ms ({}) is greater than max_ms ({})", min, max),
+            ));
+        }
+    }
+
+    fn validate_generated_fields(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let mut seen = HashSet::new();
+        for (index, field) in config.generated_fields.iter().flatten().enumerate() {
+            let path = format!("generated_fields[{}]", index);
+            if !SUPPORTED_GENERATORS.contains(&field.generator.as_str()) {
+                diagnostics.push(Self::error(
+                    format!("{}.generator", path),
+                    format!(
+                        "unknown generator '{}' (expected one of {})",
+                        field.generator,
+                        SUPPORTED_GENERATORS.join(", ")
+                    ),
+                ));
+            }
+            if field.field_type != "header" && field.field_type != "body" {
+                diagnostics.push(Self::error(
+                    format!("{}.field_type", path),
+                    format!("'{}' must be \"header\" or \"body\"", field.field_type),
+                ));
+            }
+            if field.generator == "uuid" {
+                if !matches!(field.version.as_deref(), None | Some("v4") | Some("v7")) {
+                    diagnostics.push(Self::error(
+                        format!("{}.version", path),
+                        format!(
+                            "unknown uuid version '{}' (expected v4 or v7)",
+                            field.version.as_deref().unwrap_or_default()
+                        ),
+                    ));
+                }
+                if !matches!(
+                    field.format.as_deref(),
+                    None | Some("hyphenated")
+                        | Some("simple")
+                        | Some("upper")
+                        | Some("upper_simple")
+                ) {
+                    diagnostics.push(Self::error(
+                        format!("{}.format", path),
+                        format!(
+                            "unknown uuid format '{}' (expected hyphenated, simple, upper or upper_simple)",
+                            field.format.as_deref().unwrap_or_default()
+                        ),
+                    ));
+                }
+            } else if field.generator == "timestamp" {
+                if !matches!(
+                    field.format.as_deref(),
+                    None | Some("unix_ms") | Some("unix") | Some("rfc3339")
+                ) {
+                    diagnostics.push(Self::error(
+                        format!("{}.format", path),
+                        format!(
+                            "unknown timestamp format '{}' (expected unix_ms, unix or rfc3339)",
+                            field.format.as_deref().unwrap_or_default()
+                        ),
+                    ));
+                }
+                if field.version.is_some() {
+                    diagnostics.push(Self::warning(
+                        format!("{}.version", path),
+                        "version only applies to the uuid generator, ignored for 'timestamp'"
+                            .to_string(),
+                    ));
+                }
+            } else if field.version.is_some() || field.format.is_some() {
+                diagnostics.push(Self::warning(
+                    path.clone(),
+                    format!(
+                        "version/format only apply to the uuid and timestamp generators, ignored for '{}'",
+                        field.generator
+                    ),
+                ));
+            }
+            if field.generator == "nanoid"
+                && let Some(length) = field.value.as_deref()
+                && !length
+                    .trim()
+                    .parse::<usize>()
+                    .is_ok_and(|length| length > 0)
+            {
+                diagnostics.push(Self::error(
+                    format!("{}.value", path),
+                    format!(
+                        "invalid nanoid length \"{}\" (expected a positive integer)",
+                        length
+                    ),
+                ));
+            }
+            if let Some(filters) = &field.filters {
+                for error in template::filter_chain_errors(filters) {
+                    diagnostics.push(Self::error(format!("{}.filters", path), error));
+                }
+            }
+            if let Some(unique) = field.unique.as_deref() {
+                if !matches!(unique, "regenerate" | "error") {
+                    diagnostics.push(Self::error(
+                        format!("{}.unique", path),
+                        format!(
+                            "unknown mode '{}' (expected \"regenerate\" or \"error\")",
+                            unique
+                        ),
+                    ));
+                } else if matches!(field.generator.as_str(), "fixed" | "padding") {
+                    diagnostics.push(Self::warning(
+                        format!("{}.unique", path),
+                        format!(
+                            "{} generator repeats the same value every cycle, the run will stop at cycle 2",
+                            field.generator
+                        ),
+                    ));
+                } else if field.generator == "counter" && config.batch.is_some() {
+                    diagnostics.push(Self::warning(
+                        format!("{}.unique", path),
+                        "counter values repeat across batch copies of the same cycle".to_string(),
+                    ));
+                }
+            }
+            if field.generator == "fixed" && field.value.is_none() {
+                diagnostics.push(Self::warning(
+                    format!("{}.value", path),
+                    "fixed generator without value will produce \"default\"".to_string(),
+                ));
+            }
+            // 引用变量的大小在渲染时才能确定
+            if field.generator == "padding"
+                && !field
+                    .value
+                    .as_deref()
+                    .is_some_and(|value| value.contains("{{"))
+            {
+                match field.value.as_deref().map(parse_byte_size) {
+                    Some(Some(_)) => {}
+                    Some(None) => diagnostics.push(Self::error(
+                        format!("{}.value", path),
+                        format!(
+                            "invalid padding size \"{}\" (expected e.g. 1024, 64KB, 10MB)",
+                            field.value.as_deref().unwrap_or_default()
+                        ),
+                    )),
+                    None => diagnostics.push(Self::error(
+                        format!("{}.value", path),
+                        "padding generator requires the target body size as value".to_string(),
+                    )),
+                }
+                if field.field_type != "body" {
+                    diagnostics.push(Self::error(
+                        format!("{}.field_type", path),
+                        "padding generator only applies to body fields".to_string(),
+                    ));
+                }
+            }
+            if !seen.insert(field.name.as_str()) {
+                diagnostics.push(Self::error(
+                    format!("{}.name", path),
+                    format!("duplicate generated field '{}'", field.name),
+                ));
+            }
+        }
+
+        // 旧版 `{name}` 占位符仍按生成的body字段替换，提示迁移到 `{{name}}`
+        let body_fields: Vec<&String> = config
+            .generated_fields
+            .iter()
+            .flatten()
+            .filter(|field| field.field_type == "body")
+            .map(|field| &field.name)
+            .collect();
+        for (path, request) in [
+            ("request_a", &config.request_a),
+            ("request_b", &config.request_b),
+        ] {
+            let Some(body) = &request.body else {
+                continue;
+            };
+            for name in template::legacy_placeholders(body, body_fields.iter().copied()) {
+                diagnostics.push(Self::warning(
+                    format!("{}.body", path),
+                    format!(
+                        "legacy placeholder {{{}}} is deprecated, use {{{{{}}}}}",
+                        name, name
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn validate_targets(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        if let Some(mode) = &config.target_mode
+            && mode != "round_robin"
+            && mode != "concurrent"
+        {
+            diagnostics.push(Self::error(
+                "target_mode".to_string(),
+                format!("'{}' must be \"round_robin\" or \"concurrent\"", mode),
+            ));
+        }
+        for (index, target) in config.targets.iter().flatten().enumerate() {
+            if !target.base_url.starts_with("http://") && !target.base_url.starts_with("https://") {
+                diagnostics.push(Self::error(
+                    format!("targets[{}].base_url", index),
+                    format!("'{}' must start with http:// or https://", target.base_url),
+                ));
+            }
+        }
+
+        // 套接字可能在运行前才由本地服务创建，不存在时只告警
+        let unix_sockets =
+            config
+                .unix_socket
+                .iter()
+                .map(|path| ("unix_socket".to_string(), path))
+                .chain(config.targets.iter().flatten().enumerate().filter_map(
+                    |(index, target)| {
+                        let path = target.unix_socket.as_ref()?;
+                        Some((format!("targets[{}].unix_socket", index), path))
+                    },
+                ));
+        for (path, socket) in unix_sockets {
+            if cfg!(not(unix)) {
+                diagnostics.push(Self::error(
+                    path,
+                    "Unix domain sockets are not supported on this platform".to_string(),
+                ));
+            } else if socket.trim().is_empty() {
+                diagnostics.push(Self::error(path, "socket path is empty".to_string()));
+            } else if !socket.contains("{{") && !std::path::Path::new(socket).exists() {
+                diagnostics.push(Self::warning(
+                    path,
+                    format!("socket {} does not exist yet", socket),
+                ));
+            }
+        }
+    }
+
+    fn validate_metrics(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(metrics) = &config.metrics else {
+            return;
+        };
+        match metrics.protocol.as_str() {
+            "influx" => {
+                if !(metrics.address.starts_with("http://")
+                    || metrics.address.starts_with("https://"))
+                {
+                    diagnostics.push(Self::error(
+                        "metrics.address".to_string(),
+                        format!(
+                            "influx address '{}' must be an http(s) write URL",
+                            metrics.address
+                        ),
+                    ));
+                }
+            }
+            "statsd" => {
+                if !metrics.address.contains(':') {
+                    diagnostics.push(Self::error(
+                        "metrics.address".to_string(),
+                        format!("statsd address '{}' must be host:port", metrics.address),
+                    ));
+                }
+            }
+            other => diagnostics.push(Self::error(
+                "metrics.protocol".to_string(),
+                format!(
+                    "unknown metrics protocol '{}' (expected influx or statsd)",
+                    other
+                ),
+            )),
+        }
+        if metrics.flush_interval_ms == Some(0) {
+            diagnostics.push(Self::error(
+                "metrics.flush_interval_ms".to_string(),
+                "flush_interval_ms must be greater than 0".to_string(),
+            ));
+        }
+    }
+
+    fn validate_notifications(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(notifications) = &config.notifications else {
+            return;
+        };
+        if !(notifications.webhook_url.starts_with("http://")
+            || notifications.webhook_url.starts_with("https://"))
+        {
+            diagnostics.push(Self::error(
+                "notifications.webhook_url".to_string(),
+                format!(
+                    "webhook URL '{}' must start with http:// or https://",
+                    notifications.webhook_url
+                ),
+            ));
+        }
+        if let Some(threshold) = notifications.error_rate_threshold
+            && !(0.0..=100.0).contains(&threshold)
+        {
+            diagnostics.push(Self::error(
+                "notifications.error_rate_threshold".to_string(),
+                format!(
+                    "error_rate_threshold {} is not a percentage between 0 and 100",
+                    threshold
+                ),
+            ));
+        }
+    }
+
+    fn validate_campaign(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(campaign) = &config.campaign else {
+            return;
+        };
+        if campaign.scenarios.is_empty() {
+            diagnostics.push(Self::warning(
+                "campaign.scenarios".to_string(),
+                "no scenarios configured, the campaign runs nothing".to_string(),
+            ));
+        }
+        match campaign.mode.as_deref() {
+            None | Some("sequential") => {}
+            Some("parallel") => {
+                if campaign.stop_on_failure == Some(true) {
+                    diagnostics.push(Self::warning(
+                        "campaign.stop_on_failure".to_string(),
+                        "stop_on_failure only applies to sequential campaigns".to_string(),
+                    ));
+                }
+            }
+            Some(mode) => diagnostics.push(Self::error(
+                "campaign.mode".to_string(),
+                format!("unknown mode '{}' (expected sequential or parallel)", mode),
+            )),
+        }
+        let mut names = HashSet::new();
+        for (index, entry) in campaign.scenarios.iter().enumerate() {
+            let path = format!("campaign.scenarios[{}]", index);
+            let name =
+                Campaign::entry_name(entry.name.as_deref(), entry.scenario.as_deref(), index);
+            if !names.insert(name.clone()) {
+                diagnostics.push(Self::error(
+                    format!("{}.name", path),
+                    format!("duplicate scenario name '{}'", name),
+                ));
+            }
+            if entry.max_requests == Some(0) {
+                diagnostics.push(Self::error(
+                    format!("{}.max_requests", path),
+                    "max_requests must be greater than 0".to_string(),
+                ));
+            }
+            if entry.max_duration_ms == Some(0) {
+                diagnostics.push(Self::error(
+                    format!("{}.max_duration_ms", path),
+                    "max_duration_ms must be greater than 0".to_string(),
+                ));
+            }
+            if let Some(env) = &entry.env
+                && entry.scenario.is_none()
+                && !config
+                    .environments
+                    .as_ref()
+                    .is_some_and(|environments| environments.contains_key(env))
+            {
+                diagnostics.push(Self::error(
+                    format!("{}.env", path),
+                    format!("unknown environment '{}'", env),
+                ));
+            }
+            // 场景文件在运行campaign时单独校验，这里只检查使用自身配置的场景
+            if entry.scenario.is_none()
+                && entry.max_requests.is_none()
+                && entry.max_duration_ms.is_none()
+                && config.max_requests.is_none()
+            {
+                diagnostics.push(Self::warning(
+                    format!("{}.max_requests", path),
+                    "no max_requests or max_duration_ms, the campaign never ends".to_string(),
+                ));
+            }
+        }
+    }
+
+    fn validate_time_windows(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(windows) = &config.time_windows else {
+            return;
+        };
+        if windows.time_list.is_empty() {
+            diagnostics.push(Self::error(
+                "time_windows.time_list".to_string(),
+                "time_list is empty, the run would never start".to_string(),
+            ));
+        }
+        for (index, entry) in windows.time_list.iter().enumerate() {
+            let path = format!("time_windows.time_list[{}]", index);
+            if let Err(e) = time_window::parse_days(&entry.day) {
+                diagnostics.push(Self::error(format!("{}.day", path), e.to_string()));
+            }
+            if entry.time_range.is_empty() {
+                diagnostics.push(Self::warning(
+                    format!("{}.time_range", path),
+                    "no time ranges, this entry allows nothing".to_string(),
+                ));
+            }
+            for (range_index, range) in entry.time_range.iter().enumerate() {
+                let range_path = format!("{}.time_range[{}]", path, range_index);
+                let start = time_window::parse_time(&range.start_time);
+                let end = time_window::parse_time(&range.end_time);
+                for (field, parsed) in [("start_time", &start), ("end_time", &end)] {
+                    if let Err(e) = parsed {
+                        diagnostics.push(Self::error(
+                            format!("{}.{}", range_path, field),
+                            e.to_string(),
+                        ));
+                    }
+                }
+                if let (Ok(start), Ok(end)) = (start, end)
+                    && start == end
+                {
+                    diagnostics.push(Self::error(
+                        range_path,
+                        "start_time equals end_time, the range is empty".to_string(),
+                    ));
+                }
+            }
+        }
+        if let Some(offset) = &windows.utc_offset
+            && let Err(e) = parse_utc_offset(offset)
+        {
+            diagnostics.push(Self::error(
+                "time_windows.utc_offset".to_string(),
+                e.to_string(),
+            ));
+        }
+    }
+
+    fn validate_self_monitor(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(monitor) = &config.self_monitor else {
+            return;
+        };
+        if monitor.interval_ms == Some(0) {
+            diagnostics.push(Self::error(
+                "self_monitor.interval_ms".to_string(),
+                "interval_ms must be greater than 0".to_string(),
+            ));
+        }
+        if monitor.growth_samples == Some(0) {
+            diagnostics.push(Self::error(
+                "self_monitor.growth_samples".to_string(),
+                "growth_samples must be at least 1".to_string(),
+            ));
+        }
+    }
+
+    fn validate_provisioning(
+        config: &RequestConfig,
+        known: &HashSet<String>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let Some(provisioning) = &config.provisioning else {
+            return;
+        };
+        if provisioning.count == 0 {
+            diagnostics.push(Self::error(
+                "provisioning.count".to_string(),
+                "must be greater than 0".to_string(),
+            ));
+        }
+        if provisioning.pool.trim().is_empty() {
+            diagnostics.push(Self::error(
+                "provisioning.pool".to_string(),
+                "pool must not be empty".to_string(),
+            ));
+        } else if !provisioning
+            .setup
+            .captures
+            .iter()
+            .flatten()
+            .any(|capture| capture.name == provisioning.pool)
+        {
+            diagnostics.push(Self::error(
+                "provisioning.setup.captures".to_string(),
+                format!(
+                    "no capture named '{}' to fill the pool with",
+                    provisioning.pool
+                ),
+            ));
+        }
+        for (index, capture) in provisioning.setup.captures.iter().flatten().enumerate() {
+            Self::validate_capture(
+                &format!("provisioning.setup.captures[{}]", index),
+                capture,
+                diagnostics,
+            );
+        }
+
+        // 预置请求在主循环之前发送，可以使用运行作用域的变量和 {{index}}，删除请求还可以使用 {{<pool>}}
+        let mut setup_known = known.clone();
+        setup_known.insert("index".to_string());
+        setup_known.remove(&provisioning.pool);
+        Self::validate_request(
+            &provisioning.setup,
+            "provisioning.setup",
+            &setup_known,
+            diagnostics,
+        );
+        if let Some(teardown) = &provisioning.teardown {
+            setup_known.insert(provisioning.pool.clone());
+            Self::validate_request(teardown, "provisioning.teardown", &setup_known, diagnostics);
+        }
+    }
+
+    fn validate_heartbeat(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(heartbeat) = &config.heartbeat else {
+            return;
+        };
+        if heartbeat.url.trim().is_empty() {
+            diagnostics.push(Self::error(
+                "heartbeat.url".to_string(),
+                "url must not be empty".to_string(),
+            ));
+        }
+        if let Some(method) = &heartbeat.method
+            && !["GET", "POST", "PUT", "DELETE"].contains(&method.to_uppercase().as_str())
+        {
+            diagnostics.push(Self::error(
+                "heartbeat.method".to_string(),
+                format!(
+                    "unsupported method '{}' (expected GET, POST, PUT or DELETE)",
+                    method
+                ),
+            ));
+        }
+        if heartbeat.interval_ms == 0 {
+            diagnostics.push(Self::error(
+                "heartbeat.interval_ms".to_string(),
+                "interval_ms must be greater than 0".to_string(),
+            ));
+        }
+        if heartbeat.timeout_ms == Some(0) {
+            diagnostics.push(Self::error(
+                "heartbeat.timeout_ms".to_string(),
+                "timeout_ms must be greater than 0".to_string(),
+            ));
+        }
+        // 心跳只使用运行作用域的变量，周期作用域的生成字段、目标变量和捕获不可用
+        let run_scope: HashSet<&str> = config
+            .variables
+            .iter()
+            .chain(&config.worker_variables)
+            .chain(config.environments.iter().flat_map(|envs| envs.values()))
+            .flat_map(|vars| vars.keys().map(String::as_str))
+            .collect();
+        let texts = [
+            ("url", Some(heartbeat.url.as_str())),
+            ("body", heartbeat.body.as_deref()),
+        ]
+        .into_iter()
+        .filter_map(|(field, text)| text.map(|text| (field.to_string(), text)))
+        .chain(
+            heartbeat
+                .headers
+                .iter()
+                .flatten()
+                .map(|(name, value)| (format!("headers.{}", name), value.as_str())),
+        );
+        for (field, text) in texts {
+            for placeholder in template::placeholders(text) {
+                if !run_scope.contains(placeholder) {
+                    diagnostics.push(Self::warning(
+                        format!("heartbeat.{}", field),
+                        format!(
+                            "placeholder {{{{{}}}}} is not a run or worker variable and is left as is",
+                            placeholder
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn validate_device_time(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(device_time) = &config.device_time else {
+            return;
+        };
+        if device_time.url.trim().is_empty() {
+            diagnostics.push(Self::error(
+                "device_time.url".to_string(),
+                "url must not be empty".to_string(),
+            ));
+        }
+        match device_time.source.as_deref() {
+            None | Some("date_header") => {
+                if device_time.field.is_some() {
+                    diagnostics.push(Self::warning(
+                        "device_time.field".to_string(),
+                        "field only applies to source \"body\", ignored for the Date header"
+                            .to_string(),
+                    ));
+                }
+            }
+            Some("body") => {}
+            Some(source) => diagnostics.push(Self::error(
+                "device_time.source".to_string(),
+                format!("unknown source '{}' (expected date_header or body)", source),
+            )),
+        }
+        if device_time.refresh_ms == Some(0) {
+            diagnostics.push(Self::error(
+                "device_time.refresh_ms".to_string(),
+                "refresh_ms must be greater than 0".to_string(),
+            ));
+        }
+        let time_based = config.generated_fields.iter().flatten().any(|field| {
+            matches!(field.generator.as_str(), "timestamp" | "ulid")
+                || (field.generator == "uuid" && field.version.as_deref() == Some("v7"))
+        });
+        if !time_based {
+            diagnostics.push(Self::warning(
+                "device_time".to_string(),
+                "no timestamp, ulid or uuid v7 generated fields use the device clock".to_string(),
+            ));
+        }
+    }
+
+    fn validate_auth(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let auths =
+            config
+                .digest_auth
+                .iter()
+                .map(|auth| ("digest_auth".to_string(), auth))
+                .chain(config.targets.iter().flatten().enumerate().filter_map(
+                    |(index, target)| {
+                        let auth = target.digest_auth.as_ref()?;
+                        Some((format!("targets[{}].digest_auth", index), auth))
+                    },
+                ));
+        for (path, auth) in auths {
+            match auth.auth_type.as_deref().map(str::to_lowercase).as_deref() {
+                None | Some("digest") | Some("basic") => {}
+                Some(_) => diagnostics.push(Self::error(
+                    format!("{}.auth_type", path),
+                    format!(
+                        "unknown auth_type '{}' (expected digest or basic)",
+                        auth.auth_type.as_deref().unwrap_or_default()
+                    ),
+                )),
+            }
+            if auth.realm.is_some() != auth.nonce.is_some() && !auth.uses_basic() {
+                diagnostics.push(Self::warning(
+                    path.clone(),
+                    "realm and nonce are only used together, the first request probes for a challenge".to_string(),
+                ));
+            }
+            if auth.known_challenge().is_some() && auth.preemptive != Some(true) {
+                diagnostics.push(Self::warning(
+                    format!("{}.nonce", path),
+                    "realm and nonce are only used with preemptive: true".to_string(),
+                ));
+            }
+        }
+    }
+
+    fn validate_proxy(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(proxy) = &config.proxy else {
+            return;
+        };
+        if !proxy.url.starts_with("http://")
+            && !proxy.url.starts_with("https://")
+            && !proxy.url.starts_with("{{")
+        {
+            diagnostics.push(Self::error(
+                "proxy.url".to_string(),
+                format!("'{}' must start with http:// or https://", proxy.url),
+            ));
+        }
+        match proxy.auth_type.as_deref().map(str::to_lowercase).as_deref() {
+            None | Some("basic") | Some("digest") => {}
+            Some(_) => diagnostics.push(Self::error(
+                "proxy.auth_type".to_string(),
+                format!(
+                    "unknown auth_type '{}' (expected basic or digest)",
+                    proxy.auth_type.as_deref().unwrap_or_default()
+                ),
+            )),
+        }
+        if proxy.username.is_none() && (proxy.password.is_some() || proxy.auth_type.is_some()) {
+            diagnostics.push(Self::warning(
+                "proxy.username".to_string(),
+                "no username configured, proxy credentials are not sent".to_string(),
+            ));
+        }
+        // HTTPS目标经CONNECT隧道连接，隧道只能携带Basic凭据
+        let https_targets = [&config.request_a.url, &config.request_b.url]
+            .into_iter()
+            .chain(
+                config
+                    .targets
+                    .iter()
+                    .flatten()
+                    .map(|target| &target.base_url),
+            )
+            .any(|url| url.starts_with("https://"));
+        if proxy.uses_digest() && https_targets {
+            diagnostics.push(Self::warning(
+                "proxy.auth_type".to_string(),
+                "Digest proxy authentication only applies to http:// targets, HTTPS tunnels only support Basic".to_string(),
+            ));
+        }
+        if config.unix_socket.is_some() {
+            diagnostics.push(Self::warning(
+                "proxy".to_string(),
+                "requests over unix_socket bypass the proxy".to_string(),
+            ));
+        }
+    }
+
+    fn validate_auth_recovery(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(recovery) = &config.auth_recovery else {
+            return;
+        };
+        match &recovery.login {
+            Some(login) => {
+                if login.url.trim().is_empty() {
+                    diagnostics.push(Self::error(
+                        "auth_recovery.login.url".to_string(),
+                        "url must not be empty".to_string(),
+                    ));
+                }
+                if !["GET", "POST", "PUT", "DELETE"].contains(&login.method.to_uppercase().as_str())
+                {
+                    diagnostics.push(Self::error(
+                        "auth_recovery.login.method".to_string(),
+                        format!(
+                            "unsupported method '{}' (expected GET, POST, PUT or DELETE)",
+                            login.method
+                        ),
+                    ));
+                }
+            }
+            None if config.digest_auth.is_none() => diagnostics.push(Self::warning(
+                "auth_recovery".to_string(),
+                "no login request or digest_auth configured, requests are resent without re-authenticating"
+                    .to_string(),
+            )),
+            None => {}
+        }
+    }
+
+    fn validate_csrf(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(csrf) = &config.csrf else {
+            return;
+        };
+        if csrf.login.url.trim().is_empty() {
+            diagnostics.push(Self::error(
+                "csrf.login.url".to_string(),
+                "url must not be empty".to_string(),
+            ));
+        }
+        Self::validate_capture("csrf.token", &csrf.token, diagnostics);
+        if csrf.token.scope.as_deref() == Some("cycle") {
+            diagnostics.push(Self::warning(
+                "csrf.token.scope".to_string(),
+                "the CSRF token is always captured with scope \"run\"".to_string(),
+            ));
+        }
+        if csrf
+            .header
+            .as_deref()
+            .is_some_and(|header| header.trim().is_empty())
+        {
+            diagnostics.push(Self::error(
+                "csrf.header".to_string(),
+                "header must not be empty".to_string(),
+            ));
+        }
+        for (index, method) in csrf.methods.iter().flatten().enumerate() {
+            if !["GET", "POST", "PUT", "PATCH", "DELETE"].contains(&method.to_uppercase().as_str())
+            {
+                diagnostics.push(Self::error(
+                    format!("csrf.methods[{}]", index),
+                    format!(
+                        "unknown method '{}' (expected GET, POST, PUT, PATCH or DELETE)",
+                        method
+                    ),
+                ));
+            }
+        }
+    }
+
+    fn validate_cookies(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        for (label, request) in [
+            ("request_a", &config.request_a),
+            ("request_b", &config.request_b),
+        ] {
+            let Some(cookies) = &request.cookies else {
+                continue;
+            };
+            let invalid_name = |name: &str| {
+                name.is_empty()
+                    || name.contains(|c: char| c == '=' || c == ';' || c.is_whitespace())
+            };
+            let names = cookies
+                .set
+                .iter()
+                .flat_map(|set| set.keys())
+                .map(|name| ("set", name))
+                .chain(cookies.delete.iter().flatten().map(|name| ("delete", name)))
+                .chain(
+                    cookies
+                        .read
+                        .iter()
+                        .flat_map(|read| read.values())
+                        .map(|name| ("read", name)),
+                );
+            for (field, name) in names {
+                if invalid_name(name) {
+                    diagnostics.push(Self::error(
+                        format!("{}.cookies.{}", label, field),
+                        format!("invalid cookie name '{}'", name),
+                    ));
+                }
+            }
+            if request.method.eq_ignore_ascii_case("RAW") {
+                diagnostics.push(Self::warning(
+                    format!("{}.cookies", label),
+                    "RAW requests bypass the cookie jar, cookies are not set or deleted"
+                        .to_string(),
+                ));
+            }
+            for (index, expect) in cookies.expect.iter().flatten().enumerate() {
+                let path = format!("{}.cookies.expect[{}]", label, index);
+                if invalid_name(&expect.name) {
+                    diagnostics.push(Self::error(
+                        format!("{}.name", path),
+                        format!("invalid cookie name '{}'", expect.name),
+                    ));
+                }
+                if let Some(same_site) = &expect.same_site
+                    && !["strict", "lax", "none"].contains(&same_site.to_lowercase().as_str())
+                {
+                    diagnostics.push(Self::error(
+                        format!("{}.same_site", path),
+                        format!(
+                            "unknown same_site '{}' (expected Strict, Lax or None)",
+                            same_site
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn validate_circuit_breaker(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(breaker) = &config.circuit_breaker else {
+            return;
+        };
+        if breaker.failure_threshold == 0 {
+            diagnostics.push(Self::error(
+                "circuit_breaker.failure_threshold".to_string(),
+                "failure_threshold must be at least 1".to_string(),
+            ));
+        }
+        if breaker.window_ms == 0 {
+            diagnostics.push(Self::error(
+                "circuit_breaker.window_ms".to_string(),
+                "window_ms must be greater than 0".to_string(),
+            ));
+        }
+        if breaker.half_open_successes == Some(0) {
+            diagnostics.push(Self::error(
+                "circuit_breaker.half_open_successes".to_string(),
+                "half_open_successes must be at least 1".to_string(),
+            ));
+        }
+    }
+
+    fn validate_adaptive_pacing(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(pacing) = &config.adaptive_pacing else {
+            return;
+        };
+        if pacing.max_error_rate.is_none() && pacing.max_p95_ms.is_none() {
+            diagnostics.push(Self::error(
+                "adaptive_pacing".to_string(),
+                "set max_error_rate and/or max_p95_ms to define when to slow down".to_string(),
+            ));
+        }
+        if let Some(rate) = pacing.max_error_rate
+            && !(0.0..=100.0).contains(&rate)
+        {
+            diagnostics.push(Self::error(
+                "adaptive_pacing.max_error_rate".to_string(),
+                format!("{} is not a percentage between 0 and 100", rate),
+            ));
+        }
+        if pacing.window_ms == Some(0) {
+            diagnostics.push(Self::error(
+                "adaptive_pacing.window_ms".to_string(),
+                "window_ms must be greater than 0".to_string(),
+            ));
+        }
+        if let Some(factor) = pacing.backoff_factor
+            && factor <= 1.0
+        {
+            diagnostics.push(Self::error(
+                "adaptive_pacing.backoff_factor".to_string(),
+                format!("backoff_factor must be greater than 1, got {}", factor),
+            ));
+        }
+        if let Some(factor) = pacing.recovery_factor
+            && !(factor > 0.0 && factor < 1.0)
+        {
+            diagnostics.push(Self::error(
+                "adaptive_pacing.recovery_factor".to_string(),
+                format!("recovery_factor must be between 0 and 1, got {}", factor),
+            ));
+        }
+        let min_interval_ms = pacing
+            .min_interval_ms
+            .unwrap_or(config.delay_between_a_requests_ms);
+        if min_interval_ms == 0 {
+            diagnostics.push(Self::warning(
+                "adaptive_pacing.min_interval_ms".to_string(),
+                "a zero interval cannot be slowed down; set min_interval_ms above 0".to_string(),
+            ));
+        }
+        if let Some(max_interval_ms) = pacing.max_interval_ms
+            && max_interval_ms < min_interval_ms
+        {
+            diagnostics.push(Self::error(
+                "adaptive_pacing.max_interval_ms".to_string(),
+                format!(
+                    "max_interval_ms ({}) is below the minimum interval ({}ms)",
+                    max_interval_ms, min_interval_ms
+                ),
+            ));
+        }
+    }
+
+    fn validate_fault_injection(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(faults) = &config.fault_injection else {
+            return;
+        };
+        for (name, probability) in [
+            ("delay_probability", faults.delay_probability),
+            ("abort_probability", faults.abort_probability),
+            ("truncate_probability", faults.truncate_probability),
+        ] {
+            if let Some(probability) = probability
+                && !(0.0..=1.0).contains(&probability)
+            {
+                diagnostics.push(Self::error(
+                    format!("fault_injection.{}", name),
+                    format!("{} is not a probability between 0 and 1", probability),
+                ));
+            }
+        }
+        diagnostics.push(Self::warning(
+            "fault_injection".to_string(),
+            "fault injection is enabled, requests will be deliberately delayed, aborted or corrupted"
+                .to_string(),
+        ));
+    }
+
+    fn validate_port_probes(
+        config: &RequestConfig,
+        known: &HashSet<String>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let Some(port_probes) = &config.port_probes else {
+            return;
+        };
+        if let Some(on_failure) = &port_probes.on_failure
+            && !matches!(on_failure.as_str(), "abort" | "warn")
+        {
+            diagnostics.push(Self::error(
+                "port_probes.on_failure".to_string(),
+                format!(
+                    "unknown on_failure \"{}\" (expected abort or warn)",
+                    on_failure
+                ),
+            ));
+        }
+        if port_probes.probes.is_empty() {
+            diagnostics.push(Self::warning(
+                "port_probes.probes".to_string(),
+                "no probes configured".to_string(),
+            ));
+        }
+        for (index, probe) in port_probes.probes.iter().enumerate() {
+            let path = format!("port_probes.probes[{}]", index);
+            if let Some(protocol) = &probe.protocol
+                && !matches!(protocol.as_str(), "tcp" | "udp")
+            {
+                diagnostics.push(Self::error(
+                    format!("{}.protocol", path),
+                    format!("unknown protocol \"{}\" (expected tcp or udp)", protocol),
+                ));
+            }
+            if probe.host.trim().is_empty() {
+                diagnostics.push(Self::error(
+                    format!("{}.host", path),
+                    "host is empty".to_string(),
+                ));
+            }
+            for placeholder in template::placeholders(&probe.host) {
+                if !known.contains(placeholder) {
+                    diagnostics.push(Self::error(
+                        format!("{}.host", path),
+                        format!(
+                            "placeholder {{{{{}}}}} has no matching variable",
+                            placeholder
+                        ),
+                    ));
+                }
+            }
+            if probe.port == 0 {
+                diagnostics.push(Self::error(
+                    format!("{}.port", path),
+                    "port must be between 1 and 65535".to_string(),
+                ));
+            }
+            if probe.timeout_ms == Some(0) {
+                diagnostics.push(Self::error(
+                    format!("{}.timeout_ms", path),
+                    "timeout_ms must be greater than 0".to_string(),
+                ));
+            }
+            for (field, value) in [("payload", &probe.payload), ("expect", &probe.expect)] {
+                if let Some(value) = value
+                    && let Err(e) = port_probe::decode(value, probe.encoding.as_deref())
+                {
+                    diagnostics.push(Self::error(format!("{}.{}", path, field), e.to_string()));
+                }
+            }
+        }
+    }
+
+    fn validate_rtsp_precheck(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(precheck) = &config.rtsp_precheck else {
+            return;
+        };
+        if let Some(method) = &precheck.method
+            && !matches!(method.to_uppercase().as_str(), "OPTIONS" | "DESCRIBE")
+        {
+            diagnostics.push(Self::error(
+                "rtsp_precheck.method".to_string(),
+                format!(
+                    "unknown method \"{}\" (expected OPTIONS or DESCRIBE)",
+                    method
+                ),
+            ));
+        }
+        if let Some(on_failure) = &precheck.on_failure
+            && !matches!(on_failure.as_str(), "abort" | "skip_cycle")
+        {
+            diagnostics.push(Self::error(
+                "rtsp_precheck.on_failure".to_string(),
+                format!(
+                    "unknown on_failure \"{}\" (expected abort or skip_cycle)",
+                    on_failure
+                ),
+            ));
+        }
+        if precheck.timeout_ms == Some(0) {
+            diagnostics.push(Self::error(
+                "rtsp_precheck.timeout_ms".to_string(),
+                "timeout_ms must be greater than 0".to_string(),
+            ));
+        }
+        match &precheck.urls {
+            Some(urls) => {
+                for (index, url) in urls.iter().enumerate() {
+                    if !url.starts_with("rtsp://") {
+                        diagnostics.push(Self::error(
+                            format!("rtsp_precheck.urls[{}]", index),
+                            format!("\"{}\" is not an rtsp:// URL", url),
+                        ));
+                    }
+                }
+            }
+            None => {
+                if !config
+                    .request_a
+                    .body
+                    .as_deref()
+                    .is_some_and(|body| body.contains("rtsp://"))
+                {
+                    diagnostics.push(Self::warning(
+                        "rtsp_precheck.urls".to_string(),
+                        "no urls configured and request_a body has no rtsp:// URL, nothing will be checked"
+                            .to_string(),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn validate_captures(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        for (label, request) in [
+            ("request_a", &config.request_a),
+            ("request_b", &config.request_b),
+        ] {
+            for (index, capture) in request.captures.iter().flatten().enumerate() {
+                let path = format!("{}.captures[{}]", label, index);
+                Self::validate_capture(&path, capture, diagnostics);
+                let scope = capture.scope.as_deref().unwrap_or("cycle");
+                // 请求A在同一周期内先于请求B发送，只能使用B在之前周期捕获的运行变量
+                if label == "request_b"
+                    && scope == "cycle"
+                    && Capturer::references(&config.request_a, &capture.name)
+                {
+                    diagnostics.push(Self::warning(
+                        format!("{}.scope", path),
+                        format!(
+                            "request_a references {{{{{}}}}} captured by request_b in the same cycle, use scope \"run\" to carry it to the next cycle",
+                            capture.name
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    /// 校验单个捕获配置（来源所需的字段、正则和作用域）
+    fn validate_capture(path: &str, capture: &CaptureConfig, diagnostics: &mut Vec<Diagnostic>) {
+        if capture.name.is_empty() {
+            diagnostics.push(Self::error(
+                format!("{}.name", path),
+                "capture name must not be empty".to_string(),
+            ));
+        }
+        match capture.source.as_deref().unwrap_or("json") {
+            "json" => {
+                if capture.path.is_none() {
+                    diagnostics.push(Self::error(
+                        format!("{}.path", path),
+                        "json capture requires a path (e.g. \"$.taskID\")".to_string(),
+                    ));
+                }
+            }
+            "jq" => match capture.filter.as_deref().map(JqFilter::check) {
+                None => diagnostics.push(Self::error(
+                    format!("{}.filter", path),
+                    "jq capture requires a filter (e.g. \".TaskList | length\")".to_string(),
+                )),
+                Some(Err(e)) => {
+                    diagnostics.push(Self::error(format!("{}.filter", path), e.to_string()))
+                }
+                Some(Ok(())) => {}
+            },
+            "regex" => {
+                if capture.pattern.is_none() {
+                    diagnostics.push(Self::error(
+                        format!("{}.pattern", path),
+                        "regex capture requires a pattern".to_string(),
+                    ));
+                }
+            }
+            "header" => {
+                if capture.header.is_none() {
+                    diagnostics.push(Self::error(
+                        format!("{}.header", path),
+                        "header capture requires a header name (e.g. \"Location\")".to_string(),
+                    ));
+                }
+            }
+            "cookie" => {
+                if capture.cookie.is_none() {
+                    diagnostics.push(Self::error(
+                        format!("{}.cookie", path),
+                        "cookie capture requires a cookie name (e.g. \"csrftoken\")".to_string(),
+                    ));
+                }
+            }
+            source => diagnostics.push(Self::error(
+                format!("{}.source", path),
+                format!(
+                    "unknown source \"{}\" (expected json, jq, regex, header or cookie)",
+                    source
+                ),
+            )),
+        }
+        match capture.pattern.as_deref().map(Regex::new) {
+            Some(Err(e)) => diagnostics.push(Self::error(
+                format!("{}.pattern", path),
+                format!("invalid regex: {}", e),
+            )),
+            Some(Ok(regex)) => {
+                if let Some(group) = capture.group
+                    && group >= regex.captures_len()
+                {
+                    diagnostics.push(Self::error(
+                        format!("{}.group", path),
+                        format!(
+                            "pattern has no capture group {} ({} group(s))",
+                            group,
+                            regex.captures_len() - 1
+                        ),
+                    ));
+                }
+            }
+            None => {}
+        }
+        let scope = capture.scope.as_deref().unwrap_or("cycle");
+        if !matches!(scope, "cycle" | "run") {
+            diagnostics.push(Self::error(
+                format!("{}.scope", path),
+                format!("unknown scope \"{}\" (expected cycle or run)", scope),
+            ));
+        }
+    }
+
+    fn validate_user_agent(
+        config: &RequestConfig,
+        known: &HashSet<String>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let Some(user_agent) = &config.user_agent else {
+            return;
+        };
+        if let Some(mode) = &user_agent.mode
+            && !["round_robin", "random"].contains(&mode.as_str())
+        {
+            diagnostics.push(Self::error(
+                "user_agent.mode".to_string(),
+                format!("unknown mode '{}' (expected round_robin or random)", mode),
+            ));
+        }
+        match &user_agent.rotation {
+            Some(rotation) if rotation.is_empty() => diagnostics.push(Self::error(
+                "user_agent.rotation".to_string(),
+                "rotation list is empty".to_string(),
+            )),
+            Some(_) if user_agent.value.is_some() => diagnostics.push(Self::warning(
+                "user_agent.value".to_string(),
+                "value is ignored when a rotation list is configured".to_string(),
+            )),
+            None if user_agent.value.is_none() => diagnostics.push(Self::warning(
+                "user_agent".to_string(),
+                "neither value nor rotation is configured, the default User-Agent is used"
+                    .to_string(),
+            )),
+            _ => {}
+        }
+        let values = user_agent
+            .value
+            .iter()
+            .map(|value| ("user_agent.value".to_string(), value))
+            .chain(
+                user_agent
+                    .rotation
+                    .iter()
+                    .flatten()
+                    .enumerate()
+                    .map(|(index, value)| (format!("user_agent.rotation[{}]", index), value)),
+            );
+        for (path, value) in values {
+            if value.trim().is_empty() || value.contains(['\r', '\n']) {
+                diagnostics.push(Self::error(
+                    path.clone(),
+                    "User-Agent must be a non-empty single line".to_string(),
+                ));
+            }
+            for placeholder in template::placeholders(value) {
+                if !known.contains(placeholder) {
+                    diagnostics.push(Self::error(
+                        path.clone(),
+                        format!(
+                            "placeholder {{{{{}}}}} has no matching generated field or variable",
+                            placeholder
+                        ),
+                    ));
+                }
+            }
+        }
+    }
+
+    fn validate_job_queue(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(queue) = &config.job_queue else {
+            return;
+        };
+        match queue.backend.as_deref().unwrap_or("redis") {
+            "redis" => {
+                if !queue.url.starts_with("redis://") {
+                    diagnostics.push(Self::error(
+                        "job_queue.url".to_string(),
+                        format!("\"{}\" is not a redis:// URL", queue.url),
+                    ));
+                }
+            }
+            "kafka" => diagnostics.push(Self::error(
+                "job_queue.backend".to_string(),
+                "kafka is not supported, use a Redis list".to_string(),
+            )),
+            backend => diagnostics.push(Self::error(
+                "job_queue.backend".to_string(),
+                format!("unknown backend '{}' (expected redis)", backend),
+            )),
+        }
+        if queue.input_list.is_empty() {
+            diagnostics.push(Self::error(
+                "job_queue.input_list".to_string(),
+                "input_list must not be empty".to_string(),
+            ));
+        }
+        if let Some(processing_list) = &queue.processing_list
+            && (processing_list.is_empty() || *processing_list == queue.input_list)
+        {
+            diagnostics.push(Self::error(
+                "job_queue.processing_list".to_string(),
+                "processing_list must be a non-empty list other than input_list".to_string(),
+            ));
+        }
+        if queue.idle_timeout_ms == Some(0) {
+            diagnostics.push(Self::error(
+                "job_queue.idle_timeout_ms".to_string(),
+                "idle_timeout_ms must be greater than 0 (omit it to wait forever)".to_string(),
+            ));
+        }
+        if queue.result_list.is_none() {
+            diagnostics.push(Self::warning(
+                "job_queue.result_list".to_string(),
+                "no result_list configured, job results are not published".to_string(),
+            ));
+        }
+    }
+
+    fn validate_runtime(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(runtime) = &config.runtime else {
+            return;
+        };
+        if runtime.worker_threads == Some(0) {
+            diagnostics.push(Self::error(
+                "runtime.worker_threads".to_string(),
+                "worker_threads must be greater than 0".to_string(),
+            ));
+        }
+        if runtime.max_blocking_threads == Some(0) {
+            diagnostics.push(Self::error(
+                "runtime.max_blocking_threads".to_string(),
+                "max_blocking_threads must be greater than 0".to_string(),
+            ));
+        }
+        // 远超CPU核数的工作线程只会增加调度开销
+        if let (Some(workers), Ok(cores)) =
+            (runtime.worker_threads, std::thread::available_parallelism())
+            && workers > cores.get() * 4
+        {
+            diagnostics.push(Self::warning(
+                "runtime.worker_threads".to_string(),
+                format!(
+                    "{} worker threads on {} CPU cores adds scheduling overhead without more throughput",
+                    workers, cores
+                ),
+            ));
+        }
+    }
+
+    fn validate_response_limit(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(limit) = &config.response_limit else {
+            return;
+        };
+        if limit.max_bytes == 0 {
+            diagnostics.push(Self::error(
+                "response_limit.max_bytes".to_string(),
+                "max_bytes must be greater than 0".to_string(),
+            ));
+        }
+        if limit.keep_spill_files == Some(0) {
+            diagnostics.push(Self::error(
+                "response_limit.keep_spill_files".to_string(),
+                "keep_spill_files must be greater than 0".to_string(),
+            ));
+        }
+    }
+
+    fn validate_json_templates(
+        config: &RequestConfig,
+        known: &HashSet<String>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let templates = config.json_templates.as_ref();
+        let referenced: HashSet<&str> = [&config.request_a, &config.request_b]
+            .into_iter()
+            .filter_map(|request| request.body.as_deref())
+            .flat_map(template::placeholders)
+            .collect();
+        for (name, value) in templates.into_iter().flatten() {
+            let path = format!("json_templates.{}", name);
+            let text = value.to_string();
+            for placeholder in template::placeholders(&text) {
+                // 渲染子模板时只替换生成字段和变量，不展开其他子模板
+                if templates.is_some_and(|templates| templates.contains_key(placeholder)) {
+                    diagnostics.push(Self::error(
+                        path.clone(),
+                        format!(
+                            "json templates cannot reference other json templates ({{{{{}}}}})",
+                            placeholder
+                        ),
+                    ));
+                } else if !known.contains(placeholder) {
+                    diagnostics.push(Self::error(
+                        path.clone(),
+                        format!(
+                            "placeholder {{{{{}}}}} has no matching generated field or variable",
+                            placeholder
+                        ),
+                    ));
+                }
+            }
+            for error in template::filter_errors(&text) {
+                diagnostics.push(Self::error(path.clone(), error));
+            }
+            let shadows_field = config
+                .generated_fields
+                .iter()
+                .flatten()
+                .any(|field| &field.name == name);
+            let shadows_variable = config
+                .variables
+                .as_ref()
+                .is_some_and(|variables| variables.contains_key(name));
+            if shadows_field || shadows_variable {
+                diagnostics.push(Self::warning(
+                    path.clone(),
+                    format!(
+                        "json template '{}' shadows a generated field or variable of the same name in request bodies",
+                        name
+                    ),
+                ));
+            }
+            if !referenced.contains(name.as_str()) {
+                diagnostics.push(Self::warning(
+                    path,
+                    "not referenced by request_a or request_b body".to_string(),
+                ));
+            }
+        }
+    }
+
+    fn validate_idempotency_key(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(idempotency) = &config.idempotency_key else {
+            return;
+        };
+        if idempotency.header_name().trim().is_empty() {
+            diagnostics.push(Self::error(
+                "idempotency_key.header".to_string(),
+                "header must not be empty".to_string(),
+            ));
+        }
+        match idempotency.retry_key.as_deref() {
+            None | Some("same") | Some("new") => {}
+            Some(other) => diagnostics.push(Self::error(
+                "idempotency_key.retry_key".to_string(),
+                format!("unknown retry_key '{}' (expected same or new)", other),
+            )),
+        }
+        if idempotency.retry_key.is_some() && idempotency.retries.unwrap_or(0) == 0 {
+            diagnostics.push(Self::warning(
+                "idempotency_key.retry_key".to_string(),
+                "has no effect without retries".to_string(),
+            ));
+        }
+    }
+
+    fn validate_branches(
+        config: &RequestConfig,
+        known: &HashSet<String>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let Some(branches) = config
+            .branches
+            .as_ref()
+            .filter(|branches| !branches.is_empty())
+        else {
+            return;
+        };
+        if config.send_mode.as_deref() == Some("barrier") {
+            diagnostics.push(Self::error(
+                "branches".to_string(),
+                "branches wait for request A, which is not possible when A and B are sent simultaneously (send_mode \"barrier\")".to_string(),
+            ));
+        }
+        if config.batch.is_some() {
+            diagnostics.push(Self::warning(
+                "branches".to_string(),
+                "in batch mode branches follow the result of the last request A copy".to_string(),
+            ));
+        }
+        let mut names = HashSet::new();
+        for (index, branch) in branches.iter().enumerate() {
+            let path = format!("branches[{}]", index);
+            if let Some(name) = &branch.name
+                && !names.insert(name.as_str())
+            {
+                diagnostics.push(Self::error(
+                    format!("{}.name", path),
+                    format!("duplicate branch name '{}'", name),
+                ));
+            }
+            for (pattern_index, pattern) in branch.status.iter().flatten().enumerate() {
+                if pattern != "error"
+                    && let Some(error) = StatusExpectation::pattern_error(pattern)
+                {
+                    diagnostics.push(Self::error(
+                        format!("{}.status[{}]", path, pattern_index),
+                        error,
+                    ));
+                }
+            }
+            match &branch.variable {
+                Some(variable)
+                    if !Capturer::names(&config.request_a).any(|name| name == variable) =>
+                {
+                    diagnostics.push(Self::warning(
+                        format!("{}.variable", path),
+                        format!("'{}' is not captured by request_a", variable),
+                    ));
+                }
+                Some(_) => {}
+                None if branch.equals.is_some() => diagnostics.push(Self::warning(
+                    format!("{}.equals", path),
+                    "has no effect without variable".to_string(),
+                )),
+                None if branch.status.is_none() => diagnostics.push(Self::warning(
+                    path.clone(),
+                    "no status or variable condition, the branch matches every result".to_string(),
+                )),
+                None => {}
+            }
+            match branch.then.as_deref() {
+                None | Some("end") | Some("b") | Some("retry") => {}
+                Some(other) => diagnostics.push(Self::error(
+                    format!("{}.then", path),
+                    format!(
+                        "unknown action '{}' (expected \"end\", \"b\" or \"retry\")",
+                        other
+                    ),
+                )),
+            }
+            if branch.max_retries.is_some() && branch.then.as_deref() != Some("retry") {
+                diagnostics.push(Self::warning(
+                    format!("{}.max_retries", path),
+                    "only applies when then is \"retry\"".to_string(),
+                ));
+            }
+            if let Some(request) = &branch.request {
+                Self::validate_request(request, &format!("{}.request", path), known, diagnostics);
+            }
+        }
+    }
+
+    fn validate_repeat(
+        config: &RequestConfig,
+        known: &HashSet<String>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let Some(repeat) = &config.repeat else {
+            return;
+        };
+        if repeat.count == 0 {
+            diagnostics.push(Self::error(
+                "repeat.count".to_string(),
+                "must be greater than 0".to_string(),
+            ));
+        }
+        if config.send_mode.as_deref() == Some("barrier") {
+            diagnostics.push(Self::error(
+                "repeat".to_string(),
+                "the repeated step runs after request A completes, which is not possible when A and B are sent simultaneously (send_mode \"barrier\")".to_string(),
+            ));
+        }
+        for (index, pattern) in repeat.until_status.iter().flatten().enumerate() {
+            if pattern != "error"
+                && let Some(error) = StatusExpectation::pattern_error(pattern)
+            {
+                diagnostics.push(Self::error(
+                    format!("repeat.until_status[{}]", index),
+                    error,
+                ));
+            }
+        }
+        match &repeat.until_variable {
+            Some(variable)
+                if !Capturer::names(&config.request_a)
+                    .chain(Capturer::names(&repeat.request))
+                    .any(|name| name == variable) =>
+            {
+                diagnostics.push(Self::warning(
+                    "repeat.until_variable".to_string(),
+                    format!(
+                        "'{}' is not captured by request_a or the repeated request",
+                        variable
+                    ),
+                ));
+            }
+            None if repeat.until_equals.is_some() => diagnostics.push(Self::warning(
+                "repeat.until_equals".to_string(),
+                "has no effect without until_variable".to_string(),
+            )),
+            _ => {}
+        }
+        let mut known = known.clone();
+        known.insert("iteration".to_string());
+        Self::validate_request(&repeat.request, "repeat.request", &known, diagnostics);
+    }
+
+    fn validate_send_mode(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        match config.send_mode.as_deref() {
+            None | Some("sequential") => {}
+            Some("barrier") => {
+                // 同时放行时B无法使用A的响应
+                if let Some(name) = Capturer::names(&config.request_a)
+                    .find(|name| Capturer::references(&config.request_b, name))
+                {
+                    diagnostics.push(Self::error(
+                        "send_mode".to_string(),
+                        format!(
+                            "request_b uses {{{{{}}}}} captured from request_a, which is not available when A and B are sent simultaneously",
+                            name
+                        ),
+                    ));
+                }
+                if config.delay_between_a_and_b_ms > 0
+                    || config.delay_between_a_and_b_distribution.is_some()
+                {
+                    diagnostics.push(Self::warning(
+                        "delay_between_a_and_b_ms".to_string(),
+                        "ignored in barrier mode, A and B are released at the same instant"
+                            .to_string(),
+                    ));
+                }
+            }
+            Some(mode) => diagnostics.push(Self::error(
+                "send_mode".to_string(),
+                format!("'{}' must be \"sequential\" or \"barrier\"", mode),
+            )),
+        }
+    }
+
+    fn validate_daemon(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(daemon) = &config.daemon else {
+            return;
+        };
+        if daemon.schedules.is_empty() {
+            diagnostics.push(Self::warning(
+                "daemon.schedules".to_string(),
+                "no schedules configured, the daemon will never run anything".to_string(),
+            ));
+        }
+        let mut names = HashSet::new();
+        for (index, schedule) in daemon.schedules.iter().enumerate() {
+            let path = format!("daemon.schedules[{}]", index);
+            if schedule.name.is_empty() {
+                diagnostics.push(Self::error(
+                    format!("{}.name", path),
+                    "schedule name must not be empty".to_string(),
+                ));
+            } else if !names.insert(schedule.name.as_str()) {
+                diagnostics.push(Self::error(
+                    format!("{}.name", path),
+                    format!("duplicate schedule name '{}'", schedule.name),
+                ));
+            }
+            if let Err(e) = CronSchedule::parse(&schedule.cron) {
+                diagnostics.push(Self::error(
+                    format!("{}.cron", path),
+                    format!("invalid cron expression \"{}\": {}", schedule.cron, e),
+                ));
+            }
+            if schedule.max_requests == Some(0) {
+                diagnostics.push(Self::error(
+                    format!("{}.max_requests", path),
+                    "max_requests must be greater than 0".to_string(),
+                ));
+            }
+            // 场景文件在启动守护进程时单独校验，这里只检查使用自身配置的计划
+            if schedule.scenario.is_none()
+                && schedule.max_requests.is_none()
+                && config.max_requests.is_none()
+            {
+                diagnostics.push(Self::warning(
+                    format!("{}.max_requests", path),
+                    "neither the schedule nor the config sets max_requests, each run never ends"
+                        .to_string(),
+                ));
+            }
+        }
+        if let Some(offset) = &daemon.utc_offset
+            && let Err(e) = parse_utc_offset(offset)
+        {
+            diagnostics.push(Self::error("daemon.utc_offset".to_string(), e.to_string()));
+        }
+        if let Some(bind) = &daemon.status_bind
+            && bind.parse::<std::net::SocketAddr>().is_err()
+        {
+            diagnostics.push(Self::error(
+                "daemon.status_bind".to_string(),
+                format!(
+                    "\"{}\" is not a valid address (expected e.g. 127.0.0.1:9500)",
+                    bind
+                ),
+            ));
+        }
+        if daemon.keep_runs == Some(0) {
+            diagnostics.push(Self::error(
+                "daemon.keep_runs".to_string(),
+                "keep_runs must be greater than 0".to_string(),
+            ));
+        }
+        if daemon.keep_days == Some(0) {
+            diagnostics.push(Self::error(
+                "daemon.keep_days".to_string(),
+                "keep_days must be greater than 0".to_string(),
+            ));
+        }
+    }
+
+    fn validate_batch(config: &RequestConfig, diagnostics: &mut Vec<Diagnostic>) {
+        let Some(batch) = &config.batch else {
+            return;
+        };
+        if batch.size == 0 {
+            diagnostics.push(Self::error(
+                "batch.size".to_string(),
+                "size must be at least 1".to_string(),
+            ));
+        }
+        Self::validate_task_id_field(config, "batch", batch.task_id_field.as_deref(), diagnostics);
+
+        let list_variable = batch.list_variable.as_deref().unwrap_or("task_id_list");
+        let placeholders_b = template::placeholders(config.request_b.body.as_deref().unwrap_or(""));
+        if !placeholders_b.contains(&list_variable) {
+            diagnostics.push(Self::warning(
+                "batch.list_variable".to_string(),
+                format!(
+                    "request_b body does not reference {{{{{}}}}}, the collected task IDs will not be sent",
+                    list_variable
+                ),
+            ));
+        }
+
+        // counter/timestamp 按周期生成，只有 random/uuid/ulid/nanoid 字段能区分同一周期内的副本
+        let placeholders_a = template::placeholders(config.request_a.body.as_deref().unwrap_or(""));
+        let has_fresh_field = config.generated_fields.iter().flatten().any(|field| {
+            field.field_type == "body"
+                && matches!(
+                    field.generator.as_str(),
+                    "random" | "uuid" | "ulid" | "nanoid"
+                )
+                && placeholders_a.contains(&field.name.as_str())
+        });
+        if batch.size > 1 && !has_fresh_field {
+            diagnostics.push(Self::warning(
+                "batch.size".to_string(),
+                "request_a body references no random/uuid/ulid/nanoid body field, all copies in a cycle will be identical"
+                    .to_string(),
+            ));
+        }
+    }
+
+    /// task_cleanup / task_verification / batch 共用的任务ID字段检查
+    fn validate_task_id_field(
+        config: &RequestConfig,
+        section: &str,
+        task_id_field: Option<&str>,
+        diagnostics: &mut Vec<Diagnostic>,
+    ) {
+        let field = task_id_field.unwrap_or("taskID");
+        let path = format!("{}.task_id_field", section);
+        if field.is_empty() {
+            diagnostics.push(Self::error(
+                path,
+                "task_id_field must not be empty".to_string(),
+            ));
+        } else if !config
+            .request_a
+            .body
+            .as_deref()
+            .is_some_and(|body| body.contains(&format!("\"{}\"", field)))
+        {
+            diagnostics.push(Self::warning(
+                path,
+                format!(
+                    "request_a body has no \"{}\" field, created tasks cannot be tracked",
+                    field
+                ),
+            ));
+        }
+    }
+
+    fn error(path: String, message: String) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Error,
+            path,
+            message,
+        }
+    }
+
+    fn warning(path: String, message: String) -> Diagnostic {
+        Diagnostic {
+            severity: Severity::Warning,
+            path,
+            message,
+        }
+    }
+}
@@ -0,0 +1,126 @@
+use std::sync::Arc;
+
+use remote_task::http_client::{AuthConfig, AuthType, HttpClient, HttpClientConfig};
+use remote_task::isapi::{AddTaskRequest, IsapiClient};
+use remote_task::mock_server::{MockServer, MockServerConfig};
+use serde_json::json;
+
+async fn start_with_auth() -> MockServer {
+    MockServer::start(
+        "127.0.0.1:0".parse().unwrap(),
+        MockServerConfig {
+            digest_auth: Some(("admin".to_string(), "secret".to_string())),
+            ..Default::default()
+        },
+    )
+    .await
+    .expect("mock server starts")
+}
+
+fn client(server: &MockServer, digest_password: Option<&str>) -> IsapiClient {
+    let http_client = HttpClient::new(HttpClientConfig {
+        auth: digest_password.map(|password| AuthConfig {
+            username: "admin".to_string(),
+            password: password.to_string(),
+            auth_type: AuthType::Digest,
+            preemptive: false,
+            known_challenge: None,
+        }),
+        ..Default::default()
+    })
+    .expect("http client");
+    IsapiClient::new(server.base_url(), Arc::new(http_client))
+}
+
+fn task(task_id: &str) -> AddTaskRequest {
+    AddTaskRequest {
+        task_name: task_id.to_string(),
+        task_id: task_id.to_string(),
+        node_id: "1".to_string(),
+        algo_package_id: "package".to_string(),
+        algo_id: "smokeAndFireDetection".to_string(),
+        data_source: json!({ "sourceType": "video" }),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn session_login_enables_heartbeat_without_digest() {
+    let server = start_with_auth().await;
+    let isapi = client(&server, None);
+
+    assert!(isapi.session_heartbeat().await.is_err());
+
+    let session = isapi.session_login("admin", "secret").await.unwrap();
+    assert_eq!(session.username, "admin");
+    assert!(!session.session_id.is_empty());
+    isapi.session_heartbeat().await.unwrap();
+    assert!(isapi.task_list().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn session_login_rejects_wrong_password() {
+    let server = start_with_auth().await;
+    let isapi = client(&server, None);
+
+    assert!(isapi.session_login("admin", "wrong").await.is_err());
+    assert_eq!(server.stats().auth_challenges, 1);
+    assert!(isapi.session_heartbeat().await.is_err());
+}
+
+#[tokio::test]
+async fn task_lifecycle_with_digest_auth() {
+    let server = start_with_auth().await;
+    let isapi = client(&server, Some("secret"));
+
+    let capabilities = isapi.capabilities().await.unwrap();
+    assert!(capabilities["AlgoPackageSchedulingCap"].is_object());
+
+    let status = isapi.add_task(&task("task-1")).await.unwrap();
+    assert!(status.is_ok());
+    assert_eq!(status.task_id.as_deref(), Some("task-1"));
+    assert_eq!(isapi.task_list().await.unwrap(), vec!["task-1".to_string()]);
+
+    let statuses = isapi
+        .task_status(&["task-1".to_string(), "task-2".to_string()])
+        .await
+        .unwrap();
+    assert_eq!(statuses[0].status, "running");
+    assert_eq!(statuses[1].status, "notExist");
+
+    // 重复下发返回ISAPI错误信息
+    let error = isapi.add_task(&task("task-1")).await.unwrap_err();
+    assert!(
+        format!("{:#}", error).contains("Task Already Exists"),
+        "{:#}",
+        error
+    );
+
+    assert!(
+        isapi
+            .delete_tasks(&["task-1".to_string()])
+            .await
+            .unwrap()
+            .is_ok()
+    );
+    assert!(isapi.task_list().await.unwrap().is_empty());
+    assert!(server.tasks().is_empty());
+}
+
+#[tokio::test]
+async fn session_login_encodes_the_username_in_the_capabilities_query() {
+    let server = MockServer::start(
+        "127.0.0.1:0".parse().unwrap(),
+        MockServerConfig {
+            digest_auth: Some(("ops user&1".to_string(), "secret".to_string())),
+            ..Default::default()
+        },
+    )
+    .await
+    .expect("mock server starts");
+    let isapi = client(&server, None);
+
+    let session = isapi.session_login("ops user&1", "secret").await.unwrap();
+    assert_eq!(session.username, "ops user&1");
+    isapi.session_heartbeat().await.unwrap();
+}